@@ -39,8 +39,28 @@ pub struct Args {
     )]
     pub txs: Option<Vec<String>>,
 
+    /// Read state at this one block for eth_calls and slots,
+    /// iterating over the given addresses, slots, and call data
+    /// instead of over blocks
+    #[arg(long, value_name = "BLOCK", help_heading = "Content Options", verbatim_doc_comment)]
+    pub at_block: Option<u64>,
+
+    /// Block numbers to exclude from collection, e.g. 12345,67890
+    #[arg(long, value_name = "BLOCKS", value_delimiter = ',', help_heading = "Content Options")]
+    pub skip_blocks: Option<Vec<u64>>,
+
+    /// File of block numbers to exclude from collection, one per line
+    #[arg(long, value_name = "PATH", help_heading = "Content Options")]
+    pub skip_blocks_file: Option<String>,
+
+    /// Collect every Nth block of each block range, chunk sizes still
+    /// count every block so a 1000 block chunk with step 100 holds 10 blocks
+    #[arg(long, value_name = "N", verbatim_doc_comment, help_heading = "Content Options")]
+    pub step: Option<u64>,
+
     /// Align chunk boundaries to regular intervals,
-    /// e.g. (1000 2000 3000), not (1106 2106 3106)
+    /// e.g. (1000 2000 3000), not (1106 2106 3106),
+    /// the leading partial chunk is collected in full
     #[arg(short, long, help_heading = "Content Options", verbatim_doc_comment)]
     pub align: bool,
 
@@ -55,8 +75,31 @@ pub struct Args {
     )]
     pub reorg_buffer: u64,
 
+    /// After collecting the block range, keep polling for new blocks
+    /// and collect them as they arrive (respecting --reorg-buffer)
+    #[arg(long, help_heading = "Content Options", verbatim_doc_comment)]
+    pub follow: bool,
+
+    /// Seconds between polls for new blocks when using --follow
+    #[arg(long, default_value_t = 12, value_name = "SECONDS", help_heading = "Content Options")]
+    pub poll_interval: u64,
+
+    /// Times to wait --poll-interval for a requested block above the
+    /// chain head when using --follow, without --follow chunks with
+    /// such blocks are skipped with a warning
+    #[arg(
+        long,
+        default_value_t = 5,
+        value_name = "N",
+        help_heading = "Content Options",
+        verbatim_doc_comment
+    )]
+    pub head_wait_retries: u32,
+
     /// Columns to include alongside the defaults,
-    /// use `all` to include all available columns
+    /// use `all` to include all available columns,
+    /// `block_timestamp` and `block_hash` add the timestamp
+    /// and hash of each row's block
     #[arg(short, long, value_name="COLS", num_args(0..), verbatim_doc_comment, help_heading="Content Options")]
     pub include_columns: Option<Vec<String>>,
 
@@ -65,33 +108,74 @@ pub struct Args {
     pub exclude_columns: Option<Vec<String>>,
 
     /// Columns to use instead of the defaults,
-    /// use `all` to use all available columns
+    /// use `all` to use all available columns,
+    /// use `none` to use only the included columns,
+    /// use DATATYPE:COL,COL for columns of one datatype
     #[arg(long, value_name="COLS", num_args(0..), verbatim_doc_comment, help_heading="Content Options")]
     pub columns: Option<Vec<String>>,
 
+    /// Named column preset to use instead of the defaults,
+    /// [minimal, analytics, full], include/exclude apply on top
+    #[arg(long, value_name = "PRESET", verbatim_doc_comment, help_heading = "Content Options")]
+    pub preset: Option<String>,
+
+    /// Include deprecated columns in `all` and the `full` preset
+    #[arg(long, help_heading = "Content Options")]
+    pub include_deprecated: bool,
+
     /// Set output datatype(s) of U256 integers
     /// [default: binary, string, f64]
     #[arg(long, num_args(1..), help_heading = "Content Options", verbatim_doc_comment)]
     pub u256_types: Option<Vec<String>>,
 
+    /// Handling of values too large for a u32 or u64 U256 type
+    /// [default: error] [possible values: error, saturate, string]
+    #[arg(long, value_name = "POLICY", help_heading = "Content Options", verbatim_doc_comment)]
+    pub on_overflow: Option<String>,
+
     /// Use hex string encoding for binary columns
     #[arg(long, help_heading = "Content Options")]
     pub hex: bool,
 
+    /// Store trace_address as a string like "0.1.2",
+    /// instead of as a list in parquet files
+    #[arg(long, verbatim_doc_comment, help_heading = "Content Options")]
+    pub trace_address_as_string: bool,
+
     /// Columns(s) to sort by, `none` for unordered
     #[arg(short, long, num_args(0..), help_heading="Content Options")]
     pub sort: Option<Vec<String>>,
 
+    /// Columns(s) to order rows by within files, the --sort
+    /// columns then become the key that rows are deduplicated on
+    #[arg(long, verbatim_doc_comment, num_args(1..), help_heading = "Content Options")]
+    pub order_within_file: Option<Vec<String>>,
+
+    /// Add a column computed from numeric columns, e.g.
+    /// gas_cost='gas_used * gas_price', can be repeated
+    #[arg(long, verbatim_doc_comment, value_name = "NAME=EXPR", help_heading = "Content Options")]
+    pub add_column: Option<Vec<String>>,
+
+    /// Order columns like an existing parquet file, erroring if
+    /// its columns or their types differ from those collected
+    #[arg(long, value_name = "PATH", verbatim_doc_comment, help_heading = "Content Options")]
+    pub match_schema: Option<String>,
+
     /// Exclude items from failed transactions
     #[arg(long, help_heading = "Content Options")]
     pub exclude_failed: bool,
 
+    /// Exclude rows whose value is zero, for datatypes with a value column
+    #[arg(long, help_heading = "Content Options")]
+    pub exclude_zero_value: bool,
+
     /// RPC url [default: 1. MESC 2. ETH_RPC_URL]
     #[arg(short, long, help_heading = "Source Options")]
     pub rpc: Option<String>,
 
-    /// Network name [default: name of eth_getChainId]
-    #[arg(long, help_heading = "Source Options")]
+    /// Network name used in file names and metadata
+    /// [default: name of eth_getChainId, or chain-<id> if unknown]
+    #[arg(long, verbatim_doc_comment, help_heading = "Source Options")]
     pub network_name: Option<String>,
 
     /// Ratelimit on requests per second
@@ -106,6 +190,11 @@ pub struct Args {
     #[arg(long, default_value_t = 500, value_name = "B", help_heading = "Acquisition Options")]
     pub initial_backoff: u64,
 
+    /// Retry failed block ranges one block at a time,
+    /// keeping every block that succeeds
+    #[arg(long, verbatim_doc_comment, help_heading = "Acquisition Options")]
+    pub retry_failed_blocks_individually: bool,
+
     /// Global number of concurrent requests
     #[arg(long, value_name = "M", help_heading = "Acquisition Options")]
     pub max_concurrent_requests: Option<u64>,
@@ -118,10 +207,34 @@ pub struct Args {
     #[arg(long, help_heading = "Acquisition Options")]
     pub chunk_order: Option<String>,
 
+    /// Cancel a chunk that takes longer than T ms to collect,
+    /// reporting it as failed while other chunks proceed
+    #[arg(long, verbatim_doc_comment, value_name = "T", help_heading = "Acquisition Options")]
+    pub chunk_timeout_ms: Option<u64>,
+
     /// Dry run, collect no data
     #[arg(short, long, help_heading = "Acquisition Options")]
     pub dry: bool,
 
+    /// Skip checking that the rpc endpoint supports the needed methods
+    #[arg(long, help_heading = "Acquisition Options")]
+    pub skip_preflight: bool,
+
+    /// Skip checking that the node has the historical state needed
+    /// by datatypes that read state or traces, as on an archive node
+    #[arg(long, verbatim_doc_comment, help_heading = "Acquisition Options")]
+    pub assume_archive: bool,
+
+    /// Warn if the total supply of any --contract or --address token
+    /// would overflow decimal128 u256 columns
+    #[arg(long, verbatim_doc_comment, help_heading = "Acquisition Options")]
+    pub validate_decimals_fit: bool,
+
+    /// Stop once N rows have been written across all chunks, truncating the
+    /// last file, the rows kept come from whichever chunks finish first
+    #[arg(long, value_name = "N", verbatim_doc_comment, help_heading = "Acquisition Options")]
+    pub limit: Option<u64>,
+
     /// Remember current command for future use
     #[arg(long)]
     pub remember: bool,
@@ -134,6 +247,23 @@ pub struct Args {
     #[arg(long)]
     pub no_verbose: bool,
 
+    /// Print nothing but errors, for use in scripts, the exit code is
+    /// 0 on success, 1 if some chunks failed, 2 for invalid arguments,
+    /// 3 if the rpc endpoint cannot be reached or fails preflight,
+    /// and 4 if the run fails for any other reason
+    #[arg(short, long, verbatim_doc_comment)]
+    pub quiet: bool,
+
+    /// Log filter for diagnostic logs on stderr, overrides RUST_LOG
+    /// e.g. debug, or cryo_freeze=debug,ethers_providers=trace for retries
+    #[arg(long, value_name = "FILTER", verbatim_doc_comment)]
+    pub log_level: Option<String>,
+
+    /// Format of diagnostic logs
+    /// [default: text] [possible values: text, json]
+    #[arg(long, value_name = "FORMAT", verbatim_doc_comment)]
+    pub log_format: Option<String>,
+
     /// Number of blocks per file
     #[arg(short, long, default_value_t = 1000, help_heading = "Output Options")]
     pub chunk_size: u64,
@@ -142,6 +272,16 @@ pub struct Args {
     #[arg(long, help_heading = "Output Options")]
     pub n_chunks: Option<u64>,
 
+    /// Merge a trailing chunk with fewer blocks than this into the
+    /// previous chunk, whose file name then ends at the last block
+    #[arg(long, value_name = "N", verbatim_doc_comment, help_heading = "Output Options")]
+    pub min_chunk_size: Option<u64>,
+
+    /// Size chunks adaptively to aim for files of this size, e.g. 128MB,
+    /// starting from --chunk-size, best-effort as block density varies
+    #[arg(long, value_name = "SIZE", verbatim_doc_comment, help_heading = "Output Options")]
+    pub target_file_size: Option<String>,
+
     /// Dimensions to partition by
     #[arg(long, help_heading = "Output Options")]
     pub partition_by: Option<Vec<String>>,
@@ -150,9 +290,34 @@ pub struct Args {
     #[arg(short, long, default_value = ".", help_heading = "Output Options")]
     pub output_dir: String,
 
+    /// Unix permissions of created output directories, in octal (e.g. 750)
+    #[arg(long, value_name = "MODE", help_heading = "Output Options")]
+    pub dir_mode: Option<String>,
+
+    /// Remove output directories created by this run that end up without files
+    #[arg(long, help_heading = "Output Options")]
+    pub clean_empty_dirs: bool,
+
+    /// Append a commit to a Delta Lake _delta_log in each datatype
+    /// directory after each chunk, parquet only
+    #[arg(long, help_heading = "Output Options", verbatim_doc_comment)]
+    pub delta: bool,
+
+    /// Write an index.json to the output dir after the run, listing
+    /// the schema, files, block coverage, and row counts of each datatype
+    #[arg(long, help_heading = "Output Options", verbatim_doc_comment)]
+    pub write_index: bool,
+
     /// Subdirectories for output files
-    /// can be `datatype`, `network`, or custom string
-    #[arg(long, help_heading = "Output Options", verbatim_doc_comment, num_args(1..))]
+    /// can be `datatype`, `network`, or custom string,
+    /// `--subdirs` alone places each datatype in its own directory
+    #[arg(
+        long,
+        help_heading = "Output Options",
+        verbatim_doc_comment,
+        num_args(0..),
+        default_missing_value = "datatype"
+    )]
     pub subdirs: Vec<String>,
 
     /// Label to add to each filename
@@ -163,14 +328,41 @@ pub struct Args {
     #[arg(long, help_heading = "Output Options")]
     pub overwrite: bool,
 
+    /// Continue after the highest block of the existing parquet
+    /// files in the output dir, found from their block range metadata
+    #[arg(long, verbatim_doc_comment, help_heading = "Output Options")]
+    pub resume_from_output: bool,
+
     /// Save as csv instead of parquet
     #[arg(long, help_heading = "Output Options")]
     pub csv: bool,
 
+    /// Write a row of column types below the header of csv files
+    #[arg(long, help_heading = "Output Options")]
+    pub csv_types: bool,
+
     /// Save as json instead of parquet
     #[arg(long, help_heading = "Output Options")]
     pub json: bool,
 
+    /// Write integer and decimal columns of json files as numbers, instead
+    /// of as strings that stay exact above 2^53 in JavaScript
+    #[arg(long, verbatim_doc_comment, help_heading = "Output Options")]
+    pub no_json_bigints_as_strings: bool,
+
+    /// Write one parquet file per datatype instead of one per chunk
+    #[arg(long, help_heading = "Output Options")]
+    pub single_file: bool,
+
+    /// Write per-chunk aggregates of numeric columns to companion files,
+    /// any of [sum, min, max, count]
+    #[arg(long, value_name = "AGGS", num_args(1..), verbatim_doc_comment, help_heading = "Output Options")]
+    pub aggregate: Option<Vec<String>>,
+
+    /// Append chunks to the single file in block order [default: true]
+    #[arg(long, value_name = "BOOL", num_args(0..=1), default_missing_value = "true", help_heading = "Output Options")]
+    pub ordered: Option<bool>,
+
     /// Number of rows per row group in parquet file
     #[arg(long, value_name = "GROUP_SIZE", help_heading = "Output Options")]
     pub row_group_size: Option<usize>,
@@ -183,10 +375,80 @@ pub struct Args {
     #[arg(long, help_heading = "Output Options")]
     pub no_stats: bool,
 
+    /// Do not record the block timestamp range of each chunk,
+    /// which can require looking up the chunk's boundary blocks
+    #[arg(long, verbatim_doc_comment, help_heading = "Output Options")]
+    pub no_timestamp_range: bool,
+
+    /// Downcast u64/i64 columns of each chunk to u32/i32 when every value fits,
+    /// narrowed columns are listed in the file metadata
+    #[arg(long, verbatim_doc_comment, help_heading = "Output Options")]
+    pub optimize_types: bool,
+
+    /// Split rows of each chunk into date=YYYY-MM-DD directories
+    /// by block timestamp, requires a timestamp column
+    #[arg(long, verbatim_doc_comment, help_heading = "Output Options")]
+    pub partition_by_date: bool,
+
+    /// Split rows of each chunk into COLUMN=VALUE directories
+    /// by the value of a column, e.g. contract_address
+    #[arg(long, value_name = "COLUMN", verbatim_doc_comment, help_heading = "Output Options")]
+    pub partition_by_column: Option<String>,
+
+    /// Split logs of each chunk into one file per topic0, with the
+    /// topic hash in the file name, e.g. logs__0xddf2...__00000100_to_00000199
+    #[arg(long, verbatim_doc_comment, help_heading = "Output Options")]
+    pub split_logs_by_topic0: bool,
+
+    /// Most values of --partition-by-column or --split-logs-by-topic0 that a
+    /// chunk may be split into, bounding the files open at once when streaming
+    #[arg(
+        long,
+        default_value_t = 1000,
+        value_name = "N",
+        verbatim_doc_comment,
+        help_heading = "Output Options"
+    )]
+    pub max_open_partitions: usize,
+
+    /// Write each RPC response as it arrives instead of holding whole chunks
+    /// in memory, rows are only sorted within each response
+    #[arg(long, verbatim_doc_comment, help_heading = "Output Options")]
+    pub streaming: bool,
+
     /// Compression algorithm and level
     #[arg(long, help_heading="Output Options", value_name="NAME [#]", num_args(1..=2), default_value = "lz4")]
     pub compression: Vec<String>,
 
+    /// Compression of individual parquet columns, overriding --compression
+    /// e.g. transaction_hash=uncompressed,input=zstd:9
+    #[arg(long, help_heading = "Output Options", value_name = "COL=NAME[:#]", num_args(1..), verbatim_doc_comment)]
+    pub column_compression: Option<Vec<String>>,
+
+    /// Parquet columns to dictionary encode, e.g. from_address,to_address
+    #[arg(long, help_heading = "Output Options", value_name = "COLS", num_args(1..))]
+    pub dictionary_columns: Option<Vec<String>>,
+
+    /// Parquet columns to never dictionary encode
+    #[arg(long, help_heading = "Output Options", value_name = "COLS", num_args(1..))]
+    pub no_dictionary_columns: Option<Vec<String>>,
+
+    /// Scale of the parquet decimal128 type of u256 columns, with an optional
+    /// precision of at most 38, e.g. value=18 or value=18:30
+    /// [default: scale 0, precision 38]
+    #[arg(long, help_heading = "Output Options", value_name = "COL=SCALE[:PRECISION]", num_args(1..), verbatim_doc_comment)]
+    pub decimal_scale: Option<Vec<String>>,
+
+    /// Write hashes and addresses as parquet fixed size binary columns
+    /// of 32 and 20 bytes instead of variable length binary or strings
+    #[arg(long, help_heading = "Output Options", verbatim_doc_comment)]
+    pub fixed_size_hex: bool,
+
+    /// Write hex address columns in EIP-55 checksum form,
+    /// lowercase by default so that addresses join exactly
+    #[arg(long, help_heading = "Output Options", verbatim_doc_comment)]
+    pub checksum_addresses: bool,
+
     /// Directory to save summary report
     /// [default: {output_dir}/.cryo/reports]
     #[arg(long, help_heading = "Output Options", verbatim_doc_comment)]
@@ -196,6 +458,26 @@ pub struct Args {
     #[arg(long, help_heading = "Output Options")]
     pub no_report: bool,
 
+    /// Log the resolved schema of each datatype as json before collecting
+    #[arg(long, visible_alias = "report-schema-on-start", help_heading = "Output Options")]
+    pub print_schema: bool,
+
+    /// Schema language of cryo export-schema,
+    /// can be json-schema, avro, or parquet-metadata
+    #[arg(
+        long = "as",
+        default_value = "json-schema",
+        value_name = "FORMAT",
+        help_heading = "Output Options",
+        verbatim_doc_comment
+    )]
+    pub schema_format: String,
+
+    /// Rewrite the report and sync single files to disk every N blocks,
+    /// or every N seconds if N ends in s (e.g. 30s)
+    #[arg(long, value_name = "N", help_heading = "Output Options", verbatim_doc_comment)]
+    pub checkpoint_every: Option<String>,
+
     /// Address(es)
     #[arg(long, help_heading = "Dataset-specific Options", num_args(1..))]
     pub address: Option<Vec<String>>,
@@ -220,6 +502,10 @@ pub struct Args {
     #[arg(long, help_heading = "Dataset-specific Options", num_args(1..))]
     pub inputs: Option<Vec<String>>,
 
+    /// Solidity return type to decode eth_calls outputs as, e.g. uint256
+    #[arg(long, value_name = "TYPE", help_heading = "Dataset-specific Options")]
+    pub decode_output: Option<String>,
+
     /// Slot(s)
     #[arg(long, help_heading = "Dataset-specific Options", num_args(1..))]
     pub slot: Option<Vec<String>>,
@@ -248,18 +534,105 @@ pub struct Args {
     #[arg(long, value_name = "SIG", help_heading = "Dataset-specific Options", num_args(1..))]
     pub event_signature: Option<String>,
 
-    /// Blocks per request (eth_getLogs)
+    /// Priority fee percentiles for gas_fees, one reward column each
+    #[arg(long, value_name = "P", help_heading = "Dataset-specific Options", num_args(1..))]
+    pub reward_percentiles: Option<Vec<f64>>,
+
+    /// Drop logs of blocks that are no longer canonical, checked
+    /// against the block hash of each block number
+    #[arg(long, help_heading = "Dataset-specific Options", verbatim_doc_comment)]
+    pub deduplicate_logs_on_reorg: bool,
+
+    /// Blocks per request (eth_getLogs),
+    /// or transactions per batch when tracing by transaction
     #[arg(
         long,
         value_name = "BLOCKS",
         default_value_t = 1,
-        help_heading = "Dataset-specific Options"
+        help_heading = "Dataset-specific Options",
+        verbatim_doc_comment
     )]
     pub inner_request_size: u64,
 
+    /// Cap on blocks per eth_getLogs request, for providers that
+    /// reject larger block spans, lowers --inner-request-size
+    #[arg(long, value_name = "N", help_heading = "Dataset-specific Options", verbatim_doc_comment)]
+    pub max_blocks_per_log_request: Option<u64>,
+
     /// Event signature for log decoding
     #[arg(long, value_name = "tracer", help_heading = "Dataset-specific Options")]
     pub js_tracer: Option<String>,
+
+    /// eth_getLogs filter as JSON, or a path to a JSON file, merged into the
+    /// filter of each chunk, cryo supplies fromBlock and toBlock
+    #[arg(
+        long,
+        value_name = "JSON",
+        help_heading = "Dataset-specific Options",
+        verbatim_doc_comment
+    )]
+    pub log_filter_json: Option<String>,
+
+    /// Only collect blocks with at least one log matching an eth_getLogs
+    /// filter, given as JSON or a path to a JSON file of address and topics
+    #[arg(
+        long,
+        value_name = "JSON",
+        help_heading = "Dataset-specific Options",
+        verbatim_doc_comment
+    )]
+    pub blocks_with_logs: Option<String>,
+
+    /// Trace requests per block or per transaction
+    /// [default: block] [possible values: block, transaction]
+    #[arg(
+        long,
+        value_name = "GRANULARITY",
+        help_heading = "Dataset-specific Options",
+        verbatim_doc_comment
+    )]
+    pub trace_granularity: Option<String>,
+
+    /// Trace block with trace_block (parity) or debug_traceBlockByNumber
+    /// callTracer (geth), granularity only applies to parity
+    /// [default: parity] [possible values: parity, geth]
+    #[arg(
+        long,
+        value_name = "BACKEND",
+        help_heading = "Dataset-specific Options",
+        verbatim_doc_comment
+    )]
+    pub trace_backend: Option<String>,
+
+    /// Fetch logs with eth_getLogs (getlogs) or with a filter installed
+    /// by eth_newFilter and read by eth_getFilterLogs (filter)
+    /// [default: getlogs] [possible values: getlogs, filter]
+    #[arg(
+        long,
+        value_name = "BACKEND",
+        help_heading = "Dataset-specific Options",
+        verbatim_doc_comment
+    )]
+    pub logs_backend: Option<String>,
+
+    /// Request logs in pages, following the page key that the provider
+    /// returns with each page, for providers that paginate eth_getLogs
+    #[arg(long, help_heading = "Dataset-specific Options", verbatim_doc_comment)]
+    pub paginate: bool,
+
+    /// Most logs per page with --paginate [default: 10000]
+    #[arg(long, value_name = "N", help_heading = "Dataset-specific Options")]
+    pub page_size: Option<u64>,
+
+    /// Genesis file to read genesis_allocations from,
+    /// either a full genesis.json or its alloc object
+    #[arg(
+        long,
+        value_name = "PATH",
+        help_heading = "Dataset-specific Options",
+        verbatim_doc_comment
+    )]
+    pub genesis: Option<String>,
 }
 
 impl Args {
@@ -318,7 +691,34 @@ fn get_after_str() -> String {
       <white><bold>cryo help</bold></white>"#
     );
     let post_subcommands = " <DATASET(S)>         display info about a dataset";
-    format!("{}{}{}", header, subcommands, post_subcommands)
+    let count_subcommand = cstr!(
+        r#"
+      <white><bold>cryo count</bold></white>"#
+    );
+    let post_count_subcommand = " <DATASET(S)>        count rows without writing files";
+    let export_schema_subcommand = cstr!(
+        r#"
+      <white><bold>cryo export-schema</bold></white>"#
+    );
+    let post_export_schema_subcommand = " <DATASET(S)> --as FORMAT  print schema of a dataset";
+    let inspect_subcommand = cstr!(
+        r#"
+      <white><bold>cryo inspect</bold></white>"#
+    );
+    let post_inspect_subcommand =
+        " <FILE(S)>         print rows, null counts, and metadata of files";
+    format!(
+        "{}{}{}{}{}{}{}{}{}",
+        header,
+        subcommands,
+        post_subcommands,
+        count_subcommand,
+        post_count_subcommand,
+        export_schema_subcommand,
+        post_export_schema_subcommand,
+        inspect_subcommand,
+        post_inspect_subcommand
+    )
 }
 
 fn get_datatype_help() -> &'static str {
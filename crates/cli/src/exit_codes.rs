@@ -0,0 +1,82 @@
+use cryo_freeze::{CollectError, FreezeSummary, ParseError};
+
+/// every chunk was collected or skipped
+pub const SUCCESS: i32 = 0;
+
+/// some chunks failed, the other chunks were written
+pub const PARTIAL_FAILURE: i32 = 1;
+
+/// invalid arguments or configuration, nothing was collected
+pub const CONFIG_ERROR: i32 = 2;
+
+/// rpc endpoint could not be reached, failed preflight, or is not an archive node, nothing was
+/// collected
+pub const CONNECTION_ERROR: i32 = 3;
+
+/// run failed for any other reason, such as an io or decode error, nothing was collected
+pub const FAILURE: i32 = 4;
+
+/// run was interrupted before every chunk was collected
+pub const INTERRUPTED: i32 = 130;
+
+/// exit code of a finished run
+pub fn summary_exit_code(summary: &FreezeSummary) -> i32 {
+    if !summary.errored.is_empty() {
+        PARTIAL_FAILURE
+    } else if !summary.cancelled.is_empty() {
+        INTERRUPTED
+    } else {
+        SUCCESS
+    }
+}
+
+/// exit code of a run that failed before collecting
+pub fn error_exit_code(error: &CollectError) -> i32 {
+    match error {
+        CollectError::ParseError(ParseError::ProviderError(_)) |
+        CollectError::Rpc(_) |
+        CollectError::Timeout(_) |
+        CollectError::TooManyRequestsError |
+        CollectError::PreflightError(_) |
+        CollectError::ArchiveNodeRequired(_) => CONNECTION_ERROR,
+        CollectError::ParseError(_) | CollectError::Schema(_) | CollectError::BadSchemaError => {
+            CONFIG_ERROR
+        }
+        _ => FAILURE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cryo_freeze::err;
+
+    #[test]
+    fn test_exit_codes() {
+        let summary = FreezeSummary::default();
+        assert_eq!(summary_exit_code(&summary), SUCCESS);
+
+        // chunks that failed with others completed are a partial failure
+        let summary = FreezeSummary {
+            completed: vec![Default::default()],
+            errored: vec![(None, err("chunk failed"))],
+            ..Default::default()
+        };
+        assert_eq!(summary_exit_code(&summary), PARTIAL_FAILURE);
+
+        let summary = FreezeSummary { cancelled: vec![Default::default()], ..Default::default() };
+        assert_eq!(summary_exit_code(&summary), INTERRUPTED);
+
+        let error: CollectError = ParseError::ParseError("invalid --blocks".to_string()).into();
+        assert_eq!(error_exit_code(&error), CONFIG_ERROR);
+        let error = CollectError::PreflightError("method not supported".to_string());
+        assert_eq!(error_exit_code(&error), CONNECTION_ERROR);
+        let error = CollectError::ArchiveNodeRequired("missing trie node".to_string());
+        assert_eq!(error_exit_code(&error), CONNECTION_ERROR);
+
+        // fatal errors are not reported as partial failures
+        let error = CollectError::Io(std::io::Error::other("disk full"));
+        assert_eq!(error_exit_code(&error), FAILURE);
+        assert_ne!(error_exit_code(&error), PARTIAL_FAILURE);
+    }
+}
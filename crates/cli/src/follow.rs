@@ -0,0 +1,226 @@
+use crate::{args::Args, parse::execution::parse_execution_env};
+use cryo_freeze::{
+    err, BlockChunk, ChunkData, CollectError, ExecutionEnv, FileOutput, FreezeSummary, Partition,
+    Query, Source,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::watch;
+
+/// after the initial range, poll for new blocks and collect each new range as its own chunk
+///
+/// stops on shutdown once the chunks in flight have been written, or when a chunk fails. the
+/// returned summary covers the initial range and every collected step
+pub(crate) async fn follow(
+    args: &Args,
+    query: &Query,
+    source: Arc<Source>,
+    sink: &FileOutput,
+    mut shutdown: watch::Receiver<bool>,
+    summary: FreezeSummary,
+) -> Result<Option<FreezeSummary>, CollectError> {
+    let templates = follow_templates(query)?;
+    let mut next_block = next_block(query)?;
+    let poll_interval = Duration::from_secs(args.poll_interval);
+    let mut summary = summary;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(poll_interval) => {}
+            _ = shutdown.changed() => break,
+        }
+        let (step_summary, step_next_block) =
+            follow_step(args, query, &templates, source.clone(), sink, &shutdown, next_block)
+                .await?;
+        next_block = step_next_block;
+        if let Some(step_summary) = step_summary {
+            let errored = !step_summary.errored.is_empty();
+            summary.extend(step_summary);
+            if errored {
+                break
+            }
+        }
+        if *shutdown.borrow() {
+            break
+        }
+    }
+    Ok(Some(summary))
+}
+
+/// collect blocks from next_block up to the current head minus the reorg buffer
+///
+/// returns the summary of the collection (if any new blocks) and the next block to collect
+async fn follow_step(
+    args: &Args,
+    query: &Query,
+    templates: &[Partition],
+    source: Arc<Source>,
+    sink: &FileOutput,
+    shutdown: &watch::Receiver<bool>,
+    next_block: u64,
+) -> Result<(Option<FreezeSummary>, u64), CollectError> {
+    let head = source.get_block_number().await?.as_u64();
+    let safe_head = head.saturating_sub(args.reorg_buffer);
+    if safe_head < next_block {
+        return Ok((None, next_block))
+    }
+
+    let partitions = templates
+        .iter()
+        .map(|template| Partition {
+            label: None,
+            block_numbers: Some(vec![BlockChunk::Range(next_block, safe_head)]),
+            ..template.clone()
+        })
+        .collect();
+    let query = Query { partitions, ..query.clone() };
+    let env = parse_execution_env(args, query.n_tasks() as u64)?;
+    let env = ExecutionEnv { shutdown: Some(shutdown.clone()), ..env }.set_start_time();
+    let summary = cryo_freeze::freeze(&query, &source, sink, &env).await?;
+    Ok((summary, safe_head + 1))
+}
+
+/// partitions containing the last block of the query, one per combination of other dimensions
+fn follow_templates(query: &Query) -> Result<Vec<Partition>, CollectError> {
+    let last_block = next_block(query)? - 1;
+    Ok(query
+        .partitions
+        .iter()
+        .filter(|partition| max_block(partition) == Some(last_block))
+        .cloned()
+        .collect())
+}
+
+fn next_block(query: &Query) -> Result<u64, CollectError> {
+    query
+        .partitions
+        .iter()
+        .filter_map(max_block)
+        .max()
+        .map(|block| block + 1)
+        .ok_or(err("--follow requires a block range"))
+}
+
+fn max_block(partition: &Partition) -> Option<u64> {
+    partition.block_numbers.as_ref()?.iter().filter_map(|chunk| chunk.max_value()).max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::{file_output::parse_file_output, parse_query},
+        test_helpers::{make_source, TempDir},
+    };
+    use clap_cryo::Parser;
+    use ethers::prelude::*;
+
+    fn output_files(sink: &FileOutput) -> Vec<String> {
+        let mut files: Vec<_> = std::fs::read_dir(&sink.output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        files.sort();
+        files
+    }
+
+    fn push_blocks(mock: &MockProvider, head: u64, blocks: &[u64]) {
+        // mock responses are popped from the back
+        for number in blocks.iter().rev() {
+            let block = Block::<TxHash> { number: Some((*number).into()), ..Default::default() };
+            mock.push(block).unwrap();
+        }
+        mock.push(U64::from(head)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn follow_collects_new_blocks() {
+        let (provider, mock) = Provider::mocked();
+        let source = make_source(provider);
+        let output_dir = TempDir::new("follow");
+        let output_dir = output_dir.to_string_lossy().to_string();
+        let args = Args::parse_from(vec![
+            "cryo",
+            "blocks",
+            "--blocks",
+            "100:101",
+            "--output-dir",
+            output_dir.as_str(),
+            "--no-verbose",
+            "--no-report",
+        ]);
+        let query = parse_query(&args, source.clone()).await.unwrap();
+        let sink = parse_file_output(&args, &source, &query.schemas).unwrap();
+        let templates = follow_templates(&query).unwrap();
+        let (_sender, shutdown) = watch::channel(false);
+        let next_block = next_block(&query).unwrap();
+        assert_eq!(next_block, 101);
+
+        // head advances to 102
+        push_blocks(&mock, 102, &[101, 102]);
+        let (summary, next_block) =
+            follow_step(&args, &query, &templates, source.clone(), &sink, &shutdown, next_block)
+                .await
+                .unwrap();
+        assert_eq!(summary.unwrap().n_rows, 2);
+        assert_eq!(next_block, 103);
+
+        // head has not advanced
+        push_blocks(&mock, 102, &[]);
+        let (summary, next_block) =
+            follow_step(&args, &query, &templates, source.clone(), &sink, &shutdown, next_block)
+                .await
+                .unwrap();
+        assert!(summary.is_none());
+        assert_eq!(next_block, 103);
+
+        // head advances to 105
+        push_blocks(&mock, 105, &[103, 104, 105]);
+        let (summary, next_block) =
+            follow_step(&args, &query, &templates, source.clone(), &sink, &shutdown, next_block)
+                .await
+                .unwrap();
+        assert_eq!(summary.unwrap().n_rows, 3);
+        assert_eq!(next_block, 106);
+
+        assert_eq!(
+            output_files(&sink),
+            vec![
+                "ethereum__blocks__00000101_to_00000102.parquet",
+                "ethereum__blocks__00000103_to_00000105.parquet",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn follow_accumulates_summaries() {
+        let (provider, mock) = Provider::mocked();
+        let source = make_source(provider);
+        let output_dir = TempDir::new("follow_summary");
+        let output_dir = output_dir.to_string_lossy().to_string();
+        let args = Args::parse_from(vec![
+            "cryo",
+            "blocks",
+            "--blocks",
+            "100:101",
+            "--output-dir",
+            output_dir.as_str(),
+            "--poll-interval",
+            "0",
+            "--no-verbose",
+            "--no-report",
+        ]);
+        let query = parse_query(&args, source.clone()).await.unwrap();
+        let sink = parse_file_output(&args, &source, &query.schemas).unwrap();
+        let initial =
+            FreezeSummary { completed: query.partitions.clone(), n_rows: 1, ..Default::default() };
+
+        // the first step collects blocks 101 and 102, the second step fails on block 103
+        push_blocks(&mock, 103, &[]);
+        push_blocks(&mock, 102, &[101, 102]);
+        let (_sender, shutdown) = watch::channel(false);
+        let summary =
+            follow(&args, &query, source, &sink, shutdown, initial).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 2);
+        assert_eq!(summary.errored.len(), 1);
+        assert_eq!(summary.n_rows, 3);
+    }
+}
@@ -8,14 +8,23 @@
 ))]
 
 mod args;
+mod exit_codes;
+mod follow;
 mod parse;
 mod remember;
 mod run;
+#[cfg(test)]
+mod test_helpers;
 
 // used in main.rs but not lib.rs
 use eyre as _;
 use tokio as _;
+use tracing_subscriber as _;
 
 pub use args::Args;
+pub use exit_codes::{
+    error_exit_code, summary_exit_code, CONFIG_ERROR, CONNECTION_ERROR, FAILURE, INTERRUPTED,
+    PARTIAL_FAILURE, SUCCESS,
+};
 pub use parse::{parse_args, parse_query, parse_str};
-pub use run::run;
+pub use run::{run, run_with_shutdown, shutdown_signal};
@@ -1,37 +1,57 @@
 //! cryo_cli is a cli for cryo_freeze
 
 use clap_cryo::Parser;
-
-mod args;
-mod parse;
-mod remember;
-mod run;
-
-pub use args::Args;
+use cryo_cli::{
+    error_exit_code, run_with_shutdown, shutdown_signal, summary_exit_code, Args, SUCCESS,
+};
 use eyre::Result;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 #[allow(unreachable_code)]
 #[allow(clippy::needless_return)]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    match run::run(args).await {
-        Ok(Some(freeze_summary)) if freeze_summary.errored.is_empty() => Ok(()),
-        Ok(Some(_freeze_summary)) => std::process::exit(1),
+    init_logging(&args)?;
+    match run_with_shutdown(args, Some(shutdown_signal())).await {
+        Ok(Some(freeze_summary)) => match summary_exit_code(&freeze_summary) {
+            SUCCESS => Ok(()),
+            code => std::process::exit(code),
+        },
         Ok(None) => Ok(()),
         Err(e) => {
+            let code = error_exit_code(&e);
+
             // handle debug build
             #[cfg(debug_assertions)]
             {
-                return Err(eyre::Report::from(e))
+                eprintln!("Error: {:?}", eyre::Report::from(e));
+                std::process::exit(code);
             }
 
             // handle release build
             #[cfg(not(debug_assertions))]
             {
-                println!("{}", e);
-                std::process::exit(1);
+                eprintln!("{}", e);
+                std::process::exit(code);
             }
         }
     }
 }
+
+/// install a tracing subscriber writing to stderr, if --log-level or RUST_LOG is set
+fn init_logging(args: &Args) -> Result<()> {
+    let filter = match (&args.log_level, std::env::var(EnvFilter::DEFAULT_ENV)) {
+        (Some(log_level), _) => EnvFilter::try_new(log_level)?,
+        (None, Ok(_)) => EnvFilter::try_from_default_env()?,
+        (None, Err(_)) if args.print_schema => EnvFilter::try_new("cryo::schema=info")?,
+        (None, Err(_)) => return Ok(()),
+    };
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr);
+    match args.log_format.as_deref() {
+        None | Some("text") => builder.init(),
+        Some("json") => builder.json().init(),
+        Some(_) => return Err(eyre::eyre!("invalid --log-format, use text or json")),
+    };
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use polars::prelude::*;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
 use cryo_freeze::{BlockChunk, ChunkData, Datatype, ParseError, Source, Subchunk, Table};
 
@@ -14,6 +14,7 @@ pub(crate) async fn parse_blocks(
         None => return Ok((None, None)),
     };
 
+    let skip_blocks = parse_skip_blocks(args)?;
     let (file_labels, file_chunks) = if !files.is_empty() {
         let mut file_labels = Vec::new();
         let mut file_chunks = Vec::new();
@@ -27,6 +28,11 @@ pub(crate) async fn parse_blocks(
             };
             let integers = read_integer_column(path, column)
                 .map_err(|_e| ParseError::ParseError("could not read input".to_string()))?;
+            let integers: Vec<u64> =
+                integers.into_iter().filter(|x| !skip_blocks.contains(x)).collect();
+            if integers.is_empty() {
+                continue
+            }
             let chunk = BlockChunk::Numbers(integers);
             let chunk_label = path
                 .split("__")
@@ -47,6 +53,10 @@ pub(crate) async fn parse_blocks(
             let outputs = parse_block_inputs(explicit_number, source.clone()).await?;
             block_chunks.extend(outputs);
         }
+        let (block_chunks, n_merged) = merge_overlapping_ranges(block_chunks);
+        if n_merged > 0 && !args.quiet {
+            eprintln!("warning: merged {} overlapping block ranges", n_merged);
+        }
         postprocess_block_chunks(block_chunks, args, source).await?
     } else {
         Vec::new()
@@ -67,6 +77,39 @@ pub(crate) async fn parse_blocks(
     Ok((labels, Some(block_chunks)))
 }
 
+/// merge overlapping block ranges of separate --blocks inputs so no block is collected twice
+///
+/// ranges are returned in ascending order followed by explicit block numbers, along with the
+/// number of ranges that were merged into another
+fn merge_overlapping_ranges(block_chunks: Vec<BlockChunk>) -> (Vec<BlockChunk>, usize) {
+    let mut ranges = Vec::new();
+    let mut numbers = Vec::new();
+    for chunk in block_chunks.into_iter() {
+        match chunk {
+            BlockChunk::Range(start, end) => ranges.push((start, end)),
+            chunk => numbers.push(chunk),
+        }
+    }
+    ranges.sort();
+
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    let mut n_merged = 0;
+    for (start, end) in ranges.into_iter() {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => {
+                *last_end = (*last_end).max(end);
+                n_merged += 1;
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let mut block_chunks: Vec<BlockChunk> =
+        merged.into_iter().map(|(start, end)| BlockChunk::Range(start, end)).collect();
+    block_chunks.extend(numbers);
+    (block_chunks, n_merged)
+}
+
 fn read_integer_column(path: &str, column: &str) -> Result<Vec<u64>, ParseError> {
     let file = std::fs::File::open(path)
         .map_err(|_e| ParseError::ParseError("could not open file path".to_string()))?;
@@ -116,16 +159,180 @@ pub(crate) async fn postprocess_block_chunks(
         block_chunks
     };
 
-    // split block range into chunks
+    if args.step == Some(0) {
+        return Err(ParseError::ParseError("--step must be greater than 0".to_string()))
+    }
+    if args.step.is_some() && block_chunks.iter().any(|x| matches!(x, BlockChunk::Numbers(_))) {
+        return Err(ParseError::ParseError(
+            "--step applies to block ranges, not to lists of block numbers".to_string(),
+        ))
+    }
+
+    // split block range into chunks, separate ranges are chunked separately
+    //
+    // with --step, chunks are sized by the blocks they span and then thinned to every step-th
+    // block counted from the start of their range
     let block_chunks = match args.n_chunks {
-        Some(n_chunks) => block_chunks.subchunk_by_count(&n_chunks),
-        None => block_chunks.subchunk_by_size(&args.chunk_size),
+        Some(n_chunks) => {
+            let start = block_chunks.iter().filter_map(|x| x.min_value()).min();
+            let chunks = block_chunks.subchunk_by_count(&n_chunks);
+            apply_step(chunks, start.unwrap_or_default(), args.step)
+        }
+        None => {
+            let (ranges, numbers): (Vec<_>, Vec<_>) =
+                block_chunks.into_iter().partition(|x| matches!(x, BlockChunk::Range(..)));
+            let mut chunks = Vec::new();
+            for range in ranges.iter() {
+                let start = range.min_value().unwrap_or_default();
+                let range_chunks = range.subchunk_by_size(&args.chunk_size);
+                let range_chunks = coalesce_trailing_chunk(range_chunks, args.min_chunk_size);
+                chunks.extend(apply_step(range_chunks, start, args.step));
+            }
+            if !numbers.is_empty() {
+                let number_chunks = numbers.subchunk_by_size(&args.chunk_size);
+                chunks.extend(coalesce_trailing_chunk(number_chunks, args.min_chunk_size));
+            }
+            chunks
+        }
     };
 
     // apply reorg buffer
     let block_chunks = apply_reorg_buffer(block_chunks, args.reorg_buffer, source).await?;
 
-    Ok(block_chunks)
+    let block_chunks = apply_skip_blocks(block_chunks, &parse_skip_blocks(args)?);
+    match args.resume_from_output {
+        true => resume_from_output(block_chunks, args),
+        false => Ok(block_chunks),
+    }
+}
+
+/// drop blocks below the high water mark of the existing files of every datatype
+///
+/// gaps below the high water mark are reported rather than collected
+fn resume_from_output(chunks: Vec<BlockChunk>, args: &Args) -> Result<Vec<BlockChunk>, ParseError> {
+    if args.csv || args.json {
+        return Err(ParseError::ParseError(
+            "--resume-from-output reads block ranges from parquet metadata, use parquet output"
+                .to_string(),
+        ))
+    }
+    let output_dir = std::path::Path::new(&args.output_dir);
+    let mut resume_block = None;
+    for datatype in super::schemas::parse_datatypes(&args.datatype)?.iter() {
+        let coverage = cryo_freeze::scan_block_coverage(output_dir, datatype.name().as_str())?;
+        let high_water_mark = coverage.high_water_mark().unwrap_or(0);
+        if !args.quiet {
+            let gaps = coverage.gaps();
+            eprintln!(
+                "{}: high water mark is block {} from {} existing files ({} overlapping)",
+                datatype.name(),
+                high_water_mark,
+                coverage.n_files,
+                coverage.n_overlapping,
+            );
+            if let Some((start, end)) = gaps.first() {
+                eprintln!(
+                    "warning: {} gaps below the high water mark are not collected, first gap is \
+                    blocks {} to {}",
+                    gaps.len(),
+                    start,
+                    end
+                );
+            }
+        }
+        resume_block = Some(resume_block.unwrap_or(high_water_mark).min(high_water_mark));
+    }
+    let Some(resume_block) = resume_block else { return Ok(chunks) };
+    let chunks = chunks
+        .into_iter()
+        .filter_map(|chunk| match chunk {
+            BlockChunk::Range(_, end) if end < resume_block => None,
+            BlockChunk::Range(start, end) => Some(BlockChunk::Range(start.max(resume_block), end)),
+            BlockChunk::Numbers(numbers) => {
+                let numbers: Vec<u64> =
+                    numbers.into_iter().filter(|x| *x >= resume_block).collect();
+                (!numbers.is_empty()).then_some(BlockChunk::Numbers(numbers))
+            }
+        })
+        .collect();
+    Ok(chunks)
+}
+
+/// block numbers given by --skip-blocks and --skip-blocks-file
+pub(crate) fn parse_skip_blocks(args: &Args) -> Result<BTreeSet<u64>, ParseError> {
+    let mut skip_blocks: BTreeSet<u64> = args.skip_blocks.iter().flatten().copied().collect();
+    if let Some(path) = &args.skip_blocks_file {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            ParseError::ParseError(format!("could not read --skip-blocks-file {}: {}", path, e))
+        })?;
+        for line in contents.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            let block_number = line.parse::<u64>().map_err(|_| {
+                ParseError::ParseError(format!("invalid block number in {}: {}", path, line))
+            })?;
+            skip_blocks.insert(block_number);
+        }
+    }
+    Ok(skip_blocks)
+}
+
+/// remove skipped blocks from chunks, dropping chunks left empty
+///
+/// chunks with a skipped block become lists of block numbers, named by their remaining blocks
+fn apply_skip_blocks(chunks: Vec<BlockChunk>, skip_blocks: &BTreeSet<u64>) -> Vec<BlockChunk> {
+    if skip_blocks.is_empty() {
+        return chunks
+    }
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            let skipped = match &chunk {
+                BlockChunk::Range(start, end) => skip_blocks.range(start..=end).next().is_some(),
+                BlockChunk::Numbers(numbers) => numbers.iter().any(|x| skip_blocks.contains(x)),
+            };
+            if skipped {
+                let values = chunk.values();
+                BlockChunk::Numbers(
+                    values.into_iter().filter(|x| !skip_blocks.contains(x)).collect(),
+                )
+            } else {
+                chunk
+            }
+        })
+        .filter(|chunk| chunk.size() > 0)
+        .collect()
+}
+
+/// merge the last chunk into the one before it if it has fewer than min_chunk_size blocks
+fn coalesce_trailing_chunk(
+    mut chunks: Vec<BlockChunk>,
+    min_chunk_size: Option<u64>,
+) -> Vec<BlockChunk> {
+    let Some(min_chunk_size) = min_chunk_size else { return chunks };
+    if chunks.len() < 2 || chunks[chunks.len() - 1].size() >= min_chunk_size {
+        return chunks
+    }
+    let (Some(last), Some(previous)) = (chunks.pop(), chunks.pop()) else { return chunks };
+    let merged = match (previous, last) {
+        (BlockChunk::Range(start, _), BlockChunk::Range(_, end)) => BlockChunk::Range(start, end),
+        (previous, last) => BlockChunk::Numbers([previous.values(), last.values()].concat()),
+    };
+    chunks.push(merged);
+    chunks
+}
+
+/// keep blocks whose distance from start is a multiple of step, dropping chunks left empty
+fn apply_step(chunks: Vec<BlockChunk>, start: u64, step: Option<u64>) -> Vec<BlockChunk> {
+    let Some(step) = step else { return chunks };
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            let values = chunk.values();
+            BlockChunk::Numbers(
+                values.into_iter().filter(|x| (x - start).is_multiple_of(step)).collect(),
+            )
+        })
+        .filter(|chunk| chunk.size() > 0)
+        .collect()
 }
 
 pub(crate) async fn get_default_block_chunks(
@@ -278,22 +485,20 @@ async fn parse_block_range(
     let (start_block, end_block) = match (first_ref, second_ref) {
         _ if first_ref.starts_with('-') => {
             let end_block = parse_block_number(second_ref, RangePosition::Last, source).await?;
-            let start_block =
-                end_block
-                    .checked_sub(first_ref[1..].parse::<u64>().map_err(|_e| {
-                        ParseError::ParseError("start_block parse error".to_string())
-                    })?)
-                    .ok_or_else(|| ParseError::ParseError("start_block underflow".to_string()))?;
+            let start_block = end_block
+                .checked_sub(parse_block_integer(&first_ref[1..]).ok_or_else(|| {
+                    ParseError::ParseError(format!("invalid block offset: {}", first_ref))
+                })?)
+                .ok_or_else(|| ParseError::ParseError("start_block underflow".to_string()))?;
             (start_block, end_block)
         }
         _ if second_ref.starts_with('+') => {
             let start_block = parse_block_number(first_ref, RangePosition::First, source).await?;
-            let end_block =
-                start_block
-                    .checked_add(second_ref[1..].parse::<u64>().map_err(|_e| {
-                        ParseError::ParseError("start_block parse error".to_string())
-                    })?)
-                    .ok_or_else(|| ParseError::ParseError("end_block underflow".to_string()))?;
+            let end_block = start_block
+                .checked_add(parse_block_integer(&second_ref[1..]).ok_or_else(|| {
+                    ParseError::ParseError(format!("invalid block offset: {}", second_ref))
+                })?)
+                .ok_or_else(|| ParseError::ParseError("end_block underflow".to_string()))?;
             (start_block, end_block)
         }
         _ => {
@@ -321,6 +526,11 @@ async fn parse_block_number(
     range_position: RangePosition,
     source: Arc<Source>,
 ) -> Result<u64, ParseError> {
+    if let Some(date) = cryo_freeze::parse_date(block_ref) {
+        return cryo_freeze::first_block_of_date(date, &source)
+            .await
+            .map_err(|e| ParseError::ParseError(format!("could not resolve block of date: {}", e)))
+    }
     match (block_ref, range_position) {
         ("latest", _) => source.get_block_number().await.map(|n| n.as_u64()).map_err(|_e| {
             ParseError::ParseError("Error retrieving latest block number".to_string())
@@ -332,31 +542,37 @@ async fn parse_block_number(
             })
         }
         ("", RangePosition::None) => Err(ParseError::ParseError("invalid input".to_string())),
+        _ if block_ref.starts_with("0x") | block_ref.starts_with("0X") => {
+            parse_block_integer(block_ref).ok_or_else(|| invalid_block_ref(block_ref))
+        }
         _ if block_ref.ends_with('B') | block_ref.ends_with('b') => {
             let s = &block_ref[..block_ref.len() - 1];
-            s.parse::<f64>()
-                .map(|n| (1e9 * n) as u64)
-                .map_err(|_e| ParseError::ParseError("Error parsing block ref".to_string()))
+            s.parse::<f64>().map(|n| (1e9 * n) as u64).map_err(|_e| invalid_block_ref(block_ref))
         }
         _ if block_ref.ends_with('M') | block_ref.ends_with('m') => {
             let s = &block_ref[..block_ref.len() - 1];
-            s.parse::<f64>()
-                .map(|n| (1e6 * n) as u64)
-                .map_err(|_e| ParseError::ParseError("Error parsing block ref".to_string()))
+            s.parse::<f64>().map(|n| (1e6 * n) as u64).map_err(|_e| invalid_block_ref(block_ref))
         }
         _ if block_ref.ends_with('K') | block_ref.ends_with('k') => {
             let s = &block_ref[..block_ref.len() - 1];
-            s.parse::<f64>()
-                .map(|n| (1e3 * n) as u64)
-                .map_err(|_e| ParseError::ParseError("Error parsing block ref".to_string()))
+            s.parse::<f64>().map(|n| (1e3 * n) as u64).map_err(|_e| invalid_block_ref(block_ref))
         }
-        _ => block_ref
-            .parse::<f64>()
-            .map_err(|_e| ParseError::ParseError("Error parsing block ref".to_string()))
-            .map(|x| x as u64),
+        _ => block_ref.parse::<f64>().map_err(|_e| invalid_block_ref(block_ref)).map(|x| x as u64),
     }
 }
 
+/// parse a plain block number, either decimal or 0x-prefixed hex
+fn parse_block_integer(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse::<u64>().ok(),
+    }
+}
+
+fn invalid_block_ref(block_ref: &str) -> ParseError {
+    ParseError::ParseError(format!("invalid block number: {}", block_ref))
+}
+
 async fn apply_reorg_buffer(
     block_chunks: Vec<BlockChunk>,
     reorg_filter: u64,
@@ -394,6 +610,7 @@ pub(crate) async fn get_latest_block_number(source: Arc<Source>) -> Result<u64,
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_helpers::{make_source, TempDir};
     use ethers::prelude::*;
 
     enum BlockTokenTest<'a> {
@@ -403,17 +620,7 @@ mod tests {
 
     async fn block_token_test_helper(tests: Vec<(BlockTokenTest<'_>, bool)>) {
         let (provider, mock) = Provider::mocked();
-        let source = Source {
-            provider: provider.into(),
-            semaphore: Arc::new(None),
-            rate_limiter: Arc::new(None),
-            chain_id: 1,
-            inner_request_size: 1,
-            max_concurrent_chunks: None,
-            rpc_url: "".to_string(),
-            labels: cryo_freeze::SourceLabels::default(),
-        };
-        let source = Arc::new(source);
+        let source = make_source(provider);
         for (test, res) in tests {
             match test {
                 BlockTokenTest::WithMock((token, expected, latest)) => {
@@ -465,16 +672,7 @@ mod tests {
 
     async fn block_input_test_helper(tests: Vec<(BlockInputTest<'_>, bool)>) {
         let (provider, mock) = Provider::mocked();
-        let source = Arc::new(Source {
-            provider: provider.into(),
-            chain_id: 1,
-            rpc_url: "".to_string(),
-            inner_request_size: 1,
-            semaphore: Arc::new(None),
-            max_concurrent_chunks: Some(1),
-            rate_limiter: Arc::new(None),
-            labels: cryo_freeze::SourceLabels::default(),
-        });
+        let source = make_source(provider);
         for (test, res) in tests {
             match test {
                 BlockInputTest::WithMock((inputs, expected, latest)) => {
@@ -538,17 +736,7 @@ mod tests {
 
     async fn block_number_test_helper(tests: Vec<(BlockNumberTest<'_>, bool)>) {
         let (provider, mock) = Provider::mocked();
-        let source = Source {
-            provider: provider.into(),
-            semaphore: Arc::new(None),
-            rate_limiter: Arc::new(None),
-            chain_id: 1,
-            inner_request_size: 1,
-            max_concurrent_chunks: Some(1),
-            rpc_url: "".to_string(),
-            labels: cryo_freeze::SourceLabels::default(),
-        };
-        let source = Arc::new(source);
+        let source = make_source(provider);
         for (test, res) in tests {
             match test {
                 BlockNumberTest::WithMock((block_ref, range_position, expected, latest)) => {
@@ -603,6 +791,24 @@ mod tests {
             (BlockTokenTest::WithMock((r"1:", BlockChunk::Range(1, 12), 12)), true), /* Implicit latest */
             // Number type
             (BlockTokenTest::WithoutMock((r"1", BlockChunk::Numbers(vec![1]))), true), /* Single block */
+            // Underscores and hex
+            (
+                BlockTokenTest::WithoutMock((r"18_000_000", BlockChunk::Numbers(vec![18000000]))),
+                true,
+            ), /* Underscores */
+            (
+                BlockTokenTest::WithoutMock((r"0x112A880", BlockChunk::Numbers(vec![18000000]))),
+                true,
+            ), /* Hex */
+            (BlockTokenTest::WithoutMock((r"0x10b", BlockChunk::Numbers(vec![267]))), true), /* Hex ending in suffix */
+            (
+                BlockTokenTest::WithoutMock((
+                    r"0x100:18_000_000",
+                    BlockChunk::Range(256, 17999999),
+                )),
+                true,
+            ), /* Mixed */
+            (BlockTokenTest::WithoutMock((r"0x100:+0x10", BlockChunk::Range(256, 271))), true), /* Hex offset */
         ];
         block_token_test_helper(tests).await;
     }
@@ -665,7 +871,214 @@ mod tests {
             (BlockNumberTest::WithoutMock((r"1b", RangePosition::None, 1000000000)), true), // b
             (BlockNumberTest::WithoutMock((r"1m", RangePosition::None, 1000000)), true), // m
             (BlockNumberTest::WithoutMock((r"1k", RangePosition::None, 1000)), true), // k
+            (BlockNumberTest::WithoutMock((r"0x112a880", RangePosition::None, 18000000)), true), /* hex */
         ];
         block_number_test_helper(tests).await;
     }
+
+    #[tokio::test]
+    async fn block_token_parsing_errors() {
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        for (token, offending) in
+            [("0xZZ", "0xZZ"), ("0x100:12abc", "12abc"), ("0x", "0x"), ("10:+0xG", "+0xG")]
+        {
+            let Err(ParseError::ParseError(message)) =
+                parse_block_token(token, true, source.clone()).await
+            else {
+                panic!("{} should not parse", token)
+            };
+            assert!(message.ends_with(offending), "{}: {}", token, message);
+        }
+    }
+
+    fn chunk_bounds(chunks: &[BlockChunk]) -> Vec<(u64, u64)> {
+        chunks.iter().map(|c| (c.min_value().unwrap(), c.max_value().unwrap())).collect()
+    }
+
+    #[tokio::test]
+    async fn block_chunk_alignment() {
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let block_chunks = vec![BlockChunk::Range(18_000_123, 18_002_999)];
+
+        // default chunks are offset by the start block
+        let args = Args { chunk_size: 1000, ..Default::default() };
+        let chunks =
+            postprocess_block_chunks(block_chunks.clone(), &args, source.clone()).await.unwrap();
+        assert_eq!(
+            chunk_bounds(&chunks),
+            vec![(18_000_123, 18_001_122), (18_001_123, 18_002_122), (18_002_123, 18_002_999)]
+        );
+
+        // aligned chunks snap the leading chunk to the previous multiple of the chunk size
+        let args = Args { chunk_size: 1000, align: true, ..Default::default() };
+        let chunks = postprocess_block_chunks(block_chunks, &args, source.clone()).await.unwrap();
+        assert_eq!(
+            chunk_bounds(&chunks),
+            vec![(18_000_000, 18_000_999), (18_001_000, 18_001_999), (18_002_000, 18_002_999)]
+        );
+
+        // trailing partial chunks are dropped
+        let block_chunks = vec![BlockChunk::Range(18_000_123, 18_002_500)];
+        let chunks = postprocess_block_chunks(block_chunks, &args, source).await.unwrap();
+        assert_eq!(chunk_bounds(&chunks), vec![(18_000_000, 18_000_999), (18_001_000, 18_001_999)]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_overlapping_ranges() {
+        let block_chunks = vec![
+            BlockChunk::Range(5000, 5100),
+            BlockChunk::Range(100, 200),
+            BlockChunk::Range(99000, 99050),
+            BlockChunk::Range(150, 250),
+            BlockChunk::Numbers(vec![7, 8]),
+        ];
+        let (block_chunks, n_merged) = merge_overlapping_ranges(block_chunks);
+        assert_eq!(n_merged, 1);
+        let values: Vec<(Option<u64>, Option<u64>, u64)> =
+            block_chunks.iter().map(|c| (c.min_value(), c.max_value(), c.size())).collect();
+        assert_eq!(
+            values,
+            vec![
+                (Some(100), Some(250), 151),
+                (Some(5000), Some(5100), 101),
+                (Some(99000), Some(99050), 51),
+                (Some(7), Some(8), 2),
+            ]
+        );
+
+        // each range is chunked on its own
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let args = Args { chunk_size: 100, ..Default::default() };
+        let chunks = postprocess_block_chunks(block_chunks, &args, source).await.unwrap();
+        assert_eq!(
+            chunk_bounds(&chunks),
+            vec![(100, 199), (200, 250), (5000, 5099), (5100, 5100), (99000, 99050), (7, 8)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_step_keeps_chunk_sizes() {
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let block_chunks = vec![BlockChunk::Range(1_050, 3_449)];
+        let args = Args { chunk_size: 1000, step: Some(100), ..Default::default() };
+        let chunks = postprocess_block_chunks(block_chunks, &args, source.clone()).await.unwrap();
+
+        let values: Vec<Vec<u64>> = chunks.iter().map(|chunk| chunk.values()).collect();
+        assert_eq!(
+            values,
+            vec![
+                (1_050..2_050).step_by(100).collect::<Vec<_>>(),
+                (2_050..3_050).step_by(100).collect(),
+                (3_050..3_450).step_by(100).collect(),
+            ]
+        );
+        assert_eq!(values[0].len(), 10);
+        assert_eq!(chunks[0].stub().unwrap(), "00001050_to_00001950");
+
+        // lists of block numbers are not stepped
+        let block_numbers = vec![BlockChunk::Numbers(vec![5_000, 6_000, 7_000])];
+        assert!(postprocess_block_chunks(block_numbers, &args, source).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_min_chunk_size_merges_trailing_chunk() {
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let block_chunks = vec![BlockChunk::Range(0, 2_499)];
+        let args = Args { chunk_size: 1000, min_chunk_size: Some(600), ..Default::default() };
+        let chunks = postprocess_block_chunks(block_chunks.clone(), &args, source.clone()).await;
+        let sizes: Vec<u64> = chunks.unwrap().iter().map(|chunk| chunk.size()).collect();
+        assert_eq!(sizes, vec![1000, 1500]);
+
+        // trailing chunks at least min_chunk_size are kept
+        let args = Args { chunk_size: 1000, min_chunk_size: Some(500), ..Default::default() };
+        let chunks = postprocess_block_chunks(block_chunks, &args, source).await.unwrap();
+        let sizes: Vec<u64> = chunks.iter().map(|chunk| chunk.size()).collect();
+        assert_eq!(sizes, vec![1000, 1000, 500]);
+        assert_eq!(chunks[2].stub().unwrap(), "00002000_to_00002499");
+    }
+
+    #[tokio::test]
+    async fn test_skip_blocks() {
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let dir = TempDir::new("skip_blocks");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("skip_blocks.txt");
+        std::fs::write(&path, "2099\n\n1500\n").unwrap();
+        let args = Args {
+            chunk_size: 1000,
+            skip_blocks: Some(vec![3_000, 150]),
+            skip_blocks_file: Some(path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let block_chunks = vec![BlockChunk::Range(100, 2_099), BlockChunk::Range(2_100, 2_199)];
+        let chunks = postprocess_block_chunks(block_chunks, &args, source).await.unwrap();
+
+        let values: Vec<Vec<u64>> = chunks.iter().map(|chunk| chunk.values()).collect();
+        let expected = |range: std::ops::Range<u64>, skipped: &[u64]| {
+            range.filter(|x| !skipped.contains(x)).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            values,
+            vec![
+                expected(100..1_100, &[150]),
+                expected(1_100..2_100, &[1_500, 2_099]),
+                expected(2_100..2_200, &[]),
+            ]
+        );
+        assert_eq!(chunks[1].stub().unwrap(), "00001100_to_00002098");
+        assert!(matches!(chunks[2], BlockChunk::Range(2_100, 2_199)));
+
+        // every skipped block is listed in the summary, whether or not it was requested
+        let skipped: Vec<u64> = parse_skip_blocks(&args).unwrap().into_iter().collect();
+        assert_eq!(skipped, vec![150, 1_500, 2_099, 3_000]);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_output() {
+        use clap_cryo::Parser;
+        use cryo_freeze::ExecutionEnvBuilder;
+
+        let output_dir = TempDir::new("resume");
+        let output_dir_str = output_dir.to_string_lossy().to_string();
+        let parse = |extra: &[&str]| {
+            let mut args = vec!["cryo", "blocks", "--output-dir", output_dir_str.as_str()];
+            args.extend(["--no-verbose", "--no-report"]);
+            args.extend(extra);
+            Args::parse_from(args)
+        };
+
+        // collect blocks 100 and 101 into the output dir
+        let (provider, mock) = Provider::mocked();
+        for number in [101u64, 100] {
+            mock.push(Block::<TxHash> { number: Some(number.into()), ..Default::default() })
+                .unwrap();
+        }
+        let source = make_source(provider);
+        let args = parse(&["--blocks", "100:102"]);
+        let query = crate::parse::parse_query(&args, source.clone()).await.unwrap();
+        let sink =
+            crate::parse::file_output::parse_file_output(&args, &source, &query.schemas).unwrap();
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+        let summary = cryo_freeze::freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+
+        // resuming drops the collected blocks from the chunks of a wider range
+        let args = parse(&["--chunk-size", "50", "--resume-from-output", "--quiet"]);
+        let block_chunks = vec![BlockChunk::Range(100, 199)];
+        let chunks = postprocess_block_chunks(block_chunks.clone(), &args, source.clone()).await;
+        assert_eq!(chunk_bounds(&chunks.unwrap()), vec![(102, 149), (150, 199)]);
+        let block_numbers = vec![BlockChunk::Numbers(vec![100, 101, 102])];
+        let chunks = postprocess_block_chunks(block_numbers, &args, source.clone()).await;
+        assert_eq!(chunks.unwrap()[0].values(), vec![102]);
+
+        // block ranges are only read from parquet files
+        let args = parse(&["--resume-from-output", "--csv"]);
+        assert!(postprocess_block_chunks(block_chunks, &args, source).await.is_err());
+    }
 }
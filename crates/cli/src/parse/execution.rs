@@ -1,11 +1,15 @@
 use crate::args::Args;
-use cryo_freeze::{ExecutionEnv, ExecutionEnvBuilder, ParseError};
+use cryo_freeze::{Checkpoint, ExecutionEnv, ExecutionEnvBuilder, ParseError};
+use std::time::Duration;
 
 pub(crate) fn parse_execution_env(args: &Args, n_tasks: u64) -> Result<ExecutionEnv, ParseError> {
     let args_str =
         serde_json::to_string(args).map_err(|e| ParseError::ParseError(e.to_string()))?;
 
-    let verbose = match (args.no_verbose, args.verbose) {
+    if args.quiet && args.verbose {
+        return Err(ParseError::ParseError("--quiet cannot be used with --verbose".to_string()))
+    }
+    let verbose = match (args.no_verbose || args.quiet, args.verbose) {
         (true, true) => return Err(ParseError::ParseError("".to_string())),
         (true, false) => 0,
         (false, true) => 2, // future: allow arbitrary numbers
@@ -17,9 +21,13 @@ pub(crate) fn parse_execution_env(args: &Args, n_tasks: u64) -> Result<Execution
         .verbose(verbose)
         .report(!args.no_report)
         .report_dir(args.report_dir.clone())
+        .checkpoint(parse_checkpoint(args)?)
+        .print_schema(args.print_schema)
+        .limit(args.limit)
+        .chunk_timeout(parse_chunk_timeout(args)?)
         .args(args_str);
 
-    let builder = if !args.no_verbose {
+    let builder = if !args.no_verbose && !args.quiet {
         builder
             .bar(n_tasks)
             .map_err(|_| ParseError::ParseError("could not create progress bar".to_string()))?
@@ -29,3 +37,25 @@ pub(crate) fn parse_execution_env(args: &Args, n_tasks: u64) -> Result<Execution
 
     Ok(builder.build())
 }
+
+fn parse_chunk_timeout(args: &Args) -> Result<Option<Duration>, ParseError> {
+    match args.chunk_timeout_ms {
+        Some(0) => Err(ParseError::ParseError("--chunk-timeout-ms must be positive".to_string())),
+        Some(ms) => Ok(Some(Duration::from_millis(ms))),
+        None => Ok(None),
+    }
+}
+
+fn parse_checkpoint(args: &Args) -> Result<Option<Checkpoint>, ParseError> {
+    let Some(raw) = &args.checkpoint_every else { return Ok(None) };
+    let parse_n = |n: &str| {
+        n.parse::<u64>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| ParseError::ParseError(format!("invalid --checkpoint-every: {}", raw)))
+    };
+    match raw.strip_suffix('s') {
+        Some(seconds) => Ok(Some(Checkpoint::Seconds(parse_n(seconds)?))),
+        None => Ok(Some(Checkpoint::Blocks(parse_n(raw)?))),
+    }
+}
@@ -1,23 +1,35 @@
 use crate::args::Args;
-use cryo_freeze::{FileFormat, FileOutput, ParseError, Source, SubDir};
+use cryo_freeze::{
+    create_output_dir, network_name, Aggregation, Datatype, FileFormat, FileOutput, ParseError,
+    Source, SubDir, Table, DECIMAL128_MAX_PRECISION,
+};
 use polars::prelude::*;
-use std::fs;
+use std::{collections::HashMap, path::Path, str::FromStr};
 
-pub(crate) fn parse_file_output(args: &Args, source: &Source) -> Result<FileOutput, ParseError> {
+pub(crate) fn parse_file_output(
+    args: &Args,
+    source: &Source,
+    schemas: &HashMap<Datatype, Table>,
+) -> Result<FileOutput, ParseError> {
     // process output directory
-    std::fs::create_dir_all(args.output_dir.clone())
-        .map_err(|_| ParseError::ParseError("could not create dir".to_string()))?;
-    let output_dir = std::fs::canonicalize(args.output_dir.clone()).map_err(|_e| {
+    let dir_mode = parse_dir_mode(args)?;
+    let output_dir = if args.dry && !Path::new(&args.output_dir).exists() {
+        // dry runs do not create the output directory
+        std::path::absolute(&args.output_dir)
+    } else {
+        create_output_dir(Path::new(&args.output_dir), dir_mode)?;
+        std::fs::canonicalize(args.output_dir.clone())
+    };
+    let output_dir = output_dir.map_err(|_e| {
         ParseError::ParseError("Failed to canonicalize output directory".to_string())
     })?;
-    match fs::create_dir_all(&output_dir) {
-        Ok(_) => {}
-        Err(e) => return Err(ParseError::ParseError(format!("Error creating directory: {}", e))),
-    };
 
     let label = &args.label;
 
     let parquet_compression = parse_compression(&args.compression)?;
+    let column_compression = parse_column_compression(args)?;
+    let dictionary_encoding = parse_dictionary_encoding(args)?;
+    let decimal_types = parse_decimal_types(args)?;
 
     let row_group_size = parse_row_group_size(
         args.row_group_size,
@@ -26,9 +38,101 @@ pub(crate) fn parse_file_output(args: &Args, source: &Source) -> Result<FileOutp
     );
 
     let format = parse_output_format(args)?;
+    if args.single_file && (format != FileFormat::Parquet) {
+        return Err(ParseError::ParseError("--single-file requires parquet output".to_string()))
+    }
+    if !column_compression.is_empty() && (format != FileFormat::Parquet) {
+        return Err(ParseError::ParseError(
+            "--column-compression requires parquet output".to_string(),
+        ))
+    }
+    if !dictionary_encoding.is_empty() && (format != FileFormat::Parquet) {
+        return Err(ParseError::ParseError(
+            "--dictionary-columns and --no-dictionary-columns require parquet output".to_string(),
+        ))
+    }
+    if !decimal_types.is_empty() && (format != FileFormat::Parquet) {
+        return Err(ParseError::ParseError("--decimal-scale requires parquet output".to_string()))
+    }
+    if args.fixed_size_hex && (format != FileFormat::Parquet) {
+        return Err(ParseError::ParseError("--fixed-size-hex requires parquet output".to_string()))
+    }
+    let fixed_size_binary = match args.fixed_size_hex {
+        true => parse_fixed_size_binary(schemas)?,
+        false => HashMap::new(),
+    };
+    // binary columns of csv and json files are always hex
+    if args.checksum_addresses && !args.hex && (format == FileFormat::Parquet) {
+        return Err(ParseError::ParseError(
+            "--checksum-addresses requires --hex for parquet output".to_string(),
+        ))
+    }
+    if args.csv_types && (format != FileFormat::Csv) {
+        return Err(ParseError::ParseError("--csv-types requires csv output".to_string()))
+    }
+    if args.no_json_bigints_as_strings && (format != FileFormat::Json) {
+        return Err(ParseError::ParseError(
+            "--no-json-bigints-as-strings requires json output".to_string(),
+        ))
+    }
+    let target_file_size = parse_target_file_size(args)?;
+    if args.single_file && target_file_size.is_some() {
+        return Err(ParseError::ParseError(
+            "--target-file-size cannot be used with --single-file".to_string(),
+        ))
+    }
+    let aggregates = parse_aggregates(args)?;
+    if args.single_file && !aggregates.is_empty() {
+        return Err(ParseError::ParseError(
+            "--aggregate cannot be used with --single-file".to_string(),
+        ))
+    }
+    if args.single_file && args.optimize_types {
+        return Err(ParseError::ParseError(
+            "--optimize-types cannot be used with --single-file".to_string(),
+        ))
+    }
+    if args.single_file && args.partition_by_date {
+        return Err(ParseError::ParseError(
+            "--partition-by-date cannot be used with --single-file".to_string(),
+        ))
+    }
+    if args.partition_by_column.is_some() && (args.single_file || args.partition_by_date) {
+        return Err(ParseError::ParseError(
+            "--partition-by-column cannot be used with --single-file or --partition-by-date"
+                .to_string(),
+        ))
+    }
+    if args.split_logs_by_topic0 {
+        if args.single_file || args.partition_by_date || args.partition_by_column.is_some() {
+            return Err(ParseError::ParseError(
+                "--split-logs-by-topic0 cannot be used with --single-file, --partition-by-date, or --partition-by-column"
+                    .to_string(),
+            ))
+        }
+        if schemas.keys().any(|datatype| *datatype != Datatype::Logs) {
+            return Err(ParseError::ParseError(
+                "--split-logs-by-topic0 can only be used when collecting logs alone".to_string(),
+            ))
+        }
+    }
+    if args.streaming {
+        let conflicts = [
+            ("--single-file", args.single_file),
+            ("--aggregate", !aggregates.is_empty()),
+            ("--optimize-types", args.optimize_types),
+            ("--partition-by-date", args.partition_by_date),
+        ];
+        if let Some((flag, _)) = conflicts.iter().find(|(_, is_set)| *is_set) {
+            return Err(ParseError::ParseError(format!("--streaming cannot be used with {}", flag)))
+        }
+    }
     let file_prefix = parse_network_name(args, source.chain_id);
 
     let subdirs = parse_subdirs(args);
+    if args.delta {
+        check_delta(args, &format, &subdirs, schemas)?;
+    }
 
     let output = FileOutput {
         output_dir,
@@ -39,12 +143,110 @@ pub(crate) fn parse_file_output(args: &Args, source: &Source) -> Result<FileOutp
         format,
         suffix: label.clone(),
         parquet_compression,
+        column_compression,
+        dictionary_encoding,
+        decimal_types,
         row_group_size,
+        single_file: args.single_file,
+        ordered: args.ordered.unwrap_or(args.single_file),
+        aggregates,
+        timestamp_range: !args.no_timestamp_range,
+        optimize_types: args.optimize_types,
+        partition_by_date: args.partition_by_date,
+        partition_by_column: match args.split_logs_by_topic0 {
+            true => Some("topic0".to_string()),
+            false => args.partition_by_column.clone(),
+        },
+        max_open_partitions: args.max_open_partitions,
+        streaming: args.streaming,
+        dir_mode,
+        csv_types: args.csv_types,
+        json_bigints_as_strings: !args.no_json_bigints_as_strings,
+        target_file_size,
+        clean_empty_dirs: args.clean_empty_dirs,
+        fixed_size_binary,
+        delta: args.delta,
+        partition_key_in_filename: args.split_logs_by_topic0,
+        checksum_addresses: args.checksum_addresses,
+        write_index: args.write_index,
     };
 
     Ok(output)
 }
 
+/// byte widths of the fixed size columns of the query, which must agree across datatypes
+fn parse_fixed_size_binary(
+    schemas: &HashMap<Datatype, Table>,
+) -> Result<HashMap<String, usize>, ParseError> {
+    let mut widths: HashMap<String, usize> = HashMap::new();
+    for (datatype, schema) in schemas.iter() {
+        for (column, width) in datatype.column_widths() {
+            if !schema.has_column(column) {
+                continue
+            }
+            match widths.insert(column.to_string(), width) {
+                Some(other) if other != width => {
+                    return Err(ParseError::ParseError(format!(
+                        "column {} has widths {} and {} in different datatypes",
+                        column, other, width
+                    )))
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(widths)
+}
+
+fn parse_dir_mode(args: &Args) -> Result<Option<u32>, ParseError> {
+    let Some(raw_mode) = &args.dir_mode else { return Ok(None) };
+    if !cfg!(unix) {
+        return Err(ParseError::ParseError("--dir-mode is only supported on unix".to_string()))
+    }
+    match u32::from_str_radix(raw_mode, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(Some(mode)),
+        _ => {
+            Err(ParseError::ParseError(format!("invalid --dir-mode, expected octal: {}", raw_mode)))
+        }
+    }
+}
+
+fn parse_aggregates(args: &Args) -> Result<Vec<Aggregation>, ParseError> {
+    let mut aggregates = Vec::new();
+    for arg in args.aggregate.iter().flatten() {
+        for name in arg.split(',').filter(|name| !name.is_empty()) {
+            let aggregate = Aggregation::from_str(name)?;
+            if !aggregates.contains(&aggregate) {
+                aggregates.push(aggregate)
+            }
+        }
+    }
+    Ok(aggregates)
+}
+
+/// delta tables hold parquet files of a single schema, so each datatype needs its own directory
+fn check_delta(
+    args: &Args,
+    format: &FileFormat,
+    subdirs: &[SubDir],
+    schemas: &HashMap<Datatype, Table>,
+) -> Result<(), ParseError> {
+    if *format != FileFormat::Parquet {
+        return Err(ParseError::ParseError("--delta requires parquet output".to_string()))
+    }
+    if args.single_file || args.streaming {
+        return Err(ParseError::ParseError(
+            "--delta cannot be used with --single-file or --streaming".to_string(),
+        ))
+    }
+    if schemas.len() > 1 && !subdirs.iter().any(|subdir| matches!(subdir, SubDir::Datatype)) {
+        return Err(ParseError::ParseError(
+            "--delta with several datatypes requires --subdirs datatype".to_string(),
+        ))
+    }
+    Ok(())
+}
+
 pub(crate) fn parse_subdirs(args: &Args) -> Vec<SubDir> {
     let mut subdirs = Vec::new();
     for arg in args.subdirs.iter() {
@@ -62,29 +264,7 @@ pub(crate) fn parse_subdirs(args: &Args) -> Vec<SubDir> {
 pub(crate) fn parse_network_name(args: &Args, chain_id: u64) -> String {
     match &args.network_name {
         Some(name) => name.clone(),
-        None => match chain_id {
-            1 => "ethereum".to_string(),
-            5 => "goerli".to_string(),
-            10 => "optimism".to_string(),
-            56 => "bnb".to_string(),
-            69 => "optimism_kovan".to_string(),
-            100 => "gnosis".to_string(),
-            137 => "polygon".to_string(),
-            420 => "optimism_goerli".to_string(),
-            1101 => "polygon_zkevm".to_string(),
-            1442 => "polygon_zkevm_testnet".to_string(),
-            8453 => "base".to_string(),
-            10200 => "gnosis_chidao".to_string(),
-            17000 => "holesky".to_string(),
-            42161 => "arbitrum".to_string(),
-            42170 => "arbitrum_nova".to_string(),
-            43114 => "avalanche".to_string(),
-            80001 => "polygon_mumbai".to_string(),
-            84531 => "base_goerli".to_string(),
-            7777777 => "zora".to_string(),
-            11155111 => "sepolia".to_string(),
-            chain_id => "network_".to_string() + chain_id.to_string().as_str(),
-        },
+        None => network_name(chain_id),
     }
 }
 
@@ -135,6 +315,96 @@ fn parse_compression(input: &Vec<String>) -> Result<ParquetCompression, ParseErr
     }
 }
 
+fn parse_column_compression(
+    args: &Args,
+) -> Result<HashMap<String, ParquetCompression>, ParseError> {
+    let mut column_compression = HashMap::new();
+    for arg in args.column_compression.iter().flatten() {
+        for entry in arg.split(',').filter(|entry| !entry.is_empty()) {
+            let Some((column, compression)) = entry.split_once('=') else {
+                return Err(ParseError::ParseError(format!(
+                    "invalid --column-compression, expected COLUMN=COMPRESSION: {}",
+                    entry
+                )))
+            };
+            let compression: Vec<String> = compression.split(':').map(String::from).collect();
+            column_compression.insert(column.to_string(), parse_compression(&compression)?);
+        }
+    }
+    Ok(column_compression)
+}
+
+fn parse_dictionary_encoding(args: &Args) -> Result<HashMap<String, bool>, ParseError> {
+    let mut dictionary_encoding = HashMap::new();
+    for (columns, enabled) in
+        [(&args.dictionary_columns, true), (&args.no_dictionary_columns, false)]
+    {
+        for arg in columns.iter().flatten() {
+            for column in arg.split(',').filter(|column| !column.is_empty()) {
+                if dictionary_encoding.insert(column.to_string(), enabled) == Some(!enabled) {
+                    return Err(ParseError::ParseError(format!(
+                        "{} is in both --dictionary-columns and --no-dictionary-columns",
+                        column
+                    )))
+                }
+            }
+        }
+    }
+    Ok(dictionary_encoding)
+}
+
+fn parse_decimal_types(args: &Args) -> Result<HashMap<String, (usize, usize)>, ParseError> {
+    let mut decimal_types = HashMap::new();
+    for arg in args.decimal_scale.iter().flatten() {
+        for entry in arg.split(',').filter(|entry| !entry.is_empty()) {
+            let invalid = || {
+                ParseError::ParseError(format!(
+                    "invalid --decimal-scale, expected COLUMN=SCALE[:PRECISION]: {}",
+                    entry
+                ))
+            };
+            let (column, decimal_type) = entry.split_once('=').ok_or_else(invalid)?;
+            let (scale, precision) = match decimal_type.split_once(':') {
+                Some((scale, precision)) => (scale, precision),
+                None => (decimal_type, "38"),
+            };
+            let scale: usize = scale.parse().map_err(|_| invalid())?;
+            let precision: usize = precision.parse().map_err(|_| invalid())?;
+            if precision == 0 || precision > DECIMAL128_MAX_PRECISION {
+                return Err(ParseError::ParseError(format!(
+                    "decimal precision of {} must be between 1 and {}",
+                    column, DECIMAL128_MAX_PRECISION
+                )))
+            }
+            if scale > precision {
+                return Err(ParseError::ParseError(format!(
+                    "decimal scale of {} must be at most its precision {}",
+                    column, precision
+                )))
+            }
+            decimal_types.insert(column.to_string(), (precision, scale));
+        }
+    }
+    Ok(decimal_types)
+}
+
+fn parse_target_file_size(args: &Args) -> Result<Option<u64>, ParseError> {
+    let Some(raw) = &args.target_file_size else { return Ok(None) };
+    let upper = raw.to_uppercase();
+    let units = [("GB", 1_000_000_000), ("MB", 1_000_000), ("KB", 1_000), ("B", 1)];
+    let (digits, multiplier) = units
+        .iter()
+        .find_map(|(unit, multiplier)| upper.strip_suffix(unit).map(|n| (n, *multiplier)))
+        .unwrap_or((upper.as_str(), 1));
+    match digits.trim().parse::<u64>() {
+        Ok(n) if n > 0 => Ok(Some(n * multiplier)),
+        _ => Err(ParseError::ParseError(format!(
+            "invalid --target-file-size, expected a size such as 128MB: {}",
+            raw
+        ))),
+    }
+}
+
 fn parse_row_group_size(
     row_group_size: Option<usize>,
     n_row_groups: Option<usize>,
@@ -146,3 +416,103 @@ fn parse_row_group_size(
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_source, TempDir};
+
+    #[test]
+    fn test_parse_network_name() {
+        let args = Args::default();
+        assert_eq!(parse_network_name(&args, 1), "ethereum");
+        assert_eq!(parse_network_name(&args, 11155111), "sepolia");
+        assert_eq!(parse_network_name(&args, 987654), "chain-987654");
+
+        let args = Args { network_name: Some("devnet".to_string()), ..Default::default() };
+        assert_eq!(parse_network_name(&args, 1), "devnet");
+    }
+
+    #[test]
+    fn test_parse_decimal_types() {
+        let decimal_scale = Some(vec!["value=18,gas_price=9:20".to_string()]);
+        let args = Args { decimal_scale, ..Default::default() };
+        let decimal_types = parse_decimal_types(&args).unwrap();
+        assert_eq!(decimal_types.get("value"), Some(&(38, 18)));
+        assert_eq!(decimal_types.get("gas_price"), Some(&(20, 9)));
+
+        for invalid in ["value=18:39", "value=18:10", "value", "value=x"] {
+            let args =
+                Args { decimal_scale: Some(vec![invalid.to_string()]), ..Default::default() };
+            assert!(parse_decimal_types(&args).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_dry_run_does_not_create_output_dir() {
+        use clap_cryo::Parser;
+        use ethers::prelude::*;
+
+        let root = TempDir::new("dry_output");
+        let output_dir = root.join("data");
+        let output_dir_str = output_dir.to_string_lossy().to_string();
+        let args = Args::parse_from(vec![
+            "cryo",
+            "blocks",
+            "--output-dir",
+            output_dir_str.as_str(),
+            "--dry",
+        ]);
+        let source = make_source(Provider::mocked().0);
+        let sink = parse_file_output(&args, &source, &HashMap::new()).unwrap();
+        assert_eq!(sink.output_dir, output_dir);
+        assert!(!root.exists());
+    }
+
+    #[tokio::test]
+    async fn test_subdirs_flag_places_each_datatype_in_its_directory() {
+        use clap_cryo::Parser;
+        use cryo_freeze::ExecutionEnvBuilder;
+        use ethers::prelude::*;
+
+        let (provider, mock) = Provider::mocked();
+        // responses are popped from the back, blocks are collected before logs, and the block is
+        // fetched again for the timestamp range of the logs file
+        let block = Block::<TxHash> { number: Some(100.into()), ..Default::default() };
+        mock.push(block.clone()).unwrap();
+        mock.push::<Vec<Log>, _>(vec![]).unwrap();
+        mock.push(block).unwrap();
+        let source = make_source(provider);
+        let output_dir = TempDir::new("subdirs");
+        let output_dir_str = output_dir.to_string_lossy().to_string();
+        let args = Args::parse_from(vec![
+            "cryo",
+            "blocks",
+            "logs",
+            "--blocks",
+            "100",
+            "--output-dir",
+            output_dir_str.as_str(),
+            "--subdirs",
+            "--no-verbose",
+            "--no-report",
+        ]);
+        assert_eq!(args.subdirs, vec!["datatype".to_string()]);
+        let query = crate::parse::parse_query(&args, source.clone()).await.unwrap();
+        let sink = parse_file_output(&args, &source, &query.schemas).unwrap();
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = cryo_freeze::freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert!(summary.errored.is_empty());
+        for datatype in ["blocks", "logs"] {
+            let filename = format!("ethereum__{}__00000100_to_00000100.parquet", datatype);
+            assert!(output_dir.join(datatype).join(filename).exists());
+        }
+        let mut entries: Vec<String> = std::fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec!["blocks", "logs"]);
+    }
+}
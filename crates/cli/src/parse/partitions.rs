@@ -5,8 +5,8 @@ use super::{
 };
 use crate::args::Args;
 use cryo_freeze::{
-    AddressChunk, CallDataChunk, Datatype, Dim, ParseError, Partition, PartitionLabels, SlotChunk,
-    Source, Table, TimeDimension, TopicChunk, TransactionChunk,
+    AddressChunk, BlockChunk, CallDataChunk, Datatype, Dim, ParseError, Partition, PartitionLabels,
+    SlotChunk, Source, Table, TimeDimension, TopicChunk, TransactionChunk,
 };
 use ethers::prelude::*;
 use rand::{seq::SliceRandom, thread_rng};
@@ -23,7 +23,10 @@ pub(crate) async fn parse_partitions(
     // TODO: map from args to dim is not exhaustive
 
     // parse chunk data
-    let (block_number_labels, block_numbers) = blocks::parse_blocks(args, source.clone()).await?;
+    let (block_number_labels, block_numbers) = match args.at_block {
+        Some(block) => parse_at_block(args, block, schemas)?,
+        None => blocks::parse_blocks(args, source.clone()).await?,
+    };
     let (block_number_labels, block_numbers) = if block_numbers.is_none() {
         timestamps::parse_timestamps(args, source.clone()).await?
     } else {
@@ -126,6 +129,29 @@ pub(crate) async fn parse_partitions(
     Ok((partitions, partition_by, time_dimension))
 }
 
+/// pin the state block of eth_calls and slots, the block becomes a single unlabeled chunk so that
+/// partitions are split over the other dimensions, e.g. addresses and slots
+fn parse_at_block(
+    args: &Args,
+    block: u64,
+    schemas: &HashMap<Datatype, Table>,
+) -> Result<(Option<ChunkLabels>, Option<Vec<BlockChunk>>), ParseError> {
+    if args.blocks.is_some() || args.timestamps.is_some() || args.txs.is_some() {
+        return Err(ParseError::ParseError(
+            "--at-block cannot be combined with --blocks, --timestamps, or --txs".to_string(),
+        ))
+    }
+    for datatype in schemas.keys() {
+        if !matches!(datatype, Datatype::EthCalls | Datatype::Slots) {
+            return Err(ParseError::ParseError(format!(
+                "--at-block only applies to eth_calls and slots, not {}",
+                datatype.name()
+            )))
+        }
+    }
+    Ok((None, Some(vec![BlockChunk::Numbers(vec![block])])))
+}
+
 fn parse_time_dimension(partition: &Partition) -> TimeDimension {
     if partition.transactions.is_some() {
         TimeDimension::Transactions
@@ -229,3 +255,58 @@ fn parse_topic(
         Ok((None, None))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse::schemas::parse_schemas, test_helpers::make_source};
+
+    #[tokio::test]
+    async fn test_at_block_reads_slots_at_one_block() {
+        let slots: Vec<String> = (1..=3).map(|slot| format!("0x{:064x}", slot)).collect();
+        let args = Args {
+            datatype: vec!["slots".to_string()],
+            at_block: Some(18_000_000),
+            address: Some(vec![format!("0x{}", "11".repeat(20))]),
+            slot: Some(slots.clone()),
+            ..Default::default()
+        };
+        let (_, schemas) = parse_schemas(&args).unwrap();
+        let (partitions, partition_by, _) =
+            parse_partitions(&args, make_source(Provider::mocked().0), &schemas).await.unwrap();
+        assert_eq!(partition_by, vec![Dim::BlockNumber]);
+        assert_eq!(partitions.len(), 1);
+
+        let params = partitions[0].param_sets(None).unwrap();
+        assert_eq!(params.len(), slots.len());
+        assert!(params.iter().all(|p| p.block_number == Some(18_000_000)));
+        let mut read: Vec<String> =
+            params.iter().map(|p| format!("0x{}", hex::encode(p.slot.clone().unwrap()))).collect();
+        read.sort();
+        assert_eq!(read, slots);
+    }
+
+    #[tokio::test]
+    async fn test_at_block_rejects_block_ranges_and_other_datatypes() {
+        let args = Args {
+            datatype: vec!["slots".to_string()],
+            at_block: Some(100),
+            blocks: Some(vec!["100:200".to_string()]),
+            ..Default::default()
+        };
+        let (_, schemas) = parse_schemas(&args).unwrap();
+        assert!(parse_partitions(&args, make_source(Provider::mocked().0), &schemas)
+            .await
+            .is_err());
+
+        let args = Args {
+            datatype: vec!["blocks".to_string()],
+            at_block: Some(100),
+            ..Default::default()
+        };
+        let (_, schemas) = parse_schemas(&args).unwrap();
+        assert!(parse_partitions(&args, make_source(Provider::mocked().0), &schemas)
+            .await
+            .is_err());
+    }
+}
@@ -1,6 +1,10 @@
 use super::{parse_schemas, partitions};
 use crate::args::Args;
-use cryo_freeze::{Dim, ParseError, Query, QueryLabels, Schemas, Source};
+use cryo_freeze::{
+    Dim, LogsBackend, ParseError, Query, QueryLabels, Schemas, Source, TraceBackend,
+    TraceGranularity,
+};
+use ethers::types::Filter;
 use std::sync::Arc;
 
 /// parse Query struct from cli Args
@@ -15,7 +19,19 @@ pub async fn parse_query(args: &Args, source: Arc<Source>) -> Result<Query, Pars
     let (partitions, partitioned_by, time_dimension) =
         partitions::parse_partitions(args, source, &schemas).await?;
     let datatypes = cryo_freeze::cluster_datatypes(datatypes);
-    let labels = QueryLabels { align: args.align, reorg_buffer: args.reorg_buffer };
+    let trace_granularity = parse_trace_granularity(args)?;
+    let trace_backend = parse_trace_backend(args)?;
+    let logs_backend = parse_logs_backend(args)?;
+    let log_filter = parse_log_filter(args)?;
+    let blocks_log_filter = match &args.blocks_with_logs {
+        Some(input) => Some(parse_filter_json(input, "--blocks-with-logs")?),
+        None => None,
+    };
+    let labels = QueryLabels {
+        align: args.align,
+        reorg_buffer: args.reorg_buffer,
+        skipped_blocks: super::blocks::parse_skip_blocks(args)?.into_iter().collect(),
+    };
     Ok(Query {
         datatypes,
         schemas,
@@ -24,10 +40,124 @@ pub async fn parse_query(args: &Args, source: Arc<Source>) -> Result<Query, Pars
         partitioned_by,
         exclude_failed: args.exclude_failed,
         js_tracer: args.js_tracer.clone(),
+        log_filter,
+        blocks_log_filter,
+        deduplicate_logs: args.deduplicate_logs_on_reorg,
+        exclude_zero_value: args.exclude_zero_value,
+        trace_granularity,
+        trace_backend,
+        logs_backend,
+        genesis_path: args.genesis.clone(),
         labels,
     })
 }
 
+fn parse_trace_granularity(args: &Args) -> Result<TraceGranularity, ParseError> {
+    match args.trace_granularity.as_deref() {
+        None | Some("block") => Ok(TraceGranularity::Block),
+        Some("transaction") => Ok(TraceGranularity::Transaction),
+        Some(_) => Err(ParseError::ParseError(
+            "invalid --trace-granularity, use block or transaction".to_string(),
+        )),
+    }
+}
+
+fn parse_trace_backend(args: &Args) -> Result<TraceBackend, ParseError> {
+    match args.trace_backend.as_deref() {
+        None | Some("parity") => Ok(TraceBackend::Parity),
+        Some("geth") => Ok(TraceBackend::Geth),
+        Some(_) => {
+            Err(ParseError::ParseError("invalid --trace-backend, use parity or geth".to_string()))
+        }
+    }
+}
+
+const DEFAULT_LOGS_PAGE_SIZE: u64 = 10_000;
+
+fn parse_logs_backend(args: &Args) -> Result<LogsBackend, ParseError> {
+    if args.page_size.is_some() && !args.paginate {
+        return Err(ParseError::ParseError("--page-size requires --paginate".to_string()))
+    }
+    match (args.paginate, args.logs_backend.as_deref()) {
+        (true, None | Some("getlogs")) => {
+            return match args.page_size.unwrap_or(DEFAULT_LOGS_PAGE_SIZE) {
+                0 => Err(ParseError::ParseError("--page-size must be greater than 0".to_string())),
+                page_size => Ok(LogsBackend::Paginated(page_size)),
+            }
+        }
+        (true, Some(_)) => {
+            return Err(ParseError::ParseError(
+                "--paginate can only be used with --logs-backend getlogs".to_string(),
+            ))
+        }
+        (false, _) => {}
+    }
+    match args.logs_backend.as_deref() {
+        None | Some("getlogs") => Ok(LogsBackend::GetLogs),
+        Some("filter") => Ok(LogsBackend::Filter),
+        Some(_) => {
+            Err(ParseError::ParseError("invalid --logs-backend, use getlogs or filter".to_string()))
+        }
+    }
+}
+
+fn parse_log_filter(args: &Args) -> Result<Option<Filter>, ParseError> {
+    let Some(input) = &args.log_filter_json else { return Ok(None) };
+    let filter = parse_filter_json(input, "--log-filter-json")?;
+    if filter.address.is_some() && (args.address.is_some() || args.contract.is_some()) {
+        return Err(ParseError::ParseError(
+            "--log-filter-json address conflicts with --address".to_string(),
+        ))
+    }
+    let topic_args = [&args.topic0, &args.topic1, &args.topic2, &args.topic3];
+    for (i, (topic, topic_arg)) in filter.topics.iter().zip(topic_args).enumerate() {
+        if topic.is_some() && topic_arg.is_some() {
+            return Err(ParseError::ParseError(format!(
+                "--log-filter-json topics conflict with --topic{}",
+                i
+            )))
+        }
+    }
+
+    Ok(Some(filter))
+}
+
+/// parse an eth_getLogs filter of address and topics, given as json or a path to a json file
+fn parse_filter_json(input: &str, flag: &str) -> Result<Filter, ParseError> {
+    let json = match std::path::Path::new(input).is_file() {
+        true => std::fs::read_to_string(input)
+            .map_err(|_| ParseError::ParseError(format!("could not read {} file", flag)))?,
+        false => input.to_string(),
+    };
+    let value: serde_json::Value = serde_json::from_str(&json)
+        .map_err(|_| ParseError::ParseError(format!("{} is not valid json", flag)))?;
+    let Some(fields) = value.as_object() else {
+        return Err(ParseError::ParseError(format!("{} must be a json object", flag)))
+    };
+
+    // cryo sets the block range of each chunk
+    for key in fields.keys() {
+        match key.as_str() {
+            "address" | "topics" => {}
+            "fromBlock" | "toBlock" | "blockHash" => {
+                return Err(ParseError::ParseError(format!(
+                    "{} cannot set {}, block ranges are set by cryo",
+                    flag, key
+                )))
+            }
+            _ => {
+                return Err(ParseError::ParseError(format!(
+                    "{} has unknown filter field {}",
+                    flag, key
+                )))
+            }
+        }
+    }
+
+    serde_json::from_value(value)
+        .map_err(|e| ParseError::ParseError(format!("{} is not a valid log filter: {}", flag, e)))
+}
+
 fn find_arg_aliases(args: &Args, schemas: &Schemas) -> Vec<(Dim, Dim)> {
     // does not currently handle optional args, just required args
     let mut swaps = Vec::new();
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
 use cryo_freeze::{
-    ColumnEncoding, Datatype, FileFormat, LogDecoder, MultiDatatype, ParseError, Table,
+    CallOutputDecoder, ColumnEncoding, ComputedColumn, Datatype, FileFormat, LogDecoder,
+    MultiDatatype, OverflowPolicy, ParseError, Table,
 };
 
 use super::file_output;
@@ -9,7 +10,7 @@ use crate::args::Args;
 use cryo_freeze::U256Type;
 use std::str::FromStr;
 
-fn parse_datatypes(raw_inputs: &Vec<String>) -> Result<Vec<Datatype>, ParseError> {
+pub(crate) fn parse_datatypes(raw_inputs: &Vec<String>) -> Result<Vec<Datatype>, ParseError> {
     let mut datatypes = Vec::new();
 
     'outer: for raw_input in raw_inputs {
@@ -32,13 +33,29 @@ pub(crate) fn parse_schemas(
     // parse inputs
     let datatypes = parse_datatypes(&args.datatype)?;
     let sort = parse_sort_columns(&args.sort, &datatypes)?;
+    if args.order_within_file.is_some() && datatypes.len() > 1 {
+        return Err(ParseError::ParseError(
+            "--order-within-file not supported for multiple datasets".to_string(),
+        ))
+    }
+    let computed_columns = parse_computed_columns(args, &datatypes)?;
+    let columns = parse_datatype_columns(&args.columns, &datatypes)?;
     let u256_types = parse_u256_types(args)?;
+    let overflow_policy = match &args.on_overflow {
+        Some(policy) => policy.parse()?,
+        None => OverflowPolicy::default(),
+    };
+    let reward_percentiles = parse_reward_percentiles(args)?;
     let output_format = file_output::parse_output_format(args)?;
     let binary_column_format = match args.hex | (output_format != FileFormat::Parquet) {
         true => ColumnEncoding::Hex,
         false => ColumnEncoding::Binary,
     };
 
+    if let Some(preset) = &args.preset {
+        ensure_known_preset(preset, &datatypes)?
+    };
+
     let log_decoder = match args.event_signature {
         Some(ref sig) => match LogDecoder::new(sig.clone()) {
             Ok(res) => Some(res),
@@ -47,6 +64,18 @@ pub(crate) fn parse_schemas(
         None => None,
     };
 
+    let call_output_decoder = match &args.decode_output {
+        Some(output_type) => {
+            if !datatypes.contains(&Datatype::EthCalls) {
+                return Err(ParseError::ParseError(
+                    "--decode-output requires the eth_calls datatype".to_string(),
+                ))
+            }
+            Some(CallOutputDecoder::new(output_type.clone()).map_err(ParseError::ParseError)?)
+        }
+        None => None,
+    };
+
     // create schemas
     let schemas: Result<HashMap<Datatype, Table>, ParseError> = datatypes
         .iter()
@@ -57,11 +86,27 @@ pub(crate) fn parse_schemas(
                     &binary_column_format,
                     &args.include_columns,
                     &args.exclude_columns,
-                    &args.columns,
+                    &columns[datatype],
+                    &args.preset,
                     sort[datatype].clone(),
                     log_decoder.clone(),
+                    args.include_deprecated,
                 )
-                .map(|schema| (*datatype, schema))
+                .and_then(|mut schema| {
+                    schema.overflow_policy = overflow_policy;
+                    schema.file_order = args.order_within_file.clone();
+                    schema.set_trace_address_type(&output_format, args.trace_address_as_string);
+                    if let Some(reward_percentiles) = &reward_percentiles {
+                        schema.set_reward_percentiles(reward_percentiles.clone());
+                    }
+                    if let Some(decoder) = &call_output_decoder {
+                        schema.set_call_output_decoder(decoder.clone());
+                    }
+                    for column in computed_columns.iter() {
+                        schema.add_computed_column(column.clone())?;
+                    }
+                    Ok((*datatype, schema))
+                })
                 .map_err(|e| {
                     ParseError::ParseError(format!(
                         "Failed to get schema for datatype: {:?}, {:?}",
@@ -81,7 +126,70 @@ pub(crate) fn parse_schemas(
         ensure_excluded_columns(exclude_columns, schemas)?
     };
 
-    Ok((datatypes, schemas?))
+    // rows can only be ordered by collected columns
+    let mut schemas = schemas?;
+    if let Some(order) = &args.order_within_file {
+        for schema in schemas.values() {
+            if let Some(column) = order.iter().find(|column| !schema.has_column(column)) {
+                return Err(ParseError::ParseError(format!(
+                    "--order-within-file column {} is not collected for {}",
+                    column,
+                    schema.datatype.name()
+                )))
+            }
+        }
+    }
+
+    // order columns like the file that new files will be appended to
+    if let Some(path) = &args.match_schema {
+        match_schema(path, &mut schemas)?
+    };
+
+    Ok((datatypes, schemas))
+}
+
+fn match_schema(path: &str, schemas: &mut HashMap<Datatype, Table>) -> Result<(), ParseError> {
+    if schemas.len() != 1 {
+        return Err(ParseError::ParseError(
+            "--match-schema can only be used with a single datatype".to_string(),
+        ))
+    }
+    let reference = cryo_freeze::read_parquet_schema(path)?;
+    for schema in schemas.values_mut() {
+        schema.match_schema(&reference).map_err(|e| {
+            ParseError::ParseError(format!("schema does not match {}: {}", path, e))
+        })?;
+    }
+    Ok(())
+}
+
+fn parse_computed_columns(
+    args: &Args,
+    datatypes: &[Datatype],
+) -> Result<Vec<ComputedColumn>, ParseError> {
+    let Some(raw) = &args.add_column else { return Ok(Vec::new()) };
+    if datatypes.len() > 1 {
+        return Err(ParseError::ParseError(
+            "--add-column not supported for multiple datasets".to_string(),
+        ))
+    }
+    raw.iter().map(|column| column.parse()).collect()
+}
+
+fn parse_reward_percentiles(args: &Args) -> Result<Option<Vec<f64>>, ParseError> {
+    let Some(percentiles) = &args.reward_percentiles else { return Ok(None) };
+    if let Some(percentile) = percentiles.iter().find(|p| !(0.0..=100.0).contains(*p)) {
+        return Err(ParseError::ParseError(format!(
+            "reward percentile must be between 0 and 100, got {}",
+            percentile
+        )))
+    }
+    if percentiles.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(ParseError::ParseError(
+            "reward percentiles must be in ascending order".to_string(),
+        ))
+    }
+    Ok(Some(percentiles.clone()))
 }
 
 fn parse_u256_types(args: &Args) -> Result<Vec<U256Type>, ParseError> {
@@ -108,6 +216,16 @@ fn parse_u256_types(args: &Args) -> Result<Vec<U256Type>, ParseError> {
     )
 }
 
+fn ensure_known_preset(preset: &str, datatypes: &[Datatype]) -> Result<(), ParseError> {
+    if (preset == "full") ||
+        datatypes.iter().any(|datatype| datatype.presets().contains_key(preset))
+    {
+        Ok(())
+    } else {
+        Err(ParseError::ParseError(format!("unknown column preset: {}", preset)))
+    }
+}
+
 fn ensure_included_columns(
     include_columns: &[String],
     schemas: &cryo_freeze::Schemas,
@@ -164,6 +282,49 @@ fn ensure_excluded_columns(
     Ok(())
 }
 
+/// route column lists to datatypes, lists like `blocks:block_number,block_hash` only apply to
+/// their datatype, other lists apply to every datatype
+fn parse_datatype_columns(
+    raw_columns: &Option<Vec<String>>,
+    datatypes: &[Datatype],
+) -> Result<HashMap<Datatype, Option<Vec<String>>>, ParseError> {
+    let Some(raw_columns) = raw_columns else {
+        return Ok(HashMap::from_iter(datatypes.iter().map(|datatype| (*datatype, None))))
+    };
+    let mut global = Vec::new();
+    let mut specific: HashMap<Datatype, Vec<String>> = HashMap::new();
+    for raw_column in raw_columns.iter() {
+        match raw_column.split_once(':') {
+            Some((name, columns)) => {
+                let datatype = Datatype::from_str(name)?;
+                if !datatypes.contains(&datatype) {
+                    return Err(ParseError::ParseError(format!(
+                        "--columns given for {}, which is not being collected",
+                        datatype.name()
+                    )))
+                }
+                let columns = columns.split(',').filter(|column| !column.is_empty());
+                specific.entry(datatype).or_default().extend(columns.map(|c| c.to_string()));
+            }
+            None => global.push(raw_column.clone()),
+        }
+    }
+
+    // datatypes without any list of their own keep their default columns
+    let apply_to_all = !global.is_empty() || specific.is_empty();
+    Ok(datatypes
+        .iter()
+        .map(|datatype| {
+            let columns = match (specific.get(datatype), apply_to_all) {
+                (Some(columns), _) => Some([global.clone(), columns.clone()].concat()),
+                (None, true) => Some(global.clone()),
+                (None, false) => None,
+            };
+            (*datatype, columns)
+        })
+        .collect())
+}
+
 fn parse_sort_columns(
     raw_sort: &Option<Vec<String>>,
     datatypes: &[Datatype],
@@ -192,3 +353,38 @@ fn parse_sort_columns(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_datatype_columns() {
+        let args = Args {
+            datatype: vec!["blocks".to_string(), "logs".to_string()],
+            columns: Some(vec![
+                "blocks:block_number,block_hash".to_string(),
+                "logs:address,topic0".to_string(),
+            ]),
+            ..Default::default()
+        };
+        let (_, schemas) = parse_schemas(&args).unwrap();
+        assert_eq!(schemas[&Datatype::Blocks].columns(), vec!["block_number", "block_hash"]);
+        assert_eq!(schemas[&Datatype::Logs].columns(), vec!["address", "topic0"]);
+
+        // global lists apply to every datatype, datatypes without a list keep their defaults
+        let columns = |raw: &[&str]| {
+            let raw = Some(raw.iter().map(|column| column.to_string()).collect());
+            parse_datatype_columns(&raw, &[Datatype::Blocks, Datatype::Logs]).unwrap()
+        };
+        let routed = columns(&["block_number", "logs:address"]);
+        assert_eq!(routed[&Datatype::Blocks], Some(vec!["block_number".to_string()]));
+        assert_eq!(
+            routed[&Datatype::Logs],
+            Some(vec!["block_number".to_string(), "address".to_string()])
+        );
+        assert_eq!(columns(&["logs:address"])[&Datatype::Blocks], None);
+        let raw = Some(vec!["transactions:value".to_string()]);
+        assert!(parse_datatype_columns(&raw, &[Datatype::Blocks]).is_err());
+    }
+}
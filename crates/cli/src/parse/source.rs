@@ -1,7 +1,7 @@
 use std::env;
 
 use crate::args::Args;
-use cryo_freeze::{sources::ProviderWrapper, ParseError, Source, SourceLabels};
+use cryo_freeze::{sources::ProviderWrapper, HeadGap, ParseError, Source, SourceLabels};
 use ethers::prelude::*;
 use governor::{Quota, RateLimiter};
 use polars::prelude::*;
@@ -20,15 +20,13 @@ pub(crate) async fn parse_source(args: &Args) -> Result<Source, ParseError> {
         let chain_id = provider.get_chainid().await.map_err(ParseError::ProviderError)?.as_u64();
         (provider.into(), chain_id)
     } else if rpc_url.starts_with("ws") {
-        let provider = Provider::<Ws>::connect(&rpc_url).await.map_err(|_| {
-            ParseError::ParseError("could not instantiate HTTP Provider".to_string())
-        })?;
+        let provider =
+            Provider::<Ws>::connect(&rpc_url).await.map_err(ParseError::ProviderError)?;
         let chain_id = provider.get_chainid().await.map_err(ParseError::ProviderError)?.as_u64();
         (provider.into(), chain_id)
     } else if rpc_url.ends_with(".ipc") {
-        let provider: Provider<Ipc> = Provider::connect_ipc(&rpc_url).await.map_err(|_| {
-            ParseError::ParseError("could not instantiate HTTP Provider".to_string())
-        })?;
+        let provider: Provider<Ipc> =
+            Provider::connect_ipc(&rpc_url).await.map_err(ParseError::ProviderError)?;
         let chain_id = provider.get_chainid().await.map_err(ParseError::ProviderError)?.as_u64();
         (provider.into(), chain_id)
     } else {
@@ -53,6 +51,23 @@ pub(crate) async fn parse_source(args: &Args) -> Result<Source, ParseError> {
         Some(max) => Some(max),
         None => Some(4),
     };
+    let max_blocks_per_log_request = match args.max_blocks_per_log_request {
+        Some(0) => {
+            return Err(ParseError::ParseError(
+                "--max-blocks-per-log-request must be at least 1".to_string(),
+            ))
+        }
+        max => max,
+    };
+
+    // blocks above the head are waited for when following the chain, and skipped otherwise
+    let head_gap = match args.follow {
+        true => HeadGap::Wait(
+            args.head_wait_retries,
+            std::time::Duration::from_secs(args.poll_interval),
+        ),
+        false => HeadGap::Skip,
+    };
 
     let semaphore = tokio::sync::Semaphore::new(max_concurrent_requests as usize);
     let semaphore = Arc::new(Some(semaphore));
@@ -61,6 +76,11 @@ pub(crate) async fn parse_source(args: &Args) -> Result<Source, ParseError> {
         chain_id,
         inner_request_size: args.inner_request_size,
         max_concurrent_chunks,
+        retry_failed_blocks_individually: args.retry_failed_blocks_individually,
+        log_filters: Default::default(),
+        block_receipts_unsupported: Default::default(),
+        max_blocks_per_log_request,
+        head_gap,
         semaphore,
         rate_limiter: rate_limiter.into(),
         rpc_url,
@@ -1,16 +1,36 @@
-use crate::{args, parse, remember};
+use crate::{args, follow, parse, remember};
 use clap_cryo::Parser;
 use color_print::cstr;
 use colored::Colorize;
-use cryo_freeze::{err, CollectError, ExecutionEnv, FreezeSummary};
+use cryo_freeze::{
+    err, CollectError, ExecutionEnv, FreezeConfig, FreezeSummary, Query, SchemaFormat, Source,
+};
 use std::{sync::Arc, time::SystemTime};
+use tokio::sync::watch;
 
 /// run cli
 pub async fn run(args: args::Args) -> Result<Option<FreezeSummary>, CollectError> {
+    run_with_shutdown(args, None).await
+}
+
+/// run cli, finishing in-flight chunks and skipping the rest once shutdown is true
+pub async fn run_with_shutdown(
+    args: args::Args,
+    shutdown: Option<watch::Receiver<bool>>,
+) -> Result<Option<FreezeSummary>, CollectError> {
     // handle subcommands
     if args.datatype.first() == Some(&"help".to_string()) {
         return handle_help_subcommands(args).await
     }
+    if args.datatype.first() == Some(&"count".to_string()) {
+        return handle_count_subcommand(args).await
+    }
+    if args.datatype.first() == Some(&"export-schema".to_string()) {
+        return handle_export_schema_subcommand(args)
+    }
+    if args.datatype.first() == Some(&"inspect".to_string()) {
+        return handle_inspect_subcommand(args)
+    }
 
     let cryo_dir: std::path::PathBuf = args.output_dir.clone().into();
     let cryo_dir = cryo_dir.join(".cryo");
@@ -18,26 +38,33 @@ pub async fn run(args: args::Args) -> Result<Option<FreezeSummary>, CollectError
     // remember previous command
     let args = if args.datatype.is_empty() {
         let remembered = remember::load_remembered_command(cryo_dir.clone())?;
-        if remembered.cryo_version != cryo_freeze::CRYO_VERSION {
-            eprintln!("remembered command comes from different cryo version, proceed with caution");
-            eprintln!();
-        };
-        println!(
-            "{} {} {}",
-            "remembering previous command:".truecolor(170, 170, 170),
-            "cryo".bold().white(),
-            remembered.command.into_iter().skip(1).collect::<Vec<_>>().join(" ").white().bold()
-        );
-        println!();
-        args.merge_with_precedence(remembered.args)
+        let args = args.merge_with_precedence(remembered.args);
+        if !args.quiet {
+            if remembered.cryo_version != cryo_freeze::CRYO_VERSION {
+                eprintln!(
+                    "remembered command comes from different cryo version, proceed with caution"
+                );
+                eprintln!();
+            };
+            println!(
+                "{} {} {}",
+                "remembering previous command:".truecolor(170, 170, 170),
+                "cryo".bold().white(),
+                remembered.command.into_iter().skip(1).collect::<Vec<_>>().join(" ").white().bold()
+            );
+            println!();
+        }
+        args
     } else {
         args
     };
 
     // remember current command
     if args.remember {
-        println!("remembering this command for future use");
-        println!();
+        if !args.quiet {
+            println!("remembering this command for future use");
+            println!();
+        }
         remember::save_remembered_command(cryo_dir, &args)?;
     }
 
@@ -48,10 +75,75 @@ pub async fn run(args: args::Args) -> Result<Option<FreezeSummary>, CollectError
         Err(e) => return Err(e.into()),
     };
 
+    if !args.dry {
+        check_source(&args, &query, &source).await?;
+    }
+
     let source = Arc::new(source);
-    let env = ExecutionEnv { t_start_parse, ..env };
+    let env = ExecutionEnv { t_start_parse, shutdown, ..env };
     let env = env.set_start_time();
-    cryo_freeze::freeze(&query, &source, &sink, &env).await
+    let config = FreezeConfig::from_parts(query, sink, env.clone());
+    let summary = cryo_freeze::library::freeze_with_source(&config, &source).await?;
+    let (query, sink) = (config.query(), config.sink());
+
+    // keep collecting new blocks
+    match summary {
+        Some(summary) if args.follow && summary.errored.is_empty() && !env.shutdown_requested() => {
+            let shutdown = env.shutdown.clone().unwrap_or_else(shutdown_signal);
+            follow::follow(&args, query, source, sink, shutdown, summary).await
+        }
+        summary => Ok(summary),
+    }
+}
+
+/// check that the node can serve the query before collecting anything
+async fn check_source(
+    args: &args::Args,
+    query: &Query,
+    source: &Source,
+) -> Result<(), CollectError> {
+    if !args.assume_archive {
+        cryo_freeze::check_archive(query, source).await?;
+    }
+    if !args.skip_preflight {
+        cryo_freeze::preflight(query, source).await?;
+    }
+    if args.validate_decimals_fit {
+        for warning in cryo_freeze::check_decimals_fit(query, source).await? {
+            if !args.quiet {
+                eprintln!("warning: {}", warning);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// receiver that changes to true on SIGINT or SIGTERM, a second signal exits immediately
+pub fn shutdown_signal() -> watch::Receiver<bool> {
+    let (sender, receiver) = watch::channel(false);
+    tokio::spawn(async move {
+        wait_for_signal().await;
+        eprintln!("shutting down after in-flight chunks finish, interrupt again to exit now");
+        let _ = sender.send(true);
+        wait_for_signal().await;
+        std::process::exit(crate::exit_codes::INTERRUPTED);
+    });
+    receiver
+}
+
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut sigterm) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = sigterm.recv() => {}
+            }
+            return
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
 }
 
 async fn handle_help_subcommands(args: args::Args) -> Result<Option<FreezeSummary>, CollectError> {
@@ -62,15 +154,21 @@ async fn handle_help_subcommands(args: args::Args) -> Result<Option<FreezeSummar
             r#"<white><bold>Block specification syntax</bold></white>
 - can use numbers                    <white><bold>--blocks 5000 6000 7000</bold></white>
 - can use ranges                     <white><bold>--blocks 12M:13M 15M:16M</bold></white>
+- overlapping ranges are merged      <white><bold>--blocks 100:200 150:250</bold></white> == <white><bold>100:250</bold></white>
 - can use a parquet file             <white><bold>--blocks ./path/to/file.parquet[:COLUMN_NAME]</bold></white>
 - can use multiple parquet files     <white><bold>--blocks ./path/to/files/*.parquet[:COLUMN_NAME]</bold></white>
 - numbers can contain { _ . K M B }  <white><bold>5_000 5K 15M 15.5M</bold></white>
+- numbers can be 0x-prefixed hex     <white><bold>0x112A880:18_001_000</bold></white>
 - omitting range end means latest    <white><bold>15.5M:</bold></white> == <white><bold>15.5M:latest</bold></white>
 - omitting range start means 0       <white><bold>:700</bold></white> == <white><bold>0:700</bold></white>
 - minus on start means minus end     <white><bold>-1000:7000</bold></white> == <white><bold>6000:7000</bold></white>
 - plus sign on end means plus start  <white><bold>15M:+1000</bold></white> == <white><bold>15M:15.001K</bold></white>
 - can use every nth value            <white><bold>2000:5000:1000</bold></white> == <white><bold>2000 3000 4000</bold></white>
 - can use n values total             <white><bold>100:200/5</bold></white> == <white><bold>100 124 149 174 199</bold></white>
+- can use UTC dates, resolved to the  <white><bold>2024-01-01:2024-01-02</bold></white>
+  first block on or after midnight
+- eth_calls and slots can instead pin  <white><bold>--at-block 18000000 --slot SLOT1 SLOT2</bold></white>
+  one block and iterate over the rest
 
 <white><bold>Transaction specification syntax</bold></white>
 - can use transaction hashes         <white><bold>--txs TX_HASH1 TX_HASH2 TX_HASH3</bold></white>
@@ -98,3 +196,148 @@ async fn handle_help_subcommands(args: args::Args) -> Result<Option<FreezeSummar
     }
     Ok(None)
 }
+
+/// print the schema of each dataset in the schema language given by --as
+fn handle_export_schema_subcommand(
+    args: args::Args,
+) -> Result<Option<FreezeSummary>, CollectError> {
+    let format: SchemaFormat = args.schema_format.parse()?;
+    let args = args::Args { datatype: args.datatype[1..].to_vec(), ..args };
+    let (datatypes, schemas) = super::parse::schemas::parse_schemas(&args)?;
+    for datatype in datatypes.into_iter() {
+        match schemas.get(&datatype) {
+            Some(schema) => println!("{}", schema.export_schema(format)),
+            None => return Err(err("missing schema for datatype")),
+        }
+    }
+    Ok(None)
+}
+
+/// print the row count, null counts, and cryo metadata of parquet files
+fn handle_inspect_subcommand(args: args::Args) -> Result<Option<FreezeSummary>, CollectError> {
+    if args.datatype.len() < 2 {
+        return Err(err("usage: cryo inspect <FILE>.parquet"))
+    }
+    for (index, path) in args.datatype[1..].iter().enumerate() {
+        let inspection = cryo_freeze::inspect_parquet(path)?;
+        if index > 0 {
+            println!();
+        }
+        println!("{}", path.bold().white());
+        println!("rows: {}", inspection.n_rows);
+        println!("columns:");
+        for (name, data_type, null_count) in inspection.columns.iter() {
+            println!(
+                "- {} {}: {} nulls",
+                name,
+                format!("({})", data_type).truecolor(170, 170, 170),
+                null_count
+            );
+        }
+        let cryo_metadata: Vec<_> =
+            inspection.metadata.iter().filter(|(key, _)| key.starts_with("cryo_")).collect();
+        if !cryo_metadata.is_empty() {
+            println!("cryo metadata:");
+            for (key, value) in cryo_metadata.into_iter() {
+                println!("- {}: {}", key, value);
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// collect data and print row counts without writing any files
+async fn handle_count_subcommand(args: args::Args) -> Result<Option<FreezeSummary>, CollectError> {
+    let args = args::Args { datatype: args.datatype[1..].to_vec(), ..args };
+    let (query, source, _sink, env) = match parse::parse_args(&args).await {
+        Ok(opts) => opts,
+        Err(e) => return Err(e.into()),
+    };
+    if !args.assume_archive {
+        cryo_freeze::check_archive(&query, &source).await?;
+    }
+    if !args.skip_preflight {
+        cryo_freeze::preflight(&query, &source).await?;
+    }
+    let query = Arc::new(query);
+    let summary = cryo_freeze::count_rows(query.clone(), Arc::new(source)).await?;
+
+    if env.verbose >= 2 {
+        for (partition, datatype, n_rows) in summary.chunks.iter() {
+            let label = partition.label(&query.partitioned_by)?;
+            println!("{} {} {}", datatype.name(), label.truecolor(170, 170, 170), n_rows);
+        }
+        println!();
+    }
+    let mut totals: Vec<_> = summary.totals.into_iter().collect();
+    totals.sort_by_key(|(datatype, _)| datatype.name());
+    for (datatype, n_rows) in totals.into_iter() {
+        println!("{}: {} rows", datatype.name().bold(), n_rows);
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::{file_output::parse_file_output, parse_query},
+        test_helpers::{make_source, TempDir},
+    };
+    use cryo_freeze::ExecutionEnvBuilder;
+    use ethers::{
+        prelude::*,
+        providers::{JsonRpcError, MockResponse},
+    };
+
+    #[tokio::test]
+    async fn test_transactions_without_block_receipts() {
+        let (provider, mock) = Provider::mocked();
+        let tx = Transaction { hash: H256::from_low_u64_be(1), ..Default::default() };
+        let receipt = TransactionReceipt {
+            transaction_hash: tx.hash,
+            status: Some(1.into()),
+            gas_used: Some(21_000.into()),
+            ..Default::default()
+        };
+        let block =
+            Block { number: Some(100.into()), transactions: vec![tx], ..Default::default() };
+        // responses are popped from the back, preflight probes the block and its receipts, then
+        // the chunk fetches the block, the receipt of each transaction, and the block timestamp
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        mock.push(receipt).unwrap();
+        mock.push(block).unwrap();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32601,
+            message: "the method eth_getBlockReceipts does not exist/is not available".to_string(),
+            data: None,
+        }));
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        let output_dir = TempDir::new("receipts");
+        let output_dir_str = output_dir.to_string_lossy().to_string();
+        let args = args::Args::parse_from(vec![
+            "cryo",
+            "transactions",
+            "--blocks",
+            "100",
+            "--output-dir",
+            output_dir_str.as_str(),
+            "--no-verbose",
+            "--no-report",
+        ]);
+        let source = make_source(provider);
+        let query = parse_query(&args, source.clone()).await.unwrap();
+        let sink = parse_file_output(&args, &source, &query.schemas).unwrap();
+
+        check_source(&args, &query, &source).await.unwrap();
+        let env = ExecutionEnvBuilder::new().verbose(0).build();
+        let summary = cryo_freeze::freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+        assert!(summary.errored.is_empty());
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", false])).unwrap();
+        mock.assert_request("eth_getBlockReceipts", [U64::from(100)]).unwrap();
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", true])).unwrap();
+        mock.assert_request("eth_getTransactionReceipt", [H256::from_low_u64_be(1)]).unwrap();
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", false])).unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+//! helpers for building sources and temp dirs in unit tests
+
+use cryo_freeze::{sources::ProviderWrapper, Source, SourceLabels};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// source that collects one chunk at a time, so mocked responses are consumed in order
+pub(crate) fn make_source(provider: impl Into<ProviderWrapper>) -> Arc<Source> {
+    Arc::new(Source {
+        provider: provider.into(),
+        chain_id: 1,
+        inner_request_size: 1,
+        max_concurrent_chunks: Some(1),
+        rpc_url: "".to_string(),
+        semaphore: Arc::new(None),
+        rate_limiter: Arc::new(None),
+        retry_failed_blocks_individually: false,
+        log_filters: Default::default(),
+        block_receipts_unsupported: Default::default(),
+        max_blocks_per_log_request: None,
+        head_gap: Default::default(),
+        labels: SourceLabels::default(),
+    })
+}
+
+/// directory under the system temp dir that is unique to a test and removed on drop, so that
+/// failed assertions leave nothing behind, the directory itself is not created
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> TempDir {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let index = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dirname = format!("cryo_{}_{}_{}", name, std::process::id(), index);
+        TempDir(std::env::temp_dir().join(dirname))
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
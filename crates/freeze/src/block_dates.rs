@@ -0,0 +1,136 @@
+use crate::{err, CollectError, Source};
+use chrono::NaiveDate;
+use std::future::Future;
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// parse a calendar date in the format YYYY-MM-DD
+pub fn parse_date(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// first block whose timestamp is at or after timestamp, found by binary search over blocks
+pub async fn first_block_at_or_after(timestamp: u64, source: &Source) -> Result<u64, CollectError> {
+    let latest_block = source.get_block_number().await?.as_u64();
+    let latest_timestamp = block_timestamp(latest_block, source).await?;
+    if latest_timestamp < timestamp {
+        return Err(err(&format!(
+            "no block at or after timestamp {} yet, latest block {} has timestamp {}",
+            timestamp, latest_block, latest_timestamp
+        )))
+    }
+    search_first_block(timestamp, latest_block, |n| block_timestamp(n, source)).await
+}
+
+/// first block on or after midnight UTC of date
+///
+/// dates before the day of the genesis block and dates whose midnight is after the latest block
+/// are errors
+pub async fn first_block_of_date(date: NaiveDate, source: &Source) -> Result<u64, CollectError> {
+    let latest_block = source.get_block_number().await?.as_u64();
+    resolve_date(date, latest_block, |n| block_timestamp(n, source)).await
+}
+
+async fn block_timestamp(block_number: u64, source: &Source) -> Result<u64, CollectError> {
+    let block = source
+        .get_block(block_number)
+        .await?
+        .ok_or_else(|| err(&format!("block {} not found", block_number)))?;
+    Ok(block.timestamp.as_u64())
+}
+
+async fn resolve_date<F, Fut>(
+    date: NaiveDate,
+    latest_block: u64,
+    get_timestamp: F,
+) -> Result<u64, CollectError>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<u64, CollectError>>,
+{
+    let midnight = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or_else(|| err(&format!("invalid date {}", date)))?
+        .and_utc()
+        .timestamp();
+
+    let genesis_timestamp = get_timestamp(0).await?;
+    if midnight + SECONDS_PER_DAY <= genesis_timestamp as i64 {
+        return Err(err(&format!(
+            "date {} is before the genesis block, which is at timestamp {}",
+            date, genesis_timestamp
+        )))
+    }
+    let latest_timestamp = get_timestamp(latest_block).await?;
+    if (latest_timestamp as i64) < midnight {
+        return Err(err(&format!(
+            "date {} is after the latest block {}, which is at timestamp {}",
+            date, latest_block, latest_timestamp
+        )))
+    }
+
+    // the genesis day starts before the genesis block, which is then its first block
+    let timestamp = std::cmp::max(midnight, 0) as u64;
+    search_first_block(timestamp, latest_block, get_timestamp).await
+}
+
+/// smallest block number in 0..=latest_block with a timestamp at or after timestamp, the latest
+/// block must have such a timestamp
+async fn search_first_block<F, Fut>(
+    timestamp: u64,
+    latest_block: u64,
+    get_timestamp: F,
+) -> Result<u64, CollectError>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Result<u64, CollectError>>,
+{
+    let (mut low, mut high) = (0, latest_block);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if get_timestamp(mid).await? >= timestamp {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // one block every 12 hours, starting at noon of 2024-01-01
+    const GENESIS_TIMESTAMP: u64 = 1_704_110_400;
+
+    async fn mock_timestamp(block_number: u64) -> Result<u64, CollectError> {
+        match block_number {
+            0..=9 => Ok(GENESIS_TIMESTAMP + 43_200 * block_number),
+            _ => Err(err("block not found")),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_block_of_date() {
+        let resolve = |date: &str| resolve_date(parse_date(date).unwrap(), 9, mock_timestamp);
+
+        // the genesis day starts before the genesis block
+        assert_eq!(resolve("2024-01-01").await.unwrap(), 0);
+        assert_eq!(resolve("2024-01-02").await.unwrap(), 1);
+        assert_eq!(resolve("2024-01-05").await.unwrap(), 7);
+
+        let error = resolve("2023-12-31").await.unwrap_err();
+        assert!(error.to_string().contains("before the genesis block"));
+        let error = resolve("2024-01-07").await.unwrap_err();
+        assert!(error.to_string().contains("after the latest block 9"));
+
+        // timestamps equal to a block timestamp resolve to that block
+        let timestamp = GENESIS_TIMESTAMP + 43_200 * 3;
+        assert_eq!(search_first_block(timestamp, 9, mock_timestamp).await.unwrap(), 3);
+        assert_eq!(search_first_block(timestamp + 1, 9, mock_timestamp).await.unwrap(), 4);
+
+        assert!(parse_date("2024-13-01").is_none());
+        assert!(parse_date("18000000").is_none());
+    }
+}
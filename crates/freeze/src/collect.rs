@@ -28,3 +28,65 @@ pub async fn collect(query: Arc<Query>, source: Arc<Source>) -> Result<DataFrame
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{make_query, make_source},
+        BlockChunk, Datatype, Partition,
+    };
+    use ethers::prelude::*;
+    use std::{collections::HashMap, sync::Mutex};
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id},
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+    type RecordedSpans = Arc<Mutex<Vec<(String, HashMap<String, String>)>>>;
+
+    struct SpanRecorder(RecordedSpans);
+
+    #[derive(Default)]
+    struct FieldRecorder(HashMap<String, String>);
+
+    impl Visit for FieldRecorder {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanRecorder {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            let mut fields = FieldRecorder::default();
+            attrs.record(&mut fields);
+            self.0.lock().unwrap().push((attrs.metadata().name().to_string(), fields.0));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunk_span_fields() {
+        let spans = RecordedSpans::default();
+        let subscriber = tracing_subscriber::registry().with(SpanRecorder(spans.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Vec<Trace>, _>(vec![]).unwrap();
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 100)]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Traces) };
+
+        collect(Arc::new(query), make_source(provider)).await.unwrap();
+
+        let spans = spans.lock().unwrap();
+        let chunk_spans: Vec<_> = spans.iter().filter(|(name, _)| name == "chunk").collect();
+        assert_eq!(chunk_spans.len(), 1);
+        let fields = &chunk_spans[0].1;
+        assert_eq!(fields.get("datatype").map(String::as_str), Some("traces"));
+        assert_eq!(fields.get("range").map(String::as_str), Some("00000100_to_00000100"));
+    }
+}
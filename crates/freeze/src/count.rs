@@ -0,0 +1,93 @@
+use crate::{collect_partition, CollectError, Datatype, Partition, Query, Source};
+use futures::{stream::FuturesOrdered, StreamExt};
+use std::{collections::HashMap, sync::Arc};
+
+/// row counts of a collection whose data was discarded
+#[derive(Debug, Default)]
+pub struct CountSummary {
+    /// number of rows of each datatype in each partition, in partition order
+    pub chunks: Vec<(Partition, Datatype, u64)>,
+    /// total number of rows of each datatype
+    pub totals: HashMap<Datatype, u64>,
+}
+
+/// collect data and count its rows without writing any files
+pub async fn count_rows(
+    query: Arc<Query>,
+    source: Arc<Source>,
+) -> Result<CountSummary, CollectError> {
+    query.is_valid()?;
+
+    let semaphore =
+        source.max_concurrent_chunks.map(|x| Arc::new(tokio::sync::Semaphore::new(x as usize)));
+    let mut futures = FuturesOrdered::new();
+    for datatype in query.datatypes.iter() {
+        for partition in query.partitions.iter() {
+            let (datatype, partition) = (datatype.clone(), partition.clone());
+            let (query, source, semaphore) = (query.clone(), source.clone(), semaphore.clone());
+            futures.push_back(tokio::spawn(async move {
+                let _permit = match &semaphore {
+                    Some(semaphore) => Some(semaphore.acquire().await),
+                    None => None,
+                };
+                let dfs = collect_partition(datatype, partition.clone(), query, source).await?;
+                Ok::<_, CollectError>((partition, dfs))
+            }));
+        }
+    }
+
+    let mut summary = CountSummary::default();
+    while let Some(result) = futures.next().await {
+        let (partition, dfs) = result
+            .map_err(|_| CollectError::CollectError("error joining chunks".to_string()))??;
+        let mut dfs: Vec<_> = dfs.into_iter().collect();
+        dfs.sort_by_key(|(datatype, _)| datatype.name());
+        for (datatype, df) in dfs {
+            let n_rows = df.height() as u64;
+            *summary.totals.entry(datatype).or_default() += n_rows;
+            summary.chunks.push((partition.clone(), datatype, n_rows));
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{make_query, make_source},
+        BlockChunk,
+    };
+    use ethers::prelude::*;
+
+    fn make_log(block_number: u64, log_index: u64) -> Log {
+        Log {
+            block_number: Some(block_number.into()),
+            transaction_hash: Some(H256::zero()),
+            transaction_index: Some(0.into()),
+            log_index: Some(log_index.into()),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_count_matches_collected_rows() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Vec<Log>, _>(vec![make_log(101, 0)]).unwrap();
+        mock.push::<Vec<Log>, _>(vec![make_log(100, 0), make_log(100, 1), make_log(100, 2)])
+            .unwrap();
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = (100..102)
+            .map(|block| Partition {
+                block_numbers: Some(vec![BlockChunk::Range(block, block)]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Logs) };
+
+        let summary = count_rows(Arc::new(query), Arc::new(source)).await.unwrap();
+        let chunk_counts: Vec<_> = summary.chunks.iter().map(|(_, _, n)| *n).collect();
+        assert_eq!(chunk_counts, vec![3, 1]);
+        assert_eq!(summary.totals[&Datatype::Logs], 4);
+    }
+}
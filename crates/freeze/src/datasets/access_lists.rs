@@ -0,0 +1,151 @@
+use crate::*;
+use ethers::prelude::*;
+use polars::prelude::*;
+
+/// columns for access lists
+#[cryo_to_df::to_df(Datatype::AccessLists)]
+#[derive(Default)]
+pub struct AccessLists {
+    n_rows: u64,
+    block_number: Vec<Option<u32>>,
+    transaction_index: Vec<Option<u64>>,
+    transaction_hash: Vec<Vec<u8>>,
+    address: Vec<Vec<u8>>,
+    // null for addresses listed without storage keys
+    storage_key: Vec<Option<Vec<u8>>>,
+    chain_id: Vec<u64>,
+}
+
+#[async_trait::async_trait]
+impl Dataset for AccessLists {
+    fn aliases() -> Vec<&'static str> {
+        vec!["access_list"]
+    }
+
+    fn default_columns() -> Option<Vec<&'static str>> {
+        Some(vec!["block_number", "transaction_hash", "address", "storage_key", "chain_id"])
+    }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getBlockByNumber"]
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByBlock for AccessLists {
+    type Response = Block<Transaction>;
+
+    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+        source
+            .get_block_with_txs(request.block_number()?)
+            .await?
+            .ok_or(CollectError::CollectError("block not found".to_string()))
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::AccessLists)?;
+        for tx in response.transactions.iter() {
+            process_access_list(tx, columns, schema);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByTransaction for AccessLists {
+    type Response = Transaction;
+
+    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+        source
+            .get_transaction(request.ethers_transaction_hash()?)
+            .await?
+            .ok_or(CollectError::CollectError("transaction not found".to_string()))
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::AccessLists)?;
+        process_access_list(&response, columns, schema);
+        Ok(())
+    }
+}
+
+/// process access list of a transaction into one row per storage key, or one row per address
+/// listed without storage keys, transactions without access lists add no rows
+fn process_access_list(tx: &Transaction, columns: &mut AccessLists, schema: &Table) {
+    let Some(access_list) = &tx.access_list else { return };
+    for item in access_list.0.iter() {
+        let storage_keys: Vec<Option<&H256>> = match item.storage_keys.is_empty() {
+            true => vec![None],
+            false => item.storage_keys.iter().map(Some).collect(),
+        };
+        for storage_key in storage_keys {
+            columns.n_rows += 1;
+            store!(schema, columns, block_number, tx.block_number.map(|x| x.as_u32()));
+            store!(schema, columns, transaction_index, tx.transaction_index.map(|x| x.as_u64()));
+            store!(schema, columns, transaction_hash, tx.hash.as_bytes().to_vec());
+            store!(schema, columns, address, item.address.as_bytes().to_vec());
+            store!(schema, columns, storage_key, storage_key.map(|key| key.as_bytes().to_vec()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::make_query;
+
+    #[test]
+    fn test_decode_access_list_transaction() {
+        let tx: Transaction = serde_json::from_value(serde_json::json!({
+            "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000064",
+            "blockNumber": "0xc5d488",
+            "from": "0x0000000000000000000000000000000000000001",
+            "gas": "0x5208",
+            "gasPrice": "0x3b9aca00",
+            "hash": "0x0000000000000000000000000000000000000000000000000000000000000007",
+            "input": "0x",
+            "nonce": "0x0",
+            "to": "0x0000000000000000000000000000000000000002",
+            "transactionIndex": "0x3",
+            "value": "0x0",
+            "type": "0x1",
+            "chainId": "0x1",
+            "accessList": [
+                {
+                    "address": "0x0000000000000000000000000000000000000003",
+                    "storageKeys": [
+                        "0x0000000000000000000000000000000000000000000000000000000000000001",
+                        "0x0000000000000000000000000000000000000000000000000000000000000002"
+                    ]
+                },
+                {
+                    "address": "0x0000000000000000000000000000000000000004",
+                    "storageKeys": []
+                }
+            ],
+            "v": "0x0",
+            "r": "0x1",
+            "s": "0x1"
+        }))
+        .unwrap();
+        let legacy_tx = Transaction { transaction_type: Some(0.into()), ..Default::default() };
+        let query = make_query(Datatype::AccessLists);
+        let schema = query.schemas.get_schema(&Datatype::AccessLists).unwrap();
+
+        let mut columns = AccessLists::default();
+        process_access_list(&legacy_tx, &mut columns, schema);
+        assert_eq!(columns.n_rows, 0);
+        process_access_list(&tx, &mut columns, schema);
+
+        assert_eq!(columns.n_rows, 3);
+        assert_eq!(columns.block_number, vec![Some(12965000); 3]);
+        assert_eq!(columns.address[0], H160::from_low_u64_be(3).as_bytes().to_vec());
+        assert_eq!(columns.address[2], H160::from_low_u64_be(4).as_bytes().to_vec());
+        let storage_keys: Vec<_> = columns
+            .storage_key
+            .iter()
+            .map(|key| key.as_ref().map(|key| H256::from_slice(key).to_low_u64_be()))
+            .collect();
+        assert_eq!(storage_keys, vec![Some(1), Some(2), None]);
+    }
+}
@@ -32,6 +32,10 @@ impl Dataset for AddressAppearances {
     fn default_sort() -> Option<Vec<&'static str>> {
         Some(vec!["block_number", "transaction_hash", "address", "relationship"])
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getBlockByNumber", "eth_getLogs", "trace_block"]
+    }
 }
 
 type BlockLogsTraces = (Block<TxHash>, Vec<Log>, Vec<Trace>);
@@ -40,7 +44,7 @@ type BlockLogsTraces = (Block<TxHash>, Vec<Log>, Vec<Trace>);
 impl CollectByBlock for AddressAppearances {
     type Response = BlockLogsTraces;
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
         let block_number = request.ethers_block_number()?;
         let block = source.get_block(request.block_number()?).await?;
         let block = block.ok_or(CollectError::CollectError("block not found".to_string()))?;
@@ -52,7 +56,7 @@ impl CollectByBlock for AddressAppearances {
             ..Default::default()
         };
         let logs = source.get_logs(&filter).await?;
-        let traces = source.trace_block(request.block_number()?.into()).await?;
+        let traces = traces::fetch_block_traces(request.block_number()?, &source, &query).await?;
         Ok((block, logs, traces))
     }
 
@@ -17,7 +17,11 @@ pub struct BalanceDiffs {
 }
 
 #[async_trait::async_trait]
-impl Dataset for BalanceDiffs {}
+impl Dataset for BalanceDiffs {
+    fn required_methods() -> Vec<&'static str> {
+        vec!["trace_replayBlockTransactions"]
+    }
+}
 
 type BlockTxsTraces = (Option<u32>, Vec<Option<Vec<u8>>>, Vec<ethers::types::BlockTrace>);
 
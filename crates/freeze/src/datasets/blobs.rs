@@ -0,0 +1,164 @@
+use crate::*;
+use ethers::prelude::*;
+use polars::prelude::*;
+
+/// gas consumed by each blob, fixed by EIP-4844
+const GAS_PER_BLOB: u64 = 131072;
+
+/// columns for blobs
+#[cryo_to_df::to_df(Datatype::Blobs)]
+#[derive(Default)]
+pub struct Blobs {
+    n_rows: u64,
+    block_number: Vec<Option<u32>>,
+    transaction_index: Vec<Option<u64>>,
+    transaction_hash: Vec<Vec<u8>>,
+    // position of the blob within its transaction
+    blob_index: Vec<u32>,
+    blob_versioned_hash: Vec<Vec<u8>>,
+    // null when the signed max fee does not fit a u64
+    max_fee_per_blob_gas: Vec<Option<u64>>,
+    // blob gas of the whole transaction
+    blob_gas_used: Vec<u64>,
+    chain_id: Vec<u64>,
+}
+
+#[async_trait::async_trait]
+impl Dataset for Blobs {
+    fn default_columns() -> Option<Vec<&'static str>> {
+        Some(vec![
+            "block_number",
+            "transaction_hash",
+            "blob_versioned_hash",
+            "max_fee_per_blob_gas",
+            "blob_gas_used",
+            "chain_id",
+        ])
+    }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getBlockByNumber"]
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByBlock for Blobs {
+    type Response = Block<Transaction>;
+
+    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+        source
+            .get_block_with_txs(request.block_number()?)
+            .await?
+            .ok_or(CollectError::CollectError("block not found".to_string()))
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::Blobs)?;
+        for tx in response.transactions.iter() {
+            process_blob_transaction(tx, columns, schema)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByTransaction for Blobs {
+    type Response = Transaction;
+
+    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+        source
+            .get_transaction(request.ethers_transaction_hash()?)
+            .await?
+            .ok_or(CollectError::CollectError("transaction not found".to_string()))
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::Blobs)?;
+        process_blob_transaction(&response, columns, schema)
+    }
+}
+
+/// process blobs of a transaction into columns, transactions without blobs add no rows
+fn process_blob_transaction(tx: &Transaction, columns: &mut Blobs, schema: &Table) -> R<()> {
+    let Some(blob_hashes) = tx.other.get_deserialized::<Vec<H256>>("blobVersionedHashes") else {
+        return Ok(())
+    };
+    let blob_hashes = blob_hashes?;
+    let max_fee_per_blob_gas = match tx.other.get_deserialized::<U256>("maxFeePerBlobGas") {
+        Some(max_fee) => u64::try_from(max_fee?).ok(),
+        None => return Err(err("blob transaction has no maxFeePerBlobGas")),
+    };
+    let blob_gas_used = GAS_PER_BLOB * blob_hashes.len() as u64;
+
+    for (blob_index, blob_hash) in blob_hashes.iter().enumerate() {
+        columns.n_rows += 1;
+        store!(schema, columns, block_number, tx.block_number.map(|x| x.as_u32()));
+        store!(schema, columns, transaction_index, tx.transaction_index.map(|x| x.as_u64()));
+        store!(schema, columns, transaction_hash, tx.hash.as_bytes().to_vec());
+        store!(schema, columns, blob_index, blob_index as u32);
+        store!(schema, columns, blob_versioned_hash, blob_hash.as_bytes().to_vec());
+        store!(schema, columns, max_fee_per_blob_gas, max_fee_per_blob_gas);
+        store!(schema, columns, blob_gas_used, blob_gas_used);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::make_query;
+
+    #[test]
+    fn test_decode_blob_transaction() {
+        let tx: Transaction = serde_json::from_value(serde_json::json!({
+            "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000064",
+            "blockNumber": "0x12a05f6",
+            "from": "0x0000000000000000000000000000000000000001",
+            "gas": "0x5208",
+            "gasPrice": "0x3b9aca00",
+            "maxFeePerGas": "0x3b9aca00",
+            "maxPriorityFeePerGas": "0x1",
+            "maxFeePerBlobGas": "0x2540be400",
+            "hash": "0x0000000000000000000000000000000000000000000000000000000000000007",
+            "input": "0x",
+            "nonce": "0x0",
+            "to": "0x0000000000000000000000000000000000000002",
+            "transactionIndex": "0x3",
+            "value": "0x0",
+            "type": "0x3",
+            "chainId": "0x1",
+            "accessList": [],
+            "blobVersionedHashes": [
+                "0x01a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+                "0x01d8680a6e2d4a6ec0c9ee7d4d5ba0f5c8e6c9ca4e8f2408b6e2edbc0d2b5a1d"
+            ],
+            "v": "0x0",
+            "r": "0x1",
+            "s": "0x1"
+        }))
+        .unwrap();
+        let legacy_tx = Transaction { transaction_type: Some(0.into()), ..Default::default() };
+        let query = make_query(Datatype::Blobs);
+        let schema = query.schemas.get_schema(&Datatype::Blobs).unwrap();
+
+        let mut columns = Blobs::default();
+        process_blob_transaction(&legacy_tx, &mut columns, schema).unwrap();
+        assert_eq!(columns.n_rows, 0);
+        process_blob_transaction(&tx, &mut columns, schema).unwrap();
+
+        assert_eq!(columns.n_rows, 2);
+        assert_eq!(columns.block_number, vec![Some(19531254); 2]);
+        assert_eq!(columns.transaction_index, vec![Some(3); 2]);
+        assert_eq!(columns.blob_index, vec![0, 1]);
+        assert_eq!(columns.blob_versioned_hash[0][..2], [0x01, 0xa9]);
+        assert_eq!(columns.max_fee_per_blob_gas, vec![Some(10_000_000_000); 2]);
+        assert_eq!(columns.blob_gas_used, vec![2 * GAS_PER_BLOB; 2]);
+
+        // max fees are signed by the sender and may exceed a u64
+        let mut tx = tx;
+        tx.other.insert("maxFeePerBlobGas".to_string(), serde_json::json!(U256::MAX));
+        let mut columns = Blobs::default();
+        process_blob_transaction(&tx, &mut columns, schema).unwrap();
+        assert_eq!(columns.max_fee_per_blob_gas, vec![None; 2]);
+    }
+}
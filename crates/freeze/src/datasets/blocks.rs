@@ -21,6 +21,14 @@ pub struct Blocks {
     total_difficulty: Vec<Option<U256>>,
     size: Vec<Option<u32>>,
     base_fee_per_gas: Vec<Option<u64>>,
+    // base_fee_per_gas * gas_used, null before london
+    burnt_fees: Vec<Option<U256>>,
+    // base reward and uncle inclusion rewards of the author, null outside of mainnet
+    block_reward: Vec<Option<U256>>,
+    // rewards paid to the authors of uncles, null outside of mainnet
+    uncle_reward: Vec<Option<U256>>,
+    // sum of the fees paid by transactions, burnt fees included
+    total_fees: Vec<Option<U256>>,
     chain_id: Vec<u64>,
 }
 
@@ -38,31 +46,59 @@ impl Dataset for Blocks {
             "chain_id",
         ])
     }
+
+    fn presets() -> std::collections::HashMap<&'static str, Vec<&'static str>> {
+        [
+            ("minimal", vec!["block_number", "block_hash", "timestamp"]),
+            (
+                "analytics",
+                vec!["block_number", "timestamp", "author", "gas_used", "size", "base_fee_per_gas"],
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getBlockByNumber"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("author", 20)].into_iter().collect()
+    }
+
+    fn deprecated_columns() -> Vec<&'static str> {
+        // no longer meaningful after the merge
+        vec!["total_difficulty"]
+    }
 }
 
 #[async_trait::async_trait]
 impl CollectByBlock for Blocks {
-    type Response = Block<TxHash>;
+    type Response = (Block<TxHash>, BlockRewards);
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
         let block = source
             .get_block(request.block_number()?)
             .await?
             .ok_or(CollectError::CollectError("block not found".to_string()))?;
-        Ok(block)
+        let schema = query.schemas.get_schema(&Datatype::Blocks)?;
+        let rewards = fetch_block_rewards(&block, &source, schema).await?;
+        Ok((block, rewards))
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
         let schema = query.schemas.get_schema(&Datatype::Blocks)?;
-        process_block(response, columns, schema)
+        let (block, rewards) = response;
+        process_block(block, rewards, columns, schema)
     }
 }
 
 #[async_trait::async_trait]
 impl CollectByTransaction for Blocks {
-    type Response = Block<TxHash>;
+    type Response = (Block<TxHash>, BlockRewards);
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
         let transaction = source
             .get_transaction(request.ethers_transaction_hash()?)
             .await?
@@ -71,17 +107,113 @@ impl CollectByTransaction for Blocks {
             .get_block_by_hash(transaction.block_hash.ok_or(err("no block block_hash found"))?)
             .await?
             .ok_or(CollectError::CollectError("block not found".to_string()))?;
-        Ok(block)
+        let schema = query.schemas.get_schema(&Datatype::Blocks)?;
+        let rewards = fetch_block_rewards(&block, &source, schema).await?;
+        Ok((block, rewards))
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
         let schema = query.schemas.get_schema(&Datatype::Blocks)?;
-        process_block(response, columns, schema)
+        let (block, rewards) = response;
+        process_block(block, rewards, columns, schema)
+    }
+}
+
+/// rewards of a block that are not part of the block itself, null unless their column is collected
+#[derive(Clone, Debug, Default)]
+pub struct BlockRewards {
+    /// base reward and uncle inclusion rewards of the author
+    pub block_reward: Option<U256>,
+    /// rewards paid to the authors of uncles
+    pub uncle_reward: Option<U256>,
+    /// sum of the fees paid by transactions
+    pub total_fees: Option<U256>,
+}
+
+/// chain id of ethereum mainnet, the only chain whose issuance schedule is known
+const MAINNET_CHAIN_ID: u64 = 1;
+
+/// base reward of a mainnet block, before the rewards of uncles
+fn mainnet_base_reward(block_number: u64) -> U256 {
+    let ether = U256::exp10(18);
+    match block_number {
+        0..=4_369_999 => ether * 5,
+        4_370_000..=7_279_999 => ether * 3,
+        7_280_000..=15_537_393 => ether * 2,
+        // no issuance to the author after the merge
+        _ => U256::zero(),
+    }
+}
+
+/// collect the reward columns of schema
+///
+/// block_reward only needs the block, uncle_reward requests each uncle of the block with
+/// eth_getUncleByBlockNumberAndIndex, and total_fees requests the receipts of the block with
+/// eth_getBlockReceipts, and the transactions of the block when receipts lack effective_gas_price
+pub(crate) async fn fetch_block_rewards<TX>(
+    block: &Block<TX>,
+    source: &Source,
+    schema: &Table,
+) -> R<BlockRewards> {
+    let mut rewards = BlockRewards::default();
+    let block_number = block.number.ok_or(err("no block number"))?.as_u64();
+    let is_mainnet = source.chain_id == MAINNET_CHAIN_ID;
+    let base_reward = mainnet_base_reward(block_number);
+
+    if is_mainnet && schema.has_column("block_reward") {
+        rewards.block_reward = Some(base_reward + base_reward * block.uncles.len() / 32);
+    }
+
+    if is_mainnet && schema.has_column("uncle_reward") {
+        let mut uncle_reward = U256::zero();
+        for index in 0..block.uncles.len() {
+            let uncle = source
+                .get_uncle(block_number, index as u64)
+                .await?
+                .ok_or(err("uncle not found"))?;
+            let uncle_number = uncle.number.ok_or(err("no uncle block number"))?.as_u64();
+            uncle_reward += base_reward * (uncle_number + 8).saturating_sub(block_number) / 8;
+        }
+        rewards.uncle_reward = Some(uncle_reward);
+    }
+
+    if schema.has_column("total_fees") && block.transactions.is_empty() {
+        rewards.total_fees = Some(U256::zero());
+    } else if schema.has_column("total_fees") {
+        let receipts = source.get_block_receipts(block_number).await?;
+        // receipts of pre-london nodes may omit effective_gas_price, legacy transactions then pay
+        // the gas price of the transaction
+        let gas_prices: std::collections::HashMap<H256, U256> =
+            if receipts.iter().any(|receipt| receipt.effective_gas_price.is_none()) {
+                source
+                    .get_block_with_txs(block_number)
+                    .await?
+                    .ok_or(err("block not found"))?
+                    .transactions
+                    .into_iter()
+                    .filter_map(|tx| Some((tx.hash, tx.gas_price?)))
+                    .collect()
+            } else {
+                std::collections::HashMap::new()
+            };
+        rewards.total_fees = receipts.iter().try_fold(U256::zero(), |total, receipt| {
+            let gas_price = receipt
+                .effective_gas_price
+                .or_else(|| gas_prices.get(&receipt.transaction_hash).copied())?;
+            Some(total + receipt.gas_used? * gas_price)
+        });
     }
+
+    Ok(rewards)
 }
 
 /// process block into columns
-pub(crate) fn process_block<TX>(block: Block<TX>, columns: &mut Blocks, schema: &Table) -> R<()> {
+pub(crate) fn process_block<TX>(
+    block: Block<TX>,
+    rewards: BlockRewards,
+    columns: &mut Blocks,
+    schema: &Table,
+) -> R<()> {
     columns.n_rows += 1;
 
     store!(schema, columns, block_hash, block.hash.map(|x| x.0.to_vec()));
@@ -98,5 +230,139 @@ pub(crate) fn process_block<TX>(block: Block<TX>, columns: &mut Blocks, schema:
     store!(schema, columns, total_difficulty, block.total_difficulty);
     store!(schema, columns, base_fee_per_gas, block.base_fee_per_gas.map(|x| x.as_u64()));
     store!(schema, columns, size, block.size.map(|x| x.as_u32()));
+    store!(schema, columns, burnt_fees, block.base_fee_per_gas.map(|fee| fee * block.gas_used));
+    store!(schema, columns, block_reward, rewards.block_reward);
+    store!(schema, columns, uncle_reward, rewards.uncle_reward);
+    store!(schema, columns, total_fees, rewards.total_fees);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+
+    #[tokio::test]
+    async fn test_burnt_fees() {
+        let block = |base_fee_per_gas: Option<u64>| Block::<TxHash> {
+            number: Some(15_537_394.into()),
+            gas_used: 21_000.into(),
+            base_fee_per_gas: base_fee_per_gas.map(U256::from),
+            ..Default::default()
+        };
+        let query = make_query(Datatype::Blocks);
+        let schema = query.schemas.get_schema(&Datatype::Blocks).unwrap();
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+
+        let mut columns = Blocks::default();
+        for block in [block(Some(7_000_000_000)), block(None)] {
+            let rewards = BlockRewards::default();
+            process_block(block, rewards, &mut columns, schema).unwrap();
+        }
+        assert_eq!(columns.burnt_fees, vec![Some(U256::from(21_000u64 * 7_000_000_000)), None]);
+
+        // rewards that need no requests, e.g. the block reward of a post-merge block
+        let schema = Datatype::Blocks
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &Some(vec!["block_number".to_string(), "block_reward".to_string()]),
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let rewards = fetch_block_rewards(&block(None), &source, &schema).await.unwrap();
+        assert_eq!(rewards.block_reward, Some(U256::zero()));
+        assert_eq!(rewards.total_fees, None);
+        assert_eq!(mainnet_base_reward(4_369_999), U256::exp10(18) * 5);
+    }
+
+    #[tokio::test]
+    async fn test_total_fees_of_legacy_receipts() {
+        let (provider, mock) = Provider::mocked();
+        let source = make_source(provider);
+        let schema = Datatype::Blocks
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &Some(vec!["block_number".to_string(), "total_fees".to_string()]),
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let hashes = [H256::from_low_u64_be(1), H256::from_low_u64_be(2)];
+        let block = Block::<TxHash> {
+            number: Some(1_000_000.into()),
+            transactions: hashes.to_vec(),
+            ..Default::default()
+        };
+        // the first receipt omits effective_gas_price, as pre-london receipts of many nodes do
+        let receipts = vec![
+            TransactionReceipt {
+                transaction_hash: hashes[0],
+                gas_used: Some(21_000.into()),
+                ..Default::default()
+            },
+            TransactionReceipt {
+                transaction_hash: hashes[1],
+                gas_used: Some(50_000.into()),
+                effective_gas_price: Some(30.into()),
+                ..Default::default()
+            },
+        ];
+        let block_with_txs = Block::<Transaction> {
+            number: block.number,
+            transactions: vec![
+                Transaction { hash: hashes[0], gas_price: Some(20.into()), ..Default::default() },
+                Transaction { hash: hashes[1], gas_price: Some(30.into()), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        mock.push(block_with_txs).unwrap();
+        mock.push::<Vec<TransactionReceipt>, _>(receipts).unwrap();
+
+        let rewards = fetch_block_rewards(&block, &source, &schema).await.unwrap();
+        assert_eq!(rewards.total_fees, Some(U256::from(21_000 * 20 + 50_000 * 30)));
+        mock.assert_request("eth_getBlockReceipts", ["0xf4240"]).unwrap();
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0xf4240", true])).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_block_above_head_is_waited_for() {
+        let (provider, mock) = Provider::mocked();
+        let block = Block::<TxHash> { number: Some(100.into()), ..Default::default() };
+        // responses are popped from the back: the block is not found, the head is still below
+        // it, and the block is then found
+        mock.push(block).unwrap();
+        mock.push(U64::from(99)).unwrap();
+        mock.push::<Option<Block<TxHash>>, _>(None).unwrap();
+        let wait = HeadGap::Wait(3, std::time::Duration::from_millis(1));
+        let source = Source { head_gap: wait, ..(*make_source(provider)).clone() };
+        let found = source.get_block(100).await.unwrap();
+        assert_eq!(found.and_then(|block| block.number), Some(100.into()));
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", false])).unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", false])).unwrap();
+
+        // a block at or below the head is missing rather than not produced yet
+        mock.push(U64::from(120)).unwrap();
+        mock.push::<Option<Block<TxHash>>, _>(None).unwrap();
+        assert!(source.get_block(100).await.unwrap().is_none());
+
+        // without waiting, a block above the head is an error of its own
+        mock.push(U64::from(99)).unwrap();
+        mock.push::<Option<Block<TxHash>>, _>(None).unwrap();
+        let source = Source { head_gap: HeadGap::Skip, ..source };
+        let error = source.get_block(100).await.unwrap_err();
+        assert!(matches!(error, CollectError::BlockNotProduced(100)));
+    }
+}
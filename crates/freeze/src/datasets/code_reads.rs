@@ -17,7 +17,11 @@ pub struct CodeReads {
 }
 
 #[async_trait::async_trait]
-impl Dataset for CodeReads {}
+impl Dataset for CodeReads {
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
+}
 
 type BlockTxsTraces = (Option<u32>, Vec<Option<Vec<u8>>>, Vec<BTreeMap<H160, AccountState>>);
 
@@ -28,14 +28,22 @@ impl Dataset for Contracts {
     fn default_sort() -> Option<Vec<&'static str>> {
         Some(vec!["block_number", "create_index"])
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["trace_block"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("deployer", 20), ("factory", 20)].into_iter().collect()
+    }
 }
 
 #[async_trait::async_trait]
 impl CollectByBlock for Contracts {
     type Response = Vec<Trace>;
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
-        source.trace_block(request.ethers_block_number()?).await
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        traces::fetch_block_traces(request.block_number()?, &source, &query).await
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
@@ -23,6 +23,14 @@ impl Dataset for Erc20Balances {
     fn required_parameters() -> Vec<Dim> {
         vec![Dim::Contract, Dim::Address]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_call"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("erc20", 20)].into_iter().collect()
+    }
 }
 
 #[async_trait::async_trait]
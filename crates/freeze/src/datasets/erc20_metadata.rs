@@ -31,6 +31,14 @@ impl Dataset for Erc20Metadata {
     fn arg_aliases() -> Option<std::collections::HashMap<Dim, Dim>> {
         Some([(Dim::Contract, Dim::Address)].into_iter().collect())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_call"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("erc20", 20)].into_iter().collect()
+    }
 }
 
 pub(crate) fn remove_control_characters(s: &str) -> String {
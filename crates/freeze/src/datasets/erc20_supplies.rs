@@ -30,6 +30,14 @@ impl Dataset for Erc20Supplies {
     fn default_blocks() -> Option<String> {
         Some("latest".to_string())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_call"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("erc20", 20)].into_iter().collect()
+    }
 }
 
 #[async_trait::async_trait]
@@ -47,6 +47,14 @@ impl Dataset for Erc20Transfers {
     fn arg_aliases() -> Option<std::collections::HashMap<Dim, Dim>> {
         Some([(Dim::Contract, Dim::Address)].into_iter().collect())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getLogs"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("erc20", 20)].into_iter().collect()
+    }
 }
 
 #[async_trait::async_trait]
@@ -2,7 +2,7 @@ use crate::*;
 use ethers::prelude::*;
 use polars::prelude::*;
 
-/// columns for transactions
+/// columns for erc721 transfers
 #[cryo_to_df::to_df(Datatype::Erc721Transfers)]
 #[derive(Default)]
 pub struct Erc721Transfers {
@@ -12,7 +12,7 @@ pub struct Erc721Transfers {
     transaction_index: Vec<u32>,
     log_index: Vec<u32>,
     transaction_hash: Vec<Vec<u8>>,
-    erc20: Vec<Vec<u8>>,
+    erc721: Vec<Vec<u8>>,
     from_address: Vec<Vec<u8>>,
     to_address: Vec<Vec<u8>>,
     token_id: Vec<U256>,
@@ -28,7 +28,7 @@ impl Dataset for Erc721Transfers {
             "transaction_index",
             "log_index",
             "transaction_hash",
-            "erc20",
+            "erc721",
             "from_address",
             "to_address",
             "token_id",
@@ -47,6 +47,14 @@ impl Dataset for Erc721Transfers {
     fn arg_aliases() -> Option<std::collections::HashMap<Dim, Dim>> {
         Some([(Dim::Contract, Dim::Address)].into_iter().collect())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getLogs"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("erc721", 20)].into_iter().collect()
+    }
 }
 
 #[async_trait::async_trait]
@@ -69,7 +77,7 @@ impl CollectByBlock for Erc721Transfers {
         let filter = Filter { topics, ..request.ethers_log_filter()? };
         let logs = source.get_logs(&filter).await?;
 
-        Ok(logs.into_iter().filter(|x| x.topics.len() == 4 && x.data.len() == 0).collect())
+        Ok(logs.into_iter().filter(is_erc721_transfer).collect())
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
@@ -93,6 +101,7 @@ impl CollectByTransaction for Erc721Transfers {
     }
 }
 
+/// erc20 and erc721 share the Transfer signature, erc721 also indexes the token_id
 fn is_erc721_transfer(log: &Log) -> bool {
     log.topics.len() == 4 && log.data.len() == 0 && log.topics[0] == *EVENT_ERC721_TRANSFER
 }
@@ -113,7 +122,7 @@ fn process_erc721_transfers(
             store!(schema, columns, transaction_index, ti.as_u32());
             store!(schema, columns, log_index, li.as_u32());
             store!(schema, columns, transaction_hash, tx.as_bytes().to_vec());
-            store!(schema, columns, erc20, log.address.as_bytes().to_vec());
+            store!(schema, columns, erc721, log.address.as_bytes().to_vec());
             store!(schema, columns, from_address, log.topics[1].as_bytes()[12..].to_vec());
             store!(schema, columns, to_address, log.topics[2].as_bytes()[12..].to_vec());
             store!(schema, columns, token_id, log.topics[3].as_bytes().into());
@@ -121,3 +130,71 @@ fn process_erc721_transfers(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::make_query;
+
+    fn address_topic(address: u64) -> H256 {
+        H256::from(H160::from_low_u64_be(address))
+    }
+
+    #[test]
+    fn test_erc721_transfers_schema() {
+        let query = make_query(Datatype::Erc721Transfers);
+        let schema = query.schemas.get_schema(&Datatype::Erc721Transfers).unwrap();
+        for column in [
+            "block_number",
+            "transaction_hash",
+            "log_index",
+            "erc721",
+            "from_address",
+            "to_address",
+        ] {
+            assert!(schema.has_column(column), "{}", column);
+        }
+        assert!(!schema.has_column("erc20"));
+        assert_eq!(schema.column_type("token_id"), Some(ColumnType::UInt256));
+    }
+
+    #[test]
+    fn test_decode_erc721_transfer() {
+        let erc721_transfer = Log {
+            address: H160::from_low_u64_be(10),
+            topics: vec![
+                *EVENT_ERC721_TRANSFER,
+                address_topic(1),
+                address_topic(2),
+                H256::from_low_u64_be(1234),
+            ],
+            block_number: Some(100.into()),
+            transaction_hash: Some(H256::from_low_u64_be(5)),
+            transaction_index: Some(0.into()),
+            log_index: Some(3.into()),
+            ..Default::default()
+        };
+        // erc20 transfers index only the addresses and hold the amount in data
+        let erc20_transfer = Log {
+            address: H160::from_low_u64_be(20),
+            topics: vec![*EVENT_ERC20_TRANSFER, address_topic(1), address_topic(2)],
+            data: H256::from_low_u64_be(1234).as_bytes().to_vec().into(),
+            log_index: Some(4.into()),
+            ..erc721_transfer.clone()
+        };
+        let logs: Vec<Log> =
+            vec![erc721_transfer, erc20_transfer].into_iter().filter(is_erc721_transfer).collect();
+        let query = make_query(Datatype::Erc721Transfers);
+        let schema = query.schemas.get_schema(&Datatype::Erc721Transfers).unwrap();
+
+        let mut columns = Erc721Transfers::default();
+        process_erc721_transfers(logs, &mut columns, schema).unwrap();
+
+        assert_eq!(columns.n_rows, 1);
+        assert_eq!(columns.log_index, vec![3]);
+        assert_eq!(columns.erc721, vec![H160::from_low_u64_be(10).as_bytes().to_vec()]);
+        assert_eq!(columns.from_address, vec![H160::from_low_u64_be(1).as_bytes().to_vec()]);
+        assert_eq!(columns.to_address, vec![H160::from_low_u64_be(2).as_bytes().to_vec()]);
+        assert_eq!(columns.token_id, vec![U256::from(1234)]);
+    }
+}
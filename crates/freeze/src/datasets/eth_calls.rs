@@ -13,11 +13,17 @@ pub struct EthCalls {
     call_data_hash: Vec<Vec<u8>>,
     output_data: Vec<Vec<u8>>,
     output_data_hash: Vec<Vec<u8>>,
+    // output decoded by the call output decoder of the schema
+    dynamic_cols: indexmap::IndexMap<String, CallOutputValues>,
     chain_id: Vec<u64>,
 }
 
 #[async_trait::async_trait]
 impl Dataset for EthCalls {
+    fn aliases() -> Vec<&'static str> {
+        vec!["calls"]
+    }
+
     fn default_columns() -> Option<Vec<&'static str>> {
         Some(vec!["block_number", "contract_address", "call_data", "output_data", "chain_id"])
     }
@@ -37,6 +43,10 @@ impl Dataset for EthCalls {
     fn required_parameters() -> Vec<Dim> {
         vec![Dim::Contract, Dim::CallData]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_call"]
+    }
 }
 
 type EthCallsResponse = (u32, Vec<u8>, Vec<u8>, Vec<u8>);
@@ -75,5 +85,99 @@ fn process_eth_call(response: EthCallsResponse, columns: &mut EthCalls, schema:
     store!(schema, columns, call_data, call_data.clone());
     store!(schema, columns, call_data_hash, ethers_core::utils::keccak256(call_data).into());
     store!(schema, columns, output_data, output_data.to_vec());
+    if let Some(decoder) = &schema.call_output_decoder {
+        let name = decoder.column_name();
+        if schema.has_column(&name) {
+            let values = columns
+                .dynamic_cols
+                .entry(name)
+                .or_insert_with(|| decoder.values(&schema.binary_type));
+            decoder.push_output(&output_data, values);
+        }
+    }
     store!(schema, columns, output_data_hash, ethers_core::utils::keccak256(output_data).into());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_eth_calls_schema() {
+        assert_eq!(Datatype::from_str("calls").unwrap(), Datatype::EthCalls);
+        let schema = |encoding: ColumnEncoding| {
+            Datatype::EthCalls
+                .table_schema(&[], &encoding, &None, &None, &None, &None, None, None, false)
+                .unwrap()
+        };
+        let hex = schema(ColumnEncoding::Hex);
+        assert_eq!(
+            hex.columns(),
+            vec!["block_number", "contract_address", "call_data", "output_data", "chain_id"]
+        );
+        assert_eq!(hex.column_type("call_data"), Some(ColumnType::Hex));
+        assert_eq!(hex.column_type("output_data"), Some(ColumnType::Hex));
+        let binary = schema(ColumnEncoding::Binary);
+        assert_eq!(binary.column_type("output_data"), Some(ColumnType::Binary));
+    }
+
+    #[tokio::test]
+    async fn test_eth_calls_over_blocks() {
+        let (provider, mock) = Provider::mocked();
+        // responses are popped from the back, so the output of block 100 is pushed last
+        mock.push::<Bytes, _>(Bytes::from(vec![2u8; 32])).unwrap();
+        mock.push::<Bytes, _>(Bytes::from(vec![1u8; 32])).unwrap();
+        let source = make_source(provider);
+        let query = Arc::new(make_query(Datatype::EthCalls));
+        let contract = vec![0x11u8; 20];
+        // totalSupply()
+        let call_data = vec![0x18u8, 0x16, 0x0d, 0xdd];
+
+        let mut columns = EthCalls::default();
+        for number in [100u64, 101] {
+            let request = Params {
+                block_number: Some(number),
+                contract: Some(contract.clone()),
+                call_data: Some(call_data.clone()),
+                ..Default::default()
+            };
+            let response =
+                <EthCalls as CollectByBlock>::extract(request, source.clone(), query.clone())
+                    .await
+                    .unwrap();
+            <EthCalls as CollectByBlock>::transform(response, &mut columns, &query).unwrap();
+        }
+        assert_eq!(columns.n_rows, 2);
+        assert_eq!(columns.block_number, vec![100, 101]);
+        assert_eq!(columns.contract_address, vec![contract.clone(), contract]);
+        assert_eq!(columns.call_data, vec![call_data.clone(), call_data]);
+        assert_eq!(columns.output_data, vec![vec![1u8; 32], vec![2u8; 32]]);
+        assert_ne!(columns.output_data_hash[0], columns.output_data_hash[1]);
+    }
+
+    #[test]
+    fn test_decode_uint256_output() {
+        let mut query = make_query(Datatype::EthCalls);
+        let decoder = CallOutputDecoder::new("uint256".to_string()).unwrap();
+        let schema = query.schemas.get_mut(&Datatype::EthCalls).unwrap();
+        schema.set_call_output_decoder(decoder);
+        assert_eq!(schema.column_type("decoded_output_d128"), Some(ColumnType::Decimal128));
+        let schema = query.schemas.get_schema(&Datatype::EthCalls).unwrap();
+
+        // outputs that are empty or too short for a uint256 are decoded as null
+        let supply = U256::exp10(24);
+        let encoded = ethers_core::abi::encode(&[ethers_core::abi::Token::Uint(supply)]);
+        let outputs = [encoded, vec![], vec![1u8; 4]];
+        let mut columns = EthCalls::default();
+        for output in outputs {
+            process_eth_call((100, vec![0x11; 20], vec![], output), &mut columns, schema);
+        }
+        let df =
+            columns.create_dfs(&query.schemas, 1).unwrap().remove(&Datatype::EthCalls).unwrap();
+        let decoded: Vec<_> =
+            df.column("decoded_output_d128").unwrap().utf8().unwrap().into_iter().collect();
+        assert_eq!(decoded, vec![Some("1000000000000000000000000"), None, None]);
+    }
+}
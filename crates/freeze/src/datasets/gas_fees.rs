@@ -0,0 +1,161 @@
+use crate::*;
+use ethers::prelude::*;
+use polars::prelude::*;
+
+/// priority fee percentiles collected when none are configured
+pub const DEFAULT_REWARD_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// most blocks that nodes return from a single eth_feeHistory request
+pub const MAX_FEE_HISTORY_BLOCKS: u64 = 1024;
+
+/// name of the reward column of a priority fee percentile, e.g. reward_p50 or reward_p12_5
+pub fn reward_column_name(percentile: f64) -> String {
+    format!("reward_p{}", percentile.to_string().replace('.', "_"))
+}
+
+/// columns for gas fees
+#[cryo_to_df::to_df(Datatype::GasFees)]
+#[derive(Default)]
+pub struct GasFees {
+    n_rows: u64,
+    block_number: Vec<u32>,
+    base_fee_per_gas: Vec<Option<u64>>,
+    gas_used_ratio: Vec<f64>,
+    // priority fee of each reward percentile of the schema
+    dynamic_cols: indexmap::IndexMap<String, Vec<Option<u64>>>,
+    chain_id: Vec<u64>,
+}
+
+#[async_trait::async_trait]
+impl Dataset for GasFees {
+    fn use_block_ranges() -> bool {
+        true
+    }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_feeHistory"]
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByBlock for GasFees {
+    type Response = Vec<FeeHistory>;
+
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let schema = query.schemas.get_schema(&Datatype::GasFees)?;
+        let (start, end) = request.block_range()?;
+        let mut responses = Vec::new();
+        for (block_count, last_block) in fee_history_requests(start, end) {
+            let response =
+                source.fee_history(block_count, last_block.into(), &schema.reward_percentiles);
+            responses.push(response.await?);
+        }
+        Ok(responses)
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::GasFees)?;
+        for fee_history in response.into_iter() {
+            process_fee_history(fee_history, columns, schema)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByTransaction for GasFees {
+    type Response = ();
+}
+
+/// block count and last block of each eth_feeHistory request covering a block range
+fn fee_history_requests(start: u64, end: u64) -> Vec<(u64, u64)> {
+    let mut requests = Vec::new();
+    let mut first = start;
+    while first <= end {
+        let last = end.min(first + MAX_FEE_HISTORY_BLOCKS - 1);
+        requests.push((last - first + 1, last));
+        first = last + 1;
+    }
+    requests
+}
+
+/// process fee history into columns, one row per block, fees too large for a u64 are null
+fn process_fee_history(response: FeeHistory, columns: &mut GasFees, schema: &Table) -> R<()> {
+    // reward columns are created even when the response covers no blocks
+    let reward_columns: Vec<(usize, String)> = schema
+        .reward_percentiles
+        .iter()
+        .map(|percentile| reward_column_name(*percentile))
+        .enumerate()
+        .filter(|(_, name)| schema.has_column(name))
+        .collect();
+    for (_, name) in reward_columns.iter() {
+        columns.dynamic_cols.entry(name.clone()).or_default();
+    }
+
+    let oldest_block = response.oldest_block.as_u64();
+    for (index, gas_used_ratio) in response.gas_used_ratio.iter().enumerate() {
+        let base_fee_per_gas = response
+            .base_fee_per_gas
+            .get(index)
+            .ok_or_else(|| err("fee history is missing base fee of block"))?;
+        columns.n_rows += 1;
+        store!(schema, columns, block_number, (oldest_block + index as u64) as u32);
+        store!(schema, columns, base_fee_per_gas, u64::try_from(*base_fee_per_gas).ok());
+        store!(schema, columns, gas_used_ratio, *gas_used_ratio);
+        for (percentile_index, name) in reward_columns.iter() {
+            let reward = response
+                .reward
+                .get(index)
+                .and_then(|rewards| rewards.get(*percentile_index))
+                .ok_or_else(|| err("fee history is missing reward of block"))?;
+            if let Some(values) = columns.dynamic_cols.get_mut(name) {
+                values.push(u64::try_from(*reward).ok());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::make_query;
+
+    #[test]
+    fn test_fee_history_requests() {
+        assert_eq!(fee_history_requests(100, 100), vec![(1, 100)]);
+        assert_eq!(fee_history_requests(0, 1023), vec![(1024, 1023)]);
+        assert_eq!(fee_history_requests(0, 2999), vec![(1024, 1023), (1024, 2047), (952, 2999)]);
+    }
+
+    #[test]
+    fn test_decode_fee_history() {
+        let response = FeeHistory {
+            // fee history includes the base fee of the block after the range
+            base_fee_per_gas: vec![100.into(), U256::MAX, 121.into()],
+            gas_used_ratio: vec![0.5, 0.75],
+            oldest_block: 1000.into(),
+            reward: vec![vec![1.into(), 2.into(), 3.into()], vec![4.into(), 5.into(), U256::MAX]],
+        };
+        let query = make_query(Datatype::GasFees);
+        let schema = query.schemas.get_schema(&Datatype::GasFees).unwrap();
+
+        let mut columns = GasFees::default();
+        process_fee_history(response, &mut columns, schema).unwrap();
+        let dfs = columns.create_dfs(&query.schemas, 1).unwrap();
+        let df = &dfs[&Datatype::GasFees];
+
+        assert_eq!(df.height(), 2);
+        let column = |name: &str| -> Vec<Option<u64>> {
+            df.column(name).unwrap().u64().unwrap().into_iter().collect()
+        };
+        // fees too large for a u64 are null rather than a panic
+        assert_eq!(column("base_fee_per_gas"), vec![Some(100), None]);
+        assert_eq!(column("reward_p10"), vec![Some(1), Some(4)]);
+        assert_eq!(column("reward_p90"), vec![Some(3), None]);
+        let blocks: Vec<_> =
+            df.column("block_number").unwrap().u32().unwrap().into_iter().collect();
+        assert_eq!(blocks, vec![Some(1000), Some(1001)]);
+    }
+}
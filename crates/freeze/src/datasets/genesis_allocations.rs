@@ -0,0 +1,195 @@
+use crate::*;
+use ethers::prelude::*;
+use polars::prelude::*;
+
+/// columns for genesis allocations
+///
+/// accounts allocated by the genesis file of the chain, given by the genesis path of the query.
+/// the allocation is not tied to a block range, so it is collected once per run for block 0
+#[cryo_to_df::to_df(Datatype::GenesisAllocations)]
+#[derive(Default)]
+pub struct GenesisAllocations {
+    n_rows: u64,
+    address: Vec<Vec<u8>>,
+    balance: Vec<U256>,
+    nonce: Vec<u64>,
+    code: Vec<Vec<u8>>,
+    // json object of storage slots and their values, null for accounts without storage
+    storage: Vec<Option<String>>,
+    chain_id: Vec<u64>,
+}
+
+#[async_trait::async_trait]
+impl Dataset for GenesisAllocations {
+    fn aliases() -> Vec<&'static str> {
+        vec!["genesis", "genesis_alloc"]
+    }
+
+    fn default_sort() -> Option<Vec<&'static str>> {
+        Some(vec!["address"])
+    }
+
+    fn default_blocks() -> Option<String> {
+        Some("0".to_string())
+    }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec![]
+    }
+}
+
+/// account allocated by a genesis file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenesisAccount {
+    /// address of account
+    pub address: H160,
+    /// balance of account
+    pub balance: U256,
+    /// nonce of account
+    pub nonce: u64,
+    /// code of account
+    pub code: Vec<u8>,
+    /// storage slots of account, as a json object
+    pub storage: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl CollectByBlock for GenesisAllocations {
+    type Response = Vec<GenesisAccount>;
+
+    async fn extract(_: Params, _: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let path = query.genesis_path.as_ref().ok_or_else(|| {
+            err("genesis_allocations needs a genesis file, cryo has no built-in allocations")
+        })?;
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| err(format!("could not read genesis file {}: {}", path, e).as_str()))?;
+        parse_genesis(&json)
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::GenesisAllocations)?;
+        for account in response.into_iter() {
+            columns.n_rows += 1;
+            store!(schema, columns, address, account.address.as_bytes().to_vec());
+            store!(schema, columns, balance, account.balance);
+            store!(schema, columns, nonce, account.nonce);
+            store!(schema, columns, code, account.code);
+            store!(schema, columns, storage, account.storage);
+        }
+        Ok(())
+    }
+}
+
+impl CollectByTransaction for GenesisAllocations {
+    type Response = ();
+}
+
+/// parse the accounts of a genesis file, either a full genesis with an `alloc` object or the
+/// `alloc` object by itself
+pub fn parse_genesis(json: &str) -> R<Vec<GenesisAccount>> {
+    let genesis: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| err(format!("invalid genesis: {}", e).as_str()))?;
+    let alloc = genesis.get("alloc").unwrap_or(&genesis);
+    let alloc = alloc.as_object().ok_or_else(|| err("genesis alloc is not an object"))?;
+    let mut accounts = Vec::new();
+    for (address, account) in alloc.iter() {
+        let invalid =
+            |field: &str| err(format!("invalid {} of {} in genesis", field, address).as_str());
+        let address = decode_hex(address)
+            .filter(|bytes| bytes.len() == 20)
+            .map(|bytes| H160::from_slice(&bytes))
+            .ok_or_else(|| invalid("address"))?;
+        let balance = match account.get("balance") {
+            Some(value) => parse_quantity(value).ok_or_else(|| invalid("balance"))?,
+            None => U256::zero(),
+        };
+        let nonce = match account.get("nonce") {
+            Some(value) => parse_quantity(value)
+                .filter(|nonce| *nonce <= U256::from(u64::MAX))
+                .ok_or_else(|| invalid("nonce"))?
+                .as_u64(),
+            None => 0,
+        };
+        let code = match account.get("code").and_then(|code| code.as_str()) {
+            Some(code) => decode_hex(code).ok_or_else(|| invalid("code"))?,
+            None => vec![],
+        };
+        let storage = match account.get("storage") {
+            Some(serde_json::Value::Object(slots)) if !slots.is_empty() => {
+                Some(serde_json::to_string(slots).map_err(|_| invalid("storage"))?)
+            }
+            _ => None,
+        };
+        accounts.push(GenesisAccount { address, balance, nonce, code, storage });
+    }
+    accounts.sort_by_key(|account| account.address);
+    Ok(accounts)
+}
+
+/// decode hex string, which genesis files can write with or without a 0x prefix
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    prefix_hex::decode(format!("0x{}", value)).ok()
+}
+
+/// parse a quantity given as a hex or decimal string, or as a number
+fn parse_quantity(value: &serde_json::Value) -> Option<U256> {
+    match value {
+        serde_json::Value::String(value) => match value.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).ok(),
+            None => U256::from_dec_str(value).ok(),
+        },
+        serde_json::Value::Number(value) => value.as_u64().map(U256::from),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_genesis() {
+        let genesis = r#"{
+            "config": { "chainId": 1337 },
+            "alloc": {
+                "0x0000000000000000000000000000000000000001": { "balance": "1000000000000000000" },
+                "0000000000000000000000000000000000000002": {
+                    "balance": "0x10",
+                    "nonce": "0x2",
+                    "code": "0x6000",
+                    "storage": {
+                        "0x0000000000000000000000000000000000000000000000000000000000000000": "0x01"
+                    }
+                }
+            }
+        }"#;
+        let accounts = parse_genesis(genesis).unwrap();
+        assert_eq!(
+            accounts,
+            vec![
+                GenesisAccount {
+                    address: H160::from_low_u64_be(1),
+                    balance: U256::exp10(18),
+                    ..Default::default()
+                },
+                GenesisAccount {
+                    address: H160::from_low_u64_be(2),
+                    balance: 16.into(),
+                    nonce: 2,
+                    code: vec![0x60, 0x00],
+                    storage: Some(
+                        r#"{"0x0000000000000000000000000000000000000000000000000000000000000000":"0x01"}"#
+                            .to_string()
+                    ),
+                },
+            ]
+        );
+
+        // alloc objects by themselves are accepted, malformed accounts are rejected
+        let alloc = r#"{ "0x0000000000000000000000000000000000000003": { "balance": "0x0" } }"#;
+        assert_eq!(parse_genesis(alloc).unwrap().len(), 1);
+        let invalid = r#"{ "alloc": { "0x03": { "balance": "0x0" } } }"#;
+        assert!(parse_genesis(invalid).unwrap_err().to_string().contains("invalid address"));
+    }
+}
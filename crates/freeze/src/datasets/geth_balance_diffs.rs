@@ -17,7 +17,11 @@ pub struct GethBalanceDiffs {
 }
 
 #[async_trait::async_trait]
-impl Dataset for GethBalanceDiffs {}
+impl Dataset for GethBalanceDiffs {
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
+}
 
 #[async_trait::async_trait]
 impl CollectByBlock for GethBalanceDiffs {
@@ -24,7 +24,11 @@ pub struct GethCalls {
 }
 
 #[async_trait::async_trait]
-impl Dataset for GethCalls {}
+impl Dataset for GethCalls {
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
+}
 
 #[async_trait::async_trait]
 impl CollectByBlock for GethCalls {
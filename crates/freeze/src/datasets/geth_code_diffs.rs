@@ -16,7 +16,11 @@ pub struct GethCodeDiffs {
 }
 
 #[async_trait::async_trait]
-impl Dataset for GethCodeDiffs {}
+impl Dataset for GethCodeDiffs {
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
+}
 
 #[async_trait::async_trait]
 impl CollectByBlock for GethCodeDiffs {
@@ -17,7 +17,11 @@ pub struct GethNonceDiffs {
 }
 
 #[async_trait::async_trait]
-impl Dataset for GethNonceDiffs {}
+impl Dataset for GethNonceDiffs {
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
+}
 
 #[async_trait::async_trait]
 impl CollectByBlock for GethNonceDiffs {
@@ -32,6 +32,10 @@ impl Dataset for GethOpcodes {
         let f = |x: &&str| x != &"memory" && x != &"stack" && x != &"storage";
         Some(GethOpcodes::column_types().into_keys().filter(f).collect())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
 }
 
 #[async_trait::async_trait]
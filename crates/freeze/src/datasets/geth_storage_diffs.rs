@@ -16,7 +16,11 @@ pub struct GethStorageDiffs {
     pub(crate) chain_id: Vec<u64>,
 }
 
-impl Dataset for GethStorageDiffs {}
+impl Dataset for GethStorageDiffs {
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
+}
 
 #[async_trait::async_trait]
 impl CollectByBlock for GethStorageDiffs {
@@ -18,6 +18,10 @@ impl Dataset for JavascriptTraces {
     fn aliases() -> Vec<&'static str> {
         vec!["js_traces"]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
 }
 
 type BlockTxsTraces = (Option<u32>, Vec<Option<Vec<u8>>>, Vec<serde_json::Value>);
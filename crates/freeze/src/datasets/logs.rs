@@ -9,8 +9,12 @@ pub struct Logs {
     n_rows: u64,
     block_number: Vec<u32>,
     block_hash: Vec<Option<Vec<u8>>>,
+    // position of the transaction within its block
     transaction_index: Vec<u32>,
+    // position of the log within its block, counted across all transactions
     log_index: Vec<u32>,
+    // position of the log within its transaction, only some clients report this
+    transaction_log_index: Vec<Option<u32>>,
     transaction_hash: Vec<Vec<u8>>,
     address: Vec<Vec<u8>>,
     topic0: Vec<Option<Vec<u8>>>,
@@ -46,6 +50,29 @@ impl Dataset for Logs {
         ])
     }
 
+    fn presets() -> std::collections::HashMap<&'static str, Vec<&'static str>> {
+        [
+            ("minimal", vec!["block_number", "log_index", "transaction_hash", "address", "topic0"]),
+            (
+                "analytics",
+                vec![
+                    "block_number",
+                    "transaction_index",
+                    "log_index",
+                    "transaction_hash",
+                    "address",
+                    "topic0",
+                    "topic1",
+                    "topic2",
+                    "topic3",
+                    "data",
+                ],
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+
     fn optional_parameters() -> Vec<Dim> {
         vec![Dim::Address, Dim::Topic0, Dim::Topic1, Dim::Topic2, Dim::Topic3]
     }
@@ -57,14 +84,29 @@ impl Dataset for Logs {
     fn arg_aliases() -> Option<std::collections::HashMap<Dim, Dim>> {
         Some([(Dim::Contract, Dim::Address)].into_iter().collect())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getLogs"]
+    }
 }
 
 #[async_trait::async_trait]
 impl CollectByBlock for Logs {
     type Response = Vec<Log>;
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
-        source.get_logs(&request.ethers_log_filter()?).await
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let filter = request.ethers_log_filter_merged(&query.log_filter)?;
+        let logs = match query.logs_backend {
+            LogsBackend::GetLogs => source.get_logs(&filter).await?,
+            LogsBackend::Filter => source.get_filter_logs(&filter).await?,
+            LogsBackend::Paginated(page_size) => {
+                source.get_logs_paginated(&filter, page_size).await?
+            }
+        };
+        match query.deduplicate_logs {
+            true => keep_canonical_logs(logs, &source).await,
+            false => Ok(logs),
+        }
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
@@ -87,6 +129,32 @@ impl CollectByTransaction for Logs {
     }
 }
 
+/// keep logs of canonical blocks, once per (block_hash, log_index)
+///
+/// logs collected across a reorg can come from both the old and the new block of a height,
+/// the canonical hash of each height is fetched to tell them apart
+async fn keep_canonical_logs(logs: Vec<Log>, source: &Source) -> R<Vec<Log>> {
+    let block_numbers: std::collections::BTreeSet<u64> =
+        logs.iter().filter_map(|log| log.block_number.map(|x| x.as_u64())).collect();
+    let mut canonical_hashes = std::collections::HashMap::new();
+    for block_number in block_numbers.into_iter() {
+        let block = source.get_block(block_number).await?.ok_or(err("block not found"))?;
+        canonical_hashes.insert(block_number, block.hash);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    Ok(logs
+        .into_iter()
+        .filter(|log| match log.block_number {
+            Some(block_number) => {
+                canonical_hashes.get(&block_number.as_u64()) == Some(&log.block_hash)
+            }
+            None => true,
+        })
+        .filter(|log| seen.insert((log.block_hash, log.log_index)))
+        .collect())
+}
+
 /// process block into columns
 fn process_logs(logs: Vec<Log>, columns: &mut Logs, schema: &Table) -> R<()> {
     let decode_keys = match &schema.log_decoder {
@@ -126,6 +194,12 @@ fn process_logs(logs: Vec<Log>, columns: &mut Logs, schema: &Table) -> R<()> {
             store!(schema, columns, block_hash, log.block_hash.map(|bh| bh.as_bytes().to_vec()));
             store!(schema, columns, transaction_index, ti.as_u32());
             store!(schema, columns, log_index, li.as_u32());
+            store!(
+                schema,
+                columns,
+                transaction_log_index,
+                log.transaction_log_index.map(|tli| tli.as_u32())
+            );
             store!(schema, columns, transaction_hash, tx.as_bytes().to_vec());
             store!(schema, columns, address, log.address.as_bytes().to_vec());
             store!(schema, columns, data, log.data.to_vec());
@@ -150,3 +224,268 @@ fn process_logs(logs: Vec<Log>, columns: &mut Logs, schema: &Table) -> R<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+
+    #[tokio::test]
+    async fn test_keep_canonical_logs_after_reorg() {
+        let stale_hash = H256::from_low_u64_be(1);
+        let canonical_hash = H256::from_low_u64_be(2);
+        let make_log = |block_number: u64, block_hash: H256, log_index: u64| Log {
+            block_number: Some(block_number.into()),
+            block_hash: Some(block_hash),
+            log_index: Some(log_index.into()),
+            ..Default::default()
+        };
+        // block 100 was replaced, its new logs have different log indexes
+        let logs = vec![
+            make_log(100, stale_hash, 0),
+            make_log(100, stale_hash, 1),
+            make_log(100, canonical_hash, 0),
+            make_log(100, canonical_hash, 0),
+            make_log(101, H256::from_low_u64_be(3), 0),
+        ];
+        let (provider, mock) = Provider::mocked();
+        for (number, hash) in [(101u64, H256::from_low_u64_be(3)), (100, canonical_hash)] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                hash: Some(hash),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let source = make_source(provider);
+
+        let logs = keep_canonical_logs(logs, &source).await.unwrap();
+        let kept: Vec<_> = logs.iter().map(|log| (log.block_hash, log.log_index)).collect();
+        assert_eq!(
+            kept,
+            vec![
+                (Some(canonical_hash), Some(0.into())),
+                (Some(H256::from_low_u64_be(3)), Some(0.into()))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_block_timestamp_is_joined_into_logs() {
+        let (provider, mock) = Provider::mocked();
+        // each block is requested once, even though block 100 has two logs
+        for (number, timestamp) in [(101u64, 1_700_000_012u64), (100, 1_700_000_000)] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                timestamp: timestamp.into(),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let make_log = |block_number: u64, log_index: u64| Log {
+            block_number: Some(block_number.into()),
+            transaction_hash: Some(H256::zero()),
+            transaction_index: Some(0.into()),
+            log_index: Some(log_index.into()),
+            ..Default::default()
+        };
+        mock.push::<Vec<Log>, _>(vec![make_log(100, 0), make_log(100, 1), make_log(101, 0)])
+            .unwrap();
+        let source = Arc::new(Source { inner_request_size: 2, ..(*make_source(provider)).clone() });
+
+        let include = Some(vec!["block_timestamp".to_string()]);
+        let schema = Datatype::Logs
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Binary,
+                &include,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let query = Query {
+            schemas: std::collections::HashMap::from([(Datatype::Logs, schema)]),
+            ..make_query(Datatype::Logs)
+        };
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 101)]),
+            ..Default::default()
+        };
+
+        let dfs =
+            Logs::collect_by_block(partition, source, Arc::new(query), Some(2)).await.unwrap();
+        let df = &dfs[&Datatype::Logs];
+        let timestamps: Vec<_> =
+            df.column("block_timestamp").unwrap().u32().unwrap().into_iter().collect();
+        assert_eq!(timestamps, vec![Some(1_700_000_000), Some(1_700_000_000), Some(1_700_000_012)]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_range_is_retried_block_by_block() {
+        let rpc_error = || {
+            MockResponse::Error(JsonRpcError {
+                code: -32000,
+                message: "block unavailable".to_string(),
+                data: None,
+            })
+        };
+        let make_log = |block_number: u64| Log {
+            block_number: Some(block_number.into()),
+            transaction_hash: Some(H256::zero()),
+            transaction_index: Some(0.into()),
+            log_index: Some(0.into()),
+            ..Default::default()
+        };
+        // the range request fails, then blocks 100, 101, and 102 are requested in order
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Vec<Log>, _>(vec![make_log(102)]).unwrap();
+        mock.push_response(rpc_error());
+        mock.push::<Vec<Log>, _>(vec![make_log(100)]).unwrap();
+        mock.push_response(rpc_error());
+        let source = Arc::new(Source {
+            inner_request_size: 3,
+            retry_failed_blocks_individually: true,
+            ..(*make_source(provider)).clone()
+        });
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 102)]),
+            ..Default::default()
+        };
+        let query = Arc::new(make_query(Datatype::Logs));
+
+        let result = Logs::collect_by_block(partition, source, query, Some(3)).await;
+        let Err(CollectError::PartialChunk(dfs, failed_blocks)) = result else {
+            panic!("expected a partial chunk")
+        };
+        let blocks: Vec<_> = dfs[&Datatype::Logs]
+            .column("block_number")
+            .unwrap()
+            .u32()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(blocks, vec![Some(100), Some(102)]);
+        let failed: Vec<u64> =
+            failed_blocks.iter().map(|(block_number, _)| *block_number).collect();
+        assert_eq!(failed, vec![101]);
+    }
+
+    #[tokio::test]
+    async fn test_filter_backend_reinstalls_expired_filter() {
+        let log = Log {
+            block_number: Some(100.into()),
+            transaction_hash: Some(H256::zero()),
+            transaction_index: Some(0.into()),
+            log_index: Some(0.into()),
+            ..Default::default()
+        };
+        // the first filter expires before its logs are read, so a second filter is installed,
+        // which the second chunk then reuses
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Vec<Log>, _>(vec![log.clone()]).unwrap();
+        mock.push::<Vec<Log>, _>(vec![log]).unwrap();
+        mock.push(U256::from(2)).unwrap();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32000,
+            message: "filter not found".to_string(),
+            data: None,
+        }));
+        mock.push(U256::from(1)).unwrap();
+        let source = make_source(provider);
+        let query =
+            Arc::new(Query { logs_backend: LogsBackend::Filter, ..make_query(Datatype::Logs) });
+
+        for _ in 0..2 {
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Range(100, 100)]),
+                ..Default::default()
+            };
+            let dfs = Logs::collect_by_block(partition, source.clone(), query.clone(), Some(1))
+                .await
+                .unwrap();
+            assert_eq!(dfs[&Datatype::Logs].height(), 1);
+        }
+        let ids: Vec<U256> = source.log_filters.lock().unwrap().values().copied().collect();
+        assert_eq!(ids, vec![U256::from(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_paginated_backend_concatenates_pages() {
+        let log = |log_index: u64| Log {
+            block_number: Some(100.into()),
+            transaction_hash: Some(H256::zero()),
+            transaction_index: Some(0.into()),
+            log_index: Some(log_index.into()),
+            ..Default::default()
+        };
+        // responses are popped from the back, so the last page is pushed first
+        let (provider, mock) = Provider::mocked();
+        mock.push(serde_json::json!({ "logs": [log(2)] })).unwrap();
+        mock.push(serde_json::json!({ "logs": [log(0), log(1)], "pageKey": "page-2" })).unwrap();
+        let query = Arc::new(Query {
+            logs_backend: LogsBackend::Paginated(2),
+            ..make_query(Datatype::Logs)
+        });
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 100)]),
+            ..Default::default()
+        };
+
+        let dfs =
+            Logs::collect_by_block(partition, make_source(provider), query, Some(1)).await.unwrap();
+        let indices: Vec<_> =
+            dfs[&Datatype::Logs].column("log_index").unwrap().u32().unwrap().into_iter().collect();
+        assert_eq!(indices, vec![Some(0), Some(1), Some(2)]);
+
+        let filter = |page_key: Option<&str>| {
+            let mut params = serde_json::json!({
+                "fromBlock": "0x64",
+                "toBlock": "0x64",
+                "topics": [],
+                "pageSize": 2,
+            });
+            if let Some(page_key) = page_key {
+                params["pageKey"] = page_key.into();
+            }
+            [params]
+        };
+        mock.assert_request("eth_getLogs", filter(None)).unwrap();
+        mock.assert_request("eth_getLogs", filter(Some("page-2"))).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_blocks_per_log_request() {
+        // one log per request, a sixth request would find no response and fail the chunk
+        let (provider, mock) = Provider::mocked();
+        for block_number in [0u64, 2000, 4000, 6000, 8000] {
+            let log = Log {
+                block_number: Some(block_number.into()),
+                transaction_hash: Some(H256::zero()),
+                transaction_index: Some(0.into()),
+                log_index: Some(0.into()),
+                ..Default::default()
+            };
+            mock.push::<Vec<Log>, _>(vec![log]).unwrap();
+        }
+        let source = Arc::new(Source {
+            inner_request_size: 10000,
+            max_blocks_per_log_request: Some(2000),
+            ..(*make_source(provider)).clone()
+        });
+        assert_eq!(source.block_range_size(&Datatype::Logs), 2000);
+        assert_eq!(source.block_range_size(&Datatype::GasFees), 10000);
+
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(0, 9999)]),
+            ..Default::default()
+        };
+        let query = Arc::new(make_query(Datatype::Logs));
+        let datatype = MetaDatatype::Scalar(Datatype::Logs);
+        let dfs = collect_partition(datatype, partition, query, source).await.unwrap();
+        assert_eq!(dfs[&Datatype::Logs].height(), 5);
+    }
+}
@@ -1,3 +1,5 @@
+/// access lists
+pub mod access_lists;
 /// address appearances
 pub mod address_appearances;
 /// balance diffs
@@ -6,6 +8,8 @@ pub mod balance_diffs;
 pub mod balance_reads;
 /// balances
 pub mod balances;
+/// blobs
+pub mod blobs;
 /// blocks
 pub mod blocks;
 /// code diffs
@@ -32,6 +36,10 @@ pub mod erc721_transfers;
 pub mod eth_calls;
 /// four byte counts
 pub mod four_byte_counts;
+/// gas fees
+pub mod gas_fees;
+/// genesis allocations
+pub mod genesis_allocations;
 /// geth balance diffs
 pub mod geth_balance_diffs;
 /// geth calls
@@ -56,6 +64,10 @@ pub mod nonce_diffs;
 pub mod nonce_reads;
 /// nonces
 pub mod nonces;
+/// pending transactions
+pub mod pending_transactions;
+/// prestate
+pub mod prestate;
 /// slots
 pub mod slots;
 /// storage diffs
@@ -71,10 +83,12 @@ pub mod transactions;
 /// vm traces
 pub mod vm_traces;
 
+pub use access_lists::*;
 pub use address_appearances::*;
 pub use balance_diffs::*;
 pub use balance_reads::*;
 pub use balances::*;
+pub use blobs::*;
 pub use blocks::*;
 pub use code_diffs::*;
 pub use code_reads::*;
@@ -88,6 +102,8 @@ pub use erc721_metadata::*;
 pub use erc721_transfers::*;
 pub use eth_calls::*;
 pub use four_byte_counts::*;
+pub use gas_fees::*;
+pub use genesis_allocations::*;
 pub use geth_balance_diffs::*;
 pub use geth_calls::*;
 pub use geth_code_diffs::*;
@@ -100,6 +116,8 @@ pub use native_transfers::*;
 pub use nonce_diffs::*;
 pub use nonce_reads::*;
 pub use nonces::*;
+pub use pending_transactions::*;
+pub use prestate::*;
 pub use slots::*;
 pub use storage_diffs::*;
 pub use storage_reads::*;
@@ -23,14 +23,18 @@ impl Dataset for NativeTransfers {
     fn optional_parameters() -> Vec<Dim> {
         vec![Dim::FromAddress, Dim::ToAddress]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["trace_block"]
+    }
 }
 
 #[async_trait::async_trait]
 impl CollectByBlock for NativeTransfers {
     type Response = Vec<Trace>;
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
-        let traces = source.trace_block(request.block_number()?.into()).await?;
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let traces = traces::fetch_block_traces(request.block_number()?, &source, &query).await?;
         Ok(filter_traces_by_from_to_addresses(traces, &request.from_address, &request.to_address))
     }
 
@@ -26,6 +26,10 @@ impl Dataset for Nonces {
     fn default_blocks() -> Option<String> {
         Some("latest".to_string())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getTransactionCount"]
+    }
 }
 
 type BlockTxAddressOutput = (u32, Option<Vec<u8>>, Vec<u8>, u64);
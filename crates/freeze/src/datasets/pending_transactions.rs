@@ -0,0 +1,102 @@
+use crate::*;
+use ethers::prelude::*;
+use polars::prelude::*;
+
+/// columns for pending transactions
+///
+/// a point-in-time snapshot of the mempool, as the pending block of the node. the snapshot is not
+/// tied to a block range, so it is collected once per run from a single block, e.g. `latest`
+#[cryo_to_df::to_df(Datatype::PendingTransactions)]
+#[derive(Default)]
+pub struct PendingTransactions {
+    n_rows: u64,
+    // unix time in seconds at which the mempool was read
+    snapshot_time: Vec<u64>,
+    transaction_hash: Vec<Vec<u8>>,
+    nonce: Vec<u64>,
+    from_address: Vec<Vec<u8>>,
+    to_address: Vec<Option<Vec<u8>>>,
+    value: Vec<U256>,
+    input: Vec<Vec<u8>>,
+    gas_limit: Vec<u64>,
+    gas_price: Vec<Option<u64>>,
+    transaction_type: Vec<Option<u32>>,
+    max_priority_fee_per_gas: Vec<Option<u64>>,
+    max_fee_per_gas: Vec<Option<u64>>,
+    chain_id: Vec<u64>,
+}
+
+#[async_trait::async_trait]
+impl Dataset for PendingTransactions {
+    fn aliases() -> Vec<&'static str> {
+        vec!["mempool"]
+    }
+
+    fn default_sort() -> Option<Vec<&'static str>> {
+        Some(vec!["from_address", "nonce"])
+    }
+
+    fn default_blocks() -> Option<String> {
+        Some("latest".to_string())
+    }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getBlockByNumber"]
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByBlock for PendingTransactions {
+    type Response = (u64, Block<Transaction>);
+
+    async fn extract(_: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
+        let snapshot_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|_| err("system time is before unix epoch"))?
+            .as_secs();
+        let block = source
+            .get_pending_block_with_txs()
+            .await?
+            .ok_or(CollectError::CollectError("pending block not found".to_string()))?;
+        Ok((snapshot_time, block))
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::PendingTransactions)?;
+        let (snapshot_time, block) = response;
+        for tx in block.transactions.into_iter() {
+            process_pending_transaction(tx, snapshot_time, columns, schema);
+        }
+        Ok(())
+    }
+}
+
+impl CollectByTransaction for PendingTransactions {
+    type Response = ();
+}
+
+fn process_pending_transaction(
+    tx: Transaction,
+    snapshot_time: u64,
+    columns: &mut PendingTransactions,
+    schema: &Table,
+) {
+    columns.n_rows += 1;
+    store!(schema, columns, snapshot_time, snapshot_time);
+    store!(schema, columns, transaction_hash, tx.hash.as_bytes().to_vec());
+    store!(schema, columns, nonce, tx.nonce.as_u64());
+    store!(schema, columns, from_address, tx.from.as_bytes().to_vec());
+    store!(schema, columns, to_address, tx.to.map(|x| x.as_bytes().to_vec()));
+    store!(schema, columns, value, tx.value);
+    store!(schema, columns, input, tx.input.to_vec());
+    store!(schema, columns, gas_limit, tx.gas.as_u64());
+    store!(schema, columns, gas_price, tx.gas_price.map(|gas_price| gas_price.as_u64()));
+    store!(schema, columns, transaction_type, tx.transaction_type.map(|value| value.as_u32()));
+    store!(schema, columns, max_fee_per_gas, tx.max_fee_per_gas.map(|value| value.as_u64()));
+    store!(
+        schema,
+        columns,
+        max_priority_fee_per_gas,
+        tx.max_priority_fee_per_gas.map(|value| value.as_u64())
+    );
+}
@@ -0,0 +1,178 @@
+use crate::*;
+use ethers::prelude::*;
+use polars::prelude::*;
+use std::collections::BTreeMap;
+
+/// columns for prestate
+#[cryo_to_df::to_df(Datatype::Prestate)]
+#[derive(Default)]
+pub struct Prestate {
+    n_rows: u64,
+    block_number: Vec<Option<u32>>,
+    transaction_index: Vec<Option<u32>>,
+    transaction_hash: Vec<Option<Vec<u8>>>,
+    address: Vec<Vec<u8>>,
+    balance: Vec<Option<U256>>,
+    nonce: Vec<Option<u64>>,
+    code: Vec<Option<Vec<u8>>>,
+    // accounts without storage reads have a single row without slot
+    storage_slot: Vec<Option<Vec<u8>>>,
+    storage_value: Vec<Option<Vec<u8>>>,
+    chain_id: Vec<u64>,
+}
+
+#[async_trait::async_trait]
+impl Dataset for Prestate {
+    fn aliases() -> Vec<&'static str> {
+        vec!["prestates"]
+    }
+
+    fn default_columns() -> Option<Vec<&'static str>> {
+        Some(vec![
+            "block_number",
+            "transaction_index",
+            "address",
+            "balance",
+            "nonce",
+            "code",
+            "storage_slot",
+            "storage_value",
+            "chain_id",
+        ])
+    }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
+}
+
+type BlockTxsTraces = (Option<u32>, Vec<Option<Vec<u8>>>, Vec<BTreeMap<H160, AccountState>>);
+
+#[async_trait::async_trait]
+impl CollectByBlock for Prestate {
+    type Response = BlockTxsTraces;
+
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let schema = query.schemas.get_schema(&Datatype::Prestate)?;
+        let include_txs = schema.has_column("transaction_hash");
+        source
+            .geth_debug_trace_block_prestate(request.block_number()? as u32, include_txs)
+            .await
+            .map_err(unsupported_prestate_error)
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::Prestate)?;
+        process_prestate(&response, columns, schema)
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByTransaction for Prestate {
+    type Response = BlockTxsTraces;
+
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let schema = query.schemas.get_schema(&Datatype::Prestate)?;
+        let include_block_number = schema.has_column("block_number");
+        let tx = request.transaction_hash()?;
+        source
+            .geth_debug_trace_transaction_prestate(tx, include_block_number)
+            .await
+            .map_err(unsupported_prestate_error)
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let schema = query.schemas.get_schema(&Datatype::Prestate)?;
+        process_prestate(&response, columns, schema)
+    }
+}
+
+/// name the tracer when the node rejects it, json-rpc errors alone do not say which is missing
+fn unsupported_prestate_error(error: CollectError) -> CollectError {
+    if crate::preflight::is_unsupported(&error) {
+        CollectError::CollectError(format!(
+            "rpc endpoint does not support debug tracing with prestateTracer: {}",
+            error
+        ))
+    } else {
+        error
+    }
+}
+
+/// process prestate traces into one row per storage slot of each account
+fn process_prestate(response: &BlockTxsTraces, columns: &mut Prestate, schema: &Table) -> R<()> {
+    let (block_number, txs, traces) = response;
+    for (index, (trace, tx)) in traces.iter().zip(txs).enumerate() {
+        for (address, account_state) in trace.iter() {
+            let slots: Vec<(Option<&H256>, Option<&H256>)> = match &account_state.storage {
+                Some(storage) if !storage.is_empty() => {
+                    storage.iter().map(|(slot, value)| (Some(slot), Some(value))).collect()
+                }
+                _ => vec![(None, None)],
+            };
+            let code = match &account_state.code {
+                Some(code) => Some(
+                    prefix_hex::decode::<Vec<u8>>(code)
+                        .map_err(|_| err("invalid code in prestate trace"))?,
+                ),
+                None => None,
+            };
+            for (slot, value) in slots.into_iter() {
+                columns.n_rows += 1;
+                store!(schema, columns, block_number, *block_number);
+                store!(schema, columns, transaction_index, Some(index as u32));
+                store!(schema, columns, transaction_hash, tx.clone());
+                store!(schema, columns, address, address.as_bytes().to_vec());
+                store!(schema, columns, balance, account_state.balance);
+                store!(schema, columns, nonce, account_state.nonce.map(|x| x.as_u64()));
+                store!(schema, columns, code, code.clone());
+                store!(schema, columns, storage_slot, slot.map(|x| x.as_bytes().to_vec()));
+                store!(schema, columns, storage_value, value.map(|x| x.as_bytes().to_vec()));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::make_query;
+
+    #[test]
+    fn test_decode_prestate() {
+        let trace: BTreeMap<H160, AccountState> = serde_json::from_value(serde_json::json!({
+            "0x0000000000000000000000000000000000000001": {
+                "balance": "0x64",
+                "nonce": 3
+            },
+            "0x0000000000000000000000000000000000000002": {
+                "balance": "0x0",
+                "code": "0x6080",
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001":
+                        "0x0000000000000000000000000000000000000000000000000000000000000007",
+                    "0x0000000000000000000000000000000000000000000000000000000000000002":
+                        "0x0000000000000000000000000000000000000000000000000000000000000008"
+                }
+            }
+        }))
+        .unwrap();
+        let response = (Some(100), vec![None], vec![trace]);
+        let query = make_query(Datatype::Prestate);
+        let schema = query.schemas.get_schema(&Datatype::Prestate).unwrap();
+
+        let mut columns = Prestate::default();
+        process_prestate(&response, &mut columns, schema).unwrap();
+
+        assert_eq!(columns.n_rows, 3);
+        assert_eq!(columns.block_number, vec![Some(100); 3]);
+        assert_eq!(columns.address[0], H160::from_low_u64_be(1).as_bytes().to_vec());
+        assert_eq!(columns.balance, vec![Some(100.into()), Some(0.into()), Some(0.into())]);
+        assert_eq!(columns.nonce, vec![Some(3), None, None]);
+        assert_eq!(columns.code, vec![None, Some(vec![0x60, 0x80]), Some(vec![0x60, 0x80])]);
+        assert_eq!(columns.storage_slot[0], None);
+        assert_eq!(columns.storage_slot[2], Some(H256::from_low_u64_be(2).as_bytes().to_vec()));
+        assert_eq!(columns.storage_value[1], Some(H256::from_low_u64_be(7).as_bytes().to_vec()));
+    }
+}
@@ -35,6 +35,10 @@ impl Dataset for Slots {
     fn default_blocks() -> Option<String> {
         Some("latest".to_string())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getStorageAt"]
+    }
 }
 
 type BlockTxAddressOutput = (u32, Option<Vec<u8>>, Vec<u8>, Vec<u8>, Vec<u8>);
@@ -77,3 +81,42 @@ fn process_slot(columns: &mut Slots, data: BlockTxAddressOutput, schema: &Table)
     store!(schema, columns, value, output);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+
+    #[tokio::test]
+    async fn test_slots_at_one_block() {
+        let (provider, mock) = Provider::mocked();
+        // responses are popped from the back, so the value of the first slot is pushed last
+        for value in [3u64, 2, 1] {
+            mock.push(H256::from_low_u64_be(value)).unwrap();
+        }
+        let source = make_source(provider);
+        let query = Arc::new(make_query(Datatype::Slots));
+        let address = vec![0x11u8; 20];
+        let slots: Vec<Vec<u8>> =
+            (1..=3u64).map(|slot| H256::from_low_u64_be(slot).as_bytes().to_vec()).collect();
+
+        let mut columns = Slots::default();
+        for slot in slots.iter() {
+            let request = Params {
+                block_number: Some(100),
+                address: Some(address.clone()),
+                slot: Some(slot.clone()),
+                ..Default::default()
+            };
+            let response =
+                <Slots as CollectByBlock>::extract(request, source.clone(), query.clone())
+                    .await
+                    .unwrap();
+            <Slots as CollectByBlock>::transform(response, &mut columns, &query).unwrap();
+        }
+        assert_eq!(columns.n_rows, 3);
+        assert_eq!(columns.block_number, vec![100, 100, 100]);
+        assert_eq!(columns.slot, slots);
+        assert_eq!(columns.value, slots);
+    }
+}
@@ -22,6 +22,10 @@ impl Dataset for StorageDiffs {
     fn aliases() -> Vec<&'static str> {
         vec!["slot_diffs"]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["trace_replayBlockTransactions"]
+    }
 }
 
 type BlockTxsTraces = (Option<u32>, Vec<Option<Vec<u8>>>, Vec<ethers::types::BlockTrace>);
@@ -22,6 +22,10 @@ impl Dataset for StorageReads {
     fn aliases() -> Vec<&'static str> {
         vec!["slot_reads"]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["debug_traceBlockByNumber"]
+    }
 }
 
 type BlockTxsTraces = (Option<u32>, Vec<Option<Vec<u8>>>, Vec<BTreeMap<H160, AccountState>>);
@@ -43,6 +43,14 @@ impl Dataset for TraceCalls {
     fn arg_aliases() -> Option<std::collections::HashMap<Dim, Dim>> {
         Some([(Dim::Address, Dim::Contract), (Dim::ToAddress, Dim::Contract)].into_iter().collect())
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["trace_call"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("action_from", 20), ("action_to", 20)].into_iter().collect()
+    }
 }
 
 #[async_trait::async_trait]
@@ -90,7 +98,7 @@ fn process_transaction_traces(
             schema,
             columns,
             trace_address,
-            trace.trace_address.iter().map(|n| n.to_string()).collect::<Vec<String>>().join("_")
+            trace.trace_address.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(".")
         );
         store!(schema, columns, subtraces, trace.subtraces as u32);
         store!(schema, columns, transaction_index, transaction_index as u32);
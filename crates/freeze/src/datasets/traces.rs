@@ -35,14 +35,22 @@ impl Dataset for Traces {
     fn optional_parameters() -> Vec<Dim> {
         vec![Dim::FromAddress, Dim::ToAddress]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["trace_block"]
+    }
+
+    fn column_widths() -> std::collections::HashMap<&'static str, usize> {
+        [("action_from", 20), ("action_to", 20)].into_iter().collect()
+    }
 }
 
 #[async_trait::async_trait]
 impl CollectByBlock for Traces {
     type Response = Vec<Trace>;
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
-        let traces = source.trace_block(request.block_number()?.into()).await?;
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let traces = fetch_block_traces(request.block_number()?, &source, &query).await?;
         Ok(filter_traces_by_from_to_addresses(traces, &request.from_address, &request.to_address))
     }
 
@@ -69,6 +77,122 @@ impl CollectByTransaction for Traces {
     }
 }
 
+/// fetch traces of a block using the trace backend and granularity of the query
+pub(crate) async fn fetch_block_traces(
+    block_number: u64,
+    source: &Source,
+    query: &Query,
+) -> R<Vec<Trace>> {
+    match (&query.trace_backend, &query.trace_granularity) {
+        (TraceBackend::Parity, TraceGranularity::Block) => {
+            source.trace_block(block_number.into()).await
+        }
+        (TraceBackend::Parity, TraceGranularity::Transaction) => {
+            source.trace_block_by_transactions(block_number, source.inner_request_size).await
+        }
+        (TraceBackend::Geth, _) => {
+            let block = source
+                .get_block(block_number)
+                .await?
+                .ok_or(CollectError::CollectError("could not get block for txs".to_string()))?;
+            let (_, _, frames) =
+                source.geth_debug_trace_block_calls(block_number as u32, false).await?;
+            call_frames_to_traces(frames, &block)
+        }
+    }
+}
+
+/// convert geth callTracer frames of a block into parity-style traces
+///
+/// each frame of transaction i becomes one trace, ordered depth-first, with trace_address
+/// set to its path in the call tree
+pub(crate) fn call_frames_to_traces(
+    frames: Vec<CallFrame>,
+    block: &Block<TxHash>,
+) -> R<Vec<Trace>> {
+    let block_number = block
+        .number
+        .ok_or(CollectError::CollectError("block number not found".to_string()))?
+        .as_u64();
+    let block_hash = block.hash.unwrap_or_default();
+    let mut traces = Vec::new();
+    for (tx_index, frame) in frames.into_iter().enumerate() {
+        let tx_hash = block.transactions.get(tx_index).cloned();
+        let mut stack = vec![(frame, Vec::new())];
+        while let Some((frame, trace_address)) = stack.pop() {
+            let calls = frame.calls.clone().unwrap_or_default();
+            for (i, call) in calls.iter().enumerate().rev() {
+                let mut child_address = trace_address.clone();
+                child_address.push(i);
+                stack.push((call.clone(), child_address));
+            }
+            let (action, result, action_type) = call_frame_to_action(frame.clone())?;
+            traces.push(Trace {
+                action,
+                result,
+                trace_address,
+                subtraces: calls.len(),
+                transaction_position: Some(tx_index),
+                transaction_hash: tx_hash,
+                block_number,
+                block_hash,
+                action_type,
+                error: frame.error,
+            })
+        }
+    }
+    Ok(traces)
+}
+
+fn call_frame_to_action(frame: CallFrame) -> R<(Action, Option<Res>, ActionType)> {
+    let to = match frame.to {
+        Some(NameOrAddress::Address(address)) => address,
+        _ => H160::zero(),
+    };
+    let value = frame.value.unwrap_or_default();
+    let output = frame.output.unwrap_or_default();
+    let succeeded = frame.error.is_none();
+    let call_type = match frame.typ.as_str() {
+        "CALL" => CallType::Call,
+        "CALLCODE" => CallType::CallCode,
+        "DELEGATECALL" => CallType::DelegateCall,
+        "STATICCALL" => CallType::StaticCall,
+        "CREATE" | "CREATE2" => {
+            let action = Action::Create(Create {
+                from: frame.from,
+                value,
+                gas: frame.gas,
+                init: frame.input,
+            });
+            let result = succeeded.then_some(Res::Create(CreateResult {
+                gas_used: frame.gas_used,
+                code: output,
+                address: to,
+            }));
+            return Ok((action, result, ActionType::Create))
+        }
+        "SELFDESTRUCT" => {
+            let action = Action::Suicide(Suicide {
+                address: frame.from,
+                refund_address: to,
+                balance: value,
+            });
+            return Ok((action, None, ActionType::Suicide))
+        }
+        other => return Err(err(&format!("unknown callTracer frame type: {}", other))),
+    };
+    let action = Action::Call(ethers::types::Call {
+        from: frame.from,
+        to,
+        value,
+        gas: frame.gas,
+        input: frame.input,
+        call_type,
+    });
+    let result = succeeded.then_some(Res::Call(CallResult { gas_used: frame.gas_used, output }));
+    Ok((action, result, ActionType::Call))
+}
+
 pub(crate) fn filter_traces_by_from_to_addresses(
     traces: Vec<Trace>,
     from_address: &Option<Vec<u8>>,
@@ -117,7 +241,7 @@ pub(crate) fn process_traces(traces: &[Trace], columns: &mut Traces, schemas: &S
             schema,
             columns,
             trace_address,
-            trace.trace_address.iter().map(|n| n.to_string()).collect::<Vec<String>>().join("_")
+            trace.trace_address.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(".")
         );
         store!(schema, columns, subtraces, trace.subtraces as u32);
         store!(schema, columns, transaction_index, trace.transaction_position.map(|x| x as u32));
@@ -271,3 +395,285 @@ pub(crate) fn filter_failed_traces(traces: Vec<Trace>) -> Vec<Trace> {
 
     filtered
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+
+    fn make_trace(tx_hash: H256, tx_index: usize, trace_address: Vec<usize>) -> Trace {
+        Trace {
+            action: Action::Call(ethers::types::Call {
+                from: H160::from_low_u64_be(1),
+                to: H160::from_low_u64_be(2),
+                value: U256::from(tx_index),
+                gas: U256::from(21000),
+                input: Bytes::default(),
+                call_type: CallType::Call,
+            }),
+            result: Some(Res::Call(CallResult {
+                gas_used: U256::from(21000),
+                output: Bytes::default(),
+            })),
+            trace_address,
+            subtraces: 0,
+            transaction_position: Some(tx_index),
+            transaction_hash: Some(tx_hash),
+            block_number: 100,
+            block_hash: H256::from_low_u64_be(100),
+            action_type: ActionType::Call,
+            error: None,
+        }
+    }
+
+    fn make_traces_query(trace_granularity: TraceGranularity) -> Arc<Query> {
+        Arc::new(Query { trace_granularity, ..make_query(Datatype::Traces) })
+    }
+
+    #[tokio::test]
+    async fn test_trace_granularities_match() {
+        let tx_hashes = vec![H256::from_low_u64_be(7), H256::from_low_u64_be(8)];
+        let tx_traces = [
+            vec![make_trace(tx_hashes[0], 0, vec![]), make_trace(tx_hashes[0], 0, vec![0])],
+            vec![make_trace(tx_hashes[1], 1, vec![])],
+        ];
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+
+        // block granularity uses a single trace_block request
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Vec<Trace>, _>(tx_traces.concat()).unwrap();
+        let by_block = Traces::collect_by_block(
+            partition.clone(),
+            make_source(provider),
+            make_traces_query(TraceGranularity::Block),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // transaction granularity uses eth_getBlockByNumber then trace_transaction per tx
+        // (mock responses are popped from the back)
+        let (provider, mock) = Provider::mocked();
+        for traces in tx_traces.iter().rev() {
+            mock.push::<Vec<Trace>, _>(traces.clone()).unwrap();
+        }
+        let block = Block::<TxHash> {
+            number: Some(100.into()),
+            transactions: tx_hashes,
+            ..Default::default()
+        };
+        mock.push(block).unwrap();
+        let by_transaction = Traces::collect_by_block(
+            partition,
+            make_source(provider),
+            make_traces_query(TraceGranularity::Transaction),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let by_block = &by_block[&Datatype::Traces];
+        let by_transaction = &by_transaction[&Datatype::Traces];
+        assert_eq!(by_block.height(), 3);
+        assert!(by_block.frame_equal_missing(by_transaction));
+    }
+
+    #[test]
+    fn test_call_frames_to_traces() {
+        let frame: CallFrame = serde_json::from_value(serde_json::json!({
+            "type": "CALL",
+            "from": "0x0000000000000000000000000000000000000001",
+            "to": "0x0000000000000000000000000000000000000002",
+            "value": "0x5",
+            "gas": "0x7530",
+            "gasUsed": "0x5208",
+            "input": "0x",
+            "output": "0x01",
+            "calls": [
+                {
+                    "type": "CREATE2",
+                    "from": "0x0000000000000000000000000000000000000002",
+                    "to": "0x0000000000000000000000000000000000000003",
+                    "gas": "0x100",
+                    "gasUsed": "0x80",
+                    "input": "0x6000",
+                    "output": "0x60"
+                },
+                {
+                    "type": "STATICCALL",
+                    "from": "0x0000000000000000000000000000000000000002",
+                    "to": "0x0000000000000000000000000000000000000004",
+                    "gas": "0x200",
+                    "gasUsed": "0x200",
+                    "input": "0x",
+                    "error": "execution reverted",
+                    "calls": [
+                        {
+                            "type": "SELFDESTRUCT",
+                            "from": "0x0000000000000000000000000000000000000004",
+                            "to": "0x0000000000000000000000000000000000000001",
+                            "value": "0x1",
+                            "gas": "0x0",
+                            "gasUsed": "0x0",
+                            "input": "0x"
+                        }
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+        let tx_hash = H256::from_low_u64_be(7);
+        let block = Block::<TxHash> {
+            number: Some(100.into()),
+            hash: Some(H256::from_low_u64_be(100)),
+            transactions: vec![tx_hash],
+            ..Default::default()
+        };
+
+        let traces = call_frames_to_traces(vec![frame], &block).unwrap();
+
+        let rows: Vec<_> = traces
+            .iter()
+            .map(|trace| (trace.action_type.clone(), trace.trace_address.clone(), trace.subtraces))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (ActionType::Call, vec![], 2),
+                (ActionType::Create, vec![0], 0),
+                (ActionType::Call, vec![1], 1),
+                (ActionType::Suicide, vec![1, 0], 0),
+            ]
+        );
+        assert!(traces.iter().all(|trace| trace.transaction_hash == Some(tx_hash) &&
+            trace.transaction_position == Some(0) &&
+            trace.block_number == 100));
+        match (&traces[1].action, &traces[1].result) {
+            (Action::Create(create), Some(Res::Create(result))) => {
+                assert_eq!(create.init, Bytes::from(vec![0x60, 0x00]));
+                assert_eq!(result.address, H160::from_low_u64_be(3));
+            }
+            _ => panic!("expected create trace"),
+        }
+        match &traces[2].action {
+            Action::Call(call) => assert_eq!(call.call_type, CallType::StaticCall),
+            _ => panic!("expected call trace"),
+        }
+        assert!(traces[2].result.is_none());
+        assert_eq!(traces[2].error, Some("execution reverted".to_string()));
+        match &traces[3].action {
+            Action::Suicide(suicide) => {
+                assert_eq!(suicide.refund_address, H160::from_low_u64_be(1))
+            }
+            _ => panic!("expected suicide trace"),
+        }
+
+        // rows land in the traces schema like any other trace
+        let mut columns = Traces::default();
+        process_traces(&traces, &mut columns, &make_traces_query(TraceGranularity::Block).schemas)
+            .unwrap();
+        assert_eq!(columns.n_rows, 4);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_call_frame_is_decode_error() {
+        let (provider, mock) = Provider::mocked();
+        let frames = vec![serde_json::json!({
+            "txHash": "0x8f0b8a3c1e5a1c4e6d0a4f6b2d9e3c7a5b1f0e2d4c6b8a0f1e3d5c7b9a1f2e3d",
+            "result": { "type": "CALL", "from": "not an address" },
+        })];
+        mock.push::<Vec<serde_json::Value>, _>(frames).unwrap();
+        let block = Block::<TxHash> { number: Some(100.into()), ..Default::default() };
+        mock.push(block).unwrap();
+        let query = Query { trace_backend: TraceBackend::Geth, ..make_query(Datatype::Traces) };
+
+        let result = fetch_block_traces(100, &make_source(provider), &query).await;
+
+        assert!(matches!(result, Err(CollectError::Decode(_))));
+    }
+
+    #[tokio::test]
+    async fn test_geth_call_tracer_payload() {
+        // response of geth to debug_traceBlockByNumber with the callTracer, for a block with one
+        // transaction that makes one internal call
+        let payload = serde_json::json!([{
+            "txHash": "0x8f0b8a3c1e5a1c4e6d0a4f6b2d9e3c7a5b1f0e2d4c6b8a0f1e3d5c7b9a1f2e3d",
+            "result": {
+                "from": "0x1f9090aae28b8a3dceadf281b0f12828e676c326",
+                "gas": "0x2d48c",
+                "gasUsed": "0x1e4a7",
+                "to": "0x7a250d5630b4cf539739df2c5dacb4c659f2488d",
+                "input": "0x7ff36ab5",
+                "output": "0x",
+                "value": "0x2386f26fc10000",
+                "type": "CALL",
+                "calls": [{
+                    "from": "0x7a250d5630b4cf539739df2c5dacb4c659f2488d",
+                    "gas": "0x2a1b4",
+                    "gasUsed": "0x5da6",
+                    "to": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+                    "input": "0xd0e30db0",
+                    "value": "0x2386f26fc10000",
+                    "type": "CALL"
+                }]
+            }
+        }]);
+        let (provider, mock) = Provider::mocked();
+        mock.push::<serde_json::Value, _>(payload).unwrap();
+        let tx_hash = H256::from_low_u64_be(7);
+        let block = Block::<TxHash> {
+            number: Some(100.into()),
+            transactions: vec![tx_hash],
+            ..Default::default()
+        };
+        mock.push(block).unwrap();
+        let query = Query { trace_backend: TraceBackend::Geth, ..make_query(Datatype::Traces) };
+
+        let traces = fetch_block_traces(100, &make_source(provider), &query).await.unwrap();
+        assert_eq!(traces.len(), 2);
+        assert_eq!(traces[0].trace_address, Vec::<usize>::new());
+        assert_eq!(traces[0].subtraces, 1);
+        assert_eq!(traces[1].trace_address, vec![0]);
+        assert!(traces.iter().all(|trace| trace.transaction_hash == Some(tx_hash)));
+        let Action::Call(call) = &traces[1].action else { panic!("expected a call") };
+        assert_eq!(call.to, "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2".parse().unwrap());
+        assert_eq!(call.value, U256::exp10(16));
+        let Some(Res::Call(result)) = &traces[1].result else { panic!("expected a call result") };
+        assert_eq!(result.gas_used, U256::from(0x5da6));
+    }
+
+    #[test]
+    fn test_trace_address_representations() {
+        let traces =
+            vec![make_trace(H256::zero(), 0, vec![]), make_trace(H256::zero(), 0, vec![0, 1, 0])];
+        let mut query = make_query(Datatype::Traces);
+        let schema = query.schemas.get_mut(&Datatype::Traces).unwrap();
+        schema.set_trace_address_type(&FileFormat::Csv, false);
+        let mut columns = Traces::default();
+        process_traces(&traces, &mut columns, &query.schemas).unwrap();
+        let dfs = columns.create_dfs(&query.schemas, 1).unwrap();
+        let trace_address = dfs[&Datatype::Traces].column("trace_address").unwrap().clone();
+        let strings: Vec<_> = trace_address.utf8().unwrap().into_iter().collect();
+        assert_eq!(strings, vec![Some(""), Some("0.1.0")]);
+
+        // parquet files store lists unless strings are requested
+        let schema = query.schemas.get_mut(&Datatype::Traces).unwrap();
+        schema.set_trace_address_type(&FileFormat::Parquet, false);
+        let mut columns = Traces::default();
+        process_traces(&traces, &mut columns, &query.schemas).unwrap();
+        let dfs = columns.create_dfs(&query.schemas, 1).unwrap();
+        let trace_address = dfs[&Datatype::Traces].column("trace_address").unwrap().clone();
+        let lists = trace_address.list().unwrap();
+        let positions: Vec<u32> =
+            lists.get_as_series(1).unwrap().u32().unwrap().into_no_null_iter().collect();
+        assert_eq!(positions, vec![0, 1, 0]);
+        assert_eq!(lists.get_as_series(0).unwrap().len(), 0);
+
+        let schema = query.schemas.get_mut(&Datatype::Traces).unwrap();
+        schema.set_trace_address_type(&FileFormat::Parquet, true);
+        assert_eq!(schema.column_type("trace_address"), Some(ColumnType::String));
+    }
+}
@@ -25,6 +25,13 @@ pub struct Transactions {
     chain_id: Vec<u64>,
     timestamp: Vec<u32>,
     block_hash: Vec<Vec<u8>>,
+    // json encoded, null for transactions without access lists
+    access_list: Vec<Option<String>>,
+    // json encoded, null for transactions other than eip-7702 set-code transactions
+    authorization_list: Vec<Option<String>>,
+    // from receipts, null for transactions that do not create a contract
+    contract_address: Vec<Option<Vec<u8>>>,
+    logs_bloom: Vec<Option<Vec<u8>>>,
 }
 
 #[async_trait::async_trait]
@@ -54,9 +61,53 @@ impl Dataset for Transactions {
         ])
     }
 
+    fn presets() -> std::collections::HashMap<&'static str, Vec<&'static str>> {
+        [
+            (
+                "minimal",
+                vec![
+                    "block_number",
+                    "transaction_index",
+                    "transaction_hash",
+                    "from_address",
+                    "to_address",
+                ],
+            ),
+            (
+                "analytics",
+                vec![
+                    "block_number",
+                    "timestamp",
+                    "transaction_index",
+                    "transaction_hash",
+                    "from_address",
+                    "to_address",
+                    "value",
+                    "gas_used",
+                    "gas_price",
+                    "transaction_type",
+                    "max_priority_fee_per_gas",
+                    "max_fee_per_gas",
+                    "success",
+                ],
+            ),
+        ]
+        .into_iter()
+        .collect()
+    }
+
     fn optional_parameters() -> Vec<Dim> {
         vec![Dim::FromAddress, Dim::ToAddress]
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["eth_getBlockByNumber", "eth_getBlockReceipts"]
+    }
+
+    // 2: added contract_address, logs_bloom, and authorization_list
+    fn schema_version() -> u32 {
+        2
+    }
 }
 
 /// tuple representing transaction and optional receipt
@@ -74,6 +125,8 @@ impl CollectByBlock for Transactions {
         let schema = query.schemas.get_schema(&Datatype::Transactions)?;
 
         // 1. collect transactions and filter them if optional parameters are supplied
+        // the rpc cannot filter transactions, so the whole block is fetched for each address,
+        // several addresses of a field are separate requests whose rows are combined
         // filter by from_address
         let from_filter: Box<dyn Fn(&Transaction) -> bool + Send> =
             if let Some(from_address) = &request.from_address {
@@ -94,18 +147,17 @@ impl CollectByBlock for Transactions {
         // 2. collect receipts if necessary
         // if transactions are filtered fetch by set of transaction hashes, else fetch all receipts
         // in block
-        let receipts: Vec<Option<_>> =
-            if schema.has_column("gas_used") | schema.has_column("success") {
-                // receipts required
-                let receipts = if request.from_address.is_some() || request.to_address.is_some() {
-                    source.get_tx_receipts(&transactions).await?
-                } else {
-                    source.get_tx_receipts_in_block(&block).await?
-                };
-                receipts.into_iter().map(Some).collect()
+        let receipts: Vec<Option<_>> = if needs_receipts(schema) {
+            // receipts required
+            let receipts = if request.from_address.is_some() || request.to_address.is_some() {
+                source.get_tx_receipts(&transactions).await?
             } else {
-                vec![None; block.transactions.len()]
+                source.get_tx_receipts_in_block(&block).await?
             };
+            receipts.into_iter().map(Some).collect()
+        } else {
+            vec![None; block.transactions.len()]
+        };
 
         let transactions_with_receips = transactions.into_iter().zip(receipts).collect();
         Ok((block, transactions_with_receips, query.exclude_failed))
@@ -139,7 +191,7 @@ impl CollectByTransaction for Transactions {
             .get_transaction(tx_hash)
             .await?
             .ok_or(CollectError::CollectError("transaction not found".to_string()))?;
-        let receipt = if schema.has_column("gas_used") {
+        let receipt = if needs_receipts(schema) {
             source.get_transaction_receipt(tx_hash).await?
         } else {
             None
@@ -167,6 +219,13 @@ impl CollectByTransaction for Transactions {
     }
 }
 
+/// whether any column of the schema is read from transaction receipts
+fn needs_receipts(schema: &Table) -> bool {
+    ["gas_used", "success", "contract_address", "logs_bloom"]
+        .iter()
+        .any(|column| schema.has_column(column))
+}
+
 pub(crate) fn process_transaction(
     tx: Transaction,
     receipt: Option<TransactionReceipt>,
@@ -196,7 +255,19 @@ pub(crate) fn process_transaction(
     store!(schema, columns, input, tx.input.to_vec());
     store!(schema, columns, gas_limit, tx.gas.as_u64());
     store!(schema, columns, success, success);
-    store!(schema, columns, gas_used, receipt.and_then(|r| r.gas_used.map(|x| x.as_u64())));
+    store!(
+        schema,
+        columns,
+        gas_used,
+        receipt.as_ref().and_then(|r| r.gas_used.map(|x| x.as_u64()))
+    );
+    store!(
+        schema,
+        columns,
+        contract_address,
+        receipt.as_ref().and_then(|r| r.contract_address.map(|x| x.as_bytes().to_vec()))
+    );
+    store!(schema, columns, logs_bloom, receipt.as_ref().map(|r| r.logs_bloom.0.to_vec()));
     store!(schema, columns, gas_price, tx.gas_price.map(|gas_price| gas_price.as_u64()));
     store!(schema, columns, transaction_type, tx.transaction_type.map(|value| value.as_u32()));
     store!(schema, columns, max_fee_per_gas, tx.max_fee_per_gas.map(|value| value.as_u64()));
@@ -208,6 +279,19 @@ pub(crate) fn process_transaction(
     );
     store!(schema, columns, timestamp, timestamp);
     store!(schema, columns, block_hash, tx.block_hash.unwrap_or_default().as_bytes().to_vec());
+    if schema.has_column("access_list") {
+        let access_list = tx.access_list.as_ref().map(serde_json::to_string).transpose();
+        let access_list = access_list.map_err(|_| err("could not encode access list"))?;
+        columns.access_list.push(access_list);
+    }
+    // ethers has no field for authorization lists, so they are read from the unknown fields
+    if schema.has_column("authorization_list") {
+        let authorization_list =
+            tx.other.get("authorizationList").map(serde_json::to_string).transpose();
+        let authorization_list =
+            authorization_list.map_err(|_| err("could not encode authorization list"))?;
+        columns.authorization_list.push(authorization_list);
+    }
 
     Ok(())
 }
@@ -227,3 +311,317 @@ fn tx_success(tx: &Transaction, receipt: &Option<TransactionReceipt>) -> R<bool>
         return Err(err("could not determine status of transaction"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+
+    #[test]
+    fn test_decode_access_list() {
+        let tx: Transaction = serde_json::from_value(serde_json::json!({
+            "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000064",
+            "blockNumber": "0xc5d488",
+            "from": "0x0000000000000000000000000000000000000001",
+            "gas": "0x5208",
+            "gasPrice": "0x3b9aca00",
+            "hash": "0x0000000000000000000000000000000000000000000000000000000000000007",
+            "input": "0x",
+            "nonce": "0x0",
+            "to": "0x0000000000000000000000000000000000000002",
+            "transactionIndex": "0x3",
+            "value": "0x0",
+            "type": "0x1",
+            "chainId": "0x1",
+            "accessList": [
+                {
+                    "address": "0x0000000000000000000000000000000000000003",
+                    "storageKeys": [
+                        "0x0000000000000000000000000000000000000000000000000000000000000001"
+                    ]
+                }
+            ],
+            "v": "0x0",
+            "r": "0x1",
+            "s": "0x1"
+        }))
+        .unwrap();
+        let legacy_tx = Transaction { transaction_type: Some(0.into()), ..Default::default() };
+        let receipt = TransactionReceipt { status: Some(1.into()), ..Default::default() };
+        let query = make_query(Datatype::Transactions);
+        let schema = query.schemas.get_schema(&Datatype::Transactions).unwrap();
+
+        let mut columns = Transactions::default();
+        process_transaction(tx, Some(receipt.clone()), &mut columns, schema, false, 0).unwrap();
+        process_transaction(legacy_tx, Some(receipt), &mut columns, schema, false, 0).unwrap();
+
+        assert_eq!(columns.n_rows, 2);
+        let access_list: serde_json::Value =
+            serde_json::from_str(columns.access_list[0].as_ref().unwrap()).unwrap();
+        assert_eq!(
+            access_list,
+            serde_json::json!([{
+                "address": "0x0000000000000000000000000000000000000003",
+                "storageKeys": [
+                    "0x0000000000000000000000000000000000000000000000000000000000000001"
+                ]
+            }])
+        );
+        assert_eq!(columns.access_list[1], None);
+    }
+
+    #[test]
+    fn test_decode_authorization_list() {
+        let authorization = serde_json::json!({
+            "chainId": "0x1",
+            "address": "0x0000000000000000000000000000000000000004",
+            "nonce": "0x2",
+            "yParity": "0x1",
+            "r": "0x1",
+            "s": "0x1"
+        });
+        let tx: Transaction = serde_json::from_value(serde_json::json!({
+            "blockHash": "0x0000000000000000000000000000000000000000000000000000000000000064",
+            "blockNumber": "0x14a5c3e",
+            "from": "0x0000000000000000000000000000000000000001",
+            "gas": "0x5208",
+            "gasPrice": "0x3b9aca00",
+            "maxFeePerGas": "0x3b9aca00",
+            "maxPriorityFeePerGas": "0x1",
+            "hash": "0x0000000000000000000000000000000000000000000000000000000000000008",
+            "input": "0x",
+            "nonce": "0x0",
+            "to": "0x0000000000000000000000000000000000000001",
+            "transactionIndex": "0x0",
+            "value": "0x0",
+            "type": "0x4",
+            "chainId": "0x1",
+            "accessList": [],
+            "authorizationList": [authorization],
+            "v": "0x0",
+            "r": "0x1",
+            "s": "0x1"
+        }))
+        .unwrap();
+        let legacy_tx = Transaction { transaction_type: Some(0.into()), ..Default::default() };
+        let receipt = TransactionReceipt { status: Some(1.into()), ..Default::default() };
+        let query = make_query(Datatype::Transactions);
+        let schema = query.schemas.get_schema(&Datatype::Transactions).unwrap();
+
+        let mut columns = Transactions::default();
+        process_transaction(tx, Some(receipt.clone()), &mut columns, schema, false, 0).unwrap();
+        process_transaction(legacy_tx, Some(receipt), &mut columns, schema, false, 0).unwrap();
+
+        assert_eq!(columns.n_rows, 2);
+        let authorization_list: serde_json::Value =
+            serde_json::from_str(columns.authorization_list[0].as_ref().unwrap()).unwrap();
+        assert_eq!(authorization_list, serde_json::json!([authorization]));
+        assert_eq!(columns.authorization_list[1], None);
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_from_address() {
+        let tx = |index: u64, from: u64, to: u64| Transaction {
+            hash: H256::from_low_u64_be(index),
+            block_number: Some(100.into()),
+            transaction_index: Some(index.into()),
+            from: H160::from_low_u64_be(from),
+            to: Some(H160::from_low_u64_be(to)),
+            ..Default::default()
+        };
+        let block = Block::<Transaction> {
+            number: Some(100.into()),
+            transactions: vec![tx(0, 1, 5), tx(1, 2, 5), tx(2, 3, 6), tx(3, 1, 6)],
+            ..Default::default()
+        };
+        let address = |value: u64| H160::from_low_u64_be(value).as_bytes().to_vec();
+        let columns = Some(
+            ["block_number", "transaction_index", "from_address", "to_address"]
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+        );
+        let schema = Datatype::Transactions
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &columns,
+                &None,
+                Some(vec!["transaction_index".to_string()]),
+                None,
+                false,
+            )
+            .unwrap();
+        let query = Arc::new(Query {
+            schemas: std::collections::HashMap::from([(Datatype::Transactions, schema)]),
+            ..make_query(Datatype::Transactions)
+        });
+        let collect = |from_addresses: Vec<u64>, to_addresses: Option<Vec<u64>>| {
+            let (provider, mock) = Provider::mocked();
+            for _ in 0..from_addresses.len() {
+                mock.push(block.clone()).unwrap();
+            }
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+                from_addresses: Some(vec![AddressChunk::Values(
+                    from_addresses.into_iter().map(address).collect(),
+                )]),
+                to_addresses: to_addresses.map(|to_addresses| {
+                    vec![AddressChunk::Values(to_addresses.into_iter().map(address).collect())]
+                }),
+                ..Default::default()
+            };
+            let source = make_source(provider);
+            let query = query.clone();
+            async move {
+                let dfs = Transactions::collect_by_block(partition, source, query, None).await;
+                let df = dfs.unwrap().remove(&Datatype::Transactions).unwrap();
+                let indices: Vec<Option<u64>> =
+                    df.column("transaction_index").unwrap().u64().unwrap().into_iter().collect();
+                indices.into_iter().flatten().collect::<Vec<u64>>()
+            }
+        };
+
+        assert_eq!(collect(vec![1], None).await, vec![0, 3]);
+        // addresses of a field are combined, from and to addresses must both match
+        assert_eq!(collect(vec![1, 3], None).await, vec![0, 2, 3]);
+        assert_eq!(collect(vec![1, 3], Some(vec![6])).await, vec![2, 3]);
+    }
+
+    fn block_with_receipts(
+        number: u64,
+        n_txs: u64,
+    ) -> (Block<Transaction>, Vec<TransactionReceipt>) {
+        let hashes: Vec<H256> =
+            (0..n_txs).map(|i| H256::from_low_u64_be(number * 100 + i)).collect();
+        let transactions =
+            hashes.iter().map(|hash| Transaction { hash: *hash, ..Default::default() }).collect();
+        let receipts = hashes
+            .iter()
+            .map(|hash| TransactionReceipt {
+                transaction_hash: *hash,
+                status: Some(1.into()),
+                gas_used: Some(21_000.into()),
+                ..Default::default()
+            })
+            .collect();
+        let block = Block::<Transaction> {
+            number: Some(number.into()),
+            transactions,
+            ..Default::default()
+        };
+        (block, receipts)
+    }
+
+    #[tokio::test]
+    async fn test_one_block_receipts_request_per_block() {
+        let (provider, mock) = Provider::mocked();
+        let (block, receipts) = block_with_receipts(100, 3);
+        mock.push::<Vec<TransactionReceipt>, _>(receipts).unwrap();
+        mock.push(block).unwrap();
+
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Arc::new(make_query(Datatype::Transactions));
+        let dfs = Transactions::collect_by_block(partition, make_source(provider), query, None)
+            .await
+            .unwrap();
+        assert_eq!(dfs[&Datatype::Transactions].height(), 3);
+
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", true])).unwrap();
+        mock.assert_request("eth_getBlockReceipts", ["0x64"]).unwrap();
+        // no eth_getTransactionReceipt requests
+        assert!(mock.assert_request("eth_getTransactionReceipt", ()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_block_receipts_fall_back_per_transaction() {
+        use ethers::providers::{JsonRpcError, MockResponse};
+
+        let (provider, mock) = Provider::mocked();
+        // responses are popped from the back, so the last block is pushed first
+        let (block, receipts) = block_with_receipts(101, 1);
+        mock.push(receipts[0].clone()).unwrap();
+        mock.push(block).unwrap();
+        let (block, receipts) = block_with_receipts(100, 1);
+        mock.push(receipts[0].clone()).unwrap();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32601,
+            message: "the method eth_getBlockReceipts does not exist/is not available".to_string(),
+            data: None,
+        }));
+        mock.push(block).unwrap();
+
+        let query = Arc::new(make_query(Datatype::Transactions));
+        let source = make_source(provider);
+        for block_number in [100, 101] {
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![block_number])]),
+                ..Default::default()
+            };
+            let dfs =
+                Transactions::collect_by_block(partition, source.clone(), query.clone(), None)
+                    .await
+                    .unwrap();
+            assert_eq!(dfs[&Datatype::Transactions].height(), 1);
+        }
+
+        // the node rejected eth_getBlockReceipts once, so the next block skips it
+        let tx_hash = |number: u64| H256::from_low_u64_be(number * 100);
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", true])).unwrap();
+        mock.assert_request("eth_getBlockReceipts", ["0x64"]).unwrap();
+        mock.assert_request("eth_getTransactionReceipt", [tx_hash(100)]).unwrap();
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x65", true])).unwrap();
+        mock.assert_request("eth_getTransactionReceipt", [tx_hash(101)]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_contract_address_is_set_for_creations_only() {
+        let (provider, mock) = Provider::mocked();
+        let (block, mut receipts) = block_with_receipts(100, 2);
+        receipts[1].contract_address = Some(H160::from_low_u64_be(7));
+        receipts[1].logs_bloom = Bloom::from_low_u64_be(1);
+        mock.push::<Vec<TransactionReceipt>, _>(receipts).unwrap();
+        mock.push(block).unwrap();
+
+        let columns = Some(vec!["contract_address".to_string(), "logs_bloom".to_string()]);
+        let schema = Datatype::Transactions
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Hex,
+                &columns,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let query = Arc::new(Query {
+            schemas: std::collections::HashMap::from([(Datatype::Transactions, schema)]),
+            ..make_query(Datatype::Transactions)
+        });
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let dfs = Transactions::collect_by_block(partition, make_source(provider), query, None)
+            .await
+            .unwrap();
+        let df = &dfs[&Datatype::Transactions];
+
+        let contract_addresses: Vec<Option<&str>> =
+            df.column("contract_address").unwrap().utf8().unwrap().into_iter().collect();
+        let created = format!("{:?}", H160::from_low_u64_be(7));
+        assert_eq!(contract_addresses, vec![None, Some(created.as_str())]);
+        let blooms: Vec<Option<&str>> =
+            df.column("logs_bloom").unwrap().utf8().unwrap().into_iter().collect();
+        assert_eq!(blooms[0], Some(format!("0x{}", "00".repeat(256)).as_str()));
+        assert!(blooms[1].unwrap().ends_with("01"));
+    }
+}
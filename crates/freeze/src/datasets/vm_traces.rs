@@ -35,6 +35,10 @@ impl Dataset for VmTraces {
     fn default_sort() -> Option<Vec<&'static str>> {
         Some(vec!["block_number", "transaction_index", "used"])
     }
+
+    fn required_methods() -> Vec<&'static str> {
+        vec!["trace_replayBlockTransactions"]
+    }
 }
 
 #[async_trait::async_trait]
@@ -63,7 +67,7 @@ impl CollectByTransaction for VmTraces {
     }
 }
 
-fn process_vm_traces(
+pub(crate) fn process_vm_traces(
     response: (Option<u32>, Option<Vec<u8>>, Vec<ethers::types::BlockTrace>),
     columns: &mut VmTraces,
     schemas: &Schemas,
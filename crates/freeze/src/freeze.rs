@@ -1,13 +1,19 @@
 use crate::{
-    collect_partition, dataframes, err, reports, summaries, CollectError, Datatype, ExecutionEnv,
-    FileOutput, FreezeSummary, MetaDatatype, Partition, Query, Source,
+    collect_partition,
+    dataframes::{self, FileAppender},
+    delta_log, err, indexes, reports, stream_partition, summaries, BlockChunk, Checkpoint,
+    ChunkData, CollectError, ColumnType, Datatype, Dim, ExecutionEnv, FileFormat, FileOutput,
+    FreezeSummary, HeadGap, MetaDatatype, Partition, Query, RowLimit, SchemaFunctions, Source,
+    TimestampRange,
 };
 use chrono::{DateTime, Local};
 use futures::{stream::FuturesUnordered, StreamExt};
+use polars::prelude::{ChunkAgg, DataFrame};
 use std::{
-    collections::{HashMap, HashSet},
-    path::PathBuf,
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
     sync::Arc,
+    time::Instant,
 };
 use tokio::sync::Semaphore;
 
@@ -22,6 +28,9 @@ type PartitionPayload = (
     Option<std::sync::Arc<Semaphore>>,
 );
 
+/// name and value of the partition key of a file, e.g. ("date", "2023-11-14")
+type PartitionKey = (String, String);
+
 /// collect data and output as files
 pub async fn freeze(
     query: &Query,
@@ -31,6 +40,47 @@ pub async fn freeze(
 ) -> Result<Option<FreezeSummary>, CollectError> {
     // check validity of query
     query.is_valid()?;
+    if sink.partition_by_date {
+        for (datatype, schema) in query.schemas.iter() {
+            if !schema.has_column("timestamp") {
+                let message =
+                    format!("partitioning by date requires timestamps in {}", datatype.name());
+                return Err(CollectError::CollectError(message))
+            }
+        }
+    }
+
+    if let Some(column) = &sink.partition_by_column {
+        for (datatype, schema) in query.schemas.iter() {
+            if !schema.has_column(column) {
+                let message =
+                    format!("cannot partition {} by missing column {}", datatype.name(), column);
+                return Err(CollectError::CollectError(message))
+            }
+        }
+    }
+
+    validate_column_options(query, sink)?;
+    if sink.single_file && env.row_limit.is_some() {
+        return Err(err("a row limit cannot be used with single files"))
+    }
+    let file_only = sink.single_file || sink.streaming || sink.delta || sink.write_index;
+    if env.writer.is_some() && (file_only || sink.target_file_size.is_some()) {
+        return Err(err("a writer cannot be used with single files, streaming, delta tables, an \
+            index, or a target file size"))
+    }
+
+    // log resolved schemas
+    if env.print_schema {
+        log_schemas(query)?;
+    }
+
+    // create output directories up front so that permission problems surface before collection
+    let created_dirs = match env.writer {
+        Some(_) => Vec::new(),
+        None if env.dry => Vec::new(),
+        None => sink.create_dirs(query)?,
+    };
 
     // get partitions
     let (payloads, skipping) = get_payloads(query, source, sink, env)?;
@@ -48,6 +98,10 @@ pub async fn freeze(
     // check if empty
     if payloads.is_empty() {
         let results = FreezeSummary { skipped: skipping, ..Default::default() };
+        if sink.write_index {
+            indexes::write_index(query, sink, &results, source.chain_id)?;
+        }
+        sink.remove_empty_dirs(&created_dirs)?;
         if env.verbose >= 1 {
             summaries::print_cryo_conclusion(&results, query, env)
         }
@@ -60,7 +114,18 @@ pub async fn freeze(
     };
 
     // perform collection
-    let results = freeze_partitions(env, payloads, skipping).await;
+    let results = match sink.target_file_size {
+        Some(target_file_size) => {
+            freeze_adaptively(env, query, source, sink, target_file_size).await?
+        }
+        None => freeze_partitions(env, query, sink, payloads, skipping).await,
+    };
+
+    // index every datatype of the run
+    if sink.write_index {
+        indexes::write_index(query, sink, &results, source.chain_id)?;
+    }
+    sink.remove_empty_dirs(&created_dirs)?;
 
     // create summary
     if env.verbose >= 1 {
@@ -76,6 +141,59 @@ pub async fn freeze(
     Ok(Some(results))
 }
 
+/// check that per-column parquet options name columns of the query's schemas
+fn validate_column_options(query: &Query, sink: &FileOutput) -> Result<(), CollectError> {
+    let column_types = |column: &str| -> Vec<ColumnType> {
+        query.schemas.values().filter_map(|schema| schema.column_type(column)).collect()
+    };
+    for column in sink.column_compression.keys() {
+        if column_types(column).is_empty() {
+            let message = format!("compression is set for {}, which is not a column", column);
+            return Err(CollectError::CollectError(message))
+        }
+    }
+    for column in sink.decimal_types.keys() {
+        if !column_types(column).contains(&ColumnType::UInt256) {
+            let message = format!("decimal type is set for {}, which is not a u256 column", column);
+            return Err(CollectError::CollectError(message))
+        }
+    }
+    for column in sink.dictionary_encoding.keys() {
+        let column_types = column_types(column);
+        if column_types.is_empty() {
+            let message =
+                format!("dictionary encoding is set for {}, which is not a column", column);
+            return Err(CollectError::CollectError(message))
+        }
+        let unsupported = column_types.iter().any(|column_type| {
+            matches!(
+                column_type,
+                ColumnType::Boolean |
+                    ColumnType::Float32 |
+                    ColumnType::Float64 |
+                    ColumnType::UInt256 |
+                    ColumnType::Decimal128
+            )
+        });
+        if unsupported {
+            let message = format!("dictionary encoding is not supported for column {}", column);
+            return Err(CollectError::CollectError(message))
+        }
+    }
+    Ok(())
+}
+
+fn log_schemas(query: &Query) -> Result<(), CollectError> {
+    let mut schemas: Vec<_> = query.schemas.iter().collect();
+    schemas.sort_by_key(|(datatype, _)| datatype.name());
+    for (datatype, schema) in schemas.into_iter() {
+        let schema = serde_json::to_string(schema)
+            .map_err(|e| err(format!("could not serialize schema: {}", e).as_str()))?;
+        tracing::info!(target: "cryo::schema", datatype = %datatype.name(), %schema, "resolved schema");
+    }
+    Ok(())
+}
+
 fn get_payloads(
     query: &Query,
     source: &Source,
@@ -98,9 +216,9 @@ fn get_payloads(
                 continue
             }
 
-            // check for path collisions
+            // check for path collisions, single file output shares paths across partitions
             let paths_set: HashSet<_> = paths.clone().into_values().collect();
-            if paths_set.intersection(&all_paths).next().is_none() {
+            if sink.single_file || paths_set.intersection(&all_paths).next().is_none() {
                 all_paths.extend(paths_set);
             } else {
                 let message =
@@ -126,6 +244,8 @@ fn get_payloads(
 
 async fn freeze_partitions(
     env: &ExecutionEnv,
+    query: &Query,
+    sink: &FileOutput,
     payloads: Vec<PartitionPayload>,
     skipped: Vec<Partition>,
 ) -> FreezeSummary {
@@ -138,37 +258,374 @@ async fn freeze_partitions(
         }
     }
 
+    // all partitions of a query share a source
+    let chain_id = payloads.first().map(|payload| payload.4.chain_id).unwrap_or_default();
+    let head_gap = payloads.first().map(|payload| payload.4.head_gap).unwrap_or_default();
+
+    // single file chunks are appended in order of their start block
+    let chunk_indices = single_file_chunk_indices(&payloads);
+
     // spawn task for each partition
     let mut futures = FuturesUnordered::new();
-    for payload in payloads.into_iter() {
-        futures.push(tokio::spawn(
-            async move { (payload.0.clone(), freeze_partition(payload).await) },
-        ));
+    for (payload, index) in payloads.into_iter().zip(chunk_indices) {
+        futures.push(tokio::spawn(async move {
+            (index, payload.0.clone(), freeze_partition(payload).await)
+        }));
     }
 
     // aggregate results
     let mut completed = Vec::new();
     let mut errored = Vec::new();
+    let mut cancelled = Vec::new();
     let mut n_rows = 0;
+    let mut timestamp_ranges = HashMap::new();
+    let mut appenders: HashMap<PathBuf, (Datatype, FileAppender)> = HashMap::new();
+    let mut reorder_buffer = ReorderBuffer::new();
+    let mut checkpointer = Checkpointer::new(env.checkpoint);
     while let Some(result) = futures.next().await {
+        let n_completed = completed.len();
         match result {
-            Ok((partition, Ok(chunk_n_rows))) => {
+            Ok((
+                _,
+                partition,
+                Ok(Some(PartitionOutput::Written(chunk_n_rows, ranges, failed))),
+            )) => {
                 n_rows += chunk_n_rows;
+                timestamp_ranges.extend(ranges);
+                errored.extend(block_errors(&partition, failed));
                 completed.push(partition)
             }
-            Ok((partition, Err(e))) => errored.push((Some(partition), e)),
+            Ok((index, partition, Ok(Some(PartitionOutput::Collected(dfs, range, failed))))) => {
+                errored.extend(block_errors(&partition, failed));
+                let ready = match sink.ordered {
+                    true => reorder_buffer.insert(index, (partition, dfs, range)),
+                    false => vec![(partition, dfs, range)],
+                };
+                for (partition, dfs, range) in ready.into_iter() {
+                    let ranges = &mut timestamp_ranges;
+                    match append_to_single_files(&mut appenders, ranges, dfs, range, query, sink) {
+                        Ok(chunk_n_rows) => {
+                            n_rows += chunk_n_rows;
+                            completed.push(partition)
+                        }
+                        Err(e) => errored.push((Some(partition), e)),
+                    }
+                }
+            }
+            Ok((_, partition, Ok(None))) => cancelled.push(partition),
+            Ok((_, partition, Err(CollectError::BlockNotProduced(block_number))))
+                if head_gap == HeadGap::Skip =>
+            {
+                tracing::warn!(block_number, "skipping chunk, block has not been produced yet");
+                cancelled.push(partition)
+            }
+            Ok((_, partition, Err(e))) => errored.push((Some(partition), e)),
             Err(_e) => errored.push((None, err("error joining chunks"))),
         }
+
+        // checkpoint progress of long runs
+        if checkpointer.is_due(&completed[n_completed..]) {
+            let summary = FreezeSummary {
+                completed: completed.clone(),
+                skipped: skipped.clone(),
+                errored: errored.iter().map(|(p, e)| (p.clone(), err(&e.to_string()))).collect(),
+                cancelled: cancelled.clone(),
+                n_rows,
+                timestamp_ranges: timestamp_ranges.clone(),
+            };
+            write_checkpoint(env, query, sink, &appenders, &summary);
+        }
+    }
+
+    // chunks left in the reorder buffer are waiting on a chunk that failed
+    completed.extend(reorder_buffer.into_values().map(|(partition, _, _)| partition));
+
+    // single files are only kept if every partition made it into them
+    if errored.is_empty() && cancelled.is_empty() {
+        for (path, (datatype, appender)) in appenders.into_iter() {
+            let mut metadata = sink.network_metadata(chain_id);
+            metadata.extend(Partition::merge(&query.partitions).block_range_metadata());
+            if let Some(range) = timestamp_ranges.get(&path) {
+                metadata.extend(range.to_metadata());
+            }
+            if let Ok(schema) = query.schemas.get_schema(&datatype) {
+                metadata.extend(schema.schema_metadata());
+            }
+            if appender.finish(&metadata).is_err() {
+                timestamp_ranges.remove(&path);
+                errored.push((None, err("error writing file")))
+            }
+        }
+    } else if sink.single_file {
+        appenders.into_values().for_each(|(_, appender)| appender.abandon());
+        timestamp_ranges.clear();
+        if errored.is_empty() {
+            cancelled.append(&mut completed);
+        } else {
+            for partition in completed.drain(..) {
+                errored.push((Some(partition), err("single file discarded after chunk errors")))
+            }
+        }
+        n_rows = 0;
+    }
+
+    if let Some(bar) = &env.bar {
+        bar.finish_and_clear();
+    }
+
+    FreezeSummary { completed, errored, skipped, cancelled, n_rows, timestamp_ranges }
+}
+
+/// collect the blocks of a query one chunk at a time, sizing each chunk from the bytes per block
+/// of the files written so far
+///
+/// this is best-effort: a chunk is sized before its blocks are seen, so files in regions whose
+/// density changes can still be far from the target, and chunks whose files are split into
+/// partition directories are not measured
+async fn freeze_adaptively(
+    env: &ExecutionEnv,
+    query: &Query,
+    source: &Source,
+    sink: &FileOutput,
+    target_file_size: u64,
+) -> Result<FreezeSummary, CollectError> {
+    if query.partitioned_by != vec![Dim::BlockNumber] {
+        return Err(err("a target file size requires partitioning by block number only"))
+    }
+    let mut blocks: Vec<u64> = query
+        .partitions
+        .iter()
+        .filter_map(|partition| partition.block_numbers.as_ref())
+        .flat_map(|chunks| chunks.iter().flat_map(|chunk| chunk.values()))
+        .collect();
+    blocks.sort();
+    blocks.dedup();
+    let initial_chunk_size = query
+        .partitions
+        .first()
+        .and_then(|partition| partition.block_numbers.as_ref())
+        .map(|chunks| chunks.size())
+        .unwrap_or(1);
+    let mut sizer = ChunkSizer::new(target_file_size, initial_chunk_size);
+
+    // progress is counted in blocks, as the number of chunks is not known up front
+    let chunk_env = ExecutionEnv { bar: None, ..env.clone() };
+    if let Some(bar) = &env.bar {
+        bar.set_length(blocks.len() as u64);
+    }
+
+    let mut summary = FreezeSummary::default();
+    let mut remaining = blocks.as_slice();
+    while !remaining.is_empty() {
+        let chunk_size = (sizer.chunk_size() as usize).min(remaining.len());
+        let (numbers, rest) = remaining.split_at(chunk_size);
+        remaining = rest;
+        let (first, last) = (numbers[0], numbers[numbers.len() - 1]);
+        let chunk = match last - first + 1 == numbers.len() as u64 {
+            true => BlockChunk::Range(first, last),
+            false => BlockChunk::Numbers(numbers.to_vec()),
+        };
+        let partition = Partition { block_numbers: Some(vec![chunk]), ..Default::default() };
+        if env.shutdown_requested() || env.row_limit_reached() {
+            summary.cancelled.push(partition);
+            continue
+        }
+
+        let chunk_query = Query { partitions: vec![partition], ..query.clone() };
+        let (payloads, skipped) = get_payloads(&chunk_query, source, sink, &chunk_env)?;
+        let paths: Vec<PathBuf> =
+            payloads.iter().flat_map(|payload| payload.2.values().cloned()).collect();
+        let results = freeze_partitions(&chunk_env, &chunk_query, sink, payloads, skipped).await;
+
+        // the largest file of the chunk is the one that should meet the target
+        let n_bytes = paths
+            .iter()
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .max();
+        if let Some(n_bytes) = n_bytes {
+            sizer.record(numbers.len() as u64, n_bytes);
+        }
+        if let Some(bar) = &env.bar {
+            bar.inc(numbers.len() as u64);
+        }
+
+        summary.extend(results);
     }
 
     if let Some(bar) = &env.bar {
         bar.finish_and_clear();
     }
+    Ok(summary)
+}
+
+/// sizes chunks from a running estimate of the output bytes per block
+struct ChunkSizer {
+    target_file_size: u64,
+    initial_chunk_size: u64,
+    n_blocks: u64,
+    n_bytes: u64,
+}
+
+impl ChunkSizer {
+    fn new(target_file_size: u64, initial_chunk_size: u64) -> Self {
+        ChunkSizer { target_file_size, initial_chunk_size, n_blocks: 0, n_bytes: 0 }
+    }
+
+    /// record the bytes written for a chunk of blocks
+    fn record(&mut self, n_blocks: u64, n_bytes: u64) {
+        self.n_blocks += n_blocks;
+        self.n_bytes += n_bytes;
+    }
+
+    /// number of blocks of the next chunk, at least one
+    fn chunk_size(&self) -> u64 {
+        if self.n_bytes == 0 {
+            return self.initial_chunk_size.max(1)
+        }
+        let size = self.target_file_size as u128 * self.n_blocks as u128 / self.n_bytes as u128;
+        size.clamp(1, u64::MAX as u128) as u64
+    }
+}
+
+/// tracks progress made since the last checkpoint
+struct Checkpointer {
+    checkpoint: Option<Checkpoint>,
+    n_blocks: u64,
+    last_checkpoint: Instant,
+}
+
+impl Checkpointer {
+    fn new(checkpoint: Option<Checkpoint>) -> Self {
+        Checkpointer { checkpoint, n_blocks: 0, last_checkpoint: Instant::now() }
+    }
 
-    FreezeSummary { completed, errored, skipped, n_rows }
+    /// record newly completed partitions, returning whether a checkpoint is due
+    fn is_due(&mut self, completed: &[Partition]) -> bool {
+        if completed.is_empty() {
+            return false
+        }
+        let due = match self.checkpoint {
+            None => false,
+            Some(Checkpoint::Blocks(n_blocks)) => {
+                self.n_blocks += completed
+                    .iter()
+                    .filter_map(|partition| partition.block_numbers.as_ref())
+                    .map(|chunks| chunks.size())
+                    .sum::<u64>();
+                self.n_blocks >= n_blocks
+            }
+            Some(Checkpoint::Seconds(seconds)) => {
+                self.last_checkpoint.elapsed().as_secs() >= seconds
+            }
+        };
+        if due {
+            self.n_blocks = 0;
+            self.last_checkpoint = Instant::now();
+        }
+        due
+    }
 }
 
-async fn freeze_partition(payload: PartitionPayload) -> Result<u64, CollectError> {
+/// sync single files to disk and overwrite the initial report with the progress so far
+fn write_checkpoint(
+    env: &ExecutionEnv,
+    query: &Query,
+    sink: &FileOutput,
+    appenders: &HashMap<PathBuf, (Datatype, FileAppender)>,
+    summary: &FreezeSummary,
+) {
+    for (_, appender) in appenders.values() {
+        if appender.sync().is_err() {
+            tracing::warn!("could not sync single file at checkpoint");
+        }
+    }
+    if env.report {
+        match reports::write_checkpoint(env, query, sink, summary) {
+            Ok(path) => {
+                tracing::debug!(path = %path.display(), n_completed = summary.completed.len(), "wrote checkpoint")
+            }
+            Err(e) => tracing::warn!(error = %e, "could not write checkpoint"),
+        }
+    }
+}
+
+/// index of each payload among the payloads of its datatype, ordered by start block
+fn single_file_chunk_indices(payloads: &[PartitionPayload]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..payloads.len()).collect();
+    order.sort_by_key(|i| {
+        let (partition, datatype, _, query, _, _, _, _) = &payloads[*i];
+        let datatype_index = query.datatypes.iter().position(|dt| dt.name() == datatype.name());
+        let start_block = partition.block_numbers.as_ref().and_then(|chunks| chunks.min_value());
+        (datatype_index, start_block)
+    });
+    let mut indices = vec![0; payloads.len()];
+    for (index, i) in order.into_iter().enumerate() {
+        indices[i] = index;
+    }
+    indices
+}
+
+/// holds chunks that complete out of order until every earlier chunk has completed
+struct ReorderBuffer<T> {
+    next_index: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> ReorderBuffer<T> {
+    fn new() -> Self {
+        ReorderBuffer { next_index: 0, pending: BTreeMap::new() }
+    }
+
+    /// add chunk, returning every chunk that is now ready, in order
+    fn insert(&mut self, index: usize, item: T) -> Vec<T> {
+        self.pending.insert(index, item);
+        let mut ready = Vec::new();
+        while let Some(item) = self.pending.remove(&self.next_index) {
+            ready.push(item);
+            self.next_index += 1;
+        }
+        ready
+    }
+
+    fn into_values(self) -> impl Iterator<Item = T> {
+        self.pending.into_values()
+    }
+}
+
+/// blocks of a partition that failed when retried individually, with their errors
+type FailedBlocks = Vec<(u64, CollectError)>;
+
+/// output of a partition that was collected
+enum PartitionOutput {
+    /// number of rows written to the partition's own files, with the timestamp range of each file
+    Written(u64, Vec<(PathBuf, TimestampRange)>, FailedBlocks),
+    /// dataframes to be appended to single files, with their paths and timestamp range
+    Collected(Vec<(Datatype, PathBuf, DataFrame)>, Option<TimestampRange>, FailedBlocks),
+}
+
+/// errors of blocks that failed individually, each reported as a partition of its own block
+fn block_errors(
+    partition: &Partition,
+    failed_blocks: FailedBlocks,
+) -> Vec<(Option<Partition>, CollectError)> {
+    failed_blocks
+        .into_iter()
+        .map(|(block_number, e)| {
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![block_number])]),
+                ..partition.clone()
+            };
+            (Some(partition), CollectError::BlockFailed(block_number, Box::new(e)))
+        })
+        .collect()
+}
+
+/// returns None if the partition was not started because of shutdown, or if the row limit of the
+/// run left no rows for it
+async fn freeze_partition(
+    payload: PartitionPayload,
+) -> Result<Option<PartitionOutput>, CollectError> {
     let (partition, datatype, paths, query, source, sink, env, semaphore) = payload;
 
     // acquire chunk semaphore
@@ -177,18 +634,161 @@ async fn freeze_partition(payload: PartitionPayload) -> Result<u64, CollectError
         None => None,
     };
 
-    // collect data
-    let dfs = collect_partition(datatype, partition, query, source).await?;
+    // do not start new chunks during shutdown or once the row limit is reached
+    if env.shutdown_requested() || env.row_limit_reached() {
+        return Ok(None)
+    }
+
+    // a chunk that runs past the chunk timeout is cancelled by dropping its future, which
+    // cancels its outstanding requests, and is reported as failed
+    let chunk_timeout = env.chunk_timeout;
+    let output = write_partition(partition, datatype, paths, query, source, sink, env);
+    match chunk_timeout {
+        Some(chunk_timeout) => tokio::time::timeout(chunk_timeout, output).await?,
+        None => output.await,
+    }
+}
+
+/// collect a partition and write it to its files, or collect it for single files
+async fn write_partition(
+    partition: Partition,
+    datatype: MetaDatatype,
+    paths: HashMap<Datatype, PathBuf>,
+    query: Arc<Query>,
+    source: Arc<Source>,
+    sink: FileOutput,
+    env: ExecutionEnv,
+) -> Result<Option<PartitionOutput>, CollectError> {
+    // write responses as they arrive instead of collecting the whole chunk
+    if sink.streaming {
+        let output =
+            stream_partition_to_files(&partition, datatype, &paths, query, source, &sink, &env)
+                .await?;
+        if let Some(bar) = env.bar {
+            bar.inc(1);
+        }
+        return Ok(Some(output))
+    }
+
+    // collect data, blocks that failed individually are reported instead of failing the chunk
+    let collected = collect_partition(datatype, partition.clone(), query.clone(), source.clone());
+    let (mut dfs, failed_blocks) = match collected.await {
+        Ok(dfs) => (dfs, Vec::new()),
+        Err(CollectError::PartialChunk(dfs, failed_blocks)) => (dfs, failed_blocks),
+        Err(e) => return Err(e),
+    };
+
+    // apply transform registered by library consumer
+    if let Some(transform) = &env.transform {
+        dfs = dfs
+            .into_iter()
+            .map(|(datatype, df)| Ok((datatype, transform.transform(datatype, df)?)))
+            .collect::<Result<_, CollectError>>()?;
+    }
+
+    // truncate to the rows left under the row limit of the run
+    if let Some(row_limit) = &env.row_limit {
+        match limit_rows(dfs, row_limit) {
+            Some(limited) => dfs = limited,
+            None => return Ok(None),
+        }
+    }
+
+    // block timestamp range of the chunk, recorded in file metadata
+    let timestamp_range = match sink.timestamp_range {
+        true => get_timestamp_range(&partition, &dfs, &source).await?,
+        false => None,
+    };
+    let mut metadata = sink.network_metadata(source.chain_id);
+    metadata.extend(partition.block_range_metadata());
+
+    // single files are appended to serially by freeze_partitions()
+    if sink.single_file {
+        let mut collected = Vec::new();
+        for (datatype, df) in dfs {
+            let path = paths.get(&datatype).ok_or_else(|| {
+                CollectError::CollectError("could not get path for datatype".to_string())
+            })?;
+            collected.push((datatype, path.clone(), df));
+        }
+        if let Some(bar) = env.bar {
+            bar.inc(1);
+        }
+        return Ok(Some(PartitionOutput::Collected(collected, timestamp_range, failed_blocks)))
+    }
 
     // write dataframes to disk
     let mut n_rows = 0;
-    for (datatype, mut df) in dfs {
+    let mut timestamp_ranges = Vec::new();
+    for (datatype, df) in dfs {
         n_rows += df.height() as u64;
         let path = paths.get(&datatype).ok_or_else(|| {
             CollectError::CollectError("could not get path for datatype".to_string())
         })?;
-        let result = dataframes::df_to_file(&mut df, path, &sink);
-        result.map_err(|_| CollectError::CollectError("error writing file".to_string()))?
+
+        // rows of a chunk can have several partition keys, each written to its own file
+        let files = split_by_partition_key(df, &sink)?
+            .into_iter()
+            .map(|(key, df)| match &key {
+                Some((name, value)) => Ok((sink.get_key_path(path, name, value)?, key, df)),
+                None => Ok((path.clone(), key, df)),
+            })
+            .collect::<Result<Vec<_>, CollectError>>()?;
+
+        let mut delta_files = Vec::new();
+        for (path, key, mut df) in files {
+            let timestamp_range = match (&key, timestamp_range) {
+                (Some(_), Some(_)) => df_timestamp_range(&df),
+                (_, range) => range,
+            };
+            let mut metadata = metadata.clone();
+            metadata.extend(timestamp_range.map(|range| range.to_metadata()).unwrap_or_default());
+
+            // narrowed types can differ between chunks, so they are recorded per file
+            if sink.optimize_types {
+                let (optimized_df, optimized_metadata) = dataframes::optimize_types(df)?;
+                df = optimized_df;
+                metadata.extend(optimized_metadata);
+            }
+
+            // aggregates are written first so that an existing chunk file implies its aggregates
+            if !sink.aggregates.is_empty() {
+                let schema = query.schemas.get_schema(&datatype)?;
+                let agg_df = dataframes::aggregate_df(&df, schema, &sink.aggregates)?;
+                let mut agg_df = json_integers_to_strings(agg_df, datatype, &query, &sink)?;
+                let mut agg_path = sink.get_aggregate_path(&query, &partition, datatype)?;
+                if let Some((name, value)) = &key {
+                    agg_path = sink.get_key_path(&agg_path, name, value)?;
+                }
+                let column_types = csv_column_types(&agg_df, datatype, &query, &sink)?;
+                let column_types = column_types.as_deref();
+                write_df(&mut agg_df, datatype, &agg_path, &sink, &env, &metadata, column_types)?;
+            }
+
+            // the schema hash describes the chunk file, not its aggregates
+            metadata.extend(query.schemas.get_schema(&datatype)?.schema_metadata());
+            let column_types = csv_column_types(&df, datatype, &query, &sink)?;
+            let df = checksum_address_columns(df, datatype, &query, &sink)?;
+            let mut df = json_integers_to_strings(df, datatype, &query, &sink)?;
+            write_df(&mut df, datatype, &path, &sink, &env, &metadata, column_types.as_deref())?;
+            if let Some(range) = timestamp_range {
+                timestamp_ranges.push((path.clone(), range));
+            }
+            tracing::debug!(datatype = %datatype.name(), path = %path.display(), n_rows = df.height(), "wrote file");
+            delta_files.push((path, key));
+        }
+
+        // each chunk is one commit, so a table never references a file that is being written
+        if sink.delta {
+            let schema = query.schemas.get_schema(&datatype)?;
+            let table_dir = sink.get_dir(datatype);
+            delta_log::append_delta_commit(
+                &table_dir,
+                schema,
+                &sink.partition_columns(),
+                &delta_files,
+            )?;
+        }
     }
 
     // update progress bar
@@ -196,5 +796,1538 @@ async fn freeze_partition(payload: PartitionPayload) -> Result<u64, CollectError
         bar.inc(1);
     }
 
+    Ok(Some(PartitionOutput::Written(n_rows, timestamp_ranges, failed_blocks)))
+}
+
+/// write a dataframe to its file, or hand it to the writer of the run if one is registered
+fn write_df(
+    df: &mut DataFrame,
+    datatype: Datatype,
+    path: &Path,
+    sink: &FileOutput,
+    env: &ExecutionEnv,
+    metadata: &[(String, String)],
+    column_types: Option<&[String]>,
+) -> Result<(), CollectError> {
+    if let Some(writer) = &env.writer {
+        return writer.write(datatype, path, df, metadata)
+    }
+    sink.create_parent_dir(path)?;
+    let result = dataframes::df_to_file(df, path, sink, metadata, column_types);
+    result.map_err(|_| CollectError::CollectError("error writing file".to_string()))
+}
+
+/// split rows of a chunk by the partition key of the sink, returning the name and value of each key
+fn split_by_partition_key(
+    df: DataFrame,
+    sink: &FileOutput,
+) -> Result<Vec<(Option<PartitionKey>, DataFrame)>, CollectError> {
+    let (name, parts) = match (&sink.partition_by_column, sink.partition_by_date) {
+        (Some(column), _) => (column.as_str(), dataframes::split_by_column(&df, column)?),
+        (None, true) => ("date", dataframes::split_by_date(&df)?),
+        (None, false) => return Ok(vec![(None, df)]),
+    };
+    if parts.len() > sink.max_open_partitions {
+        return Err(too_many_partitions(name, sink.max_open_partitions))
+    }
+    Ok(parts.into_iter().map(|(value, df)| (Some((name.to_string(), value)), df)).collect())
+}
+
+fn too_many_partitions(name: &str, max_open_partitions: usize) -> CollectError {
+    err(&format!(
+        "chunk has more than {} values of {}, raise --max-open-partitions to write them",
+        max_open_partitions, name
+    ))
+}
+
+/// write each batch of a streamed partition to the end of its files
+///
+/// rows are routed to one open file per datatype and partition key, files are only moved to their
+/// final paths once the whole partition has been written
+async fn stream_partition_to_files(
+    partition: &Partition,
+    datatype: MetaDatatype,
+    paths: &HashMap<Datatype, PathBuf>,
+    query: Arc<Query>,
+    source: Arc<Source>,
+    sink: &FileOutput,
+    env: &ExecutionEnv,
+) -> Result<PartitionOutput, CollectError> {
+    let write_error = |_| CollectError::CollectError("error writing file".to_string());
+    let mut appenders: HashMap<(Datatype, Option<String>), (PathBuf, FileAppender)> =
+        HashMap::new();
+    let mut n_rows = 0;
+    let mut collected_range: Option<TimestampRange> = None;
+    let mut on_batch = |dfs: HashMap<Datatype, DataFrame>| {
+        for (datatype, mut df) in dfs {
+            if let Some(transform) = &env.transform {
+                df = transform.transform(datatype, df)?;
+            }
+            if let Some(row_limit) = &env.row_limit {
+                let n_rows = row_limit.reserve(df.height() as u64) as usize;
+                if n_rows < df.height() {
+                    df = df.head(Some(n_rows));
+                }
+            }
+            if let Some(range) = df_timestamp_range(&df) {
+                collected_range =
+                    Some(collected_range.map_or(range, |merged| merged.merge(&range)));
+            }
+            for (key, df) in split_by_partition_key(df, sink)? {
+                let n_open = appenders.keys().filter(|(open, _)| *open == datatype).count();
+                let value = key.as_ref().map(|(_, value)| value.clone());
+                let (_, appender) = match appenders.entry((datatype, value)) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let path = paths
+                            .get(&datatype)
+                            .ok_or_else(|| err("could not get path for datatype"))?;
+                        let path = match &key {
+                            Some((name, _)) if n_open >= sink.max_open_partitions => {
+                                return Err(too_many_partitions(name, sink.max_open_partitions))
+                            }
+                            Some((name, value)) => sink.get_key_path(path, name, value)?,
+                            None => path.clone(),
+                        };
+                        sink.create_parent_dir(&path)?;
+                        let column_types = csv_column_types(&df, datatype, &query, sink)?;
+                        let appender = FileAppender::new(&path, &df.schema(), sink, column_types);
+                        entry.insert((path, appender.map_err(write_error)?))
+                    }
+                };
+                let df = checksum_address_columns(df, datatype, &query, sink)?;
+                let mut df = json_integers_to_strings(df, datatype, &query, sink)?;
+                appender.append(&mut df).map_err(write_error)?;
+                n_rows += df.height() as u64;
+            }
+        }
+        Ok(())
+    };
+    let result =
+        stream_partition(datatype, partition.clone(), query.clone(), source.clone(), &mut on_batch)
+            .await;
+    if let Err(e) = result {
+        appenders.into_values().for_each(|(_, appender)| appender.abandon());
+        return Err(e)
+    }
+
+    // block timestamp range of the chunk, recorded in file metadata
+    let timestamp_range = match (sink.timestamp_range, collected_range) {
+        (true, None) => get_timestamp_range(partition, &HashMap::new(), &source).await?,
+        (true, range) => range,
+        (false, _) => None,
+    };
+    let mut metadata = sink.network_metadata(source.chain_id);
+    metadata.extend(partition.block_range_metadata());
+    metadata.extend(timestamp_range.map(|range| range.to_metadata()).unwrap_or_default());
+
+    let mut timestamp_ranges = Vec::new();
+    for ((datatype, _), (path, appender)) in appenders.into_iter() {
+        let mut metadata = metadata.clone();
+        metadata.extend(query.schemas.get_schema(&datatype)?.schema_metadata());
+        appender.finish(&metadata).map_err(write_error)?;
+        if let Some(range) = timestamp_range {
+            timestamp_ranges.push((path.clone(), range));
+        }
+        tracing::debug!(datatype = %datatype.name(), path = %path.display(), "wrote file");
+    }
+    Ok(PartitionOutput::Written(n_rows, timestamp_ranges, Vec::new()))
+}
+
+/// min and max block timestamp of a partition
+///
+/// collected timestamps are used when present, otherwise the boundary blocks are looked up
+async fn get_timestamp_range(
+    partition: &Partition,
+    dfs: &HashMap<Datatype, DataFrame>,
+    source: &Source,
+) -> Result<Option<TimestampRange>, CollectError> {
+    if let Some(range) = dfs.values().find_map(df_timestamp_range) {
+        return Ok(Some(range))
+    }
+
+    let Some(block_chunks) = &partition.block_numbers else { return Ok(None) };
+    let (Some(min_block), Some(max_block)) = (block_chunks.min_value(), block_chunks.max_value())
+    else {
+        return Ok(None)
+    };
+    let min_timestamp = get_block_timestamp(source, min_block).await?;
+    let max_timestamp = match max_block == min_block {
+        true => min_timestamp,
+        false => get_block_timestamp(source, max_block).await?,
+    };
+    Ok(Some(TimestampRange { min_timestamp, max_timestamp }))
+}
+
+/// min and max of the timestamp or block_timestamp column of a dataframe
+fn df_timestamp_range(df: &DataFrame) -> Option<TimestampRange> {
+    let column = df.column("timestamp").or_else(|_| df.column("block_timestamp"));
+    let timestamps = column.ok()?.u32().ok()?;
+    Some(TimestampRange { min_timestamp: timestamps.min()?, max_timestamp: timestamps.max()? })
+}
+
+/// truncate dataframes of a chunk to the rows reserved under the row limit
+///
+/// returns None if the chunk had rows but none of them fit under the limit
+fn limit_rows(
+    dfs: HashMap<Datatype, DataFrame>,
+    row_limit: &RowLimit,
+) -> Option<HashMap<Datatype, DataFrame>> {
+    let had_rows = dfs.values().any(|df| df.height() > 0);
+    let dfs: HashMap<Datatype, DataFrame> = dfs
+        .into_iter()
+        .map(|(datatype, df)| {
+            let n_rows = row_limit.reserve(df.height() as u64) as usize;
+            match n_rows < df.height() {
+                true => (datatype, df.head(Some(n_rows))),
+                false => (datatype, df),
+            }
+        })
+        .collect();
+    match had_rows && dfs.values().all(|df| df.height() == 0) {
+        true => None,
+        false => Some(dfs),
+    }
+}
+
+/// column types written below the header of csv files when requested
+fn csv_column_types(
+    df: &DataFrame,
+    datatype: Datatype,
+    query: &Query,
+    sink: &FileOutput,
+) -> Result<Option<Vec<String>>, CollectError> {
+    match sink.csv_types {
+        true => Ok(Some(dataframes::df_column_types(df, query.schemas.get_schema(&datatype)?))),
+        false => Ok(None),
+    }
+}
+
+/// cast integer and decimal columns of json files to strings, if the sink asks for it
+fn json_integers_to_strings(
+    df: DataFrame,
+    datatype: Datatype,
+    query: &Query,
+    sink: &FileOutput,
+) -> Result<DataFrame, CollectError> {
+    match sink.format == FileFormat::Json && sink.json_bigints_as_strings {
+        true => {
+            let schema = query.schemas.get_schema(&datatype)?;
+            dataframes::integers_to_strings(&df, schema).map_err(CollectError::PolarsError)
+        }
+        false => Ok(df),
+    }
+}
+
+/// write the hex address columns of a datatype in checksum form, if the sink asks for it
+fn checksum_address_columns(
+    df: DataFrame,
+    datatype: Datatype,
+    query: &Query,
+    sink: &FileOutput,
+) -> Result<DataFrame, CollectError> {
+    match sink.checksum_addresses {
+        true => {
+            let schema = query.schemas.get_schema(&datatype)?;
+            let address_columns = datatype.address_columns();
+            dataframes::checksum_addresses(&df, schema, &address_columns)
+                .map_err(CollectError::PolarsError)
+        }
+        false => Ok(df),
+    }
+}
+
+async fn get_block_timestamp(source: &Source, block_number: u64) -> Result<u32, CollectError> {
+    match source.get_block(block_number).await? {
+        Some(block) => Ok(block.timestamp.as_u32()),
+        None => Err(err("could not get block for timestamp range")),
+    }
+}
+
+fn append_to_single_files(
+    appenders: &mut HashMap<PathBuf, (Datatype, FileAppender)>,
+    timestamp_ranges: &mut HashMap<PathBuf, TimestampRange>,
+    dfs: Vec<(Datatype, PathBuf, DataFrame)>,
+    timestamp_range: Option<TimestampRange>,
+    query: &Query,
+    sink: &FileOutput,
+) -> Result<u64, CollectError> {
+    let write_error = |_| CollectError::CollectError("error writing file".to_string());
+    let mut n_rows = 0;
+    for (datatype, path, df) in dfs {
+        n_rows += df.height() as u64;
+        let (_, appender) = match appenders.entry(path.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                sink.create_parent_dir(&path)?;
+                let column_types = csv_column_types(&df, datatype, query, sink)?;
+                let appender = FileAppender::new(&path, &df.schema(), sink, column_types);
+                entry.insert((datatype, appender.map_err(write_error)?))
+            }
+        };
+        let df = checksum_address_columns(df, datatype, query, sink)?;
+        let mut df = json_integers_to_strings(df, datatype, query, sink)?;
+        appender.append(&mut df).map_err(write_error)?;
+        if let Some(range) = timestamp_range {
+            timestamp_ranges
+                .entry(path.clone())
+                .and_modify(|merged| *merged = merged.merge(&range))
+                .or_insert(range);
+        }
+        tracing::debug!(datatype = %datatype.name(), path = %path.display(), n_rows = df.height(), "appended to file");
+    }
     Ok(n_rows)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{make_query, make_sink, make_source, TempDir},
+        BlockChunk, ColumnEncoding, ExecutionEnvBuilder, SubDir, U256Type,
+    };
+    use ethers::prelude::*;
+    use polars::prelude::{NamedFrom, ParquetReader, SerReader, Series};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::watch;
+    use tracing::{
+        field::{Field, Visit},
+        Event, Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, prelude::*, Layer};
+
+    /// requests shutdown as soon as the first file is written
+    struct ShutdownOnWrite(watch::Sender<bool>);
+
+    struct MessageVisitor(Option<String>);
+
+    impl Visit for MessageVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                self.0 = Some(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for ShutdownOnWrite {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut visitor = MessageVisitor(None);
+            event.record(&mut visitor);
+            if visitor.0.as_deref() == Some("wrote file") {
+                let _ = self.0.send(true);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_finishes_in_flight_chunks_only() {
+        let (sender, receiver) = watch::channel(false);
+        let subscriber = tracing_subscriber::registry().with(ShutdownOnWrite(sender));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = (100..103)
+            .map(|block| Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![block])]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("shutdown");
+        let sink = FileOutput { timestamp_range: true, ..make_sink(&output_dir) };
+        let env = ExecutionEnvBuilder::new().verbose(0).shutdown(receiver).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+        assert_eq!(summary.cancelled.len(), 2);
+        assert!(summary.errored.is_empty());
+
+        // the report lists only the completed chunk, which is on disk
+        let report_dir = output_dir.join(".cryo/reports");
+        let reports: Vec<_> = std::fs::read_dir(report_dir).unwrap().collect();
+        assert_eq!(reports.len(), 1);
+        let report = std::fs::read_to_string(reports[0].as_ref().unwrap().path()).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        let completed_paths = report["results"]["completed_paths"].as_array().unwrap();
+        assert_eq!(completed_paths.len(), 1);
+        assert!(std::path::Path::new(completed_paths[0].as_str().unwrap()).exists());
+        assert_eq!(report["results"]["n_cancelled"], 2);
+        let n_files = std::fs::read_dir(&output_dir).unwrap().count();
+        assert_eq!(n_files, 2); // one chunk file and the .cryo dir
+    }
+
+    #[tokio::test]
+    async fn test_chunk_timeout_cancels_hanging_chunk_only() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        // one request per hour, so whichever chunk requests second hangs on the rate limiter
+        let quota = governor::Quota::per_hour(std::num::NonZeroU32::new(1).unwrap());
+        let source = Source {
+            rate_limiter: Arc::new(Some(governor::RateLimiter::direct(quota))),
+            ..(*make_source(provider)).clone()
+        };
+        let partitions = (100..102)
+            .map(|block| Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![block])]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("timeout");
+        let sink = make_sink(&output_dir);
+        let env = ExecutionEnvBuilder::new()
+            .verbose(0)
+            .report(false)
+            .chunk_timeout(Some(std::time::Duration::from_millis(200)))
+            .build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+        assert_eq!(summary.errored.len(), 1);
+        assert!(matches!(summary.errored[0].1, CollectError::Timeout(_)));
+        assert!(summary.cancelled.is_empty());
+
+        // only the chunk that completed was written
+        let n_files = std::fs::read_dir(&output_dir).unwrap().count();
+        assert_eq!(n_files, 1);
+    }
+
+    #[tokio::test]
+    async fn test_row_limit_truncates_and_cancels_chunks() {
+        let (provider, mock) = Provider::mocked();
+        for number in 0..4u64 {
+            mock.push(Block::<TxHash> { number: Some(number.into()), ..Default::default() })
+                .unwrap();
+        }
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = [100, 102, 104]
+            .iter()
+            .map(|block| Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![*block, block + 1])]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("limit");
+        let sink = make_sink(&output_dir);
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).limit(Some(3)).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 2);
+        assert_eq!(summary.cancelled.len(), 1);
+        assert_eq!(summary.n_rows, 3);
+
+        // the second chunk is truncated to the single row left under the limit
+        let n_rows: Vec<usize> = ["00000100_to_00000101", "00000102_to_00000103"]
+            .iter()
+            .map(|range| {
+                let path = output_dir.join(format!("ethereum__blocks__{}.parquet", range));
+                let file = std::fs::File::open(path).unwrap();
+                ParquetReader::new(file).finish().unwrap().height()
+            })
+            .collect();
+        assert_eq!(n_rows, vec![2, 1]);
+        assert_eq!(std::fs::read_dir(&output_dir).unwrap().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_single_file_appends_every_chunk() {
+        let (provider, mock) = Provider::mocked();
+        for number in (100..104).rev() {
+            let block = Block::<TxHash> { number: Some(number.into()), ..Default::default() };
+            mock.push(block).unwrap();
+        }
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = [(100, 101), (102, 103)]
+            .into_iter()
+            .map(|(start, end)| Partition {
+                block_numbers: Some(vec![BlockChunk::Range(start, end)]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("single_file");
+        let sink = FileOutput {
+            single_file: true,
+            ordered: true,
+            timestamp_range: true,
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 2);
+        assert!(summary.errored.is_empty());
+
+        let files: Vec<_> = std::fs::read_dir(&output_dir).unwrap().map(|f| f.unwrap()).collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_name(), "ethereum__blocks__00000100_to_00000103.parquet");
+        let file = std::fs::File::open(files[0].path()).unwrap();
+        let df = ParquetReader::new(file).finish().unwrap();
+        let block_numbers: Vec<_> =
+            df.column("block_number").unwrap().u32().unwrap().into_iter().collect();
+        assert_eq!(block_numbers, vec![Some(100), Some(101), Some(102), Some(103)]);
+    }
+
+    /// collect two chunks into a single file, with the later chunk completing first
+    async fn freeze_reversed_chunks(ordered: bool) -> Vec<Option<u32>> {
+        let (provider, mock) = Provider::mocked();
+        for number in [101, 100, 103, 102] {
+            let block = Block::<TxHash> { number: Some(number.into()), ..Default::default() };
+            mock.push(block).unwrap();
+        }
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = [(102, 103), (100, 101)]
+            .into_iter()
+            .map(|(start, end)| Partition {
+                block_numbers: Some(vec![BlockChunk::Range(start, end)]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new(&format!("ordered_{}", ordered));
+        let sink = FileOutput {
+            single_file: true,
+            ordered,
+            timestamp_range: true,
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 2);
+
+        let path = output_dir.join("ethereum__blocks__00000100_to_00000103.parquet");
+        let df = ParquetReader::new(std::fs::File::open(path).unwrap()).finish().unwrap();
+        df.column("block_number").unwrap().u32().unwrap().into_iter().collect()
+    }
+
+    #[tokio::test]
+    async fn test_single_file_is_ordered_by_block() {
+        let unordered = freeze_reversed_chunks(false).await;
+        assert_eq!(unordered, vec![Some(102), Some(103), Some(100), Some(101)]);
+
+        let ordered = freeze_reversed_chunks(true).await;
+        assert_eq!(ordered, vec![Some(100), Some(101), Some(102), Some(103)]);
+    }
+
+    #[tokio::test]
+    async fn test_transform_is_applied_before_writing() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("transform");
+        let sink = FileOutput { timestamp_range: true, ..make_sink(&output_dir) };
+        let transform = |datatype: Datatype, mut df: DataFrame| {
+            let label = Series::new("label", vec![datatype.name(); df.height()]);
+            df.with_column(label).map_err(CollectError::PolarsError)?;
+            Ok(df)
+        };
+        let env = ExecutionEnvBuilder::new()
+            .verbose(0)
+            .report(false)
+            .transform(Arc::new(transform))
+            .build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+
+        let path = output_dir.join("ethereum__blocks__00000100_to_00000100.parquet");
+        let df = ParquetReader::new(std::fs::File::open(path).unwrap()).finish().unwrap();
+        let labels: Vec<_> = df.column("label").unwrap().utf8().unwrap().into_iter().collect();
+        assert_eq!(labels, vec![Some("blocks")]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_writer_captures_rows_without_files() {
+        let (provider, mock) = Provider::mocked();
+        for number in [101u64, 100] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                gas_used: (number * 10).into(),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = [100, 101]
+            .into_iter()
+            .map(|number| Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![number])]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("memory");
+        let sink = make_sink(&output_dir);
+        let writer = crate::MemoryWriter::new();
+        let env = ExecutionEnvBuilder::new()
+            .verbose(0)
+            .report(false)
+            .writer(Arc::new(writer.clone()))
+            .build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 2);
+        assert!(!output_dir.exists());
+
+        let frames = writer.frames();
+        assert_eq!(frames.len(), 2);
+        let path = output_dir.join("ethereum__blocks__00000100_to_00000100.parquet");
+        assert!(frames.iter().any(|frame| frame.path == path));
+        let df = writer.rows(Datatype::Blocks).unwrap().unwrap();
+        let numbers: Vec<_> =
+            df.column("block_number").unwrap().u32().unwrap().into_iter().collect();
+        assert_eq!(numbers, vec![Some(100), Some(101)]);
+        let gas: Vec<_> = df.column("gas_used").unwrap().u64().unwrap().into_iter().collect();
+        assert_eq!(gas, vec![Some(1000), Some(1010)]);
+        assert!(writer.rows(Datatype::Logs).unwrap().is_none());
+
+        // outputs built on files already written cannot be captured
+        let sink = FileOutput { single_file: true, ..sink };
+        assert!(freeze(&query, &source, &sink, &env).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_range_is_recorded() {
+        let (provider, mock) = Provider::mocked();
+        for (number, timestamp) in [(101, 1_700_000_012), (100, 1_700_000_000)] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                timestamp: timestamp.into(),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let log = Log {
+            block_number: Some(100.into()),
+            transaction_hash: Some(H256::zero()),
+            transaction_index: Some(0.into()),
+            log_index: Some(0.into()),
+            ..Default::default()
+        };
+        mock.push::<Vec<Log>, _>(vec![log]).unwrap();
+        let source = Source { inner_request_size: 2, ..(*make_source(provider)).clone() };
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 101)]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Logs) };
+        let output_dir = TempDir::new("timestamp_range");
+        let sink = FileOutput { timestamp_range: true, ..make_sink(&output_dir) };
+        let env = ExecutionEnvBuilder::new().verbose(0).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+        let path = output_dir.join("ethereum__logs__00000100_to_00000101.parquet");
+        let expected =
+            TimestampRange { min_timestamp: 1_700_000_000, max_timestamp: 1_700_000_012 };
+        assert_eq!(summary.timestamp_ranges[&path], expected);
+
+        // file metadata
+        let mut reader = ParquetReader::new(std::fs::File::open(&path).unwrap());
+        let metadata = reader.get_metadata().unwrap().key_value_metadata.clone().unwrap();
+        let get_value = |key: &str| {
+            metadata.iter().find(|kv| kv.key == key).and_then(|kv| kv.value.clone()).unwrap()
+        };
+        assert_eq!(get_value("cryo_min_timestamp"), "1700000000");
+        assert_eq!(get_value("cryo_max_timestamp"), "1700000012");
+        assert_eq!(get_value("cryo_network"), "ethereum");
+        assert_eq!(get_value("cryo_chain_id"), "1");
+        let schema = query.schemas.get_schema(&Datatype::Logs).unwrap();
+        assert_eq!(get_value("cryo_schema_hash"), schema.schema_hash_metadata().1);
+
+        // report
+        let report_dir = output_dir.join(".cryo/reports");
+        let report_path = std::fs::read_dir(report_dir).unwrap().next().unwrap().unwrap().path();
+        let report = std::fs::read_to_string(report_path).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+        let range = &report["results"]["timestamp_ranges"][path.to_str().unwrap()];
+        assert_eq!(range["min_timestamp"], 1_700_000_000);
+        assert_eq!(range["max_timestamp"], 1_700_000_012);
+    }
+
+    /// records the number of completed paths in each checkpoint report, as it is written
+    struct CheckpointRecorder(Arc<std::sync::Mutex<Vec<usize>>>);
+
+    struct CheckpointVisitor {
+        message: Option<String>,
+        path: Option<String>,
+    }
+
+    impl Visit for CheckpointVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            match field.name() {
+                "message" => self.message = Some(format!("{:?}", value)),
+                "path" => self.path = Some(format!("{:?}", value)),
+                _ => {}
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for CheckpointRecorder {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut visitor = CheckpointVisitor { message: None, path: None };
+            event.record(&mut visitor);
+            if let (Some("wrote checkpoint"), Some(path)) =
+                (visitor.message.as_deref(), visitor.path)
+            {
+                let report = std::fs::read_to_string(path).unwrap();
+                let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+                let completed_paths = report["results"]["completed_paths"].as_array().unwrap();
+                self.0.lock().unwrap().push(completed_paths.len());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_is_written_every_n_blocks() {
+        let checkpoints = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let subscriber =
+            tracing_subscriber::registry().with(CheckpointRecorder(checkpoints.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (provider, mock) = Provider::mocked();
+        for number in (100..105).rev() {
+            let block = Block::<TxHash> { number: Some(number.into()), ..Default::default() };
+            mock.push(block).unwrap();
+        }
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = (100..105)
+            .map(|block| Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![block])]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("checkpoint");
+        let sink = FileOutput { timestamp_range: true, ..make_sink(&output_dir) };
+        let env =
+            ExecutionEnvBuilder::new().verbose(0).checkpoint(Some(Checkpoint::Blocks(2))).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 5);
+        assert_eq!(*checkpoints.lock().unwrap(), vec![2, 4]);
+
+        // the final report replaces the checkpointed initial report
+        let reports: Vec<_> =
+            std::fs::read_dir(output_dir.join(".cryo/reports")).unwrap().collect();
+        assert_eq!(reports.len(), 1);
+    }
+
+    /// records the schema field of "resolved schema" events
+    struct SchemaRecorder(Arc<std::sync::Mutex<Vec<String>>>);
+
+    struct SchemaVisitor(Option<String>);
+
+    impl Visit for SchemaVisitor {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "schema" {
+                self.0 = Some(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SchemaRecorder {
+        fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+            let mut visitor = SchemaVisitor(None);
+            event.record(&mut visitor);
+            if let Some(schema) = visitor.0 {
+                self.0.lock().unwrap().push(schema);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_print_schema_logs_resolved_table() {
+        let schemas = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(SchemaRecorder(schemas.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let include_columns = Some(vec!["base_fee_per_gas".to_string()]);
+        let schema = Datatype::Blocks
+            .table_schema(
+                &[crate::U256Type::Binary],
+                &crate::ColumnEncoding::Hex,
+                &include_columns,
+                &None,
+                &None,
+                &None,
+                Some(vec!["block_number".to_string()]),
+                None,
+                false,
+            )
+            .unwrap();
+        let query = Query {
+            schemas: HashMap::from([(Datatype::Blocks, schema.clone())]),
+            partitions: vec![Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+                ..Default::default()
+            }],
+            ..make_query(Datatype::Blocks)
+        };
+        let output_dir = TempDir::new("print_schema");
+        let sink = make_sink(&output_dir);
+        let env = ExecutionEnvBuilder::new().verbose(0).dry(true).print_schema(true).build();
+
+        freeze(&query, &source, &sink, &env).await.unwrap();
+        let logged = schemas.lock().unwrap().clone();
+        assert_eq!(logged.len(), 1);
+        let logged: crate::Table = serde_json::from_str(&logged[0]).unwrap();
+        assert_eq!(logged, schema);
+        assert!(logged.has_column("base_fee_per_gas"));
+        assert_eq!(logged.sort_columns, Some(vec!["block_number".to_string()]));
+        assert!(!output_dir.exists());
+    }
+
+    /// chunk sizes chosen for blocks 100 to 111 when every block has logs of the given size
+    async fn adaptive_chunk_sizes(n_logs: u64, data_size: usize, name: &str) -> Vec<u64> {
+        let (provider, mock) = Provider::mocked();
+        for _ in 100..112 {
+            let logs: Vec<Log> = (0..n_logs)
+                .map(|log_index| Log {
+                    block_number: Some(100.into()),
+                    transaction_hash: Some(H256::zero()),
+                    transaction_index: Some(0.into()),
+                    log_index: Some(log_index.into()),
+                    data: vec![7u8; data_size].into(),
+                    ..Default::default()
+                })
+                .collect();
+            mock.push::<Vec<Log>, _>(logs).unwrap();
+        }
+        let source = make_source(provider);
+        let partitions = (100..112)
+            .step_by(2)
+            .map(|block| Partition {
+                block_numbers: Some(vec![BlockChunk::Range(block, block + 1)]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Logs) };
+        let output_dir = TempDir::new(&format!("target_file_size_{}", name));
+        let sink = FileOutput { target_file_size: Some(30_000), ..make_sink(&output_dir) };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert!(summary.errored.is_empty());
+        let mut chunks: Vec<_> = summary
+            .completed
+            .iter()
+            .map(|partition| partition.block_numbers.as_ref().unwrap())
+            .map(|chunks| (chunks.min_value().unwrap(), chunks.size()))
+            .collect();
+        chunks.sort();
+        assert_eq!(chunks.iter().map(|(_, size)| size).sum::<u64>(), 12);
+        chunks.into_iter().map(|(_, size)| size).collect()
+    }
+
+    #[tokio::test]
+    async fn test_target_file_size_adapts_chunk_size() {
+        let sparse = adaptive_chunk_sizes(1, 0, "sparse").await;
+        let dense = adaptive_chunk_sizes(100, 200, "dense").await;
+
+        // both start from the planned chunk size, then size chunks from the bytes per block
+        assert_eq!((sparse[0], dense[0]), (2, 2));
+        assert!(sparse.len() < dense.len());
+        assert!(sparse[1..].iter().min() > dense[1..].iter().max());
+    }
+
+    #[tokio::test]
+    async fn test_clean_empty_dirs_keeps_populated_and_existing_dirs() {
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Vec<Log>, _>(vec![]).unwrap();
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let blocks = make_query(Datatype::Blocks);
+        let logs = make_query(Datatype::Logs);
+        let query = Query {
+            datatypes: vec![
+                MetaDatatype::Scalar(Datatype::Blocks),
+                MetaDatatype::Scalar(Datatype::Logs),
+            ],
+            schemas: blocks.schemas.into_iter().chain(logs.schemas).collect(),
+            partitions: vec![partition],
+            ..make_query(Datatype::Blocks)
+        };
+        let output_dir = TempDir::new("clean_empty_dirs");
+        std::fs::create_dir_all(output_dir.join("existing")).unwrap();
+        let sink = FileOutput {
+            subdirs: vec![SubDir::Datatype],
+            partition_by_column: Some("block_number".to_string()),
+            clean_empty_dirs: true,
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert!(summary.errored.is_empty());
+
+        // the chunk has no logs, so no partition files were written under the logs directory
+        assert!(!output_dir.join("logs").exists());
+        assert!(output_dir.join("blocks/block_number=100").exists());
+        assert!(output_dir.join("existing").exists());
+    }
+
+    #[tokio::test]
+    async fn test_delta_log_references_written_files() {
+        let (provider, mock) = Provider::mocked();
+        for block_number in [101u64, 100] {
+            mock.push(Block::<TxHash> { number: Some(block_number.into()), ..Default::default() })
+                .unwrap();
+        }
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partitions = [100, 101]
+            .into_iter()
+            .map(|block_number| Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![block_number])]),
+                ..Default::default()
+            })
+            .collect();
+        let query = Query { partitions, ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("delta");
+        let sink =
+            FileOutput { subdirs: vec![SubDir::Datatype], delta: true, ..make_sink(&output_dir) };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert!(summary.errored.is_empty());
+
+        // one commit per chunk, the first also holding the protocol and schema of the table
+        let table_dir = output_dir.join("blocks");
+        let log_dir = table_dir.join(crate::DELTA_LOG_DIR);
+        let mut commits: Vec<_> =
+            std::fs::read_dir(log_dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+        commits.sort();
+        let names: Vec<_> =
+            commits.iter().map(|path| path.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, vec!["00000000000000000000.json", "00000000000000000001.json"]);
+        let actions: Vec<serde_json::Value> = commits
+            .iter()
+            .flat_map(|path| {
+                let contents = std::fs::read_to_string(path).unwrap();
+                contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(actions[0]["protocol"]["minReaderVersion"], 1);
+        let schema: serde_json::Value =
+            serde_json::from_str(actions[1]["metaData"]["schemaString"].as_str().unwrap()).unwrap();
+        let fields = schema["fields"].as_array().unwrap();
+        assert!(fields.contains(&serde_json::json!({
+            "name": "block_number",
+            "type": "long",
+            "nullable": true,
+            "metadata": {},
+        })));
+
+        let mut added: Vec<String> = actions
+            .iter()
+            .filter_map(|action| action["add"]["path"].as_str().map(|path| path.to_string()))
+            .collect();
+        added.sort();
+        let mut written: Vec<String> = std::fs::read_dir(&table_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_str().unwrap().to_string())
+            .filter(|name| name.ends_with(".parquet"))
+            .collect();
+        written.sort();
+        assert_eq!(written.len(), 2);
+        assert_eq!(added, written);
+        let sizes: Vec<u64> =
+            actions.iter().filter_map(|action| action["add"]["size"].as_u64()).collect();
+        assert!(sizes.iter().all(|size| *size > 0));
+    }
+
+    #[tokio::test]
+    async fn test_column_compression_overrides_file_compression() {
+        let (provider, mock) = Provider::mocked();
+        let block = Block::<TxHash> {
+            number: Some(100.into()),
+            hash: Some(H256::from_low_u64_be(1)),
+            ..Default::default()
+        };
+        mock.push(block).unwrap();
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("column_compression");
+        let zstd = polars::prelude::ZstdLevel::try_new(9).unwrap();
+        let sink = FileOutput {
+            parquet_compression: polars::prelude::ParquetCompression::Snappy,
+            column_compression: HashMap::from([
+                ("block_hash".to_string(), polars::prelude::ParquetCompression::Uncompressed),
+                ("extra_data".to_string(), polars::prelude::ParquetCompression::Zstd(Some(zstd))),
+            ]),
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+
+        let path = output_dir.join("ethereum__blocks__00000100_to_00000100.parquet");
+        let mut reader = ParquetReader::new(std::fs::File::open(&path).unwrap());
+        let metadata = reader.get_metadata().unwrap().clone();
+        let compression = |column: &str| {
+            let columns = metadata.row_groups[0].columns();
+            let chunk = columns.iter().find(|c| c.descriptor().path_in_schema[0] == column);
+            chunk.unwrap().compression()
+        };
+        use polars_parquet::parquet::compression::Compression;
+        assert_eq!(compression("block_hash"), Compression::Uncompressed);
+        assert_eq!(compression("extra_data"), Compression::Zstd);
+        assert_eq!(compression("block_number"), Compression::Snappy);
+        let df = reader.finish().unwrap();
+        assert_eq!(df.height(), 1);
+
+        // overrides must name a column of the query
+        let sink = FileOutput {
+            column_compression: HashMap::from([(
+                "not_a_column".to_string(),
+                polars::prelude::ParquetCompression::Uncompressed,
+            )]),
+            ..sink
+        };
+        assert!(freeze(&query, &source, &sink, &env).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_schema_version_is_recorded_in_metadata() {
+        let (provider, mock) = Provider::mocked();
+        let block = Block::<TxHash> {
+            number: Some(100.into()),
+            hash: Some(H256::from_low_u64_be(1)),
+            ..Default::default()
+        };
+        mock.push(block).unwrap();
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("schema_version");
+        let sink = FileOutput {
+            parquet_compression: polars::prelude::ParquetCompression::Snappy,
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+
+        let version = Datatype::Blocks.schema_version();
+        assert!(version > 0);
+        let path = output_dir.join("ethereum__blocks__00000100_to_00000100.parquet");
+        let mut reader = ParquetReader::new(std::fs::File::open(path).unwrap());
+        let metadata = reader.get_metadata().unwrap().key_value_metadata.clone().unwrap();
+        let value = metadata.iter().find(|kv| kv.key == "cryo_schema_version");
+        assert_eq!(value.and_then(|kv| kv.value.clone()), Some(version.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_decimal_scale_is_set_per_column() {
+        let (provider, mock) = Provider::mocked();
+        for number in [101, 100] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                hash: Some(H256::from_low_u64_be(number)),
+                total_difficulty: Some(U256::from(10).pow(20.into())),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let source = make_source(provider);
+        let columns = Some(vec!["block_number".to_string(), "total_difficulty".to_string()]);
+        let schema = Datatype::Blocks
+            .table_schema(
+                &[U256Type::Decimal128],
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &columns,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let query = Query {
+            schemas: HashMap::from([(Datatype::Blocks, schema)]),
+            ..make_query(Datatype::Blocks)
+        };
+        let output_dir = TempDir::new("decimal_scale");
+        let sink = FileOutput {
+            decimal_types: HashMap::from([("total_difficulty".to_string(), (38, 18))]),
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+        let decimal_type = |sink: FileOutput, number: u64| {
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![number])]),
+                ..Default::default()
+            };
+            let query = Query { partitions: vec![partition], ..query.clone() };
+            let (source, env) = (source.clone(), env.clone());
+            async move {
+                freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+                let filename = format!("ethereum__blocks__{:08}_to_{:08}.parquet", number, number);
+                let mut file = std::fs::File::open(sink.output_dir.join(filename)).unwrap();
+                let metadata = polars_parquet::read::read_metadata(&mut file).unwrap();
+                let schema = polars_parquet::read::infer_schema(&metadata).unwrap();
+                let field = schema.fields.iter().find(|f| f.name == "total_difficulty_d128");
+                field.unwrap().data_type.clone()
+            }
+        };
+        use polars::export::arrow::datatypes::ArrowDataType;
+        assert_eq!(decimal_type(sink.clone(), 100).await, ArrowDataType::Decimal(38, 18));
+
+        // columns without a decimal type keep the declared scale
+        let default_sink = FileOutput { decimal_types: HashMap::new(), ..sink.clone() };
+        assert_eq!(decimal_type(default_sink, 101).await, ArrowDataType::Decimal(38, 0));
+
+        // decimal types must name a u256 column of the query
+        let sink = FileOutput {
+            decimal_types: HashMap::from([("block_number".to_string(), (38, 18))]),
+            ..sink
+        };
+        assert!(freeze(&query, &source, &sink, &env).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fixed_size_binary_columns() {
+        let widths = Datatype::Blocks.column_widths();
+        assert_eq!((widths["block_hash"], widths["author"]), (32, 20));
+        assert!(!widths.contains_key("extra_data"));
+
+        let (provider, mock) = Provider::mocked();
+        for number in [101, 100] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                hash: Some(H256::from_low_u64_be(number)),
+                author: Some(H160::from_low_u64_be(number)),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let source = make_source(provider);
+        let columns = Some(vec![
+            "block_number".to_string(),
+            "block_hash".to_string(),
+            "author".to_string(),
+            "extra_data".to_string(),
+        ]);
+        let output_dir = TempDir::new("fixed_size_binary");
+        let sink = FileOutput {
+            fixed_size_binary: widths.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        // binary and hex encoded columns are both written as fixed size binary
+        for (number, encoding) in [(100, ColumnEncoding::Binary), (101, ColumnEncoding::Hex)] {
+            let schema = Datatype::Blocks
+                .table_schema(&[], &encoding, &None, &None, &columns, &None, None, None, false)
+                .unwrap();
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![number])]),
+                ..Default::default()
+            };
+            let query = Query {
+                schemas: HashMap::from([(Datatype::Blocks, schema)]),
+                partitions: vec![partition],
+                ..make_query(Datatype::Blocks)
+            };
+            freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+
+            let filename = format!("ethereum__blocks__{:08}_to_{:08}.parquet", number, number);
+            let mut file = std::fs::File::open(output_dir.join(filename)).unwrap();
+            let metadata = polars_parquet::read::read_metadata(&mut file).unwrap();
+            let schema = polars_parquet::read::infer_schema(&metadata).unwrap();
+            let data_type = |name: &str| {
+                schema.fields.iter().find(|f| f.name == name).unwrap().data_type.clone()
+            };
+            use polars::export::arrow::datatypes::ArrowDataType;
+            assert_eq!(data_type("block_hash"), ArrowDataType::FixedSizeBinary(32));
+            assert_eq!(data_type("author"), ArrowDataType::FixedSizeBinary(20));
+            assert_ne!(data_type("extra_data"), ArrowDataType::FixedSizeBinary(32));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dictionary_encoding_is_set_per_column() {
+        let (provider, mock) = Provider::mocked();
+        for number in [101u64, 100] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                hash: Some(H256::from_low_u64_be(number)),
+                author: Some(H160::from_low_u64_be(7)),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100, 101])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("dictionary");
+        let sink = FileOutput {
+            dictionary_encoding: HashMap::from([
+                ("author".to_string(), true),
+                ("block_hash".to_string(), false),
+            ]),
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+
+        let path = output_dir.join("ethereum__blocks__00000100_to_00000101.parquet");
+        let mut reader = ParquetReader::new(std::fs::File::open(path).unwrap());
+        let metadata = reader.get_metadata().unwrap().clone();
+        let is_dictionary = |column: &str| {
+            let columns = metadata.row_groups[0].columns();
+            let chunk = columns.iter().find(|c| c.descriptor().path_in_schema[0] == column);
+            chunk.unwrap().column_encoding().iter().any(|encoding| {
+                use polars_parquet::parquet::encoding::Encoding;
+                let encoding = Encoding::try_from(*encoding);
+                matches!(encoding, Ok(Encoding::RleDictionary | Encoding::PlainDictionary))
+            })
+        };
+        assert!(is_dictionary("author"));
+        assert!(!is_dictionary("block_hash"));
+        assert!(!is_dictionary("block_number"));
+
+        // dictionary encoded columns read back as their own type
+        let df = reader.finish().unwrap();
+        let authors: Vec<_> = df.column("author").unwrap().binary().unwrap().into_iter().collect();
+        let author = H160::from_low_u64_be(7);
+        assert_eq!(authors, vec![Some(author.as_bytes()); 2]);
+    }
+
+    #[tokio::test]
+    async fn test_nested_output_dir_is_created_before_collection() {
+        // no responses are mocked, so the chunk fails without writing any file
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+        let root = TempDir::new("nested_dir");
+        let output_dir = root.join("a").join("b");
+        let sink = FileOutput {
+            subdirs: vec![SubDir::Datatype],
+            dir_mode: Some(0o750),
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.errored.len(), 1);
+        let blocks_dir = output_dir.join("blocks");
+        assert!(blocks_dir.is_dir());
+        assert_eq!(std::fs::read_dir(&blocks_dir).unwrap().count(), 0);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            for dir in [root.to_path_buf(), output_dir, blocks_dir] {
+                let mode = std::fs::metadata(dir).unwrap().permissions().mode();
+                assert_eq!(mode & 0o7777, 0o750);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_creates_no_dirs() {
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+        let root = TempDir::new("dry_dirs");
+        let sink = FileOutput { subdirs: vec![SubDir::Datatype], ..make_sink(&root.join("a")) };
+        let env = ExecutionEnvBuilder::new().verbose(0).dry(true).build();
+
+        assert!(freeze(&query, &source, &sink, &env).await.unwrap().is_none());
+        assert!(!root.exists());
+    }
+
+    #[tokio::test]
+    async fn test_partition_by_date_splits_chunk_across_days() {
+        let (provider, mock) = Provider::mocked();
+        // 2023-11-14 23:59:59 and 2023-11-15 00:00:00 UTC
+        for (number, timestamp) in
+            [(100u64, 1_700_006_399u64), (101, 1_700_006_400)].into_iter().rev()
+        {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                timestamp: timestamp.into(),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 101)]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+        let output_dir = TempDir::new("partition_by_date");
+        let sink =
+            FileOutput { timestamp_range: true, partition_by_date: true, ..make_sink(&output_dir) };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+        assert!(summary.errored.is_empty());
+
+        let filename = "ethereum__blocks__00000100_to_00000101.parquet";
+        for (date, block_number, timestamp) in
+            [("2023-11-14", 100, 1_700_006_399), ("2023-11-15", 101, 1_700_006_400)]
+        {
+            let path = output_dir.join(format!("date={}", date)).join(filename);
+            let df = ParquetReader::new(std::fs::File::open(&path).unwrap()).finish().unwrap();
+            let block_numbers: Vec<_> =
+                df.column("block_number").unwrap().u32().unwrap().into_iter().collect();
+            assert_eq!(block_numbers, vec![Some(block_number)]);
+            let range = TimestampRange { min_timestamp: timestamp, max_timestamp: timestamp };
+            assert_eq!(summary.timestamp_ranges[&path], range);
+        }
+        assert!(!output_dir.join(filename).exists());
+    }
+
+    #[tokio::test]
+    async fn test_partition_by_column_routes_rows_to_open_writers() {
+        let output_dir = TempDir::new("partition_by_column");
+        let freeze_authors = |max_open_partitions: usize| {
+            let (provider, mock) = Provider::mocked();
+            for (number, author) in [(100u64, 1u64), (101, 2), (102, 1), (103, 3)] {
+                let block = Block::<TxHash> {
+                    number: Some(number.into()),
+                    author: Some(H160::from_low_u64_be(author)),
+                    ..Default::default()
+                };
+                mock.push(block).unwrap();
+            }
+            let source = make_source(provider);
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![100, 101, 102, 103])]),
+                ..Default::default()
+            };
+            let query = Query { partitions: vec![partition], ..make_query(Datatype::Blocks) };
+            let sink = FileOutput {
+                overwrite: true,
+                partition_by_column: Some("author".to_string()),
+                max_open_partitions,
+                streaming: true,
+                ..make_sink(&output_dir)
+            };
+            let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+            async move { freeze(&query, &source, &sink, &env).await.unwrap().unwrap() }
+        };
+
+        // a fourth partition value would exceed the limit of open writers
+        let summary = freeze_authors(2).await;
+        assert_eq!(summary.errored.len(), 1);
+
+        let summary = freeze_authors(3).await;
+        assert_eq!(summary.completed.len(), 1);
+        let filename = "ethereum__blocks__00000100_to_00000103.parquet";
+        let mut n_rows = Vec::new();
+        for author in 1..=3u64 {
+            let author = prefix_hex::encode(H160::from_low_u64_be(author).as_bytes().to_vec());
+            let path = output_dir.join(format!("author={}", author)).join(filename);
+            let df = ParquetReader::new(std::fs::File::open(&path).unwrap()).finish().unwrap();
+            n_rows.push(df.height());
+        }
+        assert_eq!(n_rows, vec![2, 1, 1]);
+        assert!(!output_dir.join(filename).exists());
+    }
+
+    #[tokio::test]
+    async fn test_partition_key_in_filename_splits_logs_by_topic0() {
+        let (provider, mock) = Provider::mocked();
+        let log = |log_index: u64, topic0: u64| Log {
+            block_number: Some(100.into()),
+            transaction_hash: Some(H256::zero()),
+            transaction_index: Some(0.into()),
+            log_index: Some(log_index.into()),
+            topics: vec![H256::from_low_u64_be(topic0)],
+            ..Default::default()
+        };
+        mock.push::<Vec<Log>, _>(vec![log(0, 1), log(1, 2), log(2, 1)]).unwrap();
+        let source = make_source(provider);
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Logs) };
+        let output_dir = TempDir::new("split_by_topic0");
+        let sink = FileOutput {
+            partition_by_column: Some("topic0".to_string()),
+            partition_key_in_filename: true,
+            ..make_sink(&output_dir)
+        };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+        let mut files: Vec<String> = std::fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.ends_with(".parquet"))
+            .collect();
+        files.sort();
+        let topic =
+            |value: u64| prefix_hex::encode(H256::from_low_u64_be(value).as_bytes().to_vec());
+        let expected: Vec<String> = [1, 2]
+            .iter()
+            .map(|value| format!("ethereum__logs__{}__00000100_to_00000100.parquet", topic(*value)))
+            .collect();
+        assert_eq!(files, expected);
+        let n_rows: Vec<usize> = files
+            .iter()
+            .map(|file| {
+                let file = std::fs::File::open(output_dir.join(file)).unwrap();
+                ParquetReader::new(file).finish().unwrap().height()
+            })
+            .collect();
+        assert_eq!(n_rows, vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_bounds_buffered_rows() {
+        let (provider, mock) = Provider::mocked();
+        for block_number in 100..104 {
+            let logs: Vec<Log> = (0..2)
+                .map(|log_index| Log {
+                    block_number: Some(block_number.into()),
+                    transaction_hash: Some(H256::zero()),
+                    transaction_index: Some(0.into()),
+                    log_index: Some(log_index.into()),
+                    ..Default::default()
+                })
+                .collect();
+            mock.push::<Vec<Log>, _>(logs).unwrap();
+        }
+        let source = Source { inner_request_size: 1, ..(*make_source(provider)).clone() };
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 103)]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Logs) };
+        let output_dir = TempDir::new("streaming");
+        let sink = FileOutput { streaming: true, ..make_sink(&output_dir) };
+
+        // every batch passes through the transform, so it observes the rows held at once
+        let peak_rows = Arc::new(AtomicUsize::new(0));
+        let observed = peak_rows.clone();
+        let transform = move |_: Datatype, df: DataFrame| {
+            observed.fetch_max(df.height(), Ordering::SeqCst);
+            Ok(df)
+        };
+        let env = ExecutionEnvBuilder::new()
+            .verbose(0)
+            .report(false)
+            .transform(Arc::new(transform))
+            .build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.completed.len(), 1);
+        assert_eq!(summary.n_rows, 8);
+        assert_eq!(peak_rows.load(Ordering::SeqCst), 2);
+
+        let path = output_dir.join("ethereum__logs__00000100_to_00000103.parquet");
+        let df = ParquetReader::new(std::fs::File::open(path).unwrap()).finish().unwrap();
+        let mut block_numbers: Vec<_> =
+            df.column("block_number").unwrap().u32().unwrap().into_iter().flatten().collect();
+        block_numbers.sort();
+        assert_eq!(block_numbers, vec![100, 100, 101, 101, 102, 102, 103, 103]);
+    }
+
+    #[tokio::test]
+    async fn test_unwritable_index_is_io_error() {
+        let (provider, _mock) = Provider::mocked();
+        let source = make_source(provider);
+        let query = make_query(Datatype::Blocks);
+        let output_dir = TempDir::new("index_io");
+        // a directory in place of the index file cannot be written to
+        std::fs::create_dir_all(output_dir.join(crate::INDEX_FILENAME)).unwrap();
+        let sink = FileOutput { write_index: true, ..make_sink(&output_dir) };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let result = freeze(&query, &source, &sink, &env).await;
+        assert!(matches!(result, Err(CollectError::Io(_))));
+    }
+
+    #[tokio::test]
+    async fn test_index_lists_every_datatype_and_file() {
+        let (provider, mock) = Provider::mocked();
+        // chunks run in order, blocks then logs, and responses are popped from the back
+        let log = Log {
+            block_number: Some(100.into()),
+            transaction_hash: Some(H256::from_low_u64_be(7)),
+            transaction_index: Some(0.into()),
+            log_index: Some(0.into()),
+            ..Default::default()
+        };
+        mock.push::<Vec<Log>, _>(vec![log]).unwrap();
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        let source = Source { max_concurrent_chunks: Some(1), ..(*make_source(provider)).clone() };
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let blocks = make_query(Datatype::Blocks);
+        let logs = make_query(Datatype::Logs);
+        let mut query = Query { partitions: vec![partition], ..blocks };
+        query.datatypes.extend(logs.datatypes);
+        query.schemas.extend(logs.schemas);
+        let output_dir = TempDir::new("index");
+        let sink = FileOutput { write_index: true, ..make_sink(&output_dir) };
+        let env = ExecutionEnvBuilder::new().verbose(0).report(false).build();
+
+        let summary = freeze(&query, &source, &sink, &env).await.unwrap().unwrap();
+        assert_eq!(summary.errored.len(), 0);
+
+        let json = std::fs::read_to_string(output_dir.join(crate::INDEX_FILENAME)).unwrap();
+        let index: crate::RunIndex = serde_json::from_str(&json).unwrap();
+        assert_eq!(index.chain_id, 1);
+        assert_eq!(index.datatypes.keys().collect::<Vec<_>>(), vec!["blocks", "logs"]);
+        for (name, entry) in index.datatypes.iter() {
+            let path = output_dir.join(format!("ethereum__{}__00000100_to_00000100.parquet", name));
+            assert_eq!(entry.manifest.chunks.len(), 1);
+            assert_eq!(entry.manifest.chunks[0].path, path);
+            assert_eq!(entry.manifest.chunks[0].status, crate::ChunkStatus::Completed);
+            assert_eq!(entry.manifest.chunks[0].rows, Some(1));
+            assert_eq!(entry.block_range, Some((100, 100)));
+            assert_eq!((entry.n_files, entry.n_rows), (1, 1));
+            assert_eq!(entry.schema["title"], name.as_str());
+        }
+        let schema_hash = query.schemas[&Datatype::Logs].schema_hash_metadata().1;
+        assert_eq!(index.datatypes["logs"].manifest.schema_hash, schema_hash);
+    }
+}
@@ -7,14 +7,24 @@
     attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
 ))]
 
+mod block_dates;
 mod collect;
+mod count;
 mod datasets;
 mod freeze;
+pub mod library;
 mod multi_datasets;
+mod preflight;
+#[cfg(test)]
+mod test_helpers;
 mod types;
 
+pub use block_dates::{first_block_at_or_after, first_block_of_date, parse_date};
 pub use collect::collect;
+pub use count::{count_rows, CountSummary};
 pub use datasets::*;
 pub use freeze::freeze;
+pub use library::{FreezeConfig, FreezeConfigBuilder};
 pub use multi_datasets::*;
+pub use preflight::{check_archive, check_decimals_fit, preflight};
 pub use types::*;
@@ -0,0 +1,383 @@
+//! library entrypoint that performs a whole freeze from a FreezeConfig
+//!
+//! the cli resolves its arguments into the parts of a FreezeConfig and runs through here too
+
+use crate::{
+    cluster_datatypes, err, network_name, sources::ProviderWrapper, BlockChunk, CollectError,
+    ColumnEncoding, Datatype, Dim, ExecutionEnv, ExecutionEnvBuilder, FileFormat, FileOutput,
+    FreezeSummary, LogsBackend, MetaDatatype, Partition, Query, QueryLabels, Source, Subchunk,
+    TimeDimension, TraceBackend, TraceGranularity, U256Type,
+};
+use std::{collections::HashMap, path::PathBuf};
+
+/// configuration of a complete freeze run, built with FreezeConfigBuilder
+#[derive(Clone)]
+pub struct FreezeConfig {
+    query: Query,
+    sink: FileOutput,
+    env: ExecutionEnv,
+    network_name: Option<String>,
+    inner_request_size: Option<u64>,
+    max_concurrent_chunks: Option<u64>,
+}
+
+impl FreezeConfig {
+    /// create config from an already resolved query, file output, and execution environment
+    pub fn from_parts(query: Query, sink: FileOutput, env: ExecutionEnv) -> FreezeConfig {
+        let network_name = Some(sink.prefix.clone());
+        FreezeConfig {
+            query,
+            sink,
+            env,
+            network_name,
+            inner_request_size: None,
+            max_concurrent_chunks: None,
+        }
+    }
+
+    /// query of the run
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// file output of the run
+    pub fn sink(&self) -> &FileOutput {
+        &self.sink
+    }
+}
+
+/// collect data from a provider and write it to files, as configured by config
+///
+/// a dry run collects nothing and returns no summary
+pub async fn freeze(
+    config: FreezeConfig,
+    provider: impl Into<ProviderWrapper>,
+) -> Result<Option<FreezeSummary>, CollectError> {
+    let source = Source::from_provider(provider).await?;
+    freeze_with_source(&config, &source).await
+}
+
+/// collect data from a source and write it to files, as configured by config
+pub async fn freeze_with_source(
+    config: &FreezeConfig,
+    source: &Source,
+) -> Result<Option<FreezeSummary>, CollectError> {
+    let source = Source {
+        inner_request_size: config.inner_request_size.unwrap_or(source.inner_request_size),
+        max_concurrent_chunks: config.max_concurrent_chunks.or(source.max_concurrent_chunks),
+        ..source.clone()
+    };
+    let prefix = config.network_name.clone().unwrap_or_else(|| network_name(source.chain_id));
+    let sink = FileOutput { prefix, ..config.sink.clone() };
+    crate::freeze(&config.query, &source, &sink, &config.env).await
+}
+
+/// build FreezeConfig using builder pattern
+pub struct FreezeConfigBuilder {
+    datatypes: Vec<Datatype>,
+    blocks: Option<(u64, u64)>,
+    chunk_size: u64,
+    columns: Option<Vec<String>>,
+    include_columns: Option<Vec<String>>,
+    exclude_columns: Option<Vec<String>>,
+    include_deprecated: bool,
+    u256_types: Vec<U256Type>,
+    hex: bool,
+    output_dir: PathBuf,
+    format: FileFormat,
+    overwrite: bool,
+    network_name: Option<String>,
+    inner_request_size: Option<u64>,
+    max_concurrent_chunks: Option<u64>,
+    env: Option<ExecutionEnv>,
+}
+
+impl FreezeConfigBuilder {
+    /// initialize FreezeConfigBuilder for datatypes, with the defaults of the cli
+    pub fn new(datatypes: Vec<Datatype>) -> Self {
+        FreezeConfigBuilder {
+            datatypes,
+            blocks: None,
+            chunk_size: 1000,
+            columns: None,
+            include_columns: None,
+            exclude_columns: None,
+            include_deprecated: false,
+            u256_types: vec![U256Type::Binary, U256Type::String, U256Type::F64],
+            hex: false,
+            output_dir: PathBuf::from("."),
+            format: FileFormat::Parquet,
+            overwrite: false,
+            network_name: None,
+            inner_request_size: None,
+            max_concurrent_chunks: None,
+            env: None,
+        }
+    }
+
+    /// blocks to collect, from start to end inclusive
+    pub fn blocks(mut self, start: u64, end: u64) -> Self {
+        self.blocks = Some((start, end));
+        self
+    }
+
+    /// number of blocks per file
+    pub fn chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// columns to collect instead of the default columns, `all` for every column
+    pub fn columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// columns to collect in addition to the default columns
+    pub fn include_columns(mut self, columns: Vec<String>) -> Self {
+        self.include_columns = Some(columns);
+        self
+    }
+
+    /// default columns not to collect
+    pub fn exclude_columns(mut self, columns: Vec<String>) -> Self {
+        self.exclude_columns = Some(columns);
+        self
+    }
+
+    /// include deprecated columns when collecting `all` columns
+    pub fn include_deprecated(mut self, include_deprecated: bool) -> Self {
+        self.include_deprecated = include_deprecated;
+        self
+    }
+
+    /// representations of u256 columns
+    pub fn u256_types(mut self, u256_types: Vec<U256Type>) -> Self {
+        self.u256_types = u256_types;
+        self
+    }
+
+    /// hex encode binary columns, they are always hex encoded outside of parquet files
+    pub fn hex(mut self, hex: bool) -> Self {
+        self.hex = hex;
+        self
+    }
+
+    /// directory of output files
+    pub fn output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    /// format of output files
+    pub fn format(mut self, format: FileFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// overwrite existing files instead of skipping them
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    /// prefix of file names, by default the name of the provider's chain
+    pub fn network_name(mut self, network_name: String) -> Self {
+        self.network_name = Some(network_name);
+        self
+    }
+
+    /// blocks per request for datatypes collected by block range, e.g. logs
+    pub fn inner_request_size(mut self, inner_request_size: u64) -> Self {
+        self.inner_request_size = Some(inner_request_size);
+        self
+    }
+
+    /// number of chunks to collect at once
+    pub fn max_concurrent_chunks(mut self, max_concurrent_chunks: u64) -> Self {
+        self.max_concurrent_chunks = Some(max_concurrent_chunks);
+        self
+    }
+
+    /// execution environment, by default a quiet run without reports
+    pub fn env(mut self, env: ExecutionEnv) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    /// resolve schemas and chunks into the final config
+    pub fn build(self) -> Result<FreezeConfig, CollectError> {
+        let Some((start, end)) = self.blocks else { return Err(err("no blocks specified")) };
+        if start > end {
+            return Err(err("start block is after end block"))
+        }
+        if self.chunk_size == 0 {
+            return Err(err("chunk size must be positive"))
+        }
+
+        // schemas
+        let binary_column_format = match self.hex || (self.format != FileFormat::Parquet) {
+            true => ColumnEncoding::Hex,
+            false => ColumnEncoding::Binary,
+        };
+        let mut schemas = HashMap::new();
+        for datatype in self.datatypes.iter() {
+            let mut schema = datatype.table_schema(
+                &self.u256_types,
+                &binary_column_format,
+                &self.include_columns,
+                &self.exclude_columns,
+                &self.columns,
+                &None,
+                Some(datatype.default_sort()),
+                None,
+                self.include_deprecated,
+            )?;
+            schema.set_trace_address_type(&self.format, false);
+            schemas.insert(*datatype, schema);
+        }
+
+        // chunks
+        let partitions = BlockChunk::Range(start, end)
+            .subchunk_by_size(&self.chunk_size)
+            .into_iter()
+            .map(|chunk| Partition { block_numbers: Some(vec![chunk]), ..Default::default() })
+            .collect();
+
+        let datatypes: Vec<MetaDatatype> = cluster_datatypes(self.datatypes);
+        let query = Query {
+            datatypes,
+            schemas,
+            time_dimension: TimeDimension::Blocks,
+            partitions,
+            partitioned_by: vec![Dim::BlockNumber],
+            exclude_failed: false,
+            js_tracer: None,
+            log_filter: None,
+            blocks_log_filter: None,
+            deduplicate_logs: false,
+            exclude_zero_value: false,
+            trace_granularity: TraceGranularity::Block,
+            trace_backend: TraceBackend::Parity,
+            logs_backend: LogsBackend::GetLogs,
+            genesis_path: None,
+            labels: QueryLabels { align: false, reorg_buffer: 0, skipped_blocks: vec![] },
+        };
+        let sink = FileOutput {
+            output_dir: self.output_dir,
+            prefix: String::new(),
+            suffix: None,
+            subdirs: vec![],
+            overwrite: self.overwrite,
+            format: self.format,
+            row_group_size: None,
+            parquet_statistics: true,
+            parquet_compression: polars::prelude::ParquetCompression::Lz4Raw,
+            column_compression: HashMap::new(),
+            dictionary_encoding: HashMap::new(),
+            decimal_types: HashMap::new(),
+            single_file: false,
+            ordered: false,
+            aggregates: vec![],
+            timestamp_range: true,
+            optimize_types: false,
+            partition_by_date: false,
+            partition_by_column: None,
+            max_open_partitions: 1000,
+            streaming: false,
+            dir_mode: None,
+            csv_types: false,
+            json_bigints_as_strings: true,
+            target_file_size: None,
+            clean_empty_dirs: false,
+            fixed_size_binary: HashMap::new(),
+            delta: false,
+            partition_key_in_filename: false,
+            checksum_addresses: false,
+            write_index: false,
+        };
+        let env = match self.env {
+            Some(env) => env,
+            None => ExecutionEnvBuilder::new().verbose(0).report(false).build(),
+        };
+        Ok(FreezeConfig {
+            query,
+            sink,
+            env,
+            network_name: self.network_name,
+            inner_request_size: self.inner_request_size,
+            max_concurrent_chunks: self.max_concurrent_chunks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::TempDir;
+    use ethers::prelude::*;
+
+    #[tokio::test]
+    async fn test_freeze_through_library() {
+        let (provider, mock) = Provider::mocked();
+        for number in [103u64, 102, 101, 100] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                hash: Some(H256::from_low_u64_be(number)),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        mock.push(U256::from(1)).unwrap();
+
+        let output_dir = TempDir::new("library_freeze");
+        let config = FreezeConfigBuilder::new(vec![Datatype::Blocks])
+            .blocks(100, 103)
+            .chunk_size(2)
+            .max_concurrent_chunks(1)
+            .output_dir(output_dir.to_path_buf())
+            .build()
+            .unwrap();
+        let summary = freeze(config, provider).await.unwrap().unwrap();
+
+        assert_eq!(summary.completed.len(), 2);
+        assert!(summary.errored.is_empty());
+        assert_eq!(summary.n_rows, 4);
+        for range in ["00000100_to_00000101", "00000102_to_00000103"] {
+            let path = output_dir.join(format!("ethereum__blocks__{}.parquet", range));
+            assert!(path.exists(), "missing {:?}", path);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_through_library_has_no_summary() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(U256::from(1)).unwrap();
+        let output_dir = TempDir::new("library_dry");
+        let config = FreezeConfigBuilder::new(vec![Datatype::Blocks])
+            .blocks(100, 103)
+            .output_dir(output_dir.to_path_buf())
+            .env(ExecutionEnvBuilder::new().verbose(0).dry(true).build())
+            .build()
+            .unwrap();
+        assert!(freeze(config, provider).await.unwrap().is_none());
+        assert!(!output_dir.exists());
+    }
+
+    #[test]
+    fn test_build_rejects_missing_blocks() {
+        assert!(FreezeConfigBuilder::new(vec![Datatype::Blocks]).build().is_err());
+        let config = FreezeConfigBuilder::new(vec![Datatype::Blocks]).blocks(0, 2499).build();
+        assert_eq!(config.unwrap().query().partitions.len(), 3);
+    }
+
+    #[test]
+    fn test_build_writes_json_bigints_as_strings() {
+        let config = FreezeConfigBuilder::new(vec![Datatype::Blocks])
+            .blocks(0, 99)
+            .format(FileFormat::Json)
+            .build()
+            .unwrap();
+        assert!(config.sink().json_bigints_as_strings);
+    }
+}
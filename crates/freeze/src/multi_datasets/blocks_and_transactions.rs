@@ -22,17 +22,23 @@ impl ToDataFrames for BlocksAndTransactions {
 
 #[async_trait::async_trait]
 impl CollectByBlock for BlocksAndTransactions {
-    type Response = <Transactions as CollectByBlock>::Response;
+    type Response = (<Transactions as CollectByBlock>::Response, blocks::BlockRewards);
 
     async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
-        <Transactions as CollectByBlock>::extract(request, source, query).await
+        let response =
+            <Transactions as CollectByBlock>::extract(request, source.clone(), query.clone())
+                .await?;
+        let schema = query.schemas.get_schema(&Datatype::Blocks)?;
+        let rewards = blocks::fetch_block_rewards(&response.0, &source, schema).await?;
+        Ok((response, rewards))
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
         let BlocksAndTransactions(blocks, transactions) = columns;
+        let (response, rewards) = response;
         let (block, _, _) = response.clone();
         let schema = query.schemas.get_schema(&Datatype::Blocks)?;
-        blocks::process_block(block, blocks, schema)?;
+        blocks::process_block(block, rewards, blocks, schema)?;
         <Transactions as CollectByBlock>::transform(response, transactions, query)?;
         Ok(())
     }
@@ -47,20 +53,23 @@ impl CollectByTransaction for BlocksAndTransactions {
 
     async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
         let ((tx, receipt), exclude_failed, timestamp) =
-            <Transactions as CollectByTransaction>::extract(request, source.clone(), query).await?;
+            <Transactions as CollectByTransaction>::extract(request, source.clone(), query.clone())
+                .await?;
         let block_number = tx.block_number.ok_or(err("no block number for tx"))?.as_u64();
         let block = source
             .get_block(block_number)
             .await?
             .ok_or(CollectError::CollectError("block not found".to_string()))?;
-        Ok((block, ((tx, receipt), exclude_failed, timestamp)))
+        let schema = query.schemas.get_schema(&Datatype::Blocks)?;
+        let rewards = blocks::fetch_block_rewards(&block, &source, schema).await?;
+        Ok(((block, rewards), ((tx, receipt), exclude_failed, timestamp)))
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
         let BlocksAndTransactions(blocks, transactions) = columns;
-        let (block, ((tx, receipt), exclude_failed, timestamp)) = response;
+        let ((block, rewards), ((tx, receipt), exclude_failed, timestamp)) = response;
         let schema = query.schemas.get_schema(&Datatype::Blocks)?;
-        blocks::process_block(block, blocks, schema)?;
+        blocks::process_block(block, rewards, blocks, schema)?;
         let schema = query.schemas.get_schema(&Datatype::Transactions)?;
         transactions::process_transaction(
             tx,
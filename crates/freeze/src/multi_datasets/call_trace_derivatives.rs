@@ -36,8 +36,8 @@ impl ToDataFrames for CallTraceDerivatives {
 impl CollectByBlock for CallTraceDerivatives {
     type Response = Vec<Trace>;
 
-    async fn extract(request: Params, source: Arc<Source>, _: Arc<Query>) -> R<Self::Response> {
-        source.trace_block(request.block_number()?.into()).await
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        traces::fetch_block_traces(request.block_number()?, &source, &query).await
     }
 
     fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
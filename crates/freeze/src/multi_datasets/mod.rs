@@ -4,9 +4,11 @@ mod call_trace_derivatives;
 pub mod geth_state_diffs;
 mod state_diffs;
 mod state_reads;
+mod trace_replays;
 
 pub use blocks_and_transactions::*;
 pub use call_trace_derivatives::*;
 pub use geth_state_diffs::*;
 pub use state_diffs::*;
 pub use state_reads::*;
+pub use trace_replays::*;
@@ -0,0 +1,299 @@
+use crate::*;
+use ethers::prelude::*;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// TraceReplays
+#[derive(Default)]
+pub struct TraceReplays(
+    traces::Traces,
+    balance_diffs::BalanceDiffs,
+    code_diffs::CodeDiffs,
+    nonce_diffs::NonceDiffs,
+    storage_diffs::StorageDiffs,
+    vm_traces::VmTraces,
+);
+
+type BlockTxsTraces = (Option<u32>, Vec<Option<Vec<u8>>>, Vec<BlockTrace>);
+
+const STATE_DIFF_DATATYPES: [Datatype; 4] =
+    [Datatype::BalanceDiffs, Datatype::CodeDiffs, Datatype::NonceDiffs, Datatype::StorageDiffs];
+
+impl ToDataFrames for TraceReplays {
+    fn create_dfs(
+        self,
+        schemas: &HashMap<Datatype, Table>,
+        chain_id: u64,
+    ) -> R<HashMap<Datatype, DataFrame>> {
+        let TraceReplays(traces, balances, codes, nonces, storages, vm_traces) = self;
+        let mut output = HashMap::new();
+        if schemas.contains_key(&Datatype::Traces) {
+            output.extend(traces.create_dfs(schemas, chain_id)?);
+        }
+        if schemas.contains_key(&Datatype::BalanceDiffs) {
+            output.extend(balances.create_dfs(schemas, chain_id)?);
+        }
+        if schemas.contains_key(&Datatype::CodeDiffs) {
+            output.extend(codes.create_dfs(schemas, chain_id)?);
+        }
+        if schemas.contains_key(&Datatype::NonceDiffs) {
+            output.extend(nonces.create_dfs(schemas, chain_id)?);
+        }
+        if schemas.contains_key(&Datatype::StorageDiffs) {
+            output.extend(storages.create_dfs(schemas, chain_id)?);
+        }
+        if schemas.contains_key(&Datatype::VmTraces) {
+            output.extend(vm_traces.create_dfs(schemas, chain_id)?);
+        }
+        Ok(output)
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByBlock for TraceReplays {
+    type Response = (Option<Vec<Trace>>, BlockTxsTraces);
+
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let block_number = request.block_number()?;
+        let schemas = &query.schemas;
+
+        let trace_types = replay_trace_types(schemas);
+        let block_traces = if trace_types.is_empty() {
+            vec![]
+        } else {
+            source.trace_replay_block_transactions(block_number.into(), trace_types).await?
+        };
+
+        let txs = if schemas.values().any(|x| x.has_column("transaction_hash")) {
+            let block = source
+                .get_block(block_number)
+                .await?
+                .ok_or(CollectError::CollectError("could not find block".to_string()))?;
+            block.transactions.iter().map(|tx| Some(tx.0.to_vec())).collect()
+        } else {
+            vec![None; block_traces.len()]
+        };
+
+        // call traces come from trace_block, replays have no block reward traces
+        let traces = if schemas.contains_key(&Datatype::Traces) {
+            Some(traces::fetch_block_traces(block_number, &source, &query).await?)
+        } else {
+            None
+        };
+        let traces = traces.map(|traces| {
+            traces::filter_traces_by_from_to_addresses(
+                traces,
+                &request.from_address,
+                &request.to_address,
+            )
+        });
+        Ok((traces, (Some(block_number as u32), txs, block_traces)))
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        process_trace_replays(response, None, columns, query)
+    }
+}
+
+#[async_trait::async_trait]
+impl CollectByTransaction for TraceReplays {
+    type Response = (Option<Vec<Trace>>, BlockTxsTraces);
+
+    async fn extract(request: Params, source: Arc<Source>, query: Arc<Query>) -> R<Self::Response> {
+        let transaction_hash = request.transaction_hash()?;
+        let trace_types = replay_trace_types(&query.schemas);
+        let block_traces = if trace_types.is_empty() {
+            vec![]
+        } else {
+            let tx_hash = H256::from_slice(&transaction_hash);
+            vec![source.trace_replay_transaction(tx_hash, trace_types).await?]
+        };
+        let traces = if query.schemas.contains_key(&Datatype::Traces) {
+            let traces = source.trace_transaction(request.ethers_transaction_hash()?).await?;
+            Some(traces::filter_traces_by_from_to_addresses(
+                traces,
+                &request.from_address,
+                &request.to_address,
+            ))
+        } else {
+            None
+        };
+        Ok((traces, (None, vec![Some(transaction_hash)], block_traces)))
+    }
+
+    fn transform(response: Self::Response, columns: &mut Self, query: &Arc<Query>) -> R<()> {
+        let tx = response.1 .1.first().cloned().flatten();
+        process_trace_replays(response, tx, columns, query)
+    }
+}
+
+/// trace types of the replay other than call traces
+fn replay_trace_types(schemas: &HashMap<Datatype, Table>) -> Vec<TraceType> {
+    let mut trace_types = Vec::new();
+    if STATE_DIFF_DATATYPES.iter().any(|datatype| schemas.contains_key(datatype)) {
+        trace_types.push(TraceType::StateDiff);
+    }
+    if schemas.contains_key(&Datatype::VmTraces) {
+        trace_types.push(TraceType::VmTrace);
+    }
+    trace_types
+}
+
+fn process_trace_replays(
+    response: (Option<Vec<Trace>>, BlockTxsTraces),
+    tx: Option<Vec<u8>>,
+    columns: &mut TraceReplays,
+    query: &Arc<Query>,
+) -> R<()> {
+    let TraceReplays(traces, balances, codes, nonces, storages, vm_traces) = columns;
+    let schemas = &query.schemas;
+    let (call_traces, replays) = response;
+    if let Some(call_traces) = call_traces {
+        let call_traces = if query.exclude_failed {
+            traces::filter_failed_traces(call_traces)
+        } else {
+            call_traces
+        };
+        traces::process_traces(&call_traces, traces, schemas)?;
+    }
+    if schemas.contains_key(&Datatype::BalanceDiffs) {
+        balance_diffs::process_balance_diffs(&replays, balances, schemas)?;
+    }
+    if schemas.contains_key(&Datatype::CodeDiffs) {
+        code_diffs::process_code_diffs(&replays, codes, schemas)?;
+    }
+    if schemas.contains_key(&Datatype::NonceDiffs) {
+        nonce_diffs::process_nonce_diffs(&replays, nonces, schemas)?;
+    }
+    if schemas.contains_key(&Datatype::StorageDiffs) {
+        storage_diffs::process_storage_diffs(&replays, storages, schemas)?;
+    }
+    if schemas.contains_key(&Datatype::VmTraces) {
+        let (block_number, _, block_traces) = replays;
+        vm_traces::process_vm_traces((block_number, tx, block_traces), vm_traces, schemas)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_trace_replays_cluster_traces_with_diffs() {
+        let names = |dts: Vec<Datatype>| {
+            cluster_datatypes(dts).iter().map(|dt| dt.name()).collect::<Vec<_>>()
+        };
+        let state_diffs = MultiDatatype::StateDiffs.datatypes();
+        assert_eq!(names(state_diffs.clone()), vec!["state_diffs"]);
+        assert_eq!(names(vec![Datatype::Traces]), vec!["traces"]);
+        assert_eq!(names([state_diffs, vec![Datatype::VmTraces]].concat()), vec!["trace_replays"]);
+        assert_eq!(
+            names(vec![Datatype::Traces, Datatype::BalanceDiffs, Datatype::StorageDiffs]),
+            vec!["trace_replays"]
+        );
+
+        // only the requested members of a cluster are collected
+        let mut query = make_query(Datatype::Traces);
+        query.schemas.extend(make_query(Datatype::StorageDiffs).schemas);
+        query.datatypes = cluster_datatypes(vec![Datatype::Traces, Datatype::StorageDiffs]);
+        assert_eq!(
+            query.datatypes_of(&query.datatypes[0]),
+            vec![Datatype::Traces, Datatype::StorageDiffs]
+        );
+        query.partitions = vec![Partition::default()];
+        assert_eq!(query.n_outputs(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_state_diffs_and_vm_traces_share_one_replay() {
+        let (provider, mock) = Provider::mocked();
+        for block_number in [100u64, 101] {
+            let tx_hash = H256::from_low_u64_be(block_number);
+            let block = Block::<TxHash> {
+                number: Some(block_number.into()),
+                hash: Some(H256::from_low_u64_be(block_number + 1000)),
+                transactions: vec![tx_hash],
+                ..Default::default()
+            };
+            let trace = |action: Action, action_type: ActionType| Trace {
+                action,
+                result: None,
+                trace_address: vec![],
+                subtraces: 0,
+                transaction_position: None,
+                transaction_hash: None,
+                block_number,
+                block_hash: H256::from_low_u64_be(block_number + 1000),
+                action_type,
+                error: None,
+            };
+            let call = Action::Call(ethers::types::Call { value: 1.into(), ..Default::default() });
+            let reward = Action::Reward(ethers::types::Reward {
+                author: H160::zero(),
+                value: 2.into(),
+                reward_type: RewardType::Block,
+            });
+            let traces = vec![
+                Trace { transaction_hash: Some(tx_hash), ..trace(call, ActionType::Call) },
+                trace(reward, ActionType::Reward),
+            ];
+            let account_diff = AccountDiff {
+                balance: Diff::Changed(ChangedType { from: 2.into(), to: 1.into() }),
+                nonce: Diff::Same,
+                code: Diff::Same,
+                storage: BTreeMap::new(),
+            };
+            let block_trace = BlockTrace {
+                output: Bytes::default(),
+                trace: None,
+                vm_trace: None,
+                state_diff: Some(StateDiff(BTreeMap::from([(H160::zero(), account_diff)]))),
+                transaction_hash: Some(tx_hash),
+            };
+            // responses are popped from the back, so they are pushed in reverse request order
+            mock.push::<Vec<Trace>, _>(traces).unwrap();
+            mock.push::<Block<TxHash>, _>(block).unwrap();
+            mock.push::<Vec<BlockTrace>, _>(vec![block_trace]).unwrap();
+        }
+
+        let datatypes = vec![Datatype::Traces, Datatype::BalanceDiffs, Datatype::VmTraces];
+        let mut query = make_query(Datatype::Traces);
+        for datatype in datatypes[1..].iter() {
+            query.schemas.extend(make_query(*datatype).schemas);
+        }
+        query.datatypes = cluster_datatypes(datatypes);
+        let datatype = query.datatypes[0].clone();
+        assert_eq!(datatype.name(), "trace_replays");
+        let query = Arc::new(query);
+        let source = make_source(provider);
+
+        for block_number in [101u64, 100] {
+            let partition = Partition {
+                block_numbers: Some(vec![BlockChunk::Numbers(vec![block_number])]),
+                ..Default::default()
+            };
+            let dfs = collect_partition(datatype.clone(), partition, query.clone(), source.clone())
+                .await
+                .unwrap();
+            // traces keep their block reward trace
+            assert_eq!(dfs[&Datatype::Traces].height(), 2);
+            assert_eq!(dfs[&Datatype::BalanceDiffs].height(), 1);
+            assert_eq!(dfs[&Datatype::VmTraces].height(), 0);
+        }
+
+        // one replay per block for both state diffs and vm traces
+        for block_number in [101u64, 100] {
+            let block = format!("0x{:x}", block_number);
+            mock.assert_request(
+                "trace_replayBlockTransactions",
+                serde_json::json!([block, ["stateDiff", "vmTrace"]]),
+            )
+            .unwrap();
+            mock.assert_request("eth_getBlockByNumber", serde_json::json!([block, false])).unwrap();
+            mock.assert_request("trace_block", [block]).unwrap();
+        }
+    }
+}
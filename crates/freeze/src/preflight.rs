@@ -0,0 +1,406 @@
+use crate::{
+    bytes_to_u32, ChunkData, CollectError, ColumnType, Datatype, LogsBackend, Query, Source,
+    TraceBackend, TraceGranularity, U256Type, DECIMAL128_MAX_PRECISION, FUNCTION_ERC20_DECIMALS,
+    FUNCTION_ERC20_TOTAL_SUPPLY,
+};
+use ethers::prelude::*;
+use std::{collections::BTreeMap, sync::atomic::Ordering};
+
+/// rpc methods that read the state of a block or replay its transactions, which a node without
+/// archive state can only serve for recent blocks
+const ARCHIVE_METHODS: [&str; 9] = [
+    "eth_getBalance",
+    "eth_getCode",
+    "eth_getTransactionCount",
+    "eth_getStorageAt",
+    "eth_call",
+    "trace_call",
+    "trace_replayBlockTransactions",
+    "trace_block",
+    "debug_traceBlockByNumber",
+];
+
+/// rpc methods needed to collect a datatype, adjusted for the query's trace options
+fn required_methods(datatype: &Datatype, query: &Query) -> Vec<&'static str> {
+    let mut methods = Vec::new();
+    for method in datatype.required_methods() {
+        let replacements = match (method, &query.trace_backend, &query.trace_granularity) {
+            ("eth_getLogs", _, _) if query.logs_backend == LogsBackend::Filter => {
+                vec!["eth_newFilter"]
+            }
+            ("trace_block", TraceBackend::Geth, _) => {
+                vec!["eth_getBlockByNumber", "debug_traceBlockByNumber"]
+            }
+            ("trace_block", TraceBackend::Parity, TraceGranularity::Transaction) => {
+                vec!["eth_getBlockByNumber", "trace_transaction"]
+            }
+            (method, _, _) => vec![method],
+        };
+        for method in replacements {
+            if !methods.contains(&method) {
+                methods.push(method)
+            }
+        }
+    }
+    methods
+}
+
+/// make a cheap request to method at block, discarding the result
+async fn probe(source: &Source, method: &str, block: u64) -> Result<(), CollectError> {
+    let block_number = BlockNumber::Number(block.into());
+    match method {
+        "eth_getBlockByNumber" => source.get_block(block).await.map(|_| ()),
+        "eth_getBlockReceipts" => source.get_block_receipts(block).await.map(|_| ()),
+        "eth_getLogs" => {
+            let filter = Filter::new().from_block(block).to_block(block);
+            source.get_logs(&filter).await.map(|_| ())
+        }
+        "eth_newFilter" => {
+            let filter = Filter::new().from_block(block).to_block(block);
+            source.get_filter_logs(&filter).await.map(|_| ())
+        }
+        "trace_block" => source.trace_block(block_number).await.map(|_| ()),
+        "trace_transaction" => source.trace_transaction(TxHash::zero()).await.map(|_| ()),
+        "trace_replayBlockTransactions" => source
+            .trace_replay_block_transactions(block_number, vec![TraceType::StateDiff])
+            .await
+            .map(|_| ()),
+        "trace_call" => source
+            .trace_call(TransactionRequest::new(), vec![TraceType::Trace], Some(block_number))
+            .await
+            .map(|_| ()),
+        // the genesis block has no transactions, so tracing it is cheap
+        "debug_traceBlockByNumber" => {
+            source.geth_debug_trace_block_calls(0, false).await.map(|_| ())
+        }
+        "eth_getBalance" => source.get_balance(H160::zero(), block_number).await.map(|_| ()),
+        "eth_getCode" => source.get_code(H160::zero(), block_number).await.map(|_| ()),
+        "eth_getTransactionCount" => {
+            source.get_transaction_count(H160::zero(), block_number).await.map(|_| ())
+        }
+        "eth_getStorageAt" => {
+            source.get_storage_at(H160::zero(), H256::zero(), block_number).await.map(|_| ())
+        }
+        "eth_call" => source.call(TransactionRequest::new(), block_number).await.map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+/// whether the node answered with a json-rpc error, e.g. method not found
+pub(crate) fn is_unsupported(error: &CollectError) -> bool {
+    match error {
+        CollectError::Rpc(ProviderError::JsonRpcClientError(e)) => e.as_error_response().is_some(),
+        _ => false,
+    }
+}
+
+/// whether the node answered that it does not have a method, rather than rejecting the request
+pub(crate) fn is_method_missing(error: &CollectError) -> bool {
+    let CollectError::Rpc(ProviderError::JsonRpcClientError(e)) = error else { return false };
+    let Some(response) = e.as_error_response() else { return false };
+    let message = response.message.to_lowercase();
+    response.code == -32601 ||
+        ["does not exist", "not supported", "unsupported", "method not found"]
+            .iter()
+            .any(|pattern| message.contains(pattern))
+}
+
+/// whether a request failed without reaching the node or getting an answer from it
+fn is_connection_error(error: &CollectError) -> bool {
+    match error {
+        CollectError::Rpc(ProviderError::JsonRpcClientError(e)) => {
+            e.as_error_response().is_none() && e.as_serde_error().is_none()
+        }
+        CollectError::Rpc(ProviderError::HTTPError(_)) |
+        CollectError::Timeout(_) |
+        CollectError::TooManyRequestsError => true,
+        _ => false,
+    }
+}
+
+/// check that the rpc endpoint supports the methods needed by every datatype of the query
+///
+/// each method is probed once against the first block of the query. a node without
+/// eth_getBlockReceipts passes, with receipts then fetched per transaction
+pub async fn preflight(query: &Query, source: &Source) -> Result<(), CollectError> {
+    let mut methods: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for meta_datatype in query.datatypes.iter() {
+        for datatype in query.datatypes_of(meta_datatype).iter() {
+            for method in required_methods(datatype, query) {
+                methods.entry(method).or_default().push(datatype.name());
+            }
+        }
+    }
+
+    let block = match first_block(query) {
+        Some(block) => block,
+        None => source.get_block_number().await?.as_u64(),
+    };
+
+    let mut unsupported = Vec::new();
+    for (method, datatypes) in methods.into_iter() {
+        match probe(source, method, block).await {
+            Err(e) if is_method_missing(&e) && method == "eth_getBlockReceipts" => {
+                source.block_receipts_unsupported.store(true, Ordering::Relaxed);
+            }
+            Err(e) if is_method_missing(&e) => {
+                unsupported.push(format!("{} (needed by {})", method, datatypes.join(", ")))
+            }
+            Err(e) if is_connection_error(&e) => return Err(e),
+            // the node has the method, e.g. missing state is left to check_archive
+            Err(_) | Ok(()) => {}
+        }
+    }
+
+    if unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(CollectError::PreflightError(format!(
+            "rpc endpoint does not support required methods: {}",
+            unsupported.join("; ")
+        )))
+    }
+}
+
+/// check that the node has the historical state needed by the datatypes of the query
+///
+/// datatypes that read state or replay transactions are probed with eth_getBalance at the first
+/// block of the query. other errors of the probe are left to preflight and collection
+pub async fn check_archive(query: &Query, source: &Source) -> Result<(), CollectError> {
+    let mut datatypes = Vec::new();
+    for meta_datatype in query.datatypes.iter() {
+        for datatype in query.datatypes_of(meta_datatype).iter() {
+            let methods = required_methods(datatype, query);
+            if methods.iter().any(|method| ARCHIVE_METHODS.contains(method)) {
+                datatypes.push(datatype.name())
+            }
+        }
+    }
+    let Some(block) = first_block(query) else { return Ok(()) };
+    if datatypes.is_empty() {
+        return Ok(())
+    }
+
+    let block_number = BlockNumber::Number(block.into());
+    match source.get_balance(H160::zero(), block_number).await {
+        Err(CollectError::ArchiveNodeRequired(message)) => {
+            Err(CollectError::ArchiveNodeRequired(format!(
+                "{} read the state of block {}, {} (skip this check with --assume-archive)",
+                datatypes.join(", "),
+                block,
+                message
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// lowest block of the partitions of a query
+fn first_block(query: &Query) -> Option<u64> {
+    query.partitions.iter().filter_map(|p| p.block_numbers.as_ref()?.min_value()).min()
+}
+
+/// warn about tokens whose total supply does not fit in the decimal128 columns of the query
+///
+/// the tokens are the addresses and contracts of the query, checked at the latest block. this is
+/// advisory, so tokens whose supply cannot be read are skipped
+pub async fn check_decimals_fit(
+    query: &Query,
+    source: &Source,
+) -> Result<Vec<String>, CollectError> {
+    let uses_decimal128 = query.schemas.values().any(|schema| {
+        schema.u256_types.contains(&U256Type::Decimal128) &&
+            schema.columns().iter().any(|c| schema.column_type(c) == Some(ColumnType::UInt256))
+    });
+    if !uses_decimal128 {
+        return Ok(vec![])
+    }
+
+    let mut tokens = Vec::new();
+    for partition in query.partitions.iter() {
+        for chunks in [&partition.contracts, &partition.addresses].into_iter().flatten() {
+            for address in chunks.iter().flat_map(|chunk| chunk.values()) {
+                if address.len() == 20 && !tokens.contains(&address) {
+                    tokens.push(address)
+                }
+            }
+        }
+    }
+
+    let max = U256::exp10(DECIMAL128_MAX_PRECISION) - 1;
+    let mut warnings = Vec::new();
+    for token in tokens.into_iter() {
+        let address = H160::from_slice(&token);
+        let call_data = FUNCTION_ERC20_TOTAL_SUPPLY.clone();
+        let supply = match source.call2(address, call_data, BlockNumber::Latest).await {
+            Ok(output) if output.len() == 32 => U256::from_big_endian(&output),
+            _ => continue,
+        };
+        if supply <= max {
+            continue
+        }
+        let call_data = FUNCTION_ERC20_DECIMALS.clone();
+        let decimals = match source.call2(address, call_data, BlockNumber::Latest).await {
+            Ok(output) => bytes_to_u32(output).map(|x| x.to_string()).unwrap_or("?".to_string()),
+            Err(_) => "?".to_string(),
+        };
+        warnings.push(format!(
+            "total supply {} of token {:?} ({} decimals) exceeds the decimal128 range, \
+             use a binary or string u256 type for its values instead",
+            supply, address, decimals
+        ))
+    }
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{make_query, make_source},
+        AddressChunk, BlockChunk, Partition,
+    };
+    use ethers::providers::{JsonRpcError, MockResponse};
+
+    #[tokio::test]
+    async fn test_preflight_reports_unsupported_trace_block() {
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32601,
+            message: "the method trace_block does not exist/is not available".to_string(),
+            data: None,
+        }));
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Traces) };
+
+        let result = preflight(&query, &make_source(provider)).await;
+        let message = format!("{}", result.unwrap_err());
+        assert!(message.contains("trace_block (needed by traces)"), "{}", message);
+        mock.assert_request("trace_block", [U64::from(100)]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_preflight_passes_without_block_receipts() {
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32601,
+            message: "the method eth_getBlockReceipts does not exist/is not available".to_string(),
+            data: None,
+        }));
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Transactions) };
+
+        // receipts are fetched per transaction instead
+        let source = make_source(provider);
+        preflight(&query, &source).await.unwrap();
+        assert!(source.block_receipts_unsupported.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_preflight_traces_genesis_for_geth_traces() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(Block::<TxHash> { number: Some(100.into()), ..Default::default() }).unwrap();
+        // an answer other than a missing method means the method is supported
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32000,
+            message: "genesis is not traceable".to_string(),
+            data: None,
+        }));
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query {
+            partitions: vec![partition],
+            trace_backend: TraceBackend::Geth,
+            ..make_query(Datatype::Traces)
+        };
+
+        preflight(&query, &make_source(provider)).await.unwrap();
+        let options = serde_json::json!({"tracer": "callTracer", "tracerConfig": {}});
+        mock.assert_request("debug_traceBlockByNumber", serde_json::json!(["0x0", options]))
+            .unwrap();
+        mock.assert_request("eth_getBlockByNumber", serde_json::json!(["0x64", false])).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_check_decimals_fit_warns_on_large_supply() {
+        let word = |value: U256| {
+            let mut bytes = [0u8; 32];
+            value.to_big_endian(&mut bytes);
+            Bytes::from(bytes.to_vec())
+        };
+        let (provider, mock) = Provider::mocked();
+        mock.push::<Bytes, _>(word(18.into())).unwrap();
+        mock.push::<Bytes, _>(word(U256::exp10(40))).unwrap();
+        mock.push::<Bytes, _>(word(U256::exp10(30))).unwrap();
+        let small = H160::from_low_u64_be(1).as_bytes().to_vec();
+        let large = H160::from_low_u64_be(2).as_bytes().to_vec();
+        let partition = Partition {
+            contracts: Some(vec![AddressChunk::Values(vec![small, large])]),
+            ..Default::default()
+        };
+        let mut query =
+            Query { partitions: vec![partition], ..make_query(Datatype::Erc20Transfers) };
+        let source = make_source(provider);
+        assert!(check_decimals_fit(&query, &source).await.unwrap().is_empty());
+
+        for schema in query.schemas.values_mut() {
+            schema.u256_types = vec![U256Type::Decimal128];
+        }
+        let warnings = check_decimals_fit(&query, &source).await.unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].contains("0x0000000000000000000000000000000000000002"),
+            "{}",
+            warnings[0]
+        );
+        assert!(warnings[0].contains("(18 decimals)"), "{}", warnings[0]);
+    }
+
+    #[tokio::test]
+    async fn test_check_archive_reports_missing_trie_node() {
+        let (provider, mock) = Provider::mocked();
+        mock.push_response(MockResponse::Error(JsonRpcError {
+            code: -32000,
+            message: "missing trie node 1b2e0f1a3c5d7e9f (path )".to_string(),
+            data: None,
+        }));
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Numbers(vec![100])]),
+            ..Default::default()
+        };
+        let query = Query { partitions: vec![partition], ..make_query(Datatype::Balances) };
+
+        let error = check_archive(&query, &make_source(provider)).await.unwrap_err();
+        assert!(matches!(error, CollectError::ArchiveNodeRequired(_)));
+        let message = format!("{}", error);
+        assert!(message.starts_with("archive node required: balances"), "{}", message);
+        assert!(message.contains("block 100"), "{}", message);
+        assert!(message.contains("missing trie node"), "{}", message);
+
+        // datatypes that do not read state are not checked
+        let query = Query { partitions: query.partitions, ..make_query(Datatype::Blocks) };
+        let (provider, _mock) = Provider::mocked();
+        assert!(check_archive(&query, &make_source(provider)).await.is_ok());
+    }
+
+    #[test]
+    fn test_required_methods() {
+        let query = make_query(Datatype::Traces);
+        assert_eq!(Datatype::Traces.required_methods(), vec!["trace_block"]);
+        assert_eq!(Datatype::Blocks.required_methods(), vec!["eth_getBlockByNumber"]);
+        assert_eq!(required_methods(&Datatype::Traces, &query), vec!["trace_block"]);
+
+        let query = Query { trace_backend: TraceBackend::Geth, ..query };
+        let methods = required_methods(&Datatype::Traces, &query);
+        assert_eq!(methods, vec!["eth_getBlockByNumber", "debug_traceBlockByNumber"]);
+    }
+}
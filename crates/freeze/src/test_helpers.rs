@@ -0,0 +1,138 @@
+//! helpers for building queries, sources, sinks, and temp dirs in unit tests
+
+use crate::*;
+use ethers::prelude::*;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// query over all columns of a single datatype, with no partitions
+pub(crate) fn make_query(datatype: Datatype) -> Query {
+    let u256_types = vec![U256Type::Binary];
+    let all = Some(vec!["all".to_string()]);
+    let schema = datatype
+        .table_schema(
+            &u256_types,
+            &ColumnEncoding::Binary,
+            &None,
+            &None,
+            &all,
+            &None,
+            None,
+            None,
+            true,
+        )
+        .unwrap();
+    Query {
+        datatypes: vec![MetaDatatype::Scalar(datatype)],
+        schemas: HashMap::from([(datatype, schema)]),
+        time_dimension: TimeDimension::Blocks,
+        partitions: vec![],
+        partitioned_by: vec![Dim::BlockNumber],
+        exclude_failed: false,
+        js_tracer: None,
+        log_filter: None,
+        blocks_log_filter: None,
+        deduplicate_logs: false,
+        exclude_zero_value: false,
+        trace_granularity: TraceGranularity::Block,
+        trace_backend: TraceBackend::Parity,
+        logs_backend: LogsBackend::GetLogs,
+        genesis_path: None,
+        labels: QueryLabels { align: false, reorg_buffer: 0, skipped_blocks: vec![] },
+    }
+}
+
+/// source backed by a mocked provider
+pub(crate) fn make_source(provider: Provider<MockProvider>) -> Arc<Source> {
+    Arc::new(Source {
+        provider: provider.into(),
+        chain_id: 1,
+        inner_request_size: 1,
+        max_concurrent_chunks: None,
+        rpc_url: "".to_string(),
+        semaphore: Arc::new(None),
+        rate_limiter: Arc::new(None),
+        retry_failed_blocks_individually: false,
+        log_filters: Default::default(),
+        block_receipts_unsupported: Default::default(),
+        max_blocks_per_log_request: None,
+        head_gap: Default::default(),
+        labels: SourceLabels::default(),
+    })
+}
+
+/// uncompressed parquet sink writing directly into `output_dir`
+pub(crate) fn make_sink(output_dir: &Path) -> FileOutput {
+    FileOutput {
+        output_dir: output_dir.to_path_buf(),
+        prefix: "ethereum".to_string(),
+        suffix: None,
+        subdirs: vec![],
+        overwrite: false,
+        format: FileFormat::Parquet,
+        row_group_size: None,
+        parquet_statistics: true,
+        parquet_compression: polars::prelude::ParquetCompression::Uncompressed,
+        column_compression: HashMap::new(),
+        dictionary_encoding: HashMap::new(),
+        decimal_types: HashMap::new(),
+        single_file: false,
+        ordered: false,
+        aggregates: vec![],
+        timestamp_range: false,
+        optimize_types: false,
+        partition_by_date: false,
+        partition_by_column: None,
+        max_open_partitions: 1000,
+        streaming: false,
+        dir_mode: None,
+        csv_types: false,
+        json_bigints_as_strings: false,
+        target_file_size: None,
+        clean_empty_dirs: false,
+        fixed_size_binary: HashMap::new(),
+        delta: false,
+        partition_key_in_filename: false,
+        checksum_addresses: false,
+        write_index: false,
+    }
+}
+
+/// directory under the system temp dir that is unique to a test and removed on drop, so that
+/// failed assertions leave nothing behind, the directory itself is not created
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> TempDir {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let index = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dirname = format!("cryo_{}_{}_{}", name, std::process::id(), index);
+        TempDir(std::env::temp_dir().join(dirname))
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -60,7 +60,7 @@ impl NumberChunk {
                 })
                 .collect(),
             NumberChunk::Range(start_block, end_block) => {
-                let chunks = range_to_chunks(start_block, &(end_block + 1), log_request_size);
+                let chunks = range_to_chunks(start_block, end_block, log_request_size);
                 chunks
                     .iter()
                     .map(|(start, end)| FilterBlockOption::Range {
@@ -73,14 +73,17 @@ impl NumberChunk {
     }
 
     /// align boundaries of chunk to clean boundaries
+    ///
+    /// start is snapped down to the previous multiple of chunk_size so that the leading
+    /// partial chunk is collected in full, end is trimmed to the last complete chunk
     pub fn align(self, chunk_size: u64) -> Option<NumberChunk> {
         match self {
             NumberChunk::Numbers(numbers) => Some(NumberChunk::Numbers(numbers)),
             NumberChunk::Range(start, end) => {
-                let start = ((start + chunk_size - 1) / chunk_size) * chunk_size;
-                let end = (end / chunk_size) * chunk_size;
+                let start = (start / chunk_size) * chunk_size;
+                let end = ((end + 1) / chunk_size) * chunk_size;
                 if end > start {
-                    Some(NumberChunk::Range(start, end))
+                    Some(NumberChunk::Range(start, end - 1))
                 } else {
                     None
                 }
@@ -104,3 +107,25 @@ pub(crate) fn range_to_chunks(start: &u64, end: &u64, chunk_size: &u64) -> Vec<(
     }
     chunks
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter_options_end_at_last_block() {
+        // ranges are inclusive, the last request ends at the last block of the chunk
+        let ranges: Vec<_> = NumberChunk::Range(100, 103)
+            .to_log_filter_options(&2)
+            .into_iter()
+            .map(|option| match option {
+                FilterBlockOption::Range { from_block, to_block } => (
+                    from_block.and_then(|block| block.as_number()).map(|block| block.as_u64()),
+                    to_block.and_then(|block| block.as_number()).map(|block| block.as_u64()),
+                ),
+                FilterBlockOption::AtBlockHash(_) => panic!("expected a block range"),
+            })
+            .collect();
+        assert_eq!(ranges, vec![(Some(100), Some(101)), (Some(102), Some(103))]);
+    }
+}
@@ -1,4 +1,7 @@
-use super::collect_generic::{fetch_partition, join_partition_handles};
+use super::collect_generic::{
+    fetch_partition, filter_dfs, join_block_columns, join_partition_handles, BatchCallback,
+    BlockHeaders,
+};
 use crate::{CollectError, Datatype, Params, Partition, Query, Source, ToDataFrames};
 use polars::prelude::*;
 use std::collections::HashMap;
@@ -34,30 +37,68 @@ pub trait CollectByBlock: 'static + Send + Default + ToDataFrames {
         let handles = fetch_partition(
             Self::extract,
             partition,
-            source,
+            source.clone(),
             inner_request_size,
             query.clone(),
             sender,
         )
         .await?;
-        let columns = Self::transform_channel(receiver, &query).await?;
+        let (columns, failed_blocks) = Self::transform_channel(receiver, &query).await?;
         join_partition_handles(handles).await?;
-        columns.create_dfs(&query.schemas, chain_id)
+        let dfs = filter_dfs(columns.create_dfs(&query.schemas, chain_id)?, &query)?;
+        let dfs = join_block_columns(dfs, &query, &source, &mut BlockHeaders::default()).await?;
+        match failed_blocks.is_empty() {
+            true => Ok(dfs),
+            false => Err(CollectError::PartialChunk(dfs, failed_blocks)),
+        }
+    }
+
+    /// collect data into one batch of DataFrames per response, without holding the partition
+    async fn stream_by_block(
+        partition: Partition,
+        source: Arc<Source>,
+        query: Arc<Query>,
+        inner_request_size: Option<u64>,
+        on_batch: BatchCallback<'_>,
+    ) -> R<()> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let mut headers = BlockHeaders::default();
+        let chain_id = source.chain_id;
+        let handles = fetch_partition(
+            Self::extract,
+            partition,
+            source.clone(),
+            inner_request_size,
+            query.clone(),
+            sender,
+        )
+        .await?;
+        while let Some(message) = receiver.recv().await {
+            let mut columns = Self::default();
+            Self::transform(message?, &mut columns, &query)?;
+            let dfs = filter_dfs(columns.create_dfs(&query.schemas, chain_id)?, &query)?;
+            on_batch(join_block_columns(dfs, &query, &source, &mut headers).await?)?;
+        }
+        join_partition_handles(handles).await
     }
 
-    /// convert block-derived data to dataframe
+    /// convert block-derived data to dataframe, along with the blocks that failed individually
     async fn transform_channel(
         mut receiver: mpsc::Receiver<R<Self::Response>>,
         query: &Arc<Query>,
-    ) -> R<Self> {
+    ) -> R<(Self, Vec<(u64, CollectError)>)> {
         let mut columns = Self::default();
+        let mut failed_blocks = Vec::new();
         while let Some(message) = receiver.recv().await {
             match message {
                 Ok(message) => Self::transform(message, &mut columns, query)?,
+                Err(CollectError::BlockFailed(block_number, e)) => {
+                    failed_blocks.push((block_number, *e))
+                }
                 Err(e) => return Err(e),
             }
         }
-        Ok(columns)
+        Ok((columns, failed_blocks))
     }
 
     /// whether data can be collected by block
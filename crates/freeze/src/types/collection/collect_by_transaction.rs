@@ -1,4 +1,7 @@
-use super::collect_generic::{fetch_partition, join_partition_handles};
+use super::collect_generic::{
+    fetch_partition, filter_dfs, join_block_columns, join_partition_handles, BatchCallback,
+    BlockHeaders,
+};
 use crate::{CollectError, Datatype, Params, Partition, Query, Source, ToDataFrames};
 use polars::prelude::*;
 use std::collections::HashMap;
@@ -34,7 +37,7 @@ pub trait CollectByTransaction: 'static + Send + Default + ToDataFrames {
         let handles = fetch_partition(
             Self::extract,
             partition,
-            source,
+            source.clone(),
             inner_request_size,
             query.clone(),
             sender,
@@ -42,7 +45,37 @@ pub trait CollectByTransaction: 'static + Send + Default + ToDataFrames {
         .await?;
         let columns = Self::transform_channel(receiver, &query).await?;
         join_partition_handles(handles).await?;
-        columns.create_dfs(&query.schemas, chain_id)
+        let dfs = filter_dfs(columns.create_dfs(&query.schemas, chain_id)?, &query)?;
+        join_block_columns(dfs, &query, &source, &mut BlockHeaders::default()).await
+    }
+
+    /// collect data into one batch of DataFrames per response, without holding the partition
+    async fn stream_by_transaction(
+        partition: Partition,
+        source: Arc<Source>,
+        query: Arc<Query>,
+        inner_request_size: Option<u64>,
+        on_batch: BatchCallback<'_>,
+    ) -> R<()> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        let mut headers = BlockHeaders::default();
+        let chain_id = source.chain_id;
+        let handles = fetch_partition(
+            Self::extract,
+            partition,
+            source.clone(),
+            inner_request_size,
+            query.clone(),
+            sender,
+        )
+        .await?;
+        while let Some(message) = receiver.recv().await {
+            let mut columns = Self::default();
+            Self::transform(message?, &mut columns, &query)?;
+            let dfs = filter_dfs(columns.create_dfs(&query.schemas, chain_id)?, &query)?;
+            on_batch(join_block_columns(dfs, &query, &source, &mut headers).await?)?;
+        }
+        join_partition_handles(handles).await
     }
 
     /// convert transaction-derived data to dataframe
@@ -1,8 +1,13 @@
-use crate::*;
+use crate::{
+    types::schemas::{BLOCK_HASH, BLOCK_TIMESTAMP},
+    *,
+};
+use ethers::types::Filter;
 use futures::Future;
 use polars::prelude::*;
 use std::collections::HashMap;
 use tokio::{sync::mpsc, task};
+use tracing::Instrument;
 
 /// collect single partition
 pub async fn collect_partition(
@@ -11,12 +16,96 @@ pub async fn collect_partition(
     query: Arc<Query>,
     source: Arc<Source>,
 ) -> Result<HashMap<Datatype, DataFrame>, CollectError> {
-    match query.time_dimension {
-        TimeDimension::Blocks => collect_by_block(datatype, partition, source, query).await,
-        TimeDimension::Transactions => {
-            collect_by_transaction(datatype, partition, source, query).await
+    let range = partition.label(&query.partitioned_by).unwrap_or_default();
+    let span = tracing::info_span!("chunk", datatype = %datatype.name(), range = %range);
+    async move {
+        tracing::debug!("collecting chunk");
+        let result = match query.time_dimension {
+            TimeDimension::Blocks => {
+                match blocks_with_logs(&datatype, partition, &query, &source).await {
+                    Ok(partition) => collect_by_block(datatype, partition, source, query).await,
+                    Err(e) => Err(e),
+                }
+            }
+            TimeDimension::Transactions => {
+                collect_by_transaction(datatype, partition, source, query).await
+            }
+        };
+        if let Err(e) = &result {
+            tracing::warn!(error = %e, "chunk failed");
+        }
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+/// receives each batch of dataframes collected by stream_partition()
+pub type BatchCallback<'a> =
+    &'a mut (dyn FnMut(HashMap<Datatype, DataFrame>) -> Result<(), CollectError> + Send);
+
+/// collect single partition, handing each response's data to on_batch instead of returning it
+pub async fn stream_partition(
+    datatype: MetaDatatype,
+    partition: Partition,
+    query: Arc<Query>,
+    source: Arc<Source>,
+    on_batch: BatchCallback<'_>,
+) -> Result<(), CollectError> {
+    let range = partition.label(&query.partitioned_by).unwrap_or_default();
+    let span = tracing::info_span!("chunk", datatype = %datatype.name(), range = %range);
+    async move {
+        tracing::debug!("streaming chunk");
+        let result = match query.time_dimension {
+            TimeDimension::Blocks => {
+                match blocks_with_logs(&datatype, partition, &query, &source).await {
+                    Ok(partition) => {
+                        stream_by_block(datatype, partition, source, query, on_batch).await
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            TimeDimension::Transactions => {
+                stream_by_transaction(datatype, partition, source, query, on_batch).await
+            }
+        };
+        if let Err(e) = &result {
+            tracing::warn!(error = %e, "chunk failed");
         }
+        result
     }
+    .instrument(span)
+    .await
+}
+
+/// restrict the blocks of a Blocks partition to those with at least one log matching the
+/// query's blocks_log_filter, found with a preliminary eth_getLogs over each block chunk
+///
+/// the partition keeps its label, so its output files are named after the full block range
+async fn blocks_with_logs(
+    datatype: &MetaDatatype,
+    partition: Partition,
+    query: &Query,
+    source: &Source,
+) -> Result<Partition, CollectError> {
+    let (MetaDatatype::Scalar(Datatype::Blocks), Some(filter)) =
+        (datatype, &query.blocks_log_filter)
+    else {
+        return Ok(partition)
+    };
+    let Some(chunks) = &partition.block_numbers else { return Ok(partition) };
+    let mut block_numbers = Vec::with_capacity(chunks.len());
+    for chunk in chunks.iter() {
+        let mut with_logs = std::collections::HashSet::new();
+        for block_option in chunk.to_log_filter_options(&source.inner_request_size) {
+            let filter = Filter { block_option, ..filter.clone() };
+            let logs = source.get_logs(&filter).await?;
+            with_logs.extend(logs.iter().filter_map(|log| log.block_number.map(|n| n.as_u64())));
+        }
+        let numbers = chunk.values().into_iter().filter(|n| with_logs.contains(n)).collect();
+        block_numbers.push(BlockChunk::Numbers(numbers));
+    }
+    Ok(Partition { block_numbers: Some(block_numbers), ..partition })
 }
 
 /// fetch data for a given partition
@@ -42,19 +131,72 @@ where
         let sender = sender.clone();
         let source = source.clone();
         let query = query.clone();
-        let handle = task::spawn(async move {
-            let result = f_request(rpc_params, source.clone(), query.clone()).await;
-            match sender.send(result).await {
-                Ok(_) => Ok(()),
-                Err(_) => Err(CollectError::CollectError("tokio mpsc send failure".to_string())),
+        let handle = task::spawn(
+            async move {
+                let result = f_request(rpc_params.clone(), source.clone(), query.clone()).await;
+                let results = match (result, rpc_params.block_range) {
+                    (Err(e), Some(block_range)) if source.retry_failed_blocks_individually => {
+                        retry_blocks_individually(
+                            f_request,
+                            rpc_params,
+                            block_range,
+                            e,
+                            &source,
+                            &query,
+                        )
+                        .await
+                    }
+                    (result, _) => vec![result],
+                };
+                for result in results.into_iter() {
+                    if sender.send(result).await.is_err() {
+                        return Err(CollectError::CollectError(
+                            "tokio mpsc send failure".to_string(),
+                        ))
+                    }
+                }
+                Ok(())
             }
-        });
+            .in_current_span(),
+        );
         handles.push(handle);
     }
 
     Ok(handles)
 }
 
+/// request each block of a failed block range on its own
+///
+/// blocks that still fail are returned as BlockFailed, so that the rest of the chunk is kept
+async fn retry_blocks_individually<F, Fut, T>(
+    f_request: F,
+    rpc_params: Params,
+    (start, end): (u64, u64),
+    error: CollectError,
+    source: &Arc<Source>,
+    query: &Arc<Query>,
+) -> Vec<Result<T, CollectError>>
+where
+    F: Fn(Params, Arc<Source>, Arc<Query>) -> Fut,
+    Fut: Future<Output = Result<T, CollectError>>,
+{
+    if start == end {
+        return vec![Err(CollectError::BlockFailed(start, Box::new(error)))]
+    }
+    tracing::warn!(start, end, error = %error, "block range failed, retrying blocks individually");
+    let mut results = Vec::new();
+    for block_number in start..=end {
+        let params =
+            Params { block_range: Some((block_number, block_number)), ..rpc_params.clone() };
+        let result = f_request(params, source.clone(), query.clone()).await;
+        results.push(result.map_err(|e| {
+            tracing::warn!(block_number, error = %e, "block failed");
+            CollectError::BlockFailed(block_number, Box::new(e))
+        }));
+    }
+    results
+}
+
 pub(crate) async fn join_partition_handles(
     handles: Vec<tokio::task::JoinHandle<Result<(), CollectError>>>,
 ) -> Result<(), CollectError> {
@@ -68,3 +210,192 @@ pub(crate) async fn join_partition_handles(
     }
     Ok(())
 }
+
+/// apply the row filters of a query to the dataframes of collected data, and put their columns
+/// in the order of schemas matched to an existing file
+pub(crate) fn filter_dfs(
+    dfs: HashMap<Datatype, DataFrame>,
+    query: &Query,
+) -> Result<HashMap<Datatype, DataFrame>, CollectError> {
+    dfs.into_iter()
+        .map(|(datatype, mut df)| {
+            if query.exclude_zero_value {
+                df = dataframes::drop_zero_values(df)?;
+            }
+            if let Some(schema) = query.schemas.get(&datatype) {
+                df = dataframes::add_computed_columns(df, schema)?;
+            }
+            if let Some(order) = query.schemas.get(&datatype).and_then(|s| s.column_order.as_ref())
+            {
+                df = df.select(order)?;
+            }
+            Ok((datatype, df))
+        })
+        .collect()
+}
+
+/// timestamps and hashes of the blocks of a chunk, so each block is only requested once per chunk
+#[derive(Default)]
+pub(crate) struct BlockHeaders(HashMap<u32, (u32, Vec<u8>)>);
+
+impl BlockHeaders {
+    /// look up the blocks that have not been requested yet
+    async fn fetch(&mut self, block_numbers: &[u32], source: &Source) -> Result<(), CollectError> {
+        let mut missing: Vec<u32> = block_numbers
+            .iter()
+            .copied()
+            .filter(|block_number| !self.0.contains_key(block_number))
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        let blocks = missing.iter().map(|block_number| source.get_block(*block_number as u64));
+        for (block_number, block) in missing.iter().zip(futures::future::join_all(blocks).await) {
+            let Some(block) = block? else {
+                return Err(err("could not get block for joined block columns"))
+            };
+            let hash = block.hash.unwrap_or_default().as_bytes().to_vec();
+            self.0.insert(*block_number, (block.timestamp.as_u32(), hash));
+        }
+        Ok(())
+    }
+}
+
+/// add the block columns requested by each schema, joined in by block_number
+pub(crate) async fn join_block_columns(
+    dfs: HashMap<Datatype, DataFrame>,
+    query: &Query,
+    source: &Source,
+    headers: &mut BlockHeaders,
+) -> Result<HashMap<Datatype, DataFrame>, CollectError> {
+    let mut output = HashMap::new();
+    for (datatype, mut df) in dfs.into_iter() {
+        let schema = query.schemas.get_schema(&datatype)?;
+        let joined: Vec<&str> = datatype
+            .joinable_block_columns()
+            .into_iter()
+            .filter(|column| schema.has_column(column))
+            .collect();
+        if !joined.is_empty() {
+            let block_numbers: Vec<Option<u32>> =
+                df.column("block_number")?.u32()?.into_iter().collect();
+            let known: Vec<u32> = block_numbers.iter().flatten().copied().collect();
+            headers.fetch(&known, source).await?;
+            let rows: Vec<Option<&(u32, Vec<u8>)>> = block_numbers
+                .iter()
+                .map(|block_number| block_number.and_then(|n| headers.0.get(&n)))
+                .collect();
+            if joined.contains(&BLOCK_TIMESTAMP) {
+                let column: Vec<Option<u32>> =
+                    rows.iter().map(|row| row.map(|(timestamp, _)| *timestamp)).collect();
+                df.with_column(Series::new(BLOCK_TIMESTAMP, column))?;
+            }
+            if joined.contains(&BLOCK_HASH) {
+                let column: Vec<Option<Vec<u8>>> =
+                    rows.iter().map(|row| row.map(|(_, hash)| hash.clone())).collect();
+                match schema.column_type(BLOCK_HASH) {
+                    Some(ColumnType::Hex) => {
+                        df.with_column(Series::new(BLOCK_HASH, column.to_vec_hex()))?
+                    }
+                    _ => df.with_column(Series::new(BLOCK_HASH, column))?,
+                };
+            }
+        }
+        output.insert(datatype, df);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_query, make_source};
+    use ethers::prelude::*;
+
+    #[tokio::test]
+    async fn test_join_block_columns() {
+        let (provider, mock) = Provider::mocked();
+        for number in [101u64, 100] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                hash: Some(H256::from_low_u64_be(number)),
+                timestamp: (1_700_000_000 + number).into(),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        let source = make_source(provider);
+        let include = Some(vec!["block_hash".to_string(), "block_timestamp".to_string()]);
+        let mut query = make_query(Datatype::GethCalls);
+        let schema = Datatype::GethCalls
+            .table_schema(
+                &[],
+                &ColumnEncoding::Hex,
+                &include,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        query.schemas.insert(Datatype::GethCalls, schema);
+        let df = df!("block_number" => [Some(100u32), Some(101), Some(100), None]).unwrap();
+        let dfs = HashMap::from([(Datatype::GethCalls, df)]);
+
+        let mut headers = BlockHeaders::default();
+        let dfs = join_block_columns(dfs, &query, &source, &mut headers).await.unwrap();
+        let df = &dfs[&Datatype::GethCalls];
+        let hashes: Vec<_> = df.column("block_hash").unwrap().utf8().unwrap().into_iter().collect();
+        let expected = |number: u64| format!("{:?}", H256::from_low_u64_be(number));
+        assert_eq!(
+            hashes,
+            vec![
+                Some(expected(100).as_str()),
+                Some(expected(101).as_str()),
+                Some(expected(100).as_str()),
+                None
+            ]
+        );
+        let timestamps: Vec<_> =
+            df.column("block_timestamp").unwrap().u32().unwrap().into_iter().collect();
+        assert_eq!(
+            timestamps,
+            vec![Some(1_700_000_100), Some(1_700_000_101), Some(1_700_000_100), None]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocks_with_logs() {
+        let (provider, mock) = Provider::mocked();
+        for number in [103u64, 101] {
+            let block = Block::<TxHash> {
+                number: Some(number.into()),
+                hash: Some(H256::from_low_u64_be(number)),
+                ..Default::default()
+            };
+            mock.push(block).unwrap();
+        }
+        // blocks 101 and 103 of 100..=103 have matching logs, block 103 has two
+        let logs: Vec<Log> = [101u64, 103, 103]
+            .iter()
+            .map(|number| Log { block_number: Some((*number).into()), ..Default::default() })
+            .collect();
+        mock.push::<Vec<Log>, _>(logs).unwrap();
+        let source = Arc::new(Source { inner_request_size: 5, ..(*make_source(provider)).clone() });
+        let filter = Filter::new().address(H160::from_low_u64_be(1));
+        let query = Query { blocks_log_filter: Some(filter), ..make_query(Datatype::Blocks) };
+        let partition = Partition {
+            block_numbers: Some(vec![BlockChunk::Range(100, 103)]),
+            ..Default::default()
+        };
+
+        let datatype = MetaDatatype::Scalar(Datatype::Blocks);
+        let dfs = collect_partition(datatype, partition, Arc::new(query), source).await.unwrap();
+        let df = &dfs[&Datatype::Blocks];
+        let mut numbers: Vec<_> =
+            df.column("block_number").unwrap().u32().unwrap().into_iter().flatten().collect();
+        numbers.sort();
+        assert_eq!(numbers, vec![101, 103]);
+    }
+}
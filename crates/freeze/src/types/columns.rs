@@ -50,6 +50,26 @@ pub trait ColumnData: Default + crate::Dataset {
         }
     }
 
+    /// byte widths of binary columns whose values all have one size
+    ///
+    /// hashes and topics are 32 bytes and addresses are 20 bytes, datasets declare the rest
+    fn base_column_widths() -> HashMap<&'static str, usize> {
+        let mut widths: HashMap<&'static str, usize> = Self::column_types()
+            .into_iter()
+            .filter(|(_, column_type)| *column_type == ColumnType::Binary)
+            .filter_map(|(name, _)| {
+                let topic = matches!(name, "topic0" | "topic1" | "topic2" | "topic3");
+                match (name.ends_with("hash") || topic, name.ends_with("address")) {
+                    (true, _) => Some((name, 32)),
+                    (_, true) => Some((name, 20)),
+                    _ => None,
+                }
+            })
+            .collect();
+        widths.extend(Self::column_widths());
+        widths
+    }
+
     /// input arg aliases
     fn base_arg_aliases() -> HashMap<Dim, Dim> {
         match Self::arg_aliases() {
@@ -110,4 +130,29 @@ pub trait Dataset: Sync + Send {
     fn arg_aliases() -> Option<HashMap<Dim, Dim>> {
         None
     }
+
+    /// named column presets for Dataset, `full` is always available and selects all columns
+    fn presets() -> HashMap<&'static str, Vec<&'static str>> {
+        HashMap::new()
+    }
+
+    /// rpc methods needed to collect Dataset by block with the default trace backend
+    fn required_methods() -> Vec<&'static str> {
+        vec![]
+    }
+
+    /// byte widths of fixed size binary columns not named like hashes or addresses
+    fn column_widths() -> HashMap<&'static str, usize> {
+        HashMap::new()
+    }
+
+    /// version of the columns of Dataset, bumped whenever its columns or their types change
+    fn schema_version() -> u32 {
+        1
+    }
+
+    /// columns of Dataset left out of `all` unless deprecated columns are included
+    fn deprecated_columns() -> Vec<&'static str> {
+        vec![]
+    }
 }
@@ -0,0 +1,138 @@
+use crate::{CollectError, ColumnType, Table};
+use polars::prelude::*;
+
+/// aggregate computed over each numeric column of a chunk
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Aggregation {
+    /// sum of values
+    Sum,
+    /// minimum value
+    Min,
+    /// maximum value
+    Max,
+    /// number of non-null values
+    Count,
+}
+
+impl Aggregation {
+    /// convert Aggregation to str
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Aggregation::Sum => "sum",
+            Aggregation::Min => "min",
+            Aggregation::Max => "max",
+            Aggregation::Count => "count",
+        }
+    }
+}
+
+impl std::str::FromStr for Aggregation {
+    type Err = crate::ParseError;
+
+    fn from_str(name: &str) -> Result<Aggregation, Self::Err> {
+        match name {
+            "sum" => Ok(Aggregation::Sum),
+            "min" => Ok(Aggregation::Min),
+            "max" => Ok(Aggregation::Max),
+            "count" => Ok(Aggregation::Count),
+            _ => Err(crate::ParseError::ParseError(format!("invalid aggregation: {}", name))),
+        }
+    }
+}
+
+fn is_numeric(column_type: ColumnType) -> bool {
+    matches!(
+        column_type,
+        ColumnType::UInt32 |
+            ColumnType::UInt64 |
+            ColumnType::Int32 |
+            ColumnType::Int64 |
+            ColumnType::Float32 |
+            ColumnType::Float64
+    )
+}
+
+/// aggregate the numeric columns of a chunk into a single row
+///
+/// output columns are named `<column>_<aggregation>`
+pub(crate) fn aggregate_df(
+    df: &DataFrame,
+    schema: &Table,
+    aggregations: &[Aggregation],
+) -> Result<DataFrame, CollectError> {
+    let mut columns = Vec::new();
+    for name in schema.columns() {
+        match (schema.column_type(name), df.column(name)) {
+            (Some(column_type), Ok(series)) if is_numeric(column_type) => {
+                for aggregation in aggregations.iter() {
+                    let mut aggregate = match aggregation {
+                        Aggregation::Sum => series.sum_as_series(),
+                        Aggregation::Min => series.min_as_series(),
+                        Aggregation::Max => series.max_as_series(),
+                        Aggregation::Count => {
+                            let count = (series.len() - series.null_count()) as u64;
+                            Series::new("", &[count])
+                        }
+                    };
+                    aggregate.rename(format!("{}_{}", name, aggregation.as_str()).as_str());
+                    columns.push(aggregate);
+                }
+            }
+            _ => {}
+        }
+    }
+    DataFrame::new(columns).map_err(CollectError::PolarsError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnEncoding, Datatype, U256Type};
+
+    #[test]
+    fn test_aggregate_numeric_columns() {
+        let columns = Some(vec![
+            "block_number".to_string(),
+            "block_hash".to_string(),
+            "gas_used".to_string(),
+        ]);
+        let schema = Datatype::Blocks
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &columns,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let df = df!(
+            "block_number" => &[Some(100u32), Some(101), Some(102)],
+            "block_hash" => &[vec![0u8], vec![1u8], vec![2u8]],
+            "gas_used" => &[10u64, 30, 20],
+        )
+        .unwrap();
+
+        let aggregations = [Aggregation::Sum, Aggregation::Min, Aggregation::Max];
+        let agg = aggregate_df(&df, &schema, &aggregations).unwrap();
+        assert_eq!(agg.height(), 1);
+        let expected = ["block_number", "gas_used"]
+            .iter()
+            .flat_map(|c| ["sum", "min", "max"].map(|a| format!("{}_{}", c, a)))
+            .collect::<Vec<_>>();
+        assert_eq!(agg.get_column_names(), expected);
+
+        let value = |name: &str| {
+            agg.column(name).unwrap().cast(&DataType::UInt64).unwrap().u64().unwrap().get(0)
+        };
+        assert_eq!(value("block_number_sum"), Some(303));
+        assert_eq!(value("block_number_min"), Some(100));
+        assert_eq!(value("block_number_max"), Some(102));
+        assert_eq!(value("gas_used_sum"), Some(60));
+        assert_eq!(value("gas_used_min"), Some(10));
+        assert_eq!(value("gas_used_max"), Some(30));
+    }
+}
@@ -0,0 +1,198 @@
+use polars::prelude::*;
+
+use crate::{ColumnType, Table};
+
+/// column type name of each column of a dataframe, as in ColumnType::as_str()
+///
+/// u256 columns are split into one column per U256Type, each named by the type it is stored as,
+/// columns outside of the schema (e.g. of aggregates) are named by their polars type
+pub(crate) fn df_column_types(df: &DataFrame, schema: &Table) -> Vec<String> {
+    df.get_columns()
+        .iter()
+        .map(|series| match series_column_type(series, schema) {
+            Some(column_type) => column_type.as_str().to_string(),
+            None => series.dtype().to_string(),
+        })
+        .collect()
+}
+
+/// cast integer and decimal columns to strings, so that json readers keep large values exact
+///
+/// columns are picked by their type in the schema, as in df_column_types
+pub(crate) fn integers_to_strings(df: &DataFrame, schema: &Table) -> PolarsResult<DataFrame> {
+    let columns = df
+        .get_columns()
+        .iter()
+        .map(|series| match series_column_type(series, schema) {
+            Some(
+                ColumnType::UInt32 |
+                ColumnType::UInt64 |
+                ColumnType::Int32 |
+                ColumnType::Int64 |
+                ColumnType::Decimal128,
+            ) => series.cast(&DataType::Utf8),
+            _ => Ok(series.clone()),
+        })
+        .collect::<PolarsResult<Vec<_>>>()?;
+    DataFrame::new(columns)
+}
+
+/// write hex address columns in EIP-55 checksum form, values that do not parse as addresses are
+/// kept as they are
+pub(crate) fn checksum_addresses(
+    df: &DataFrame,
+    schema: &Table,
+    address_columns: &[&str],
+) -> PolarsResult<DataFrame> {
+    let columns = df
+        .get_columns()
+        .iter()
+        .map(|series| match schema.column_type(series.name()) {
+            Some(ColumnType::Hex) if address_columns.contains(&series.name()) => {
+                let values: Vec<Option<String>> =
+                    series.utf8()?.into_iter().map(|value| value.map(to_checksum)).collect();
+                Ok(Series::new(series.name(), values))
+            }
+            _ => Ok(series.clone()),
+        })
+        .collect::<PolarsResult<Vec<_>>>()?;
+    DataFrame::new(columns)
+}
+
+fn to_checksum(value: &str) -> String {
+    match value.parse::<ethers::types::H160>() {
+        Ok(address) => ethers::utils::to_checksum(&address, None),
+        Err(_) => value.to_string(),
+    }
+}
+
+fn series_column_type(series: &Series, schema: &Table) -> Option<ColumnType> {
+    schema
+        .column_type(series.name())
+        .or_else(|| u256_column_type(series.name(), schema))
+        .or_else(|| polars_column_type(series.dtype()))
+}
+
+fn u256_column_type(name: &str, schema: &Table) -> Option<ColumnType> {
+    schema.u256_types.iter().find_map(|u256_type| {
+        let column = name.strip_suffix(u256_type.suffix().as_str())?;
+        match schema.column_type(column) {
+            Some(ColumnType::UInt256) => Some(u256_type.to_columntype()),
+            _ => None,
+        }
+    })
+}
+
+fn polars_column_type(dtype: &DataType) -> Option<ColumnType> {
+    let column_type = match dtype {
+        DataType::Boolean => ColumnType::Boolean,
+        DataType::UInt32 => ColumnType::UInt32,
+        DataType::UInt64 => ColumnType::UInt64,
+        DataType::Int32 => ColumnType::Int32,
+        DataType::Int64 => ColumnType::Int64,
+        DataType::Float32 => ColumnType::Float32,
+        DataType::Float64 => ColumnType::Float64,
+        DataType::Utf8 => ColumnType::String,
+        DataType::Binary => ColumnType::Binary,
+        _ => return None,
+    };
+    Some(column_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnEncoding, Datatype, U256Type};
+
+    #[test]
+    fn test_df_column_types() {
+        let columns = Some(vec!["block_number".to_string(), "value".to_string()]);
+        let schema = Datatype::Transactions
+            .table_schema(
+                &[U256Type::String, U256Type::F64],
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &columns,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let df = df!(
+            "block_number" => &[1u32],
+            "value_string" => &["1"],
+            "value_f64" => &[1.0f64],
+            "n_rows" => &[1u64],
+        )
+        .unwrap();
+        assert_eq!(df_column_types(&df, &schema), vec!["uint32", "string", "float64", "uint64"]);
+    }
+
+    #[test]
+    fn test_integers_to_strings_keeps_large_values_exact_in_json() {
+        let columns = Some(vec!["block_number".to_string(), "gas_used".to_string()]);
+        let schema = Datatype::Transactions
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &columns,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let large = (1u64 << 53) + 1;
+        let df = df!(
+            "block_number" => &[1u32],
+            "gas_used" => &[large],
+            "transaction_hash" => &["0x01"],
+        )
+        .unwrap();
+
+        let mut df = integers_to_strings(&df, &schema).unwrap();
+        let mut buffer = Vec::new();
+        JsonWriter::new(&mut buffer).with_json_format(JsonFormat::Json).finish(&mut df).unwrap();
+        let json = String::from_utf8(buffer).unwrap();
+        assert!(json.contains(&format!("\"gas_used\":\"{}\"", large)), "{}", json);
+        assert!(json.contains("\"block_number\":\"1\""), "{}", json);
+        assert!(json.contains("\"transaction_hash\":\"0x01\""), "{}", json);
+    }
+
+    #[test]
+    fn test_checksum_addresses_of_hex_address_columns() {
+        let columns = Some(vec!["address".to_string(), "transaction_hash".to_string()]);
+        let schema = Datatype::Logs
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &columns,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let hash = format!("0x{}", "ab".repeat(32));
+        let df = df!(
+            "address" => &[Some("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"), None],
+            "transaction_hash" => &[Some(hash.as_str()), None],
+        )
+        .unwrap();
+
+        let address_columns = Datatype::Logs.address_columns();
+        assert_eq!(address_columns, vec!["address"]);
+        let df = checksum_addresses(&df, &schema, &address_columns).unwrap();
+        let addresses: Vec<_> = df.column("address").unwrap().utf8().unwrap().into_iter().collect();
+        assert_eq!(addresses, vec![Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"), None]);
+        let hashes: Vec<_> =
+            df.column("transaction_hash").unwrap().utf8().unwrap().into_iter().collect();
+        assert_eq!(hashes, vec![Some(hash.as_str()), None]);
+    }
+}
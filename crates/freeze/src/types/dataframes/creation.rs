@@ -8,6 +8,37 @@ macro_rules! with_series {
     };
 }
 
+/// convert a Vec<String> to Series, as lists of u32 if specified, and add to Vec<Series>
+///
+/// list columns are stored as dot separated positions, e.g. "0.1.2", and split here
+#[macro_export]
+macro_rules! with_series_string {
+    ($all_series:expr, $name:expr, $value:expr, $schema:expr) => {
+        if $schema.has_column($name) {
+            if let Some(ColumnType::UInt32List) = $schema.column_type($name) {
+                let lists: Vec<Series> = $value
+                    .iter()
+                    .map(|value| {
+                        let positions: Vec<u32> = value
+                            .split('.')
+                            .filter(|position| !position.is_empty())
+                            .map(|position| position.parse().unwrap_or_default())
+                            .collect();
+                        Series::new("", positions)
+                    })
+                    .collect();
+                let dtype = DataType::List(Box::new(DataType::UInt32));
+                match lists.is_empty() {
+                    true => $all_series.push(Series::new_empty($name, &dtype)),
+                    false => $all_series.push(Series::new($name, lists)),
+                }
+            } else {
+                $all_series.push(Series::new($name, $value));
+            }
+        }
+    };
+}
+
 /// convert a Vec to Series, as hex if specified, and add to Vec<Series>
 #[macro_export]
 macro_rules! with_series_binary {
@@ -74,8 +105,9 @@ macro_rules! with_series_u256 {
                 let name = $name.to_string() + U256Type::U32.suffix().as_str();
                 let name = name.as_str();
 
-                let converted: Vec<u32> = $value.iter().map(|v| v.as_u32()).collect();
-                $all_series.push(Series::new(name, converted));
+                let values = $value.iter().map(|v| Some(*v)).collect();
+                let policy = &$schema.overflow_policy;
+                $all_series.push(narrow_u256_series(name, values, U256Type::U32, policy)?);
             }
 
             // u64
@@ -83,13 +115,19 @@ macro_rules! with_series_u256 {
                 let name = $name.to_string() + U256Type::U64.suffix().as_str();
                 let name = name.as_str();
 
-                let converted: Vec<u64> = $value.iter().map(|v| v.as_u64()).collect();
-                $all_series.push(Series::new(name, converted));
+                let values = $value.iter().map(|v| Some(*v)).collect();
+                let policy = &$schema.overflow_policy;
+                $all_series.push(narrow_u256_series(name, values, U256Type::U64, policy)?);
             }
 
             // decimal128
             if $schema.u256_types.contains(&U256Type::Decimal128) {
-                return Err(CollectError::CollectError("DECIMAL128 not implemented".to_string()))
+                let name = $name.to_string() + U256Type::Decimal128.suffix().as_str();
+                let name = name.as_str();
+
+                let values = $value.iter().map(|v| Some(*v)).collect();
+                let policy = &$schema.overflow_policy;
+                $all_series.push(decimal_u256_series(name, values, policy)?);
             }
         }
     };
@@ -153,9 +191,9 @@ macro_rules! with_series_option_u256 {
                 let name = $name.to_string() + U256Type::U32.suffix().as_str();
                 let name = name.as_str();
 
-                let converted: Vec<Option<u32>> =
-                    $value.iter().map(|v| v.map(|x| x.as_u32())).collect();
-                $all_series.push(Series::new(name, converted));
+                let values = $value.clone();
+                let policy = &$schema.overflow_policy;
+                $all_series.push(narrow_u256_series(name, values, U256Type::U32, policy)?);
             }
 
             // u64
@@ -163,14 +201,19 @@ macro_rules! with_series_option_u256 {
                 let name = $name.to_string() + U256Type::U64.suffix().as_str();
                 let name = name.as_str();
 
-                let converted: Vec<Option<u64>> =
-                    $value.iter().map(|v| v.map(|x| x.as_u64())).collect();
-                $all_series.push(Series::new(name, converted));
+                let values = $value.clone();
+                let policy = &$schema.overflow_policy;
+                $all_series.push(narrow_u256_series(name, values, U256Type::U64, policy)?);
             }
 
             // decimal128
             if $schema.u256_types.contains(&U256Type::Decimal128) {
-                return Err(CollectError::CollectError("DECIMAL128 not implemented".to_string()))
+                let name = $name.to_string() + U256Type::Decimal128.suffix().as_str();
+                let name = name.as_str();
+
+                let values = $value.clone();
+                let policy = &$schema.overflow_policy;
+                $all_series.push(decimal_u256_series(name, values, policy)?);
             }
         }
     };
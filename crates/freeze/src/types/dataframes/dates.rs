@@ -0,0 +1,50 @@
+use super::partition_keys::split_by_keys;
+use crate::{err, CollectError};
+use polars::prelude::*;
+
+/// split rows of a dataframe into UTC dates of their block timestamp
+///
+/// dates are formatted as YYYY-MM-DD and returned in ascending order
+pub(crate) fn split_by_date(df: &DataFrame) -> Result<Vec<(String, DataFrame)>, CollectError> {
+    let timestamps = df
+        .column("timestamp")
+        .and_then(|column| column.u32().cloned())
+        .map_err(|_| err("partitioning by date requires a u32 timestamp column"))?;
+
+    let mut dates = Vec::with_capacity(timestamps.len());
+    for timestamp in timestamps.into_iter() {
+        let timestamp = timestamp.ok_or_else(|| err("cannot partition rows without timestamp"))?;
+        let datetime = chrono::NaiveDateTime::from_timestamp_opt(timestamp as i64, 0)
+            .ok_or_else(|| err("invalid block timestamp"))?;
+        dates.push(datetime.format("%Y-%m-%d").to_string());
+    }
+
+    split_by_keys(df, &dates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_by_date() {
+        // 2023-11-14 23:59:59 and 2023-11-15 00:00:00 UTC
+        let df = df!(
+            "block_number" => &[100u32, 101, 102],
+            "timestamp" => &[1700006399u32, 1700006400, 1700006399],
+        )
+        .unwrap();
+
+        let parts = split_by_date(&df).unwrap();
+        let dates: Vec<&str> = parts.iter().map(|(date, _)| date.as_str()).collect();
+        assert_eq!(dates, vec!["2023-11-14", "2023-11-15"]);
+        let blocks: Vec<Vec<Option<u32>>> = parts
+            .iter()
+            .map(|(_, df)| df.column("block_number").unwrap().u32().unwrap().into_iter().collect())
+            .collect();
+        assert_eq!(blocks, vec![vec![Some(100), Some(102)], vec![Some(101)]]);
+
+        let df = df!("block_number" => &[100u32]).unwrap();
+        assert!(split_by_date(&df).is_err());
+    }
+}
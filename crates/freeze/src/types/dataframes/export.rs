@@ -1,19 +1,43 @@
-use std::path::Path;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
-use polars::prelude::*;
+use polars::{
+    export::arrow::{
+        array::{Array, BinaryArray, MutableFixedSizeBinaryArray, PrimitiveArray, Utf8Array},
+        chunk::Chunk,
+        compute::cast::{cast, CastOptions},
+        datatypes::{ArrowDataType, ArrowSchema, IntegerType, PhysicalType},
+    },
+    prelude::*,
+};
+use polars_parquet::write::{
+    row_group_iter, to_parquet_schema, transverse, DynIter, Encoding, FileWriter, KeyValue,
+    ParquetType, Version, WriteOptions,
+};
 
-use crate::types::{FileError, FileOutput};
+use crate::{
+    types::{FileError, FileOutput},
+    U256Type, DECIMAL128_MAX_PRECISION,
+};
 
 /// write polars dataframe to file
+///
+/// metadata is stored in the footer of parquet files, csv and json files have no room for it
+///
+/// column_types are written as a second header row of csv files
 pub(crate) fn df_to_file(
     df: &mut DataFrame,
     filename: &Path,
     file_output: &FileOutput,
+    metadata: &[(String, String)],
+    column_types: Option<&[String]>,
 ) -> Result<(), FileError> {
     let tmp_filename = filename.with_extension("_tmp");
     let result = match filename.extension().and_then(|ex| ex.to_str()) {
-        Some("parquet") => df_to_parquet(df, &tmp_filename, file_output),
-        Some("csv") => df_to_csv(df, &tmp_filename),
+        Some("parquet") => df_to_parquet(df, &tmp_filename, file_output, metadata),
+        Some("csv") => df_to_csv(df, &tmp_filename, column_types),
         Some("json") => df_to_json(df, &tmp_filename),
         _ => return Err(FileError::FileWriteError),
     };
@@ -28,29 +52,358 @@ fn df_to_parquet(
     df: &mut DataFrame,
     filename: &Path,
     file_output: &FileOutput,
+    metadata: &[(String, String)],
 ) -> Result<(), FileError> {
-    let file = std::fs::File::create(filename).map_err(|_e| FileError::FileWriteError)?;
-    let result = ParquetWriter::new(file)
-        .with_statistics(file_output.parquet_statistics)
-        .with_compression(file_output.parquet_compression)
-        .with_row_group_size(file_output.row_group_size)
-        .finish(df);
-    match result {
-        Err(_e) => Err(FileError::FileWriteError),
-        _ => Ok(()),
+    let mut writer = ParquetFileWriter::new(filename, &df.schema(), file_output)?;
+    writer.write(df)?;
+    writer.finish(metadata)
+}
+
+/// parquet writer that can store key-value metadata in the file footer
+struct ParquetFileWriter {
+    writer: FileWriter<std::fs::File>,
+    file: std::fs::File,
+    encodings: Vec<Vec<Encoding>>,
+    // parquet type and write options of each column, options carry the column's compression
+    fields: Vec<ParquetType>,
+    // arrow type of each column when encoded, a dictionary type for dictionary encoded columns
+    write_types: Vec<ArrowDataType>,
+    column_options: Vec<WriteOptions>,
+    row_group_size: usize,
+}
+
+impl ParquetFileWriter {
+    fn new(
+        filename: &Path,
+        schema: &Schema,
+        file_output: &FileOutput,
+    ) -> Result<ParquetFileWriter, FileError> {
+        let mut schema = ArrowSchema::from(schema.to_arrow().fields);
+        // the stored arrow type of a dictionary encoded column is left as is, so that
+        // readers get back the column's own type rather than a dictionary
+        let mut write_types = Vec::with_capacity(schema.fields.len());
+        let decimal_suffix = U256Type::Decimal128.suffix();
+        for field in schema.fields.iter_mut() {
+            let dictionary = file_output.dictionary_encoding.get(&field.name);
+            let decimal_column = field.name.strip_suffix(decimal_suffix.as_str());
+            let write_type = match (dictionary, &field.data_type) {
+                (Some(false), ArrowDataType::Dictionary(_, values, _)) => {
+                    field.data_type = *values.clone();
+                    field.data_type.clone()
+                }
+                (Some(true), data_type) if !matches!(data_type, ArrowDataType::Dictionary(..)) => {
+                    let values = Box::new(data_type.clone());
+                    ArrowDataType::Dictionary(IntegerType::UInt32, values, false)
+                }
+                // decimal128 columns are held as strings, as polars is built without decimals
+                (_, ArrowDataType::LargeUtf8) if decimal_column.is_some() => {
+                    let (precision, scale) = decimal_column
+                        .and_then(|column| file_output.decimal_types.get(column).copied())
+                        .unwrap_or((DECIMAL128_MAX_PRECISION, 0));
+                    field.data_type = ArrowDataType::Decimal(precision, scale);
+                    field.data_type.clone()
+                }
+                // hex columns are decoded, so that both encodings are stored as raw bytes
+                (_, ArrowDataType::LargeBinary | ArrowDataType::LargeUtf8)
+                    if file_output.fixed_size_binary.contains_key(&field.name) =>
+                {
+                    let width = file_output.fixed_size_binary[&field.name];
+                    field.data_type = ArrowDataType::FixedSizeBinary(width);
+                    field.data_type.clone()
+                }
+                (_, data_type) => data_type.clone(),
+            };
+            write_types.push(write_type);
+        }
+        let encodings = write_types
+            .iter()
+            .map(|write_type| {
+                transverse(write_type, |data_type| match data_type.to_physical_type() {
+                    PhysicalType::Dictionary(_) => Encoding::RleDictionary,
+                    _ => Encoding::Plain,
+                })
+            })
+            .collect();
+        let options = WriteOptions {
+            write_statistics: file_output.parquet_statistics,
+            compression: file_output.parquet_compression.into(),
+            version: Version::V2,
+            data_pagesize_limit: None,
+        };
+        let fields =
+            to_parquet_schema(&schema).map_err(|_e| FileError::FileWriteError)?.fields().to_vec();
+        let column_options = schema
+            .fields
+            .iter()
+            .map(|field| match file_output.column_compression.get(&field.name) {
+                Some(compression) => WriteOptions { compression: (*compression).into(), ..options },
+                None => options,
+            })
+            .collect();
+        let file = std::fs::File::create(filename).map_err(|_e| FileError::FileWriteError)?;
+        let handle = file.try_clone().map_err(|_e| FileError::FileWriteError)?;
+        let writer =
+            FileWriter::try_new(handle, schema, options).map_err(|_e| FileError::FileWriteError)?;
+        let row_group_size = file_output.row_group_size.unwrap_or(512 * 512);
+        Ok(ParquetFileWriter {
+            writer,
+            file,
+            row_group_size,
+            encodings,
+            fields,
+            write_types,
+            column_options,
+        })
+    }
+
+    /// write dataframe as row groups of at most row_group_size rows
+    fn write(&mut self, df: &mut DataFrame) -> Result<(), FileError> {
+        let mut offset = 0;
+        while offset < df.height() {
+            let mut row_group = df.slice(offset as i64, self.row_group_size);
+            row_group.as_single_chunk_par();
+            for chunk in row_group.iter_chunks() {
+                if chunk.arrays().len() != self.fields.len() {
+                    return Err(FileError::FileWriteError)
+                }
+                // each column is encoded with its own options, so codecs can differ by column
+                let columns: Vec<_> = chunk
+                    .into_arrays()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, array)| {
+                        let write_type = &self.write_types[index];
+                        let array = match (array.data_type() == write_type, write_type) {
+                            (true, _) => array,
+                            (false, ArrowDataType::Decimal(precision, scale)) => {
+                                to_decimal_array(array.as_ref(), *precision, *scale)?
+                            }
+                            (false, ArrowDataType::FixedSizeBinary(width)) => {
+                                to_fixed_size_array(array.as_ref(), *width)?
+                            }
+                            (false, _) => cast(array.as_ref(), write_type, CastOptions::default())
+                                .map_err(|_e| FileError::FileWriteError)?,
+                        };
+                        Ok(row_group_iter(
+                            Chunk::new(vec![array]),
+                            vec![self.encodings[index].clone()],
+                            vec![self.fields[index].clone()],
+                            self.column_options[index],
+                        ))
+                    })
+                    .collect::<Result<_, FileError>>()?;
+                let group = DynIter::new(columns.into_iter().flatten());
+                self.writer.write(group).map_err(|_e| FileError::FileWriteError)?;
+            }
+            offset += self.row_group_size;
+        }
+        Ok(())
+    }
+
+    /// sync the row groups written so far to disk
+    fn sync(&self) -> Result<(), FileError> {
+        self.file.sync_data().map_err(|_e| FileError::FileWriteError)
+    }
+
+    /// write footer, including the given key-value metadata
+    fn finish(mut self, metadata: &[(String, String)]) -> Result<(), FileError> {
+        let key_value_metadata = match metadata.is_empty() {
+            true => None,
+            false => Some(
+                metadata
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+                    .collect(),
+            ),
+        };
+        self.writer.end(key_value_metadata).map_err(|_e| FileError::FileWriteError)?;
+        Ok(())
+    }
+}
+
+/// convert decimal strings of unscaled integers into a decimal128 array, failing on values that
+/// have more digits than the precision
+fn to_decimal_array(
+    array: &dyn Array,
+    precision: usize,
+    scale: usize,
+) -> Result<Box<dyn Array>, FileError> {
+    let strings =
+        array.as_any().downcast_ref::<Utf8Array<i64>>().ok_or(FileError::FileWriteError)?;
+    let max = 10_i128.pow(precision.min(DECIMAL128_MAX_PRECISION) as u32);
+    let values = strings
+        .iter()
+        .map(|value| match value.map(|v| v.parse::<i128>()) {
+            Some(Ok(v)) if v.abs() < max => Ok(Some(v)),
+            Some(_) => Err(FileError::FileWriteError),
+            None => Ok(None),
+        })
+        .collect::<Result<Vec<_>, FileError>>()?;
+    let decimals =
+        PrimitiveArray::<i128>::from(values).to(ArrowDataType::Decimal(precision, scale));
+    Ok(decimals.boxed())
+}
+
+/// convert binary or hex string values into fixed size binary, failing on values of another size
+fn to_fixed_size_array(array: &dyn Array, width: usize) -> Result<Box<dyn Array>, FileError> {
+    let mut fixed = MutableFixedSizeBinaryArray::with_capacity(width, array.len());
+    if let Some(binary) = array.as_any().downcast_ref::<BinaryArray<i64>>() {
+        for value in binary.iter() {
+            fixed.try_push(value).map_err(|_e| FileError::FileWriteError)?;
+        }
+    } else if let Some(strings) = array.as_any().downcast_ref::<Utf8Array<i64>>() {
+        for value in strings.iter() {
+            let bytes = match value {
+                Some(value) => Some(
+                    prefix_hex::decode::<Vec<u8>>(value).map_err(|_e| FileError::FileWriteError)?,
+                ),
+                None => None,
+            };
+            fixed.try_push(bytes).map_err(|_e| FileError::FileWriteError)?;
+        }
+    } else {
+        return Err(FileError::FileWriteError)
+    }
+    let fixed: polars::export::arrow::array::FixedSizeBinaryArray = fixed.into();
+    Ok(fixed.boxed())
+}
+
+/// file that stays open while dataframes are appended to it
+pub(crate) struct FileAppender {
+    writer: AppendWriter,
+    tmp_filename: PathBuf,
+    filename: PathBuf,
+}
+
+enum AppendWriter {
+    Parquet(Box<ParquetFileWriter>),
+    Csv { file: std::fs::File, include_header: bool, column_types: Option<Vec<String>> },
+    // rows of each dataframe are spliced into a single json array
+    Json { file: std::fs::File, is_empty: bool },
+}
+
+impl FileAppender {
+    /// open a file whose schema is taken from the first dataframe to be appended
+    pub(crate) fn new(
+        filename: &Path,
+        schema: &Schema,
+        file_output: &FileOutput,
+        column_types: Option<Vec<String>>,
+    ) -> Result<FileAppender, FileError> {
+        let tmp_filename = filename.with_extension("_tmp");
+        let create =
+            || std::fs::File::create(&tmp_filename).map_err(|_e| FileError::FileWriteError);
+        let writer = match filename.extension().and_then(|ex| ex.to_str()) {
+            Some("parquet") => AppendWriter::Parquet(Box::new(ParquetFileWriter::new(
+                &tmp_filename,
+                schema,
+                file_output,
+            )?)),
+            Some("csv") => {
+                AppendWriter::Csv { file: create()?, include_header: true, column_types }
+            }
+            Some("json") => AppendWriter::Json { file: create()?, is_empty: true },
+            _ => return Err(FileError::FileWriteError),
+        };
+        Ok(FileAppender { writer, tmp_filename, filename: filename.to_path_buf() })
+    }
+
+    /// append dataframe to the end of the file
+    pub(crate) fn append(&mut self, df: &mut DataFrame) -> Result<(), FileError> {
+        match &mut self.writer {
+            AppendWriter::Parquet(writer) => writer.write(df),
+            AppendWriter::Csv { file, include_header, column_types } => {
+                match (*include_header, column_types.as_deref()) {
+                    (true, Some(column_types)) => {
+                        write_csv_header(&mut *file, df, column_types)?;
+                        CsvWriter::new(&mut *file).include_header(false).finish(df)
+                    }
+                    (include_header, _) => {
+                        CsvWriter::new(&mut *file).include_header(include_header).finish(df)
+                    }
+                }
+                .map_err(|_e| FileError::FileWriteError)?;
+                *include_header = false;
+                Ok(())
+            }
+            AppendWriter::Json { file, is_empty } => {
+                if df.height() == 0 {
+                    return Ok(())
+                }
+                let mut buffer = Vec::new();
+                JsonWriter::new(&mut buffer)
+                    .with_json_format(JsonFormat::Json)
+                    .finish(df)
+                    .map_err(|_e| FileError::FileWriteError)?;
+                let rows = &buffer[1..buffer.len() - 1];
+                let separator: &[u8] = if *is_empty { b"[" } else { b"," };
+                file.write_all(separator).map_err(|_e| FileError::FileWriteError)?;
+                file.write_all(rows).map_err(|_e| FileError::FileWriteError)?;
+                *is_empty = false;
+                Ok(())
+            }
+        }
+    }
+
+    /// sync the chunks appended so far to disk, the file stays unreadable until finished
+    pub(crate) fn sync(&self) -> Result<(), FileError> {
+        match &self.writer {
+            AppendWriter::Parquet(writer) => writer.sync(),
+            AppendWriter::Csv { file, .. } | AppendWriter::Json { file, .. } => {
+                file.sync_data().map_err(|_e| FileError::FileWriteError)
+            }
+        }
+    }
+
+    /// write footer and move file to its final path
+    pub(crate) fn finish(self, metadata: &[(String, String)]) -> Result<(), FileError> {
+        match self.writer {
+            AppendWriter::Parquet(writer) => (*writer).finish(metadata)?,
+            AppendWriter::Csv { .. } => {}
+            AppendWriter::Json { mut file, is_empty } => {
+                let end: &[u8] = if is_empty { b"[]" } else { b"]" };
+                file.write_all(end).map_err(|_e| FileError::FileWriteError)?;
+            }
+        }
+        std::fs::rename(&self.tmp_filename, &self.filename).map_err(|_e| FileError::FileWriteError)
+    }
+
+    /// discard the partially written file
+    pub(crate) fn abandon(self) {
+        let _ = std::fs::remove_file(self.tmp_filename);
     }
 }
 
 /// write polars dataframe to csv file
-fn df_to_csv(df: &mut DataFrame, filename: &Path) -> Result<(), FileError> {
-    let file = std::fs::File::create(filename).map_err(|_e| FileError::FileWriteError)?;
-    let result = CsvWriter::new(file).finish(df);
+fn df_to_csv(
+    df: &mut DataFrame,
+    filename: &Path,
+    column_types: Option<&[String]>,
+) -> Result<(), FileError> {
+    let mut file = std::fs::File::create(filename).map_err(|_e| FileError::FileWriteError)?;
+    let result = match column_types {
+        Some(column_types) => {
+            write_csv_header(&mut file, df, column_types)?;
+            CsvWriter::new(file).include_header(false).finish(df)
+        }
+        None => CsvWriter::new(file).finish(df),
+    };
     match result {
         Err(_e) => Err(FileError::FileWriteError),
         _ => Ok(()),
     }
 }
 
+/// write csv header of column names followed by a row of column types
+fn write_csv_header(
+    file: &mut std::fs::File,
+    df: &DataFrame,
+    column_types: &[String],
+) -> Result<(), FileError> {
+    let header = format!("{}\n{}\n", df.get_column_names().join(","), column_types.join(","));
+    file.write_all(header.as_bytes()).map_err(|_e| FileError::FileWriteError)
+}
+
 /// write polars dataframe to json file
 fn df_to_json(df: &mut DataFrame, filename: &Path) -> Result<(), FileError> {
     let file = std::fs::File::create(filename).map_err(|_e| FileError::FileWriteError)?;
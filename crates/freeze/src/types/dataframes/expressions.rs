@@ -0,0 +1,457 @@
+use polars::prelude::*;
+
+use crate::{err, CollectError, ColumnType, OverflowPolicy, ParseError, SchemaError, Table};
+
+/// column computed from other columns of a row, e.g. `gas_cost=gas_used * gas_price`
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ComputedColumn {
+    /// name of the column
+    pub name: String,
+    /// expression computing the value of each row
+    pub expression: ColumnExpression,
+}
+
+/// arithmetic expression over the numeric columns of a row
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ColumnExpression {
+    /// value of a column
+    Column(String),
+    /// integer literal
+    Integer(u64),
+    /// float literal
+    Float(f64),
+    /// operator applied to two expressions
+    Binary(Box<ColumnExpression>, Operator, Box<ColumnExpression>),
+}
+
+/// arithmetic operator of a ColumnExpression
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Operator {
+    /// addition
+    Add,
+    /// subtraction
+    Sub,
+    /// multiplication
+    Mul,
+    /// division, integer division for integer operands
+    Div,
+}
+
+impl std::str::FromStr for ComputedColumn {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<ComputedColumn, ParseError> {
+        let invalid = |message: &str| ParseError::ParseError(format!("{}: {}", message, s));
+        let (name, expression) = s.split_once('=').ok_or_else(|| invalid("expected NAME=EXPR"))?;
+        let name = name.trim();
+        if !is_identifier(name) {
+            return Err(invalid("invalid column name"))
+        }
+        let expression = expression.parse().map_err(|e: ParseError| invalid(&e.to_string()))?;
+        Ok(ComputedColumn { name: name.to_string(), expression })
+    }
+}
+
+impl std::str::FromStr for ColumnExpression {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<ColumnExpression, ParseError> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens: &tokens, position: 0 };
+        let expression = parser.parse_sum()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(expression),
+            Some(token) => Err(ParseError::ParseError(format!("unexpected {:?}", token))),
+        }
+    }
+}
+
+impl ColumnExpression {
+    /// type of the values of the expression: float if any operand is a float, else signed if any
+    /// operand is signed or a subtraction is used, else unsigned
+    pub fn column_type(&self, schema: &Table) -> Result<ColumnType, SchemaError> {
+        match self {
+            ColumnExpression::Column(name) => match schema.column_type(name) {
+                Some(ColumnType::UInt32 | ColumnType::UInt64) => Ok(ColumnType::UInt64),
+                Some(ColumnType::Int32 | ColumnType::Int64) => Ok(ColumnType::Int64),
+                Some(ColumnType::Float32 | ColumnType::Float64) => Ok(ColumnType::Float64),
+                Some(column_type) => Err(SchemaError::IncompatibleSchema(format!(
+                    "column {} is {}, expressions only use numeric columns",
+                    name,
+                    column_type.as_str()
+                ))),
+                None => Err(SchemaError::IncompatibleSchema(format!(
+                    "{} is not a column of {}",
+                    name,
+                    schema.datatype.name()
+                ))),
+            },
+            ColumnExpression::Integer(_) => Ok(ColumnType::UInt64),
+            ColumnExpression::Float(_) => Ok(ColumnType::Float64),
+            ColumnExpression::Binary(left, operator, right) => {
+                let types = [left.column_type(schema)?, right.column_type(schema)?];
+                if types.contains(&ColumnType::Float64) {
+                    Ok(ColumnType::Float64)
+                } else if types.contains(&ColumnType::Int64) || *operator == Operator::Sub {
+                    Ok(ColumnType::Int64)
+                } else {
+                    Ok(ColumnType::UInt64)
+                }
+            }
+        }
+    }
+}
+
+/// value of an expression in one row
+#[derive(Clone, Copy)]
+enum Value {
+    Integer(i128),
+    Float(f64),
+}
+
+/// append the computed columns of a schema to a dataframe
+///
+/// integers are computed in 128 bits, results that do not fit the column type and divisions by
+/// zero follow the overflow policy of the schema: an error, a clamped value (null for divisions
+/// by zero), or the whole column written as decimal strings (null for divisions by zero)
+pub(crate) fn add_computed_columns(
+    mut df: DataFrame,
+    schema: &Table,
+) -> Result<DataFrame, CollectError> {
+    for column in schema.computed_columns.iter() {
+        let column_type = schema.column_type(&column.name).unwrap_or(ColumnType::Float64);
+        let values = evaluate(&column.expression, &df, &schema.overflow_policy)?;
+        let series = to_series(&column.name, values, column_type, &schema.overflow_policy)?;
+        df.with_column(series)?;
+    }
+    Ok(df)
+}
+
+fn evaluate(
+    expression: &ColumnExpression,
+    df: &DataFrame,
+    policy: &OverflowPolicy,
+) -> Result<Vec<Option<Value>>, CollectError> {
+    match expression {
+        ColumnExpression::Column(name) => read_column(df.column(name)?),
+        ColumnExpression::Integer(value) => {
+            Ok(vec![Some(Value::Integer(*value as i128)); df.height()])
+        }
+        ColumnExpression::Float(value) => Ok(vec![Some(Value::Float(*value)); df.height()]),
+        ColumnExpression::Binary(left, operator, right) => {
+            let left = evaluate(left, df, policy)?;
+            let right = evaluate(right, df, policy)?;
+            left.into_iter()
+                .zip(right)
+                .map(|pair| match pair {
+                    (Some(left), Some(right)) => apply(left, *operator, right, policy),
+                    _ => Ok(None),
+                })
+                .collect()
+        }
+    }
+}
+
+fn read_column(series: &Series) -> Result<Vec<Option<Value>>, CollectError> {
+    let integer = |value: Option<i128>| value.map(Value::Integer);
+    let values = match series.dtype() {
+        DataType::UInt32 | DataType::UInt64 => {
+            let values = series.cast(&DataType::UInt64)?;
+            values.u64()?.into_iter().map(|v| integer(v.map(i128::from))).collect()
+        }
+        DataType::Int32 | DataType::Int64 => {
+            let values = series.cast(&DataType::Int64)?;
+            values.i64()?.into_iter().map(|v| integer(v.map(i128::from))).collect()
+        }
+        DataType::Float32 | DataType::Float64 => series
+            .cast(&DataType::Float64)?
+            .f64()?
+            .into_iter()
+            .map(|v| v.map(Value::Float))
+            .collect(),
+        dtype => {
+            return Err(err(&format!("column {} of type {} is not numeric", series.name(), dtype)))
+        }
+    };
+    Ok(values)
+}
+
+fn apply(
+    left: Value,
+    operator: Operator,
+    right: Value,
+    policy: &OverflowPolicy,
+) -> Result<Option<Value>, CollectError> {
+    let (left, right) = match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => (left, right),
+        (left, right) => {
+            let (left, right) = (as_float(left), as_float(right));
+            let value = match operator {
+                Operator::Add => left + right,
+                Operator::Sub => left - right,
+                Operator::Mul => left * right,
+                Operator::Div => left / right,
+            };
+            return Ok(Some(Value::Float(value)))
+        }
+    };
+    let value = match operator {
+        Operator::Add => left.saturating_add(right),
+        Operator::Sub => left.saturating_sub(right),
+        Operator::Mul => left.saturating_mul(right),
+        Operator::Div if right == 0 => match policy {
+            OverflowPolicy::Error => return Err(err("division by zero in computed column")),
+            OverflowPolicy::Saturate | OverflowPolicy::String => return Ok(None),
+        },
+        Operator::Div => left / right,
+    };
+    Ok(Some(Value::Integer(value)))
+}
+
+fn as_float(value: Value) -> f64 {
+    match value {
+        Value::Integer(value) => value as f64,
+        Value::Float(value) => value,
+    }
+}
+
+fn to_series(
+    name: &str,
+    values: Vec<Option<Value>>,
+    column_type: ColumnType,
+    policy: &OverflowPolicy,
+) -> Result<Series, CollectError> {
+    if column_type == ColumnType::Float64 {
+        let values: Vec<Option<f64>> = values.into_iter().map(|v| v.map(as_float)).collect();
+        return Ok(Series::new(name, values))
+    }
+    let integers: Vec<Option<i128>> = values
+        .into_iter()
+        .map(|v| {
+            v.map(|v| match v {
+                Value::Integer(value) => value,
+                Value::Float(value) => value as i128,
+            })
+        })
+        .collect();
+    let (min, max) = match column_type {
+        ColumnType::Int64 => (i64::MIN as i128, i64::MAX as i128),
+        _ => (0, u64::MAX as i128),
+    };
+    if integers.iter().flatten().any(|value| *value < min || *value > max) {
+        match policy {
+            OverflowPolicy::Error => {
+                let message = format!("value overflows {} column {}", column_type.as_str(), name);
+                return Err(err(&message))
+            }
+            OverflowPolicy::String => {
+                let values: Vec<Option<String>> =
+                    integers.iter().map(|v| v.map(|x| x.to_string())).collect();
+                return Ok(Series::new(name, values))
+            }
+            OverflowPolicy::Saturate => {}
+        }
+    }
+    let clamped = integers.into_iter().map(|v| v.map(|x| x.clamp(min, max)));
+    match column_type {
+        ColumnType::Int64 => {
+            Ok(Series::new(name, clamped.map(|v| v.map(|x| x as i64)).collect::<Vec<_>>()))
+        }
+        _ => Ok(Series::new(name, clamped.map(|v| v.map(|x| x as u64)).collect::<Vec<_>>())),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_') &&
+        chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Column(String),
+    Integer(u64),
+    Float(f64),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = s.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        i += 1;
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Sub),
+            '*' => Token::Operator(Operator::Mul),
+            '/' => Token::Operator(Operator::Div),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let literal: String = chars[start..i].iter().collect();
+                let invalid = || ParseError::ParseError(format!("invalid number {}", literal));
+                match literal.contains('.') {
+                    true => Token::Float(literal.parse().map_err(|_| invalid())?),
+                    false => Token::Integer(literal.parse().map_err(|_| invalid())?),
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Column(chars[start..i].iter().collect())
+            }
+            c => return Err(ParseError::ParseError(format!("unexpected character {}", c))),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// recursive descent parser, multiplication and division bind tighter than addition and
+/// subtraction, and operators of the same precedence are left associative
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn next_operator(&mut self, operators: &[Operator]) -> Option<Operator> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) if operators.contains(operator) => {
+                self.position += 1;
+                Some(*operator)
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_sum(&mut self) -> Result<ColumnExpression, ParseError> {
+        let mut expression = self.parse_product()?;
+        while let Some(operator) = self.next_operator(&[Operator::Add, Operator::Sub]) {
+            let right = self.parse_product()?;
+            expression = ColumnExpression::Binary(Box::new(expression), operator, Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_product(&mut self) -> Result<ColumnExpression, ParseError> {
+        let mut expression = self.parse_operand()?;
+        while let Some(operator) = self.next_operator(&[Operator::Mul, Operator::Div]) {
+            let right = self.parse_operand()?;
+            expression = ColumnExpression::Binary(Box::new(expression), operator, Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_operand(&mut self) -> Result<ColumnExpression, ParseError> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        match token {
+            Some(Token::Column(name)) => Ok(ColumnExpression::Column(name)),
+            Some(Token::Integer(value)) => Ok(ColumnExpression::Integer(value)),
+            Some(Token::Float(value)) => Ok(ColumnExpression::Float(value)),
+            Some(Token::Open) => {
+                let expression = self.parse_sum()?;
+                match self.tokens.get(self.position) {
+                    Some(Token::Close) => {
+                        self.position += 1;
+                        Ok(expression)
+                    }
+                    _ => Err(ParseError::ParseError("missing closing parenthesis".to_string())),
+                }
+            }
+            Some(token) => Err(ParseError::ParseError(format!("unexpected {:?}", token))),
+            None => Err(ParseError::ParseError("unexpected end of expression".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnEncoding, Datatype, U256Type};
+
+    fn transactions_schema(policy: OverflowPolicy) -> Table {
+        let columns = Some(vec!["gas_used".to_string(), "gas_price".to_string()]);
+        let mut schema = Datatype::Transactions
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &columns,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        schema.overflow_policy = policy;
+        schema
+    }
+
+    #[test]
+    fn test_product_column() {
+        let mut schema = transactions_schema(OverflowPolicy::Error);
+        schema.add_computed_column("gas_cost=gas_used * gas_price".parse().unwrap()).unwrap();
+        schema.add_computed_column("per_gas = (gas_price - 1) / 2".parse().unwrap()).unwrap();
+        assert_eq!(schema.column_type("gas_cost"), Some(ColumnType::UInt64));
+        assert_eq!(schema.column_type("per_gas"), Some(ColumnType::Int64));
+        assert!(schema.add_computed_column("x=block_hash * 2".parse().unwrap()).is_err());
+
+        let df = df!(
+            "gas_used" => [Some(21_000u64), Some(50_000), None],
+            "gas_price" => [Some(3u64), Some(1_000_000_000), Some(5)],
+        )
+        .unwrap();
+        let df = add_computed_columns(df, &schema).unwrap();
+        let gas_cost: Vec<Option<u64>> =
+            df.column("gas_cost").unwrap().u64().unwrap().into_iter().collect();
+        assert_eq!(gas_cost, vec![Some(63_000), Some(50_000_000_000_000), None]);
+        let per_gas: Vec<Option<i64>> =
+            df.column("per_gas").unwrap().i64().unwrap().into_iter().collect();
+        assert_eq!(per_gas, vec![Some(1), Some(499_999_999), Some(2)]);
+    }
+
+    #[test]
+    fn test_overflow_follows_policy() {
+        let df = || df!("gas_used" => [u64::MAX, 1], "gas_price" => [2u64, 0]).unwrap();
+        let product: ComputedColumn = "product=gas_used * gas_price".parse().unwrap();
+        let quotient: ComputedColumn = "quotient=gas_used / gas_price".parse().unwrap();
+
+        let mut schema = transactions_schema(OverflowPolicy::Error);
+        schema.add_computed_column(product.clone()).unwrap();
+        assert!(add_computed_columns(df(), &schema).is_err());
+
+        let mut schema = transactions_schema(OverflowPolicy::Saturate);
+        schema.add_computed_column(product.clone()).unwrap();
+        schema.add_computed_column(quotient).unwrap();
+        let saturated = add_computed_columns(df(), &schema).unwrap();
+        let products: Vec<Option<u64>> =
+            saturated.column("product").unwrap().u64().unwrap().into_iter().collect();
+        assert_eq!(products, vec![Some(u64::MAX), Some(0)]);
+        let quotients: Vec<Option<u64>> =
+            saturated.column("quotient").unwrap().u64().unwrap().into_iter().collect();
+        assert_eq!(quotients, vec![Some(u64::MAX / 2), None]);
+
+        let mut schema = transactions_schema(OverflowPolicy::String);
+        schema.add_computed_column(product).unwrap();
+        let strings = add_computed_columns(df(), &schema).unwrap();
+        let products: Vec<Option<&str>> =
+            strings.column("product").unwrap().utf8().unwrap().into_iter().collect();
+        assert_eq!(products, vec![Some("36893488147419103230"), Some("0")]);
+
+        assert!("x=gas_used *".parse::<ComputedColumn>().is_err());
+        assert!("gas_used * 2".parse::<ComputedColumn>().is_err());
+    }
+}
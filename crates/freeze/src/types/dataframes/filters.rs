@@ -0,0 +1,88 @@
+use polars::prelude::*;
+
+use crate::{CollectError, U256Type};
+
+/// drop rows whose value is zero, rows with a null value are kept
+///
+/// the value column is read in whichever representation it was written, i.e. `value` or any of
+/// its u256 columns (`value_binary`, `value_string`, ...), binary values may be hex encoded
+pub(crate) fn drop_zero_values(df: DataFrame) -> Result<DataFrame, CollectError> {
+    let u256_types = [
+        U256Type::Binary,
+        U256Type::String,
+        U256Type::F32,
+        U256Type::F64,
+        U256Type::U32,
+        U256Type::U64,
+        U256Type::Decimal128,
+    ];
+    let candidates = std::iter::once("value".to_string()).chain(
+        u256_types.iter().map(|u256_type| "value".to_string() + u256_type.suffix().as_str()),
+    );
+    let column_names = df.get_column_names();
+    let Some(name) = candidates.into_iter().find(|name| column_names.contains(&name.as_str()))
+    else {
+        return Ok(df)
+    };
+    let mask = nonzero_mask(df.column(&name)?)?;
+    Ok(df.filter(&mask)?)
+}
+
+fn nonzero_mask(series: &Series) -> Result<BooleanChunked, CollectError> {
+    let mask = match series.dtype() {
+        DataType::Binary => series
+            .binary()?
+            .into_iter()
+            .map(|value| value.is_none_or(|bytes| bytes.iter().any(|byte| *byte != 0)))
+            .collect(),
+        DataType::Utf8 => series
+            .utf8()?
+            .into_iter()
+            .map(|value| {
+                value.is_none_or(|value| {
+                    let digits = value.strip_prefix("0x").unwrap_or(value);
+                    digits.chars().any(|digit| digit != '0')
+                })
+            })
+            .collect(),
+        _ => series
+            .cast(&DataType::Float64)?
+            .f64()?
+            .into_iter()
+            .map(|value| value.is_none_or(|value| value != 0.0))
+            .collect(),
+    };
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_zero_values() {
+        let mut nonzero = vec![0u8; 32];
+        nonzero[31] = 5;
+        let df = df!(
+            "transaction_index" => [0u64, 1, 2],
+            "value_binary" => [vec![0u8; 32], nonzero.clone(), vec![]],
+            "value_string" => ["0", "5", "0"],
+            "value_f64" => [0.0, 5.0, 0.0],
+        )
+        .unwrap();
+        let df = drop_zero_values(df).unwrap();
+        let kept: Vec<_> =
+            df.column("transaction_index").unwrap().u64().unwrap().into_iter().collect();
+        assert_eq!(kept, vec![Some(1)]);
+
+        // hex encoded values and value columns of a single type
+        let df = df!(
+            "transaction_index" => [0u64, 1],
+            "value" => ["0x0000", "0x00a0"],
+        )
+        .unwrap();
+        let df = drop_zero_values(df).unwrap();
+        assert_eq!(df.column("value").unwrap().utf8().unwrap().get(0), Some("0x00a0"));
+        assert_eq!(df.height(), 1);
+    }
+}
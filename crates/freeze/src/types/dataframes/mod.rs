@@ -1,4 +1,11 @@
+mod aggregate;
+mod column_types;
+mod dates;
 mod export;
+mod expressions;
+mod filters;
+mod optimize;
+mod partition_keys;
 mod read;
 mod sort;
 mod u256s;
@@ -6,7 +13,16 @@ mod u256s;
 #[macro_use]
 mod creation;
 
+pub(crate) use aggregate::aggregate_df;
+pub use aggregate::Aggregation;
+pub(crate) use column_types::{checksum_addresses, df_column_types, integers_to_strings};
+pub(crate) use dates::split_by_date;
 pub(crate) use export::*;
+pub(crate) use expressions::add_computed_columns;
+pub use expressions::{ColumnExpression, ComputedColumn, Operator};
+pub(crate) use filters::drop_zero_values;
+pub(crate) use optimize::optimize_types;
+pub(crate) use partition_keys::split_by_column;
 pub use read::*;
 pub(crate) use sort::SortableDataFrame;
 pub use u256s::*;
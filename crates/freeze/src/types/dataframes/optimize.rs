@@ -0,0 +1,76 @@
+use crate::{CollectError, ColumnType};
+use polars::prelude::*;
+
+/// metadata key listing the columns narrowed by optimize_types()
+const OPTIMIZED_TYPES_KEY: &str = "cryo_optimized_types";
+
+/// downcast 64 bit integer columns to 32 bits when every value of the chunk fits
+///
+/// returns the metadata entry recording the narrowed columns, or none if no column changed
+pub(crate) fn optimize_types(
+    df: DataFrame,
+) -> Result<(DataFrame, Vec<(String, String)>), CollectError> {
+    let mut optimized = Vec::new();
+    let mut columns = Vec::new();
+    for series in df.get_columns() {
+        let narrowed = match series.dtype() {
+            DataType::UInt64 => {
+                let values = series.u64().map_err(CollectError::PolarsError)?;
+                let fits = values.max().map(|max| max <= u32::MAX as u64).unwrap_or(false);
+                fits.then_some((DataType::UInt32, ColumnType::UInt32))
+            }
+            DataType::Int64 => {
+                let values = series.i64().map_err(CollectError::PolarsError)?;
+                let fits = match (values.min(), values.max()) {
+                    (Some(min), Some(max)) => min >= i32::MIN as i64 && max <= i32::MAX as i64,
+                    _ => false,
+                };
+                fits.then_some((DataType::Int32, ColumnType::Int32))
+            }
+            _ => None,
+        };
+        match narrowed {
+            Some((dtype, column_type)) => {
+                columns.push(series.cast(&dtype).map_err(CollectError::PolarsError)?);
+                optimized.push(format!("{}:{}", series.name(), column_type.as_str()));
+            }
+            None => columns.push(series.clone()),
+        }
+    }
+
+    let df = DataFrame::new(columns).map_err(CollectError::PolarsError)?;
+    let metadata = match optimized.is_empty() {
+        true => Vec::new(),
+        false => vec![(OPTIMIZED_TYPES_KEY.to_string(), optimized.join(","))],
+    };
+    Ok((df, metadata))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimize_types_downsizes_fitting_columns() {
+        let df = df!(
+            "gas_used" => &[10u64, 4_000_000_000],
+            "value" => &[1u64, u32::MAX as u64 + 1],
+            "delta" => &[-5i64, 7],
+            "label" => &["a", "b"],
+        )
+        .unwrap();
+
+        let (df, metadata) = optimize_types(df).unwrap();
+        assert_eq!(df.column("gas_used").unwrap().dtype(), &DataType::UInt32);
+        assert_eq!(df.column("value").unwrap().dtype(), &DataType::UInt64);
+        assert_eq!(df.column("delta").unwrap().dtype(), &DataType::Int32);
+        assert_eq!(df.column("label").unwrap().dtype(), &DataType::Utf8);
+        let values: Vec<Option<u32>> =
+            df.column("gas_used").unwrap().u32().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some(10), Some(4_000_000_000)]);
+        assert_eq!(
+            metadata,
+            vec![(OPTIMIZED_TYPES_KEY.to_string(), "gas_used:uint32,delta:int32".to_string())]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{err, CollectError};
+use polars::prelude::*;
+use std::collections::BTreeMap;
+
+/// split rows of a dataframe by the value of a column
+///
+/// binary values are hex encoded and missing values are named null, values are returned in
+/// ascending order
+pub(crate) fn split_by_column(
+    df: &DataFrame,
+    column: &str,
+) -> Result<Vec<(String, DataFrame)>, CollectError> {
+    let series = df
+        .column(column)
+        .map_err(|_| err(&format!("cannot partition by missing column {}", column)))?;
+    let keys: Vec<String> = match series.dtype() {
+        DataType::Binary => series
+            .binary()?
+            .into_iter()
+            .map(|value| {
+                value.map_or("null".to_string(), |value| prefix_hex::encode(value.to_vec()))
+            })
+            .collect(),
+        _ => series
+            .cast(&DataType::Utf8)?
+            .utf8()?
+            .into_iter()
+            .map(|value| value.map_or("null".to_string(), |value| value.replace('/', "%2F")))
+            .collect(),
+    };
+    split_by_keys(df, &keys)
+}
+
+/// split rows of a dataframe into one dataframe per distinct key, in ascending order of keys
+pub(super) fn split_by_keys(
+    df: &DataFrame,
+    keys: &[String],
+) -> Result<Vec<(String, DataFrame)>, CollectError> {
+    let mut masks: BTreeMap<&str, Vec<bool>> = BTreeMap::new();
+    for (index, key) in keys.iter().enumerate() {
+        masks.entry(key.as_str()).or_insert_with(|| vec![false; keys.len()])[index] = true;
+    }
+    masks
+        .into_iter()
+        .map(|(key, mask)| {
+            let mask = BooleanChunked::new("", mask);
+            Ok((key.to_string(), df.filter(&mask).map_err(CollectError::PolarsError)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_by_column() {
+        let df = df!(
+            "block_number" => &[100u32, 101, 102, 103],
+            "contract_address" => &[vec![2u8], vec![1u8], vec![2u8], vec![3u8]],
+        )
+        .unwrap();
+
+        let parts = split_by_column(&df, "contract_address").unwrap();
+        let keys: Vec<&str> = parts.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["0x01", "0x02", "0x03"]);
+        assert_eq!(parts[1].1.height(), 2);
+
+        let parts = split_by_column(&df, "block_number").unwrap();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0].0, "100");
+        assert!(split_by_column(&df, "timestamp").is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::ParseError;
 use polars::prelude::*;
+use std::path::Path;
 
 /// read single binary column of parquet file as Vec<u8>
 pub fn read_binary_column(path: &str, column: &str) -> Result<Vec<Vec<u8>>, ParseError> {
@@ -29,3 +30,272 @@ pub fn read_binary_column(path: &str, column: &str) -> Result<Vec<Vec<u8>>, Pars
         })
         .collect()
 }
+
+/// read arrow schema of parquet file
+pub fn read_parquet_schema(path: &str) -> Result<ArrowSchema, ParseError> {
+    let file = std::fs::File::open(path)
+        .map_err(|_e| ParseError::ParseError(format!("could not open file path: {}", path)))?;
+    let schema = ParquetReader::new(file)
+        .schema()
+        .map_err(|_e| ParseError::ParseError(format!("could not read schema of {}", path)))?;
+    Ok(schema.as_ref().clone())
+}
+
+/// row count, null counts, and footer metadata of a parquet file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParquetInspection {
+    /// number of rows
+    pub n_rows: u64,
+    /// name, arrow type, and null count of each column
+    pub columns: Vec<(String, String, u64)>,
+    /// key-value metadata of the footer, e.g. cryo_schema_hash
+    pub metadata: Vec<(String, String)>,
+}
+
+/// inspect a parquet file from its footer
+///
+/// null counts come from the statistics of each row group. columns without statistics, e.g. in
+/// files written with --no-stats, and nested columns are read to count their nulls
+pub fn inspect_parquet(path: &str) -> Result<ParquetInspection, ParseError> {
+    let open = || {
+        std::fs::File::open(path)
+            .map_err(|_e| ParseError::ParseError(format!("could not open file path: {}", path)))
+    };
+    let mut reader = ParquetReader::new(open()?);
+    let schema = reader
+        .schema()
+        .map_err(|_e| ParseError::ParseError(format!("could not read schema of {}", path)))?;
+    let file_metadata = reader
+        .get_metadata()
+        .map_err(|_e| ParseError::ParseError(format!("could not read metadata of {}", path)))?
+        .clone();
+
+    let null_counts: Vec<Option<u64>> = schema
+        .fields
+        .iter()
+        .map(|field| statistics_null_count(&file_metadata, &field.name))
+        .collect();
+    let unknown: Vec<String> = schema
+        .fields
+        .iter()
+        .zip(null_counts.iter())
+        .filter(|(_, null_count)| null_count.is_none())
+        .map(|(field, _)| field.name.clone())
+        .collect();
+    let unknown_df = match unknown.is_empty() {
+        true => None,
+        false => Some(
+            ParquetReader::new(open()?)
+                .with_columns(Some(unknown))
+                .finish()
+                .map_err(|_e| ParseError::ParseError(format!("could not read {}", path)))?,
+        ),
+    };
+
+    let mut columns = Vec::new();
+    for (field, null_count) in schema.fields.iter().zip(null_counts) {
+        let null_count = match (null_count, &unknown_df) {
+            (Some(null_count), _) => null_count,
+            (None, Some(df)) => df
+                .column(&field.name)
+                .map_err(|_e| ParseError::ParseError("could not get column".to_string()))?
+                .null_count() as u64,
+            (None, None) => 0,
+        };
+        columns.push((field.name.clone(), format!("{:?}", field.data_type), null_count));
+    }
+    let metadata = file_metadata
+        .key_value_metadata()
+        .iter()
+        .flatten()
+        .map(|kv| (kv.key.clone(), kv.value.clone().unwrap_or_default()))
+        .collect();
+    Ok(ParquetInspection { n_rows: file_metadata.num_rows as u64, columns, metadata })
+}
+
+/// block range stored in the metadata of a parquet file written by cryo
+///
+/// files without block range metadata, e.g. files not written by cryo, have no block range
+pub fn read_block_range(path: &Path) -> Result<Option<(u64, u64)>, ParseError> {
+    let open_error = |_e| ParseError::ParseError(format!("could not open {}", path.display()));
+    let mut file = std::fs::File::open(path).map_err(open_error)?;
+    let metadata = polars_parquet::read::read_metadata(&mut file).map_err(|_e| {
+        ParseError::ParseError(format!("could not read metadata of {}", path.display()))
+    })?;
+    let get_block = |key: &str| {
+        metadata
+            .key_value_metadata()
+            .iter()
+            .flatten()
+            .find(|kv| kv.key == key)
+            .and_then(|kv| kv.value.as_ref())
+            .and_then(|value| value.parse::<u64>().ok())
+    };
+    match (get_block("cryo_min_block"), get_block("cryo_max_block")) {
+        (Some(min_block), Some(max_block)) => Ok(Some((min_block, max_block))),
+        _ => Ok(None),
+    }
+}
+
+/// blocks covered by the existing files of a datatype
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockCoverage {
+    /// number of files with a block range
+    pub n_files: usize,
+    /// number of files whose block range overlaps the ranges of files starting before it
+    pub n_overlapping: usize,
+    /// disjoint inclusive block ranges covered by the files, in ascending order
+    pub ranges: Vec<(u64, u64)>,
+}
+
+impl BlockCoverage {
+    /// cover the inclusive block ranges of files
+    pub fn new(mut file_ranges: Vec<(u64, u64)>) -> BlockCoverage {
+        file_ranges.sort();
+        let mut coverage = BlockCoverage { n_files: file_ranges.len(), ..Default::default() };
+        for (start, end) in file_ranges.into_iter() {
+            match coverage.ranges.last_mut() {
+                Some((_, last_end)) if start <= *last_end => {
+                    *last_end = (*last_end).max(end);
+                    coverage.n_overlapping += 1;
+                }
+                Some((_, last_end)) if start == *last_end + 1 => *last_end = end,
+                _ => coverage.ranges.push((start, end)),
+            }
+        }
+        coverage
+    }
+
+    /// first block after the highest covered block
+    pub fn high_water_mark(&self) -> Option<u64> {
+        self.ranges.last().map(|(_, end)| end + 1)
+    }
+
+    /// inclusive block ranges below the high water mark that no file covers
+    pub fn gaps(&self) -> Vec<(u64, u64)> {
+        self.ranges.windows(2).map(|pair| (pair[0].1 + 1, pair[1].0 - 1)).collect()
+    }
+}
+
+/// scan the parquet files of a datatype in a directory and its subdirectories for the blocks
+/// they cover
+///
+/// files of the datatype are named `<network>__<datatype>__<range>.parquet`, their block ranges
+/// come from their metadata
+pub fn scan_block_coverage(dir: &Path, datatype: &str) -> Result<BlockCoverage, ParseError> {
+    let infix = format!("__{}__", datatype);
+    let mut file_ranges = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(_e) => {
+                return Err(ParseError::ParseError(format!("could not read {}", dir.display())))
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                dirs.push(path)
+            } else if name.contains(&infix) && name.ends_with(".parquet") {
+                file_ranges.extend(read_block_range(&path)?);
+            }
+        }
+    }
+    Ok(BlockCoverage::new(file_ranges))
+}
+
+/// null count of a column summed over row groups, if every row group has statistics for it
+fn statistics_null_count(
+    metadata: &polars_parquet::write::FileMetaData,
+    column: &str,
+) -> Option<u64> {
+    let mut total = 0;
+    for row_group in metadata.row_groups.iter() {
+        let chunks: Vec<_> = row_group
+            .columns()
+            .iter()
+            .filter(|chunk| chunk.descriptor().path_in_schema[0] == column)
+            .collect();
+        match chunks.as_slice() {
+            [chunk] if chunk.descriptor().path_in_schema.len() == 1 => {
+                total += chunk.statistics()?.ok()?.null_count()? as u64
+            }
+            _ => return None,
+        }
+    }
+    Some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{make_sink, TempDir};
+
+    #[test]
+    fn test_inspect_parquet_null_counts() {
+        let dir = TempDir::new("inspect");
+        std::fs::create_dir_all(&dir).unwrap();
+        for statistics in [true, false] {
+            let path = dir.join(format!("stats_{}.parquet", statistics));
+            let mut df = df!(
+                "block_number" => [1u32, 2, 3, 4],
+                "author" => [Some("a"), None, None, Some("b")],
+            )
+            .unwrap();
+            let file = std::fs::File::create(&path).unwrap();
+            ParquetWriter::new(file).with_statistics(statistics).finish(&mut df).unwrap();
+
+            let inspection = inspect_parquet(path.to_str().unwrap()).unwrap();
+            assert_eq!(inspection.n_rows, 4);
+            let null_counts: Vec<_> = inspection
+                .columns
+                .iter()
+                .map(|(name, _, null_count)| (name.as_str(), *null_count))
+                .collect();
+            assert_eq!(null_counts, vec![("block_number", 0), ("author", 2)]);
+        }
+    }
+
+    #[test]
+    fn test_scan_block_coverage() {
+        let dir = TempDir::new("block_coverage");
+        let sink = crate::FileOutput {
+            parquet_compression: ParquetCompression::Snappy,
+            ..make_sink(&dir)
+        };
+        let write = |name: &str, min_block: u64, max_block: u64| {
+            let path = dir.join("blocks").join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let mut df = df!("block_number" => [min_block, max_block]).unwrap();
+            let metadata = vec![
+                ("cryo_min_block".to_string(), min_block.to_string()),
+                ("cryo_max_block".to_string(), max_block.to_string()),
+            ];
+            crate::dataframes::df_to_file(&mut df, &path, &sink, &metadata, None).unwrap();
+        };
+
+        // files covering 0:1000, one overlapping both of its neighbors
+        write("ethereum__blocks__00000000_to_00000499.parquet", 0, 499);
+        write("ethereum__blocks__00000500_to_00000999.parquet", 500, 999);
+        write("ethereum__blocks__00000400_to_00000599.parquet", 400, 599);
+        write("ethereum__transactions__00000000_to_00001999.parquet", 0, 1999);
+        let coverage = scan_block_coverage(&dir, "blocks").unwrap();
+        assert_eq!(coverage.n_files, 3);
+        assert_eq!(coverage.n_overlapping, 2);
+        assert_eq!(coverage.ranges, vec![(0, 999)]);
+        assert_eq!(coverage.high_water_mark(), Some(1000));
+        assert!(coverage.gaps().is_empty());
+
+        // resuming continues after the highest block, reporting the gap below it
+        write("ethereum__blocks__00001500_to_00001999.parquet", 1500, 1999);
+        let coverage = scan_block_coverage(&dir, "blocks").unwrap();
+        assert_eq!(coverage.high_water_mark(), Some(2000));
+        assert_eq!(coverage.gaps(), vec![(1000, 1499)]);
+
+        let missing = scan_block_coverage(&dir.join("missing"), "blocks").unwrap();
+        assert_eq!(missing.high_water_mark(), None);
+    }
+}
@@ -8,11 +8,59 @@ pub(crate) trait SortableDataFrame {
 
 impl SortableDataFrame for Result<DataFrame, CollectError> {
     fn sort_by_schema(self, schema: &Table) -> Self {
-        match (self, &schema.sort_columns) {
-            (Ok(df), Some(sort_columns)) => {
-                df.sort(sort_columns, false, false).map_err(CollectError::PolarsError)
-            }
-            (df, _) => df,
+        let mut df = self?;
+        if let Some(key) = schema.row_key() {
+            df = df.unique_stable(Some(key), UniqueKeepStrategy::First, None)?;
         }
+        match schema.row_order() {
+            Some(order) => df.sort(order, false, false).map_err(CollectError::PolarsError),
+            None => Ok(df),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnEncoding, Datatype, U256Type};
+
+    #[test]
+    fn test_file_order_sorts_rows_and_sort_columns_deduplicate_them() {
+        let columns = Some(vec!["block_number".into(), "log_index".into(), "address".into()]);
+        let sort = Some(vec!["block_number".to_string(), "log_index".to_string()]);
+        let mut schema = Datatype::Logs
+            .table_schema(
+                &[U256Type::Binary],
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &columns,
+                &None,
+                sort,
+                None,
+                false,
+            )
+            .unwrap();
+        let df = || {
+            df!(
+                "block_number" => [2u32, 1, 1, 2],
+                "log_index" => [0u32, 1, 0, 0],
+                "address" => [&b"a"[..], b"c", b"b", b"d"],
+            )
+            .map_err(CollectError::PolarsError)
+        };
+        let addresses = |df: DataFrame| -> Vec<Vec<u8>> {
+            let addresses = df.column("address").unwrap().binary().unwrap().clone();
+            addresses.into_iter().flatten().map(|address| address.to_vec()).collect()
+        };
+
+        // without a file order, rows are sorted by the sort columns and all are kept
+        let sorted = df().sort_by_schema(&schema).unwrap();
+        assert_eq!(addresses(sorted), vec![b"b", b"c", b"a", b"d"]);
+
+        // with a file order, rows are sorted by it and deduplicated on the sort columns
+        schema.file_order = Some(vec!["address".to_string()]);
+        let ordered = df().sort_by_schema(&schema).unwrap();
+        assert_eq!(addresses(ordered), vec![b"a", b"b", b"c"]);
     }
 }
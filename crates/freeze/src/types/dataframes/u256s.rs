@@ -2,6 +2,75 @@ use crate::*;
 use ethers::prelude::*;
 use polars::prelude::*;
 
+/// integer that may not fit in the u32 or u64 type of its column
+pub trait NarrowU256: Copy + std::fmt::Display {
+    /// value clamped to the range [0, max], with whether clamping changed it
+    fn clamp_to(self, max: U256) -> (U256, bool);
+}
+
+impl NarrowU256 for U256 {
+    fn clamp_to(self, max: U256) -> (U256, bool) {
+        if self > max {
+            (max, true)
+        } else {
+            (self, false)
+        }
+    }
+}
+
+impl NarrowU256 for I256 {
+    fn clamp_to(self, max: U256) -> (U256, bool) {
+        if self.is_negative() {
+            (U256::zero(), true)
+        } else {
+            self.into_raw().clamp_to(max)
+        }
+    }
+}
+
+/// convert u256-like values into a u32 or u64 Series, applying the overflow policy to values
+/// that do not fit
+pub fn narrow_u256_series<T: NarrowU256>(
+    name: &str,
+    values: Vec<Option<T>>,
+    dtype: U256Type,
+    overflow_policy: &OverflowPolicy,
+) -> Result<Series, CollectError> {
+    let max = match dtype {
+        U256Type::U32 => U256::from(u32::MAX),
+        U256Type::U64 => U256::from(u64::MAX),
+        _ => return Err(err("only u32 and u64 columns can overflow")),
+    };
+    let clamped: Vec<Option<(U256, bool)>> =
+        values.iter().map(|v| v.map(|x| x.clamp_to(max))).collect();
+    if clamped.iter().flatten().any(|(_, overflowed)| *overflowed) {
+        match overflow_policy {
+            OverflowPolicy::Error => {
+                let message = format!("value overflows {} column {}", dtype.suffix(), name);
+                return Err(CollectError::CollectError(message))
+            }
+            OverflowPolicy::String => {
+                let converted: Vec<Option<String>> =
+                    values.iter().map(|v| v.map(|x| x.to_string())).collect();
+                return Ok(Series::new(name, converted))
+            }
+            OverflowPolicy::Saturate => {}
+        }
+    }
+    match dtype {
+        U256Type::U32 => {
+            let converted: Vec<Option<u32>> =
+                clamped.iter().map(|v| v.map(|(x, _)| x.as_u32())).collect();
+            Ok(Series::new(name, converted))
+        }
+        _ => {
+            let converted: Vec<Option<u64>> =
+                clamped.iter().map(|v| v.map(|(x, _)| x.as_u64())).collect();
+            Ok(Series::new(name, converted))
+        }
+    }
+}
+
 /// Converts a Vec of U256-like data into a polars Series
 pub trait ToU256Series {
     /// convert a Vec of U256-like data into a polars Series
@@ -10,6 +79,7 @@ pub trait ToU256Series {
         name: String,
         dtype: U256Type,
         column_encoding: &ColumnEncoding,
+        overflow_policy: &OverflowPolicy,
     ) -> Result<Series, CollectError>;
 }
 
@@ -19,6 +89,7 @@ impl ToU256Series for Vec<U256> {
         name: String,
         dtype: U256Type,
         column_encoding: &ColumnEncoding,
+        overflow_policy: &OverflowPolicy,
     ) -> Result<Series, CollectError> {
         let name = name + dtype.suffix().as_str();
         let name = name.as_str();
@@ -46,15 +117,16 @@ impl ToU256Series for Vec<U256> {
                 Ok(Series::new(name, converted))
             }
             U256Type::U32 => {
-                let converted: Vec<u32> = self.iter().map(|v| v.as_u32()).collect();
-                Ok(Series::new(name, converted))
+                let values = self.iter().map(|v| Some(*v)).collect();
+                narrow_u256_series(name, values, dtype, overflow_policy)
             }
             U256Type::U64 => {
-                let converted: Vec<u64> = self.iter().map(|v| v.as_u64()).collect();
-                Ok(Series::new(name, converted))
+                let values = self.iter().map(|v| Some(*v)).collect();
+                narrow_u256_series(name, values, dtype, overflow_policy)
             }
             U256Type::Decimal128 => {
-                Err(CollectError::CollectError("DECIMAL128 not implemented".to_string()))
+                let values = self.iter().map(|v| Some(*v)).collect();
+                decimal_u256_series(name, values, overflow_policy)
             }
         }
     }
@@ -66,6 +138,7 @@ impl ToU256Series for Vec<Option<U256>> {
         name: String,
         dtype: U256Type,
         column_encoding: &ColumnEncoding,
+        overflow_policy: &OverflowPolicy,
     ) -> Result<Series, CollectError> {
         let name = name + dtype.suffix().as_str();
         let name = name.as_str();
@@ -98,19 +171,9 @@ impl ToU256Series for Vec<Option<U256>> {
                     .collect();
                 Ok(Series::new(name, converted))
             }
-            U256Type::U32 => {
-                let converted: Vec<Option<u32>> =
-                    self.iter().map(|v| v.map(|x| x.as_u32())).collect();
-                Ok(Series::new(name, converted))
-            }
-            U256Type::U64 => {
-                let converted: Vec<Option<u64>> =
-                    self.iter().map(|v| v.map(|x| x.as_u64())).collect();
-                Ok(Series::new(name, converted))
-            }
-            U256Type::Decimal128 => {
-                Err(CollectError::CollectError("DECIMAL128 not implemented".to_string()))
-            }
+            U256Type::U32 => narrow_u256_series(name, self.clone(), dtype, overflow_policy),
+            U256Type::U64 => narrow_u256_series(name, self.clone(), dtype, overflow_policy),
+            U256Type::Decimal128 => decimal_u256_series(name, self.clone(), overflow_policy),
         }
     }
 }
@@ -121,6 +184,7 @@ impl ToU256Series for Vec<I256> {
         name: String,
         dtype: U256Type,
         column_encoding: &ColumnEncoding,
+        overflow_policy: &OverflowPolicy,
     ) -> Result<Series, CollectError> {
         let name = name + dtype.suffix().as_str();
         let name = name.as_str();
@@ -148,15 +212,16 @@ impl ToU256Series for Vec<I256> {
                 Ok(Series::new(name, converted))
             }
             U256Type::U32 => {
-                let converted: Vec<u32> = self.iter().map(|v| v.as_u32()).collect();
-                Ok(Series::new(name, converted))
+                let values = self.iter().map(|v| Some(*v)).collect();
+                narrow_u256_series(name, values, dtype, overflow_policy)
             }
             U256Type::U64 => {
-                let converted: Vec<u64> = self.iter().map(|v| v.as_u64()).collect();
-                Ok(Series::new(name, converted))
+                let values = self.iter().map(|v| Some(*v)).collect();
+                narrow_u256_series(name, values, dtype, overflow_policy)
             }
             U256Type::Decimal128 => {
-                Err(CollectError::CollectError("DECIMAL128 not implemented".to_string()))
+                let values = self.iter().map(|v| Some(*v)).collect();
+                decimal_u256_series(name, values, overflow_policy)
             }
         }
     }
@@ -168,6 +233,7 @@ impl ToU256Series for Vec<Option<I256>> {
         name: String,
         dtype: U256Type,
         column_encoding: &ColumnEncoding,
+        overflow_policy: &OverflowPolicy,
     ) -> Result<Series, CollectError> {
         let name = name + dtype.suffix().as_str();
         let name = name.as_str();
@@ -200,19 +266,108 @@ impl ToU256Series for Vec<Option<I256>> {
                     .collect();
                 Ok(Series::new(name, converted))
             }
-            U256Type::U32 => {
-                let converted: Vec<Option<u32>> =
-                    self.iter().map(|v| v.map(|x| x.as_u32())).collect();
-                Ok(Series::new(name, converted))
-            }
-            U256Type::U64 => {
-                let converted: Vec<Option<u64>> =
-                    self.iter().map(|v| v.map(|x| x.as_u64())).collect();
-                Ok(Series::new(name, converted))
+            U256Type::U32 => narrow_u256_series(name, self.clone(), dtype, overflow_policy),
+            U256Type::U64 => narrow_u256_series(name, self.clone(), dtype, overflow_policy),
+            U256Type::Decimal128 => decimal_u256_series(name, self.clone(), overflow_policy),
+        }
+    }
+}
+
+/// most digits of a decimal128 value
+pub const DECIMAL128_MAX_PRECISION: usize = 38;
+
+/// convert u256-like values into a decimal128 Series
+///
+/// polars is built without decimal types, so the values are held as decimal strings of the
+/// unscaled integer and converted to decimal128 when written to parquet. values of more than 38
+/// digits are clamped under the saturate policy and fail otherwise, as decimal128 has no string
+/// fallback
+pub fn decimal_u256_series<T: NarrowU256>(
+    name: &str,
+    values: Vec<Option<T>>,
+    overflow_policy: &OverflowPolicy,
+) -> Result<Series, CollectError> {
+    let max = "9".repeat(DECIMAL128_MAX_PRECISION);
+    let mut converted: Vec<Option<String>> = Vec::with_capacity(values.len());
+    for value in values.iter() {
+        let Some(value) = value.map(|v| v.to_string()) else {
+            converted.push(None);
+            continue
+        };
+        if value.trim_start_matches('-').len() <= DECIMAL128_MAX_PRECISION {
+            converted.push(Some(value));
+            continue
+        }
+        match overflow_policy {
+            OverflowPolicy::Saturate if value.starts_with('-') => {
+                converted.push(Some(format!("-{}", max)))
             }
-            U256Type::Decimal128 => {
-                Err(CollectError::CollectError("DECIMAL128 not implemented".to_string()))
+            OverflowPolicy::Saturate => converted.push(Some(max.clone())),
+            _ => {
+                let message = format!("value overflows decimal128 column {}", name);
+                return Err(CollectError::CollectError(message))
             }
         }
     }
+    Ok(Series::new(name, converted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overflowing_values() -> Vec<U256> {
+        vec![U256::from(7), U256::from(u64::MAX) + 1]
+    }
+
+    #[test]
+    fn test_overflow_policy_error() {
+        let result = overflowing_values().to_u256_series(
+            "value".to_string(),
+            U256Type::U64,
+            &ColumnEncoding::Binary,
+            &OverflowPolicy::Error,
+        );
+        assert!(matches!(result, Err(CollectError::CollectError(_))));
+    }
+
+    #[test]
+    fn test_overflow_policy_saturate() {
+        let series = overflowing_values()
+            .to_u256_series(
+                "value".to_string(),
+                U256Type::U64,
+                &ColumnEncoding::Binary,
+                &OverflowPolicy::Saturate,
+            )
+            .unwrap();
+        assert_eq!(series.name(), "value_u64");
+        let values: Vec<_> = series.u64().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some(7), Some(u64::MAX)]);
+    }
+
+    #[test]
+    fn test_overflow_policy_string() {
+        let series = overflowing_values()
+            .to_u256_series(
+                "value".to_string(),
+                U256Type::U64,
+                &ColumnEncoding::Binary,
+                &OverflowPolicy::String,
+            )
+            .unwrap();
+        let values: Vec<_> = series.utf8().unwrap().into_iter().collect();
+        assert_eq!(values, vec![Some("7"), Some("18446744073709551616")]);
+
+        // columns without overflowing values keep their integer type
+        let series = vec![U256::from(7)]
+            .to_u256_series(
+                "value".to_string(),
+                U256Type::U64,
+                &ColumnEncoding::Binary,
+                &OverflowPolicy::String,
+            )
+            .unwrap();
+        assert_eq!(series.dtype(), &DataType::UInt64);
+    }
 }
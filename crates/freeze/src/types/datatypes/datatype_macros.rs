@@ -3,7 +3,9 @@
 macro_rules! define_datatypes {
     ($($datatype:ident),* $(,)?) => {
         /// Datatypes
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+        #[derive(
+            Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize,
+        )]
         pub enum Datatype {
             $(
                 /// $datatype
@@ -76,6 +78,47 @@ macro_rules! define_datatypes {
                 }
             }
 
+            /// named column presets of each datatype
+            pub fn presets(&self) -> HashMap<&'static str, Vec<&'static str>> {
+                match *self {
+                    $(Datatype::$datatype => $datatype::presets(),)*
+                }
+            }
+
+            /// byte widths of the fixed size binary columns of each datatype
+            pub fn column_widths(&self) -> HashMap<&'static str, usize> {
+                match *self {
+                    $(Datatype::$datatype => $datatype::base_column_widths(),)*
+                }
+            }
+
+            /// address columns of each datatype, i.e. its binary columns that are 20 bytes wide
+            pub fn address_columns(&self) -> Vec<&'static str> {
+                let widths = self.column_widths().into_iter();
+                widths.filter(|(_, width)| *width == 20).map(|(name, _)| name).collect()
+            }
+
+            /// version of the columns of each datatype
+            pub fn schema_version(&self) -> u32 {
+                match *self {
+                    $(Datatype::$datatype => $datatype::schema_version(),)*
+                }
+            }
+
+            /// deprecated columns of each datatype
+            pub fn deprecated_columns(&self) -> Vec<&'static str> {
+                match *self {
+                    $(Datatype::$datatype => $datatype::deprecated_columns(),)*
+                }
+            }
+
+            /// rpc methods needed by each datatype
+            pub fn required_methods(&self) -> Vec<&'static str> {
+                match *self {
+                    $(Datatype::$datatype => $datatype::required_methods(),)*
+                }
+            }
+
             /// required parameters of each datatype
             pub fn required_parameters(&self) -> Vec<Dim> {
                 match *self {
@@ -115,7 +158,7 @@ macro_rules! define_datatypes {
             let task = match datatype {
                 MetaDatatype::Scalar(datatype) => {
                     let inner_request_size = if datatype.use_block_ranges() {
-                        Some(source.inner_request_size)
+                        Some(source.block_range_size(&datatype))
                     } else {
                         None
                     };
@@ -141,6 +184,54 @@ macro_rules! define_datatypes {
                     MultiDatatype::StateReads => {
                         StateReads::collect_by_block(partition, source, query, None)
                     },
+                    MultiDatatype::TraceReplays => {
+                        TraceReplays::collect_by_block(partition, source, query, None)
+                    },
+                },
+            };
+            task.await
+        }
+
+        /// stream by block, calling on_batch with the data of each response
+        pub async fn stream_by_block(
+            datatype: MetaDatatype,
+            partition: Partition,
+            source: Arc<Source>,
+            query: Arc<Query>,
+            on_batch: BatchCallback<'_>,
+        ) -> Result<(), CollectError> {
+            let task = match datatype {
+                MetaDatatype::Scalar(datatype) => {
+                    let inner_request_size = if datatype.use_block_ranges() {
+                        Some(source.block_range_size(&datatype))
+                    } else {
+                        None
+                    };
+                    match datatype {
+                    $(
+                        Datatype::$datatype => $datatype::stream_by_block(partition, source, query, inner_request_size, on_batch),
+                    )*
+                    }
+                },
+                MetaDatatype::Multi(datatype) => match datatype {
+                    MultiDatatype::BlocksAndTransactions => {
+                        BlocksAndTransactions::stream_by_block(partition, source, query, None, on_batch)
+                    }
+                    MultiDatatype::CallTraceDerivatives => {
+                        CallTraceDerivatives::stream_by_block(partition, source, query, None, on_batch)
+                    }
+                    MultiDatatype::GethStateDiffs => {
+                        GethStateDiffs::stream_by_block(partition, source, query, None, on_batch)
+                    },
+                    MultiDatatype::StateDiffs => {
+                        StateDiffs::stream_by_block(partition, source, query, None, on_batch)
+                    },
+                    MultiDatatype::StateReads => {
+                        StateReads::stream_by_block(partition, source, query, None, on_batch)
+                    },
+                    MultiDatatype::TraceReplays => {
+                        TraceReplays::stream_by_block(partition, source, query, None, on_batch)
+                    },
                 },
             };
             task.await
@@ -156,7 +247,7 @@ macro_rules! define_datatypes {
             let task = match datatype {
                 MetaDatatype::Scalar(datatype) => {
                     let inner_request_size = if datatype.use_block_ranges() {
-                        Some(source.inner_request_size)
+                        Some(source.block_range_size(&datatype))
                     } else {
                         None
                     };
@@ -184,6 +275,57 @@ macro_rules! define_datatypes {
                         MultiDatatype::StateReads => {
                             StateReads::collect_by_transaction(partition, source, query, inner_request_size)
                         },
+                        MultiDatatype::TraceReplays => {
+                            TraceReplays::collect_by_transaction(partition, source, query, None)
+                        },
+                    }
+                },
+            };
+            task.await
+        }
+
+        /// stream by transaction, calling on_batch with the data of each response
+        pub async fn stream_by_transaction(
+            datatype: MetaDatatype,
+            partition: Partition,
+            source: Arc<Source>,
+            query: Arc<Query>,
+            on_batch: BatchCallback<'_>,
+        ) -> Result<(), CollectError> {
+            let task = match datatype {
+                MetaDatatype::Scalar(datatype) => {
+                    let inner_request_size = if datatype.use_block_ranges() {
+                        Some(source.block_range_size(&datatype))
+                    } else {
+                        None
+                    };
+                    match datatype {
+                    $(
+                        Datatype::$datatype => $datatype::stream_by_transaction(partition, source, query, inner_request_size, on_batch),
+                    )*
+                    }
+                },
+                MetaDatatype::Multi(datatype) => {
+                    let inner_request_size = None;
+                    match datatype {
+                        MultiDatatype::BlocksAndTransactions => {
+                            BlocksAndTransactions::stream_by_transaction(partition, source, query, inner_request_size, on_batch)
+                        }
+                        MultiDatatype::CallTraceDerivatives => {
+                            CallTraceDerivatives::stream_by_transaction(partition, source, query, None, on_batch)
+                        }
+                        MultiDatatype::GethStateDiffs => {
+                            GethStateDiffs::stream_by_transaction(partition, source, query, None, on_batch)
+                        },
+                        MultiDatatype::StateDiffs => {
+                            StateDiffs::stream_by_transaction(partition, source, query, inner_request_size, on_batch)
+                        },
+                        MultiDatatype::StateReads => {
+                            StateReads::stream_by_transaction(partition, source, query, inner_request_size, on_batch)
+                        },
+                        MultiDatatype::TraceReplays => {
+                            TraceReplays::stream_by_transaction(partition, source, query, None, on_batch)
+                        },
                     }
                 },
             };
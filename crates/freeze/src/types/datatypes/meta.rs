@@ -17,18 +17,34 @@ impl MetaDatatype {
             MetaDatatype::Multi(multi_datatype) => multi_datatype.datatypes(),
         }
     }
+
+    /// name of datatype
+    pub fn name(&self) -> String {
+        match self {
+            MetaDatatype::Scalar(datatype) => datatype.name(),
+            MetaDatatype::Multi(multi_datatype) => multi_datatype.name(),
+        }
+    }
 }
 
 /// cluster datatypes into MultiDatatype / ScalarDatatype groups
+///
+/// a datatype can belong to several MultiDatatypes, it is collected by the first of them in
+/// variant order that clusters the remaining datatypes
 pub fn cluster_datatypes(dts: Vec<Datatype>) -> Vec<MetaDatatype> {
-    // use MultiDatatypes that have at least 2 ScalarDatatypes in datatype list
-    let mdts: Vec<MultiDatatype> = MultiDatatype::variants()
-        .iter()
-        .filter(|mdt| mdt.datatypes().iter().filter(|x| dts.contains(x)).count() >= 2)
-        .cloned()
-        .collect();
-    let mdt_dts: Vec<Datatype> =
-        mdts.iter().flat_map(|mdt| mdt.datatypes()).filter(|dt| dts.contains(dt)).collect();
+    let mut mdts: Vec<MultiDatatype> = Vec::new();
+    let mut mdt_dts: Vec<Datatype> = Vec::new();
+    for mdt in MultiDatatype::variants().into_iter() {
+        let members: Vec<Datatype> = mdt
+            .datatypes()
+            .into_iter()
+            .filter(|dt| dts.contains(dt) && !mdt_dts.contains(dt))
+            .collect();
+        if mdt.clusters(&members) {
+            mdts.push(mdt);
+            mdt_dts.extend(members);
+        }
+    }
     let other_dts: Vec<Datatype> = dts.iter().filter(|dt| !mdt_dts.contains(dt)).copied().collect();
 
     [
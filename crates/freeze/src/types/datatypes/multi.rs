@@ -18,6 +18,9 @@ pub enum MultiDatatype {
 
     /// balance reads, code reads, nonce reads, and storage reads
     StateReads,
+
+    /// traces, state diffs, and vm traces from one trace_replayBlockTransactions call
+    TraceReplays,
 }
 
 impl MultiDatatype {
@@ -46,6 +49,28 @@ impl MultiDatatype {
                 Datatype::NonceReads,
                 Datatype::StorageReads,
             ],
+            MultiDatatype::TraceReplays => vec![
+                Datatype::Traces,
+                Datatype::BalanceDiffs,
+                Datatype::CodeDiffs,
+                Datatype::NonceDiffs,
+                Datatype::StorageDiffs,
+                Datatype::VmTraces,
+            ],
+        }
+    }
+
+    /// whether the requested members of a multi datatype are collected together
+    ///
+    /// a trace replay only saves requests when traces or vm traces are collected along with state
+    /// diffs, state diffs alone are collected as state_diffs
+    pub fn clusters(&self, members: &[Datatype]) -> bool {
+        match self {
+            MultiDatatype::TraceReplays => {
+                let is_trace = |dt: &&Datatype| matches!(dt, Datatype::Traces | Datatype::VmTraces);
+                members.iter().any(|dt| is_trace(&dt)) && !members.iter().all(|dt| is_trace(&dt))
+            }
+            _ => members.len() >= 2,
         }
     }
 
@@ -54,6 +79,7 @@ impl MultiDatatype {
         vec![
             MultiDatatype::BlocksAndTransactions,
             MultiDatatype::CallTraceDerivatives,
+            MultiDatatype::TraceReplays,
             MultiDatatype::GethStateDiffs,
             MultiDatatype::StateDiffs,
             MultiDatatype::StateReads,
@@ -3,10 +3,12 @@ use polars::prelude::*;
 use std::collections::HashMap;
 
 define_datatypes!(
+    AccessLists,
     AddressAppearances,
     BalanceDiffs,
     BalanceReads,
     Balances,
+    Blobs,
     Blocks,
     CodeDiffs,
     CodeReads,
@@ -20,6 +22,8 @@ define_datatypes!(
     Erc721Transfers,
     EthCalls,
     FourByteCounts,
+    GasFees,
+    GenesisAllocations,
     GethCalls,
     GethCodeDiffs,
     GethBalanceDiffs,
@@ -32,6 +36,8 @@ define_datatypes!(
     NonceDiffs,
     NonceReads,
     Nonces,
+    PendingTransactions,
+    Prestate,
     Slots,
     StorageDiffs,
     StorageReads,
@@ -0,0 +1,206 @@
+use crate::{ColumnEncoding, ColumnType, U256Type, DECIMAL128_MAX_PRECISION};
+use ethers::prelude::*;
+use ethers_core::abi::{HumanReadableParser, ParamType, Token};
+use polars::prelude::*;
+
+/// name of the column of decoded eth_call outputs
+const DECODED_OUTPUT: &str = "decoded_output";
+
+/// container for call output decoding context, serialized as its raw return type
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallOutputDecoder {
+    /// the raw solidity return type ex: uint256
+    pub raw: String,
+    /// decoded abi type of the return type
+    pub output_type: ParamType,
+}
+
+impl serde::Serialize for CallOutputDecoder {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for CallOutputDecoder {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        CallOutputDecoder::new(raw).map_err(serde::de::Error::custom)
+    }
+}
+
+/// decoded outputs of a call output column, in the representation of its column type
+///
+/// decimal128 and hex columns are held as strings, like u256 and hex columns of datasets
+#[derive(Clone, Debug, PartialEq)]
+pub enum CallOutputValues {
+    /// uint types of up to 64 bits
+    UInt64(Vec<Option<u64>>),
+    /// int types of up to 64 bits
+    Int64(Vec<Option<i64>>),
+    /// bool type
+    Boolean(Vec<Option<bool>>),
+    /// address and bytes types with binary encoding
+    Binary(Vec<Option<Vec<u8>>>),
+    /// wider integers as decimals, string type, and address and bytes types with hex encoding
+    String(Vec<Option<String>>),
+}
+
+impl NamedFrom<CallOutputValues, CallOutputValues> for Series {
+    fn new(name: &str, values: CallOutputValues) -> Self {
+        match values {
+            CallOutputValues::UInt64(values) => Series::new(name, values),
+            CallOutputValues::Int64(values) => Series::new(name, values),
+            CallOutputValues::Boolean(values) => Series::new(name, values),
+            CallOutputValues::Binary(values) => Series::new(name, values),
+            CallOutputValues::String(values) => Series::new(name, values),
+        }
+    }
+}
+
+impl CallOutputDecoder {
+    /// create a new CallOutputDecoder from a solidity return type
+    /// ex: CallOutputDecoder::new("uint256".to_string())
+    pub fn new(output_type: String) -> Result<Self, String> {
+        let parsed = HumanReadableParser::parse_type(output_type.trim()).map_err(|e| {
+            format!("invalid output type {} (expect something like uint256): {}", output_type, e)
+        })?;
+        match parsed {
+            ParamType::Array(_) | ParamType::FixedArray(..) | ParamType::Tuple(_) => Err(format!(
+                "unsupported output type {}, only single values are decoded",
+                output_type
+            )),
+            _ => Ok(Self { raw: output_type, output_type: parsed }),
+        }
+    }
+
+    /// name of the decoded column, integers wider than 64 bits are decimal128 columns
+    pub fn column_name(&self) -> String {
+        match self.output_type {
+            ParamType::Uint(bits) | ParamType::Int(bits) if bits > 64 => {
+                DECODED_OUTPUT.to_string() + U256Type::Decimal128.suffix().as_str()
+            }
+            _ => DECODED_OUTPUT.to_string(),
+        }
+    }
+
+    /// type of the decoded column
+    pub fn column_type(&self, column_encoding: &ColumnEncoding) -> ColumnType {
+        match self.output_type {
+            ParamType::Uint(bits) | ParamType::Int(bits) if bits > 64 => ColumnType::Decimal128,
+            ParamType::Uint(_) => ColumnType::UInt64,
+            ParamType::Int(_) => ColumnType::Int64,
+            ParamType::Bool => ColumnType::Boolean,
+            ParamType::String => ColumnType::String,
+            _ => match column_encoding {
+                ColumnEncoding::Binary => ColumnType::Binary,
+                ColumnEncoding::Hex => ColumnType::Hex,
+            },
+        }
+    }
+
+    /// empty values of the decoded column
+    pub fn values(&self, column_encoding: &ColumnEncoding) -> CallOutputValues {
+        match self.column_type(column_encoding) {
+            ColumnType::UInt64 => CallOutputValues::UInt64(Vec::new()),
+            ColumnType::Int64 => CallOutputValues::Int64(Vec::new()),
+            ColumnType::Boolean => CallOutputValues::Boolean(Vec::new()),
+            ColumnType::Binary => CallOutputValues::Binary(Vec::new()),
+            _ => CallOutputValues::String(Vec::new()),
+        }
+    }
+
+    /// decode a call output and append it to values, malformed or empty outputs are appended as
+    /// null, as are integers too wide for their column
+    pub fn push_output(&self, output: &[u8], values: &mut CallOutputValues) {
+        let token = ethers_core::abi::decode(std::slice::from_ref(&self.output_type), output)
+            .ok()
+            .and_then(|tokens| tokens.into_iter().next());
+        match values {
+            CallOutputValues::UInt64(values) => values.push(match token {
+                Some(Token::Uint(value)) => u64::try_from(value).ok(),
+                _ => None,
+            }),
+            CallOutputValues::Int64(values) => values.push(match token {
+                Some(Token::Int(value)) => i64::try_from(I256::from_raw(value)).ok(),
+                _ => None,
+            }),
+            CallOutputValues::Boolean(values) => values.push(match token {
+                Some(Token::Bool(value)) => Some(value),
+                _ => None,
+            }),
+            CallOutputValues::Binary(values) => values.push(match token {
+                Some(Token::Address(address)) => Some(address.as_bytes().to_vec()),
+                Some(Token::Bytes(bytes)) | Some(Token::FixedBytes(bytes)) => Some(bytes),
+                _ => None,
+            }),
+            CallOutputValues::String(values) => values.push(match token {
+                Some(Token::Uint(value)) => fit_decimal128(value.to_string()),
+                Some(Token::Int(value)) => fit_decimal128(I256::from_raw(value).to_string()),
+                Some(Token::String(value)) => Some(value),
+                Some(Token::Address(address)) => {
+                    Some(prefix_hex::encode(address.as_bytes().to_vec()))
+                }
+                Some(Token::Bytes(bytes)) | Some(Token::FixedBytes(bytes)) => {
+                    Some(prefix_hex::encode(bytes))
+                }
+                _ => None,
+            }),
+        }
+    }
+}
+
+fn fit_decimal128(value: String) -> Option<String> {
+    match value.trim_start_matches('-').len() <= DECIMAL128_MAX_PRECISION {
+        true => Some(value),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_output_decoder_types() {
+        let decoder = |raw: &str| CallOutputDecoder::new(raw.to_string()).unwrap();
+        let uint256 = decoder("uint256");
+        assert_eq!(uint256.column_name(), "decoded_output_d128");
+        assert_eq!(uint256.column_type(&ColumnEncoding::Binary), ColumnType::Decimal128);
+        assert_eq!(decoder("uint8").column_type(&ColumnEncoding::Binary), ColumnType::UInt64);
+        assert_eq!(decoder("address").column_name(), "decoded_output");
+        assert_eq!(decoder("address").column_type(&ColumnEncoding::Hex), ColumnType::Hex);
+        assert_eq!(decoder("bytes32").column_type(&ColumnEncoding::Binary), ColumnType::Binary);
+        assert!(CallOutputDecoder::new("uint256[]".to_string()).is_err());
+        assert!(CallOutputDecoder::new("not_a_type".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_call_output_decoder_values() {
+        let decoder = CallOutputDecoder::new("address".to_string()).unwrap();
+        let address = H160::from_low_u64_be(7);
+        let mut values = decoder.values(&ColumnEncoding::Hex);
+        decoder.push_output(&ethers_core::abi::encode(&[Token::Address(address)]), &mut values);
+        decoder.push_output(&[], &mut values);
+        let expected = format!("{:?}", address);
+        assert_eq!(values, CallOutputValues::String(vec![Some(expected), None]));
+    }
+
+    #[test]
+    fn test_call_output_decoder_out_of_range() {
+        // the abi decoder does not check that a word fits the declared width
+        let too_wide = U256::from(u64::MAX) + 1;
+        let decoder = CallOutputDecoder::new("uint64".to_string()).unwrap();
+        let mut values = decoder.values(&ColumnEncoding::Binary);
+        decoder
+            .push_output(&ethers_core::abi::encode(&[Token::Uint(u64::MAX.into())]), &mut values);
+        decoder.push_output(&ethers_core::abi::encode(&[Token::Uint(too_wide)]), &mut values);
+        assert_eq!(values, CallOutputValues::UInt64(vec![Some(u64::MAX), None]));
+
+        let decoder = CallOutputDecoder::new("int64".to_string()).unwrap();
+        let mut values = decoder.values(&ColumnEncoding::Binary);
+        let minus_one = I256::minus_one().into_raw();
+        decoder.push_output(&ethers_core::abi::encode(&[Token::Int(minus_one)]), &mut values);
+        decoder.push_output(&ethers_core::abi::encode(&[Token::Int(too_wide)]), &mut values);
+        assert_eq!(values, CallOutputValues::Int64(vec![Some(-1), None]));
+    }
+}
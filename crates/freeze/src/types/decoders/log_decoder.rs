@@ -1,10 +1,10 @@
-use crate::{err, CollectError, ColumnEncoding, ToU256Series, U256Type};
+use crate::{err, CollectError, ColumnEncoding, OverflowPolicy, ToU256Series, U256Type};
 use ethers::prelude::*;
 use ethers_core::abi::{AbiEncode, EventParam, HumanReadableParser, ParamType, RawLog, Token};
 use polars::prelude::*;
 use std::collections::HashSet;
 
-/// container for log decoding context
+/// container for log decoding context, serialized as its raw event signature
 #[derive(Clone, Debug, PartialEq)]
 pub struct LogDecoder {
     /// the raw event signature string ex: event Transfer(address indexed from, address indexed to,
@@ -14,6 +14,19 @@ pub struct LogDecoder {
     pub event: abi::Event,
 }
 
+impl serde::Serialize for LogDecoder {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for LogDecoder {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        LogDecoder::new(raw).map_err(serde::de::Error::custom)
+    }
+}
+
 impl LogDecoder {
     /// create a new LogDecoder from an event signature
     /// ex: LogDecoder::new("event Transfer(address indexed from, address indexed to, uint256
@@ -66,6 +79,7 @@ impl LogDecoder {
         chunk_len: usize,
         u256_types: &[U256Type],
         column_encoding: &ColumnEncoding,
+        overflow_policy: &OverflowPolicy,
     ) -> Result<Vec<Series>, CollectError> {
         // This is a smooth brain way of doing this, but I can't think of a better way right now
         let mut ints: Vec<i64> = vec![];
@@ -158,6 +172,7 @@ impl LogDecoder {
                     name.clone(),
                     u256_type.clone(),
                     column_encoding,
+                    overflow_policy,
                 )?)
             }
             Ok(series_vec)
@@ -168,6 +183,7 @@ impl LogDecoder {
                     name.clone(),
                     u256_type.clone(),
                     column_encoding,
+                    overflow_policy,
                 )?)
             }
             Ok(series_vec)
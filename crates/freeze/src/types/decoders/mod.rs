@@ -1,3 +1,6 @@
+/// call output decoder
+pub mod call_output_decoder;
 /// log decoder
 pub mod log_decoder;
+pub use call_output_decoder::*;
 pub use log_decoder::*;
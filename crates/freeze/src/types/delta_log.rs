@@ -0,0 +1,191 @@
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+
+use crate::{err, CollectError, ColumnType, Table};
+
+/// directory of the transaction log of a delta table
+pub const DELTA_LOG_DIR: &str = "_delta_log";
+
+/// file written to a delta table, with the partition key of the file if it has one
+pub(crate) type DeltaFile = (PathBuf, Option<(String, String)>);
+
+/// append a commit that adds files to the delta table at table_dir, returning its version
+///
+/// the first commit of a table also records its protocol and schema. files that are already in
+/// the table, e.g. when overwriting a chunk, are removed and added again. chunks are committed
+/// concurrently, so a commit whose version was taken by another chunk is retried at the next
+/// version, as delta writers do
+pub(crate) fn append_delta_commit(
+    table_dir: &Path,
+    schema: &Table,
+    partition_columns: &[String],
+    files: &[DeltaFile],
+) -> Result<u64, CollectError> {
+    let log_dir = table_dir.join(DELTA_LOG_DIR);
+    std::fs::create_dir_all(&log_dir)
+        .map_err(|e| err(&format!("could not create {}: {}", log_dir.display(), e)))?;
+    loop {
+        let (version, live_paths) = read_delta_log(&log_dir)?;
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut actions = Vec::new();
+        if version == 0 {
+            actions.push(json!({ "protocol": { "minReaderVersion": 1, "minWriterVersion": 2 } }));
+            actions.push(metadata_action(schema, partition_columns, now));
+        }
+        for (path, key) in files.iter() {
+            let relative = relative_path(table_dir, path)?;
+            let partition_values: serde_json::Map<String, Value> = key
+                .iter()
+                .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                .collect();
+            if live_paths.contains(&relative) {
+                actions.push(json!({
+                    "remove": {
+                        "path": relative,
+                        "deletionTimestamp": now,
+                        "dataChange": true,
+                        "partitionValues": partition_values,
+                    }
+                }));
+            }
+            let size = std::fs::metadata(path)
+                .map_err(|e| err(&format!("could not read size of {}: {}", path.display(), e)))?
+                .len();
+            actions.push(json!({
+                "add": {
+                    "path": relative,
+                    "partitionValues": partition_values,
+                    "size": size,
+                    "modificationTime": now,
+                    "dataChange": true,
+                }
+            }));
+        }
+        let mut contents = String::new();
+        for action in actions.iter() {
+            contents.push_str(&action.to_string());
+            contents.push('\n');
+        }
+
+        let commit_path = log_dir.join(format!("{:020}.json", version));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&commit_path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes()).map_err(|e| {
+                    err(&format!("could not write {}: {}", commit_path.display(), e))
+                })?;
+                return Ok(version)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(err(&format!("could not create {}: {}", commit_path.display(), e)))
+            }
+        }
+    }
+}
+
+/// next version of a delta log and the paths of the files currently in the table
+fn read_delta_log(log_dir: &Path) -> Result<(u64, HashSet<String>), CollectError> {
+    let entries = std::fs::read_dir(log_dir)
+        .map_err(|e| err(&format!("could not read {}: {}", log_dir.display(), e)))?;
+    let mut versions: Vec<(u64, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| {
+            let version = path.file_stem()?.to_str()?.parse::<u64>().ok()?;
+            Some((version, path))
+        })
+        .collect();
+    versions.sort();
+
+    let mut live_paths = HashSet::new();
+    for (_, path) in versions.iter() {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| err(&format!("could not read {}: {}", path.display(), e)))?;
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let action: Value = serde_json::from_str(line)
+                .map_err(|e| err(&format!("invalid delta log {}: {}", path.display(), e)))?;
+            if let Some(path) = action["add"]["path"].as_str() {
+                live_paths.insert(path.to_string());
+            } else if let Some(path) = action["remove"]["path"].as_str() {
+                live_paths.remove(path);
+            }
+        }
+    }
+    let version = versions.last().map_or(0, |(version, _)| version + 1);
+    Ok((version, live_paths))
+}
+
+fn metadata_action(schema: &Table, partition_columns: &[String], now: i64) -> Value {
+    json!({
+        "metaData": {
+            "id": table_id(table_id_seed(schema, now)),
+            "name": schema.datatype.name(),
+            "format": { "provider": "parquet", "options": {} },
+            "schemaString": delta_schema(schema, partition_columns).to_string(),
+            "partitionColumns": partition_columns,
+            "configuration": {},
+            "createdTime": now,
+        }
+    })
+}
+
+/// delta schema of the columns of output files, partition columns missing from the files are
+/// added as strings
+pub fn delta_schema(schema: &Table, partition_columns: &[String]) -> Value {
+    let mut fields: Vec<Value> = schema
+        .output_columns()
+        .into_iter()
+        .map(|(name, column_type)| delta_field(&name, delta_type(column_type)))
+        .collect();
+    for column in partition_columns.iter() {
+        if !fields.iter().any(|field| field["name"] == column.as_str()) {
+            fields.push(delta_field(column, json!("string")));
+        }
+    }
+    json!({ "type": "struct", "fields": fields })
+}
+
+fn delta_field(name: &str, data_type: Value) -> Value {
+    json!({ "name": name, "type": data_type, "nullable": true, "metadata": {} })
+}
+
+/// delta has no unsigned integers, so uint32 and uint64 columns are both mapped to long
+fn delta_type(column_type: ColumnType) -> Value {
+    match column_type {
+        ColumnType::Boolean => json!("boolean"),
+        ColumnType::Int32 => json!("integer"),
+        ColumnType::UInt32 | ColumnType::UInt64 | ColumnType::Int64 => json!("long"),
+        ColumnType::Float32 => json!("float"),
+        ColumnType::Float64 => json!("double"),
+        ColumnType::Decimal128 => json!("decimal(38,0)"),
+        ColumnType::String | ColumnType::Hex | ColumnType::UInt256 => json!("string"),
+        ColumnType::Binary => json!("binary"),
+        ColumnType::UInt32List => {
+            json!({ "type": "array", "elementType": "long", "containsNull": true })
+        }
+    }
+}
+
+fn relative_path(table_dir: &Path, path: &Path) -> Result<String, CollectError> {
+    let relative = path
+        .strip_prefix(table_dir)
+        .map_err(|_| err(&format!("{} is outside of the delta table", path.display())))?;
+    let parts: Vec<String> =
+        relative.components().map(|part| part.as_os_str().to_string_lossy().to_string()).collect();
+    Ok(parts.join("/"))
+}
+
+fn table_id_seed(schema: &Table, now: i64) -> u128 {
+    ((now as u128) << 64) | (schema.schema_hash() ^ std::process::id() as u64) as u128
+}
+
+/// format a seed like a uuid, delta readers only require the id to be unique per table
+fn table_id(seed: u128) -> String {
+    let hex = format!("{:032x}", seed);
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
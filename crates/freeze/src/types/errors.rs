@@ -1,4 +1,5 @@
 /// error specifications
+use crate::SchemaError;
 use ethers::prelude::*;
 use polars::prelude::*;
 use thiserror::Error;
@@ -58,9 +59,25 @@ pub enum CollectError {
     #[error(transparent)]
     ParseError(#[from] ParseError),
 
-    /// Error related to provider operations
-    #[error("Failed to get block: {0}")]
-    ProviderError(#[source] ProviderError),
+    /// Error related to RPC requests
+    #[error("RPC error: {0}")]
+    Rpc(#[from] ProviderError),
+
+    /// Error decoding an RPC response
+    #[error("Failed to decode response: {0}")]
+    Decode(#[from] serde_json::Error),
+
+    /// Error related to schemas
+    #[error(transparent)]
+    Schema(#[from] SchemaError),
+
+    /// Error related to io operations
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Error related to a request timing out
+    #[error("Request timed out")]
+    Timeout(#[from] tokio::time::error::Elapsed),
 
     /// Error related to tokio task
     #[error("Task failed: {0}")]
@@ -78,13 +95,29 @@ pub enum CollectError {
     #[error("Bad schema specified")]
     BadSchemaError,
 
+    /// Error of the checks of the rpc endpoint made before collecting
+    #[error("Preflight failed: {0}")]
+    PreflightError(String),
+
+    /// Error of a node without the historical state needed to collect a datatype
+    #[error("archive node required: {0}")]
+    ArchiveNodeRequired(String),
+
     /// Error related to too many requests
     #[error("try using a rate limit with --requests-per-second or limiting max concurrency with --max-concurrent-requests")]
     TooManyRequestsError,
 
-    /// Generic RPC Error
-    #[error("RPC call error")]
-    RPCError(String),
+    /// Error of a block above the head of the node, which has not been produced yet
+    #[error("Block {0} has not been produced yet")]
+    BlockNotProduced(u64),
+
+    /// Error of a single block, after its block range was retried block by block
+    #[error("Block {0} failed: {1}")]
+    BlockFailed(u64, Box<CollectError>),
+
+    /// Data of a chunk collected without the blocks that failed
+    #[error("{} blocks of chunk failed", .1.len())]
+    PartialChunk(std::collections::HashMap<crate::Datatype, DataFrame>, Vec<(u64, CollectError)>),
 }
 
 /// Error related to parsing
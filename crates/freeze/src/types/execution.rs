@@ -1,6 +1,14 @@
-use crate::CollectError;
+use crate::{CollectError, DataWriter, RowTransform};
 use indicatif::ProgressBar;
-use std::{path::PathBuf, sync::Arc, time::SystemTime};
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::sync::watch;
 
 /// configuration of execution environment
 #[derive(Clone)]
@@ -25,6 +33,63 @@ pub struct ExecutionEnv {
     pub t_end: Option<SystemTime>,
     /// report directory
     pub report_dir: Option<PathBuf>,
+    /// shutdown signal, no new chunks are started once it is true
+    pub shutdown: Option<watch::Receiver<bool>>,
+    /// transform applied to collected data before it is written
+    pub transform: Option<Arc<dyn RowTransform>>,
+    /// writer receiving the dataframes of each chunk instead of files
+    pub writer: Option<Arc<dyn DataWriter>>,
+    /// interval at which progress of a run is checkpointed
+    pub checkpoint: Option<Checkpoint>,
+    /// log the resolved schema of each datatype before collection starts
+    pub print_schema: bool,
+    /// cap on the number of rows written by the run, shared by all of its chunks
+    pub row_limit: Option<Arc<RowLimit>>,
+    /// time after which a chunk is cancelled and reported as failed
+    pub chunk_timeout: Option<Duration>,
+}
+
+/// cap on the total number of rows written across the chunks of a run
+///
+/// rows are reserved by chunks as they finish, so with parallel chunks the rows kept are those of
+/// the first chunks to finish rather than of the first blocks, and collection of chunks already in
+/// flight when the limit is reached runs to completion before their rows are dropped
+#[derive(Debug)]
+pub struct RowLimit {
+    limit: u64,
+    n_rows: AtomicU64,
+}
+
+impl RowLimit {
+    /// create limit of n rows
+    pub fn new(limit: u64) -> Self {
+        RowLimit { limit, n_rows: AtomicU64::new(0) }
+    }
+
+    /// reserve up to n rows, returning how many of them may be written
+    pub fn reserve(&self, n: u64) -> u64 {
+        let previous = self
+            .n_rows
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n_rows| {
+                Some((n_rows + n).min(self.limit))
+            })
+            .unwrap_or(self.limit);
+        (self.limit - previous).min(n)
+    }
+
+    /// whether every row allowed by the limit has been reserved
+    pub fn is_reached(&self) -> bool {
+        self.n_rows.load(Ordering::SeqCst) >= self.limit
+    }
+}
+
+/// interval at which the report is rewritten and single files are synced to disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checkpoint {
+    /// after every n blocks of completed chunks
+    Blocks(u64),
+    /// at the first chunk completion after n seconds
+    Seconds(u64),
 }
 
 impl ExecutionEnv {
@@ -37,6 +102,16 @@ impl ExecutionEnv {
     pub fn set_end_time(self) -> Self {
         ExecutionEnv { t_end: Some(SystemTime::now()), ..self }
     }
+
+    /// whether shutdown has been requested
+    pub fn shutdown_requested(&self) -> bool {
+        self.shutdown.as_ref().map(|shutdown| *shutdown.borrow()).unwrap_or(false)
+    }
+
+    /// whether the row limit of the run has been reached
+    pub fn row_limit_reached(&self) -> bool {
+        self.row_limit.as_ref().map(|row_limit| row_limit.is_reached()).unwrap_or(false)
+    }
 }
 
 impl Default for ExecutionEnv {
@@ -67,6 +142,13 @@ pub struct ExecutionEnvBuilder {
     t_start: SystemTime,
     t_end: Option<SystemTime>,
     report_dir: Option<PathBuf>,
+    shutdown: Option<watch::Receiver<bool>>,
+    transform: Option<Arc<dyn RowTransform>>,
+    writer: Option<Arc<dyn DataWriter>>,
+    checkpoint: Option<Checkpoint>,
+    print_schema: bool,
+    row_limit: Option<Arc<RowLimit>>,
+    chunk_timeout: Option<Duration>,
 }
 
 impl Default for ExecutionEnvBuilder {
@@ -82,6 +164,13 @@ impl Default for ExecutionEnvBuilder {
             t_start: SystemTime::now(),
             t_end: None,
             report_dir: None,
+            shutdown: None,
+            transform: None,
+            writer: None,
+            checkpoint: None,
+            print_schema: false,
+            row_limit: None,
+            chunk_timeout: None,
         }
     }
 }
@@ -134,6 +223,48 @@ impl ExecutionEnvBuilder {
         self
     }
 
+    /// shutdown signal
+    pub fn shutdown(mut self, shutdown: watch::Receiver<bool>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// transform applied to collected data before it is written
+    pub fn transform(mut self, transform: Arc<dyn RowTransform>) -> Self {
+        self.transform = Some(transform);
+        self
+    }
+
+    /// writer receiving the dataframes of each chunk instead of files
+    pub fn writer(mut self, writer: Arc<dyn DataWriter>) -> Self {
+        self.writer = Some(writer);
+        self
+    }
+
+    /// checkpoint interval
+    pub fn checkpoint(mut self, checkpoint: Option<Checkpoint>) -> Self {
+        self.checkpoint = checkpoint;
+        self
+    }
+
+    /// log resolved schemas at start
+    pub fn print_schema(mut self, print_schema: bool) -> Self {
+        self.print_schema = print_schema;
+        self
+    }
+
+    /// maximum number of rows to write
+    pub fn limit(mut self, limit: Option<u64>) -> Self {
+        self.row_limit = limit.map(|limit| Arc::new(RowLimit::new(limit)));
+        self
+    }
+
+    /// time after which a chunk is cancelled
+    pub fn chunk_timeout(mut self, chunk_timeout: Option<Duration>) -> Self {
+        self.chunk_timeout = chunk_timeout;
+        self
+    }
+
     /// build final output
     pub fn build(self) -> ExecutionEnv {
         ExecutionEnv {
@@ -147,6 +278,13 @@ impl ExecutionEnvBuilder {
             t_start: self.t_start,
             t_end: self.t_end,
             report_dir: self.report_dir,
+            shutdown: self.shutdown,
+            transform: self.transform,
+            writer: self.writer,
+            checkpoint: self.checkpoint,
+            print_schema: self.print_schema,
+            row_limit: self.row_limit,
+            chunk_timeout: self.chunk_timeout,
         }
     }
 }
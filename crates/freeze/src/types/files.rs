@@ -1,5 +1,8 @@
-use crate::{CollectError, Datatype, MetaDatatype, ParseError, Partition, Query};
-use std::{collections::HashMap, path::PathBuf};
+use crate::{err, Aggregation, CollectError, Datatype, MetaDatatype, ParseError, Partition, Query};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 /// Options for file output
 #[derive(Clone, Debug)]
@@ -22,6 +25,78 @@ pub struct FileOutput {
     pub parquet_statistics: bool,
     /// Parquet compression options
     pub parquet_compression: polars::prelude::ParquetCompression,
+    /// Parquet compression of individual columns, overriding parquet_compression
+    pub column_compression: HashMap<String, polars::prelude::ParquetCompression>,
+    /// Whether to dictionary encode individual parquet columns, others keep their default
+    pub dictionary_encoding: HashMap<String, bool>,
+    /// Precision and scale of the parquet decimal128 columns of individual u256 columns, others
+    /// are written as DECIMAL(38,0)
+    pub decimal_types: HashMap<String, (usize, usize)>,
+    /// Write one file per datatype spanning every partition of the query
+    pub single_file: bool,
+    /// Append chunks to single files in block order regardless of completion order
+    pub ordered: bool,
+    /// Aggregates of numeric columns written to a companion file for each chunk file
+    pub aggregates: Vec<Aggregation>,
+    /// Record the block timestamp range of each chunk in its file metadata and the report
+    pub timestamp_range: bool,
+    /// Downcast 64 bit integer columns of each chunk file to 32 bits when all values fit
+    pub optimize_types: bool,
+    /// Split the rows of each chunk into date=YYYY-MM-DD directories by block timestamp
+    pub partition_by_date: bool,
+    /// Split the rows of each chunk into COLUMN=VALUE directories by the value of a column
+    pub partition_by_column: Option<String>,
+    /// Most partition values that a chunk may be split into, bounding the open writers
+    pub max_open_partitions: usize,
+    /// Write the data of each RPC response as it arrives instead of holding whole chunks in memory
+    pub streaming: bool,
+    /// Unix permissions of directories created for output files
+    pub dir_mode: Option<u32>,
+    /// Write a row of column types below the header of csv files
+    pub csv_types: bool,
+    /// Write integer and decimal columns of json files as strings
+    pub json_bigints_as_strings: bool,
+    /// Size chunks to aim for files of this many bytes, from the bytes per block written so far
+    pub target_file_size: Option<u64>,
+    /// Remove output directories created by the run that hold no files once it finishes
+    pub clean_empty_dirs: bool,
+    /// Byte width of binary columns written as fixed size binary in parquet files, by column
+    pub fixed_size_binary: HashMap<String, usize>,
+    /// append a commit to the delta transaction log of each datatype directory after each chunk
+    pub delta: bool,
+    /// Put the partition value of each file in its file name instead of a COLUMN=VALUE directory
+    pub partition_key_in_filename: bool,
+    /// Write hex address columns in EIP-55 checksum form instead of lowercase
+    pub checksum_addresses: bool,
+    /// Write an index of every datatype of the run to the top of the output dir once it finishes
+    pub write_index: bool,
+}
+
+/// Range of block timestamps covered by an output file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub struct TimestampRange {
+    /// timestamp of the earliest block
+    pub min_timestamp: u32,
+    /// timestamp of the latest block
+    pub max_timestamp: u32,
+}
+
+impl TimestampRange {
+    /// smallest range covering both ranges
+    pub fn merge(&self, other: &TimestampRange) -> TimestampRange {
+        TimestampRange {
+            min_timestamp: self.min_timestamp.min(other.min_timestamp),
+            max_timestamp: self.max_timestamp.max(other.max_timestamp),
+        }
+    }
+
+    /// key-value pairs stored in file metadata
+    pub fn to_metadata(&self) -> Vec<(String, String)> {
+        vec![
+            ("cryo_min_timestamp".to_string(), self.min_timestamp.to_string()),
+            ("cryo_max_timestamp".to_string(), self.max_timestamp.to_string()),
+        ]
+    }
 }
 
 /// Possible item to use as subdirectory
@@ -36,6 +111,16 @@ pub enum SubDir {
 }
 
 impl FileOutput {
+    /// key-value pairs identifying the network of a file, stored in file metadata
+    ///
+    /// the network name is the file name prefix
+    pub fn network_metadata(&self, chain_id: u64) -> Vec<(String, String)> {
+        vec![
+            ("cryo_network".to_string(), self.prefix.clone()),
+            ("cryo_chain_id".to_string(), chain_id.to_string()),
+        ]
+    }
+
     /// get output file paths
     pub fn get_paths(
         &self,
@@ -50,7 +135,7 @@ impl FileOutput {
             query.datatypes.clone()
         };
         for meta_datatype in meta_datatypes.iter() {
-            for datatype in meta_datatype.datatypes().into_iter() {
+            for datatype in query.datatypes_of(meta_datatype).into_iter() {
                 paths.insert(datatype, self.get_path(query, partition, datatype)?);
             }
         }
@@ -64,25 +149,98 @@ impl FileOutput {
         partition: &Partition,
         datatype: Datatype,
     ) -> Result<PathBuf, CollectError> {
-        let filename = if let Some(suffix) = self.suffix.clone() {
-            format!(
-                "{}__{}__{}__{}.{}",
-                self.prefix.clone(),
-                datatype.name(),
-                suffix,
-                partition.label(&query.partitioned_by)?,
-                self.format.as_str(),
-            )
-        } else {
-            format!(
-                "{}__{}__{}.{}",
-                self.prefix.clone(),
-                datatype.name(),
-                partition.label(&query.partitioned_by)?,
-                self.format.as_str(),
-            )
-        };
-        let filename = std::path::Path::new(&filename).to_path_buf();
+        self.get_named_path(query, partition, datatype, datatype.name())
+    }
+
+    /// get path of the file holding aggregates of a chunk file
+    pub fn get_aggregate_path(
+        &self,
+        query: &Query,
+        partition: &Partition,
+        datatype: Datatype,
+    ) -> Result<PathBuf, CollectError> {
+        self.get_named_path(query, partition, datatype, datatype.name() + "_agg")
+    }
+
+    /// get path of the part of a chunk file whose rows have a partition key, e.g. a date
+    pub fn get_key_path(
+        &self,
+        path: &Path,
+        key: &str,
+        value: &str,
+    ) -> Result<PathBuf, CollectError> {
+        let file_name = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .ok_or_else(|| err("invalid output path"))?;
+        let parent = path.parent().unwrap_or(&self.output_dir);
+        if self.partition_key_in_filename {
+            // the value goes right before the chunk label, e.g.
+            // ethereum__logs__VALUE__LABEL.parquet
+            let (stem, label) =
+                file_name.rsplit_once("__").ok_or_else(|| err("invalid output path"))?;
+            return Ok(parent.join(format!("{}__{}__{}", stem, value, label)))
+        }
+        Ok(parent.join(format!("{}={}", key, value)).join(file_name))
+    }
+
+    /// create the directory of a file path before writing the file
+    pub(crate) fn create_parent_dir(&self, path: &Path) -> Result<(), CollectError> {
+        match path.parent() {
+            Some(parent) => Ok(create_output_dir(parent, self.dir_mode)?),
+            None => Ok(()),
+        }
+    }
+
+    /// create the output directory of every datatype of query before any chunk is collected
+    ///
+    /// returns the directories that did not exist before
+    pub fn create_dirs(&self, query: &Query) -> Result<Vec<PathBuf>, CollectError> {
+        let mut created = Vec::new();
+        for datatype in query.schemas.keys() {
+            let dir = self.get_dir(*datatype);
+            for missing in dir.ancestors().take_while(|dir| !dir.exists()) {
+                created.push(missing.to_path_buf());
+            }
+            create_output_dir(&dir, self.dir_mode)?;
+        }
+        Ok(created)
+    }
+
+    /// remove the created directories that hold no files, if cleaning empty directories
+    ///
+    /// nested directories are removed first so that their parents can become empty
+    pub fn remove_empty_dirs(&self, created: &[PathBuf]) -> Result<(), CollectError> {
+        if !self.clean_empty_dirs {
+            return Ok(())
+        }
+        let mut created = created.to_vec();
+        created.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+        created.dedup();
+        for dir in created.iter() {
+            let is_empty = match std::fs::read_dir(dir) {
+                Ok(mut entries) => entries.next().is_none(),
+                Err(_) => false,
+            };
+            if is_empty {
+                std::fs::remove_dir(dir).map_err(|e| {
+                    err(&format!("could not remove empty directory {}: {}", dir.display(), e))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// columns that files are partitioned by, as recorded in delta tables
+    pub fn partition_columns(&self) -> Vec<String> {
+        match (&self.partition_by_column, self.partition_by_date) {
+            (Some(column), _) => vec![column.clone()],
+            (None, true) => vec!["date".to_string()],
+            (None, false) => vec![],
+        }
+    }
+
+    pub(crate) fn get_dir(&self, datatype: Datatype) -> PathBuf {
         let mut output_dir = std::path::Path::new(&self.output_dir).to_path_buf();
         for subdir in self.subdirs.iter() {
             let subdir_str: String = match subdir {
@@ -95,11 +253,102 @@ impl FileOutput {
             };
             output_dir = output_dir.join(std::path::Path::new(&subdir_str));
         }
+        output_dir
+    }
+
+    fn get_named_path(
+        &self,
+        query: &Query,
+        partition: &Partition,
+        datatype: Datatype,
+        name: String,
+    ) -> Result<PathBuf, CollectError> {
+        let label = if self.single_file {
+            Partition::merge(&query.partitions).label(&query.partitioned_by)?
+        } else {
+            partition.label(&query.partitioned_by)?
+        };
+        let filename = if let Some(suffix) = self.suffix.clone() {
+            format!(
+                "{}__{}__{}__{}.{}",
+                self.prefix.clone(),
+                name,
+                suffix,
+                label,
+                self.format.as_str(),
+            )
+        } else {
+            format!("{}__{}__{}.{}", self.prefix.clone(), name, label, self.format.as_str(),)
+        };
+        let filename = std::path::Path::new(&filename).to_path_buf();
+        Ok(self.get_dir(datatype).join(filename))
+    }
+}
+
+/// create directory and its missing parents, applying mode to each directory created
+pub fn create_output_dir(path: &Path, mode: Option<u32>) -> Result<(), ParseError> {
+    let missing: Vec<&Path> = path.ancestors().take_while(|dir| !dir.exists()).collect();
+    std::fs::create_dir_all(path).map_err(|e| {
+        let hint = match e.kind() {
+            std::io::ErrorKind::PermissionDenied => ", check write access to its parent",
+            _ => "",
+        };
+        ParseError::ParseError(format!(
+            "could not create output directory {}: {}{}",
+            path.display(),
+            e,
+            hint
+        ))
+    })?;
+    if let Some(mode) = mode {
+        for dir in missing.into_iter() {
+            set_dir_mode(dir, mode)?;
+        }
+    }
+    Ok(())
+}
 
-        std::fs::create_dir_all(output_dir.clone())
-            .map_err(|_| ParseError::ParseError("could not create dir".to_string()))?;
+#[cfg(unix)]
+fn set_dir_mode(path: &Path, mode: u32) -> Result<(), ParseError> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).map_err(|e| {
+        ParseError::ParseError(format!(
+            "could not set permissions of output directory {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
 
-        Ok(output_dir.join(filename))
+#[cfg(not(unix))]
+fn set_dir_mode(_path: &Path, _mode: u32) -> Result<(), ParseError> {
+    Err(ParseError::ParseError("directory modes are only supported on unix".to_string()))
+}
+
+/// name of a chain used as the prefix of output files, e.g. `ethereum` for chain id 1
+pub fn network_name(chain_id: u64) -> String {
+    match chain_id {
+        1 => "ethereum".to_string(),
+        5 => "goerli".to_string(),
+        10 => "optimism".to_string(),
+        56 => "bnb".to_string(),
+        69 => "optimism_kovan".to_string(),
+        100 => "gnosis".to_string(),
+        137 => "polygon".to_string(),
+        420 => "optimism_goerli".to_string(),
+        1101 => "polygon_zkevm".to_string(),
+        1442 => "polygon_zkevm_testnet".to_string(),
+        8453 => "base".to_string(),
+        10200 => "gnosis_chidao".to_string(),
+        17000 => "holesky".to_string(),
+        42161 => "arbitrum".to_string(),
+        42170 => "arbitrum_nova".to_string(),
+        43114 => "avalanche".to_string(),
+        80001 => "polygon_mumbai".to_string(),
+        84531 => "base_goerli".to_string(),
+        7777777 => "zora".to_string(),
+        11155111 => "sepolia".to_string(),
+        chain_id => format!("chain-{}", chain_id),
     }
 }
 
@@ -126,7 +375,7 @@ impl FileFormat {
 }
 
 /// Encoding for binary data in a column
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
 pub enum ColumnEncoding {
     /// Raw binary encoding
     Binary,
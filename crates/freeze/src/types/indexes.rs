@@ -0,0 +1,153 @@
+use crate::{
+    err, inspect_parquet, ChunkData, ChunkStatus, CollectError, FileOutput, FreezeSummary,
+    ManifestChunk, ManifestV1, Partition, Query, CRYO_VERSION,
+};
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+/// file name of the index written to the output dir of a run
+pub const INDEX_FILENAME: &str = "index.json";
+
+/// summary of every datatype of a run, written to the top of the output dir after the run
+///
+/// a convenience over the metadata of each file, combining the manifest and schema of each
+/// datatype with the blocks and rows that its files cover
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RunIndex {
+    /// version of cryo that wrote the index
+    pub cryo_version: String,
+    /// chain id of the network the data was collected from
+    pub chain_id: u64,
+    /// entry of each datatype, by datatype name
+    pub datatypes: BTreeMap<String, IndexEntry>,
+}
+
+/// entry of a datatype in a RunIndex
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IndexEntry {
+    /// manifest of the chunks of the datatype, listing their files
+    pub manifest: ManifestV1,
+    /// JSON Schema of a row of the chunk files
+    pub schema: serde_json::Value,
+    /// lowest and highest block of the chunks that have files
+    pub block_range: Option<(u64, u64)>,
+    /// number of chunk files
+    pub n_files: u64,
+    /// rows of the chunk files, summed over the files whose row counts could be read
+    pub n_rows: u64,
+}
+
+impl RunIndex {
+    /// index the files of each datatype of a query, for the chunks in the summary of its run
+    ///
+    /// chunks of a run are not tracked per datatype, so a chunk with a file is completed unless it
+    /// was only skipped, and a chunk without a file is errored, cancelled, or written to the files
+    /// of its partition keys
+    pub fn from_run(
+        query: &Query,
+        sink: &FileOutput,
+        summary: &FreezeSummary,
+        chain_id: u64,
+    ) -> Result<RunIndex, CollectError> {
+        // partitions by label, each chunk is in the summary once per datatype
+        let mut partitions = BTreeMap::new();
+        let completed = add_labels(&summary.completed, query, &mut partitions)?;
+        let skipped = add_labels(&summary.skipped, query, &mut partitions)?;
+        let errored: Vec<Partition> =
+            summary.errored.iter().filter_map(|(partition, _)| partition.clone()).collect();
+        let errored = add_labels(&errored, query, &mut partitions)?;
+        add_labels(&summary.cancelled, query, &mut partitions)?;
+
+        let mut datatypes = BTreeMap::new();
+        for (datatype, schema) in query.schemas.iter() {
+            let mut manifest = ManifestV1::new(
+                chain_id,
+                datatype.name(),
+                schema.schema_hash_metadata().1,
+                datatype.schema_version(),
+            );
+            let mut block_range: Option<(u64, u64)> = None;
+            let mut n_rows = 0;
+            for (range, partition) in partitions.iter() {
+                let range = range.clone();
+                let path = sink.get_path(query, partition, *datatype)?;
+                let exists = path.exists();
+                let status = match exists {
+                    true if skipped.contains(&range) && !completed.contains(&range) => {
+                        ChunkStatus::Skipped
+                    }
+                    true => ChunkStatus::Completed,
+                    false if errored.contains(&range) => ChunkStatus::Errored,
+                    false if completed.contains(&range) => ChunkStatus::Completed,
+                    false => ChunkStatus::Cancelled,
+                };
+                let rows = if exists { read_n_rows(&path) } else { None };
+                let blocks = partition.block_numbers.as_ref().filter(|_| exists);
+                if let (Some(min), Some(max)) = (
+                    blocks.and_then(|chunks| chunks.min_value()),
+                    blocks.and_then(|chunks| chunks.max_value()),
+                ) {
+                    block_range = Some(match block_range {
+                        Some((lo, hi)) => (lo.min(min), hi.max(max)),
+                        None => (min, max),
+                    });
+                }
+                n_rows += rows.unwrap_or(0);
+                manifest.chunks.push(ManifestChunk { range, path, status, rows, checksum: None });
+            }
+            let n_files = manifest.chunks.iter().filter(|chunk| chunk.path.exists()).count();
+            let entry = IndexEntry {
+                manifest,
+                schema: schema.to_json_schema(),
+                block_range,
+                n_files: n_files as u64,
+                n_rows,
+            };
+            datatypes.insert(datatype.name(), entry);
+        }
+        Ok(RunIndex { cryo_version: CRYO_VERSION.to_string(), chain_id, datatypes })
+    }
+
+    /// serialize index to json
+    pub fn to_json(&self) -> Result<String, CollectError> {
+        serde_json::to_string_pretty(self).map_err(|_| err("could not serialize index"))
+    }
+}
+
+/// labels of partitions, adding each partition to the partitions of its label
+fn add_labels(
+    chunks: &[Partition],
+    query: &Query,
+    partitions: &mut BTreeMap<String, Partition>,
+) -> Result<HashSet<String>, CollectError> {
+    let mut labels = HashSet::new();
+    for partition in chunks.iter() {
+        let label = partition.label(&query.partitioned_by)?;
+        partitions.entry(label.clone()).or_insert_with(|| partition.clone());
+        labels.insert(label);
+    }
+    Ok(labels)
+}
+
+/// row count of a parquet file from its footer, other formats are not counted
+fn read_n_rows(path: &Path) -> Option<u64> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("parquet") => inspect_parquet(path.to_str()?).ok().map(|file| file.n_rows),
+        _ => None,
+    }
+}
+
+/// write the index of a run to the top of its output dir
+pub(crate) fn write_index(
+    query: &Query,
+    sink: &FileOutput,
+    summary: &FreezeSummary,
+    chain_id: u64,
+) -> Result<PathBuf, CollectError> {
+    let index = RunIndex::from_run(query, sink, summary, chain_id)?;
+    let path = sink.output_dir.join(INDEX_FILENAME);
+    std::fs::write(&path, index.to_json()?)?;
+    Ok(path)
+}
@@ -0,0 +1,157 @@
+use crate::ParseError;
+use std::path::PathBuf;
+
+/// version of the manifest format written by this version of cryo
+pub const MANIFEST_VERSION: u64 = 1;
+
+/// record of the chunks of a datatype, for use by external tools and by resuming runs
+///
+/// fields are only ever added to manifests of a version, and readers ignore fields that they do
+/// not know about, so manifests written by newer cryo versions of the same version still parse.
+/// a change that existing readers would misread gets a new version instead, which older readers
+/// reject with an error
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ManifestV1 {
+    /// version of the manifest format, always 1
+    pub version: u64,
+    /// chain id of the network the chunks were collected from
+    pub chain_id: u64,
+    /// name of the datatype of the chunks
+    pub datatype: String,
+    /// schema hash of the chunk files, as stored in their metadata
+    pub schema_hash: String,
+    /// schema version of the datatype, as stored in the metadata of the chunk files
+    ///
+    /// manifests written before schema versions were recorded have version 0
+    #[serde(default)]
+    pub schema_version: u32,
+    /// chunks of the datatype
+    #[serde(default)]
+    pub chunks: Vec<ManifestChunk>,
+}
+
+/// chunk of a manifest
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ManifestChunk {
+    /// label of the chunk range, as in its file name, e.g. `00000100_to_00000199`
+    pub range: String,
+    /// path of the chunk file
+    pub path: PathBuf,
+    /// outcome of collecting the chunk
+    pub status: ChunkStatus,
+    /// number of rows in the chunk file
+    #[serde(default)]
+    pub rows: Option<u64>,
+    /// checksum of the chunk file
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+/// outcome of collecting a chunk
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStatus {
+    /// chunk was written
+    Completed,
+    /// chunk file already existed
+    Skipped,
+    /// chunk failed
+    Errored,
+    /// chunk was not started or not finished because the run stopped
+    Cancelled,
+}
+
+impl ManifestV1 {
+    /// create manifest without chunks
+    pub fn new(chain_id: u64, datatype: String, schema_hash: String, schema_version: u32) -> Self {
+        ManifestV1 {
+            version: MANIFEST_VERSION,
+            chain_id,
+            datatype,
+            schema_hash,
+            schema_version,
+            chunks: vec![],
+        }
+    }
+
+    /// parse manifest from json, rejecting manifests of other versions
+    pub fn from_json(json: &str) -> Result<Self, ParseError> {
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| ParseError::ParseError(format!("invalid manifest: {}", e)))?;
+        match value.get("version").and_then(|version| version.as_u64()) {
+            Some(MANIFEST_VERSION) => {}
+            Some(version) => {
+                return Err(ParseError::ParseError(format!(
+                    "unsupported manifest version {}, this version of cryo reads version {}",
+                    version, MANIFEST_VERSION
+                )))
+            }
+            None => return Err(ParseError::ParseError("manifest has no version".to_string())),
+        }
+        serde_json::from_value(value)
+            .map_err(|e| ParseError::ParseError(format!("invalid manifest: {}", e)))
+    }
+
+    /// serialize manifest to json
+    pub fn to_json(&self) -> Result<String, ParseError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ParseError::ParseError(format!("could not serialize manifest: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_manifest() -> ManifestV1 {
+        let mut manifest =
+            ManifestV1::new(1, "logs".to_string(), "0123456789abcdef".to_string(), 1);
+        manifest.chunks = vec![
+            ManifestChunk {
+                range: "00000100_to_00000199".to_string(),
+                path: PathBuf::from("ethereum__logs__00000100_to_00000199.parquet"),
+                status: ChunkStatus::Completed,
+                rows: Some(42),
+                checksum: Some("abc".to_string()),
+            },
+            ManifestChunk {
+                range: "00000200_to_00000299".to_string(),
+                path: PathBuf::from("ethereum__logs__00000200_to_00000299.parquet"),
+                status: ChunkStatus::Errored,
+                rows: None,
+                checksum: None,
+            },
+        ];
+        manifest
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = make_manifest();
+        let json = manifest.to_json().unwrap();
+        assert!(json.contains(r#""status": "errored""#));
+        assert_eq!(ManifestV1::from_json(&json).unwrap(), manifest);
+
+        // fields added by later releases of version 1 are ignored
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["block_range"] = serde_json::json!([100, 299]);
+        value["chunks"][0]["n_bytes"] = serde_json::json!(1024);
+        assert_eq!(ManifestV1::from_json(&value.to_string()).unwrap(), manifest);
+
+        // manifests written before schema versions were recorded parse with version 0
+        value.as_object_mut().unwrap().remove("schema_version");
+        assert_eq!(ManifestV1::from_json(&value.to_string()).unwrap().schema_version, 0);
+    }
+
+    #[test]
+    fn test_manifest_rejects_other_versions() {
+        let mut value = serde_json::to_value(make_manifest()).unwrap();
+        value["version"] = serde_json::json!(2);
+        let error = ManifestV1::from_json(&value.to_string()).unwrap_err();
+        assert!(error.to_string().contains("unsupported manifest version 2"));
+
+        value.as_object_mut().unwrap().remove("version");
+        let error = ManifestV1::from_json(&value.to_string()).unwrap_err();
+        assert!(error.to_string().contains("manifest has no version"));
+    }
+}
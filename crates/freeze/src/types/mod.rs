@@ -38,16 +38,28 @@ pub mod dataframes;
 /// function and event signatures
 pub mod signatures;
 
+/// transaction logs of delta tables
+pub mod delta_log;
 /// error specifications
 pub mod errors;
 /// type specifications for output data formats
 pub mod files;
+/// combined indexes of the datatypes written by a run
+pub mod indexes;
+/// versioned manifests of collected chunks
+pub mod manifests;
 /// queries
 pub mod queries;
+/// exports of schemas to other schema languages
+pub mod schema_exports;
 /// type specifications for data schemas
 pub mod schemas;
 /// types related to summaries
 pub mod summaries;
+/// post-collection transformations
+pub mod transforms;
+/// destinations of collected data other than files
+pub mod writers;
 
 pub use chunks::{
     AddressChunk, BlockChunk, CallDataChunk, Chunk, ChunkData, ChunkStats, SlotChunk, Subchunk,
@@ -56,10 +68,23 @@ pub use chunks::{
 pub use conversions::{bytes_to_u32, ToVecHex, ToVecU8};
 pub use dataframes::*;
 pub use datatypes::*;
-pub use files::{ColumnEncoding, FileFormat, FileOutput, SubDir};
-pub use queries::{Query, QueryLabels, TimeDimension};
-pub use schemas::{ColumnType, SchemaFunctions, Schemas, Table, U256Type};
-pub use sources::{Fetcher, RateLimiter, Source, SourceLabels};
+pub use delta_log::{delta_schema, DELTA_LOG_DIR};
+pub use files::{
+    create_output_dir, network_name, ColumnEncoding, FileFormat, FileOutput, SubDir, TimestampRange,
+};
+pub use indexes::{IndexEntry, RunIndex, INDEX_FILENAME};
+pub use manifests::{ChunkStatus, ManifestChunk, ManifestV1, MANIFEST_VERSION};
+pub use queries::{LogsBackend, Query, QueryLabels, TimeDimension, TraceBackend, TraceGranularity};
+pub use schema_exports::SchemaFormat;
+pub use schemas::{
+    ColumnType, OverflowPolicy, SchemaError, SchemaFunctions, Schemas, Table, TableBuilder,
+    U256Type,
+};
+pub use sources::{Fetcher, HeadGap, RateLimiter, Source, SourceLabels};
+pub use transforms::RowTransform;
+pub use writers::DataWriter;
+#[cfg(any(test, feature = "test-util"))]
+pub use writers::{MemoryWriter, WrittenFrame};
 // pub(crate) use summaries::FreezeSummaryAgg;
 // pub use summaries::{FreezeChunkSummary, FreezeSummary};
 pub use summaries::{print_all_datasets, print_dataset_info, FreezeSummary};
@@ -67,7 +92,7 @@ pub use summaries::{print_all_datasets, print_dataset_info, FreezeSummary};
 pub use errors::{err, ChunkError, CollectError, FileError, FreezeError, ParseError, R};
 
 pub use collection::*;
-pub use execution::{ExecutionEnv, ExecutionEnvBuilder};
+pub use execution::{Checkpoint, ExecutionEnv, ExecutionEnvBuilder, RowLimit};
 
 pub use signatures::*;
 
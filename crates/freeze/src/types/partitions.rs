@@ -238,6 +238,19 @@ macro_rules! label_partition {
     }};
 }
 
+/// merge chunks of partitions along a dimension
+macro_rules! merge {
+    ($partitions:expr, $key:ident) => {{
+        let chunks: Vec<_> =
+            $partitions.iter().filter_map(|partition| partition.$key.clone()).flatten().collect();
+        if chunks.is_empty() {
+            None
+        } else {
+            Some(chunks)
+        }
+    }};
+}
+
 fn chunks_to_name<T: ChunkData>(chunks: &Option<Vec<T>>) -> Result<String, CollectError> {
     chunks
         .as_ref()
@@ -247,6 +260,39 @@ fn chunks_to_name<T: ChunkData>(chunks: &Option<Vec<T>>) -> Result<String, Colle
 }
 
 impl Partition {
+    /// merge partitions into one partition spanning all of their chunks
+    pub fn merge(partitions: &[Partition]) -> Partition {
+        Partition {
+            label: None,
+            block_numbers: merge!(partitions, block_numbers),
+            transactions: merge!(partitions, transactions),
+            call_datas: merge!(partitions, call_datas),
+            addresses: merge!(partitions, addresses),
+            contracts: merge!(partitions, contracts),
+            from_addresses: merge!(partitions, from_addresses),
+            to_addresses: merge!(partitions, to_addresses),
+            slots: merge!(partitions, slots),
+            topic0s: merge!(partitions, topic0s),
+            topic1s: merge!(partitions, topic1s),
+            topic2s: merge!(partitions, topic2s),
+            topic3s: merge!(partitions, topic3s),
+        }
+    }
+
+    /// key-value pairs of the block range of partition, stored in file metadata
+    ///
+    /// partitions without block numbers have no block range
+    pub fn block_range_metadata(&self) -> Vec<(String, String)> {
+        let Some(block_numbers) = &self.block_numbers else { return vec![] };
+        match (block_numbers.min_value(), block_numbers.max_value()) {
+            (Some(min_block), Some(max_block)) => vec![
+                ("cryo_min_block".to_string(), min_block.to_string()),
+                ("cryo_max_block".to_string(), max_block.to_string()),
+            ],
+            _ => vec![],
+        }
+    }
+
     /// get label of partition
     pub fn label_pieces(&self, partitioned_by: &[Dim]) -> Result<Vec<String>, CollectError> {
         let stored_pieces = self.label.clone().unwrap_or_else(|| vec![None; partitioned_by.len()]);
@@ -1,4 +1,5 @@
-use crate::{CollectError, Datatype, Dim, MetaDatatype, Partition, Table};
+use crate::{ChunkData, CollectError, Datatype, Dim, MetaDatatype, Partition, Table};
+use ethers::types::Filter;
 use std::collections::{HashMap, HashSet};
 
 /// Query
@@ -18,6 +19,22 @@ pub struct Query {
     pub exclude_failed: bool,
     /// Javascript tracer
     pub js_tracer: Option<String>,
+    /// eth_getLogs filter supplied by the user, merged into the filter of each chunk
+    pub log_filter: Option<Filter>,
+    /// eth_getLogs filter that restricts Blocks to block numbers with at least one matching log
+    pub blocks_log_filter: Option<Filter>,
+    /// Drop logs whose block hash is no longer canonical, e.g. after a reorg
+    pub deduplicate_logs: bool,
+    /// Drop rows whose value column is zero
+    pub exclude_zero_value: bool,
+    /// Granularity of trace requests
+    pub trace_granularity: TraceGranularity,
+    /// RPC methods used for block traces
+    pub trace_backend: TraceBackend,
+    /// RPC methods used for logs
+    pub logs_backend: LogsBackend,
+    /// Genesis file read by genesis_allocations
+    pub genesis_path: Option<String>,
     /// Labels (these are non-functional)
     pub labels: QueryLabels,
 }
@@ -29,6 +46,8 @@ pub struct QueryLabels {
     pub align: bool,
     /// reorg buffer
     pub reorg_buffer: u64,
+    /// block numbers excluded from collection
+    pub skipped_blocks: Vec<u64>,
 }
 
 impl Query {
@@ -39,7 +58,19 @@ impl Query {
 
     /// total number of outputs of query
     pub fn n_outputs(&self) -> usize {
-        self.datatypes.iter().map(|x| x.datatypes().len()).sum::<usize>() * self.partitions.len()
+        self.datatypes.iter().map(|x| self.datatypes_of(x).len()).sum::<usize>() *
+            self.partitions.len()
+    }
+
+    /// datatypes of a meta datatype that the query collects
+    ///
+    /// a multi datatype can have members that were not requested, which have no schema
+    pub fn datatypes_of(&self, meta_datatype: &MetaDatatype) -> Vec<Datatype> {
+        meta_datatype
+            .datatypes()
+            .into_iter()
+            .filter(|datatype| self.schemas.contains_key(datatype))
+            .collect()
     }
 
     /// check that query is valid
@@ -47,7 +78,7 @@ impl Query {
         // check that required parameters are present
         let mut all_datatypes = std::collections::HashSet::new();
         for datatype in self.datatypes.iter() {
-            all_datatypes.extend(datatype.datatypes())
+            all_datatypes.extend(self.datatypes_of(datatype))
         }
         let mut requirements: HashSet<Dim> = HashSet::new();
         for datatype in all_datatypes.iter() {
@@ -66,6 +97,53 @@ impl Query {
                 )))
             }
         }
+
+        // zero values can only be excluded from datatypes that collect a value column
+        if self.exclude_zero_value {
+            for datatype in all_datatypes.iter() {
+                if !self.schemas.get(datatype).is_some_and(|schema| schema.has_column("value")) {
+                    return Err(CollectError::CollectError(format!(
+                        "--exclude-zero-value requires a value column, {} has none",
+                        datatype.name()
+                    )))
+                }
+            }
+        }
+
+        // mempool snapshots and genesis allocations are read once, so repeating them for every
+        // block would be a mistake
+        for datatype in [Datatype::PendingTransactions, Datatype::GenesisAllocations] {
+            if !all_datatypes.contains(&datatype) {
+                continue
+            }
+            let n_blocks: u64 = self
+                .partitions
+                .iter()
+                .flat_map(|partition| partition.block_numbers.iter().flatten())
+                .map(|chunk| chunk.size())
+                .sum();
+            if n_blocks > 1 {
+                return Err(CollectError::CollectError(format!(
+                    "{} is not collected by block range, collect it for a single block, e.g. \
+                     --blocks {}",
+                    datatype.name(),
+                    datatype.default_blocks().unwrap_or_default()
+                )))
+            }
+        }
+
+        // block columns are joined in by block number
+        for (datatype, schema) in self.schemas.iter() {
+            for joined in datatype.joinable_block_columns() {
+                if schema.has_column(joined) && !schema.has_column("block_number") {
+                    return Err(CollectError::CollectError(format!(
+                        "{} requires the block_number column, which {} excludes",
+                        joined,
+                        datatype.name()
+                    )))
+                }
+            }
+        }
         Ok(())
     }
 }
@@ -78,3 +156,35 @@ pub enum TimeDimension {
     /// Transactions
     Transactions,
 }
+
+/// Granularity of trace requests
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TraceGranularity {
+    /// one trace_block request per block
+    #[default]
+    Block,
+    /// one trace_transaction request per transaction
+    Transaction,
+}
+
+/// RPC methods used to collect block traces
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum TraceBackend {
+    /// OpenEthereum-style trace_block / trace_transaction
+    #[default]
+    Parity,
+    /// debug_traceBlockByNumber with callTracer
+    Geth,
+}
+
+/// RPC methods used to collect logs
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LogsBackend {
+    /// eth_getLogs
+    #[default]
+    GetLogs,
+    /// eth_newFilter once per filter, then eth_getFilterLogs
+    Filter,
+    /// eth_getLogs pages of at most this many logs, following the page key of each page
+    Paginated(u64),
+}
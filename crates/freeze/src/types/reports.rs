@@ -1,6 +1,8 @@
-use crate::{err, CollectError, ExecutionEnv, FileOutput, FreezeSummary, Query};
+use crate::{CollectError, ExecutionEnv, FileOutput, FreezeSummary, Query, TimestampRange};
 use chrono::{DateTime, Local};
+use indexmap::IndexSet;
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::Write,
     path::{Path, PathBuf},
@@ -20,6 +22,14 @@ struct SerializedFreezeSummary {
     completed_paths: Vec<PathBuf>,
     errored_paths: Vec<PathBuf>,
     n_skipped: u64,
+    n_cancelled: u64,
+    // blocks excluded by --skip-blocks
+    skipped_blocks: Vec<u64>,
+    timestamp_ranges: BTreeMap<PathBuf, TimestampRange>,
+    // schema hash of each datatype, as stored in file metadata
+    schema_hashes: BTreeMap<String, String>,
+    // schema version of each datatype, as stored in file metadata
+    schema_versions: BTreeMap<String, u32>,
 }
 
 pub(crate) fn get_report_path(
@@ -53,6 +63,35 @@ pub(crate) fn write_report(
     query: &Query,
     sink: &FileOutput,
     freeze_summary: Option<&FreezeSummary>,
+) -> Result<PathBuf, CollectError> {
+    let is_complete = freeze_summary.is_some();
+    let path = write_report_file(env, query, sink, freeze_summary, is_complete)?;
+
+    // delete initial report
+    if is_complete {
+        let incomplete_path = get_report_path(env, sink, false)?;
+        std::fs::remove_file(incomplete_path)?;
+    }
+
+    Ok(path)
+}
+
+/// overwrite the initial report with the progress of a run that is still in flight
+pub(crate) fn write_checkpoint(
+    env: &ExecutionEnv,
+    query: &Query,
+    sink: &FileOutput,
+    freeze_summary: &FreezeSummary,
+) -> Result<PathBuf, CollectError> {
+    write_report_file(env, query, sink, Some(freeze_summary), false)
+}
+
+fn write_report_file(
+    env: &ExecutionEnv,
+    query: &Query,
+    sink: &FileOutput,
+    freeze_summary: Option<&FreezeSummary>,
+    is_complete: bool,
 ) -> Result<PathBuf, CollectError> {
     // determine version
     let cryo_version = CRYO_VERSION.to_string();
@@ -70,7 +109,7 @@ pub(crate) fn write_report(
         .map_err(|_| CollectError::CollectError("could not serialize report".to_string()))?;
 
     // create path
-    let path = get_report_path(env, sink, freeze_summary.is_some())?;
+    let path = get_report_path(env, sink, is_complete)?;
 
     // save to file
     let mut file = File::create(&path)
@@ -78,13 +117,6 @@ pub(crate) fn write_report(
     file.write_all(serialized.as_bytes())
         .map_err(|_| CollectError::CollectError("could not write report data".to_string()))?;
 
-    // delete initial report
-    if freeze_summary.is_some() {
-        let incomplete_path = get_report_path(env, sink, false)?;
-        std::fs::remove_file(incomplete_path)
-            .map_err(|_| err("could not delete initial report file"))?;
-    }
-
     Ok(path)
 }
 
@@ -103,6 +135,8 @@ fn serialize_summary(
         .collect::<Result<Vec<_>, _>>()?
         .into_iter()
         .flatten()
+        .collect::<IndexSet<_>>()
+        .into_iter()
         .collect();
 
     let errored_paths: Vec<PathBuf> = summary
@@ -117,12 +151,27 @@ fn serialize_summary(
         .collect::<Result<Vec<_>, _>>()?
         .into_iter()
         .flatten()
+        .collect::<IndexSet<_>>()
+        .into_iter()
         .collect();
 
     Ok(SerializedFreezeSummary {
         completed_paths,
         errored_paths,
         n_skipped: summary.skipped.len() as u64,
+        n_cancelled: summary.cancelled.len() as u64,
+        skipped_blocks: query.labels.skipped_blocks.clone(),
+        timestamp_ranges: summary.timestamp_ranges.clone().into_iter().collect(),
+        schema_hashes: query
+            .schemas
+            .iter()
+            .map(|(datatype, schema)| (datatype.name(), schema.schema_hash_metadata().1))
+            .collect(),
+        schema_versions: query
+            .schemas
+            .keys()
+            .map(|datatype| (datatype.name(), datatype.schema_version()))
+            .collect(),
     })
 }
 
@@ -109,4 +109,63 @@ impl Params {
         };
         Ok(filter)
     }
+
+    /// log filter, with the addresses and topics of a user supplied filter where not given
+    pub fn ethers_log_filter_merged(
+        &self,
+        user_filter: &Option<Filter>,
+    ) -> Result<Filter, CollectError> {
+        let filter = self.ethers_log_filter()?;
+        let Some(user_filter) = user_filter else { return Ok(filter) };
+        let mut topics = filter.topics;
+        for (topic, user_topic) in topics.iter_mut().zip(user_filter.topics.iter()) {
+            if topic.is_none() {
+                *topic = user_topic.clone();
+            }
+        }
+        Ok(Filter {
+            block_option: filter.block_option,
+            address: filter.address.or_else(|| user_filter.address.clone()),
+            topics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_log_filter_is_merged_with_chunk_range() {
+        let user_filter: Filter = serde_json::from_str(
+            r#"{
+                "address": [
+                    "0x1111111111111111111111111111111111111111",
+                    "0x2222222222222222222222222222222222222222"
+                ],
+                "topics": [
+                    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                    null,
+                    ["0x0000000000000000000000003333333333333333333333333333333333333333"]
+                ]
+            }"#,
+        )
+        .unwrap();
+        let params = Params { block_range: Some((100, 199)), ..Default::default() };
+
+        let filter = params.ethers_log_filter_merged(&Some(user_filter.clone())).unwrap();
+        assert_eq!(
+            filter.block_option,
+            FilterBlockOption::Range { from_block: Some(100.into()), to_block: Some(199.into()) }
+        );
+        assert_eq!(filter.address, user_filter.address);
+        assert_eq!(filter.topics, user_filter.topics);
+
+        // parameters given to cryo are kept
+        let topic0 = vec![0xaa; 32];
+        let params = Params { topic0: Some(topic0.clone()), ..params };
+        let filter = params.ethers_log_filter_merged(&Some(user_filter.clone())).unwrap();
+        assert_eq!(filter.topics[0], Some(ValueOrArray::Value(Some(H256::from_slice(&topic0)))));
+        assert_eq!(filter.topics[2], user_filter.topics[2]);
+    }
 }
@@ -0,0 +1,264 @@
+use crate::{ColumnType, ParseError, Table};
+use serde_json::{json, Value};
+
+/// schema language that a Table can be exported to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SchemaFormat {
+    /// JSON Schema of each row
+    JsonSchema,
+    /// Avro record schema
+    Avro,
+    /// parquet message type of the output files
+    ParquetMetadata,
+}
+
+impl std::str::FromStr for SchemaFormat {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<SchemaFormat, ParseError> {
+        match s {
+            "json-schema" => Ok(SchemaFormat::JsonSchema),
+            "avro" => Ok(SchemaFormat::Avro),
+            "parquet-metadata" => Ok(SchemaFormat::ParquetMetadata),
+            _ => Err(ParseError::ParseError(format!(
+                "invalid schema format: {}, valid formats are: json-schema, avro, parquet-metadata",
+                s
+            ))),
+        }
+    }
+}
+
+impl Table {
+    /// render the columns of output files in a schema language
+    pub fn export_schema(&self, format: SchemaFormat) -> String {
+        match format {
+            SchemaFormat::JsonSchema => pretty(&self.to_json_schema()),
+            SchemaFormat::Avro => pretty(&self.to_avro_schema()),
+            SchemaFormat::ParquetMetadata => self.to_parquet_message(),
+        }
+    }
+
+    /// JSON Schema of a row, every column is nullable
+    pub fn to_json_schema(&self) -> Value {
+        let properties: serde_json::Map<String, Value> = self
+            .output_columns()
+            .into_iter()
+            .map(|(name, column_type)| {
+                let (json_type, format) = json_schema_type(column_type);
+                (name, json!({ "type": [json_type, "null"], "format": format }))
+            })
+            .collect();
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": self.datatype.name(),
+            "type": "object",
+            "properties": properties,
+        })
+    }
+
+    /// Avro record schema, every field is a union with null
+    ///
+    /// Avro has no unsigned integers, so uint32 and uint64 columns are both mapped to long
+    pub fn to_avro_schema(&self) -> Value {
+        let fields: Vec<Value> = self
+            .output_columns()
+            .into_iter()
+            .map(|(name, column_type)| {
+                json!({ "name": name, "type": ["null", avro_type(column_type)], "default": null })
+            })
+            .collect();
+        json!({
+            "type": "record",
+            "name": self.datatype.name(),
+            "namespace": "cryo",
+            "fields": fields,
+        })
+    }
+
+    /// parquet message type, in the text format printed by parquet tools
+    pub fn to_parquet_message(&self) -> String {
+        let mut message = format!("message {} {{\n", self.datatype.name());
+        for (name, column_type) in self.output_columns() {
+            let (physical_type, logical_type) = parquet_type(column_type);
+            match logical_type {
+                // lists use the three-level layout, with their elements typed as above
+                Some(logical_type) if column_type == ColumnType::UInt32List => {
+                    message.push_str(&format!(
+                        "  optional group {} (LIST) {{\n    repeated group list {{\n      optional {} element ({});\n    }}\n  }}\n",
+                        name, physical_type, logical_type
+                    ))
+                }
+                Some(logical_type) => message.push_str(&format!(
+                    "  optional {} {} ({});\n",
+                    physical_type, name, logical_type
+                )),
+                None => message.push_str(&format!("  optional {} {};\n", physical_type, name)),
+            }
+        }
+        message.push('}');
+        message
+    }
+
+    /// columns as written to output files, with u256 columns split by u256 type
+    pub(crate) fn output_columns(&self) -> Vec<(String, ColumnType)> {
+        let columns = self.unordered_output_columns();
+        match &self.column_order {
+            Some(order) => order
+                .iter()
+                .filter_map(|name| columns.iter().find(|(column, _)| column == name).cloned())
+                .collect(),
+            None => columns,
+        }
+    }
+
+    fn unordered_output_columns(&self) -> Vec<(String, ColumnType)> {
+        let mut columns = Vec::new();
+        for column in self.columns() {
+            match self.column_type(column) {
+                Some(ColumnType::UInt256) => {
+                    for u256_type in self.u256_types.iter() {
+                        columns.push((
+                            column.to_owned() + u256_type.suffix().as_str(),
+                            u256_type.to_columntype(),
+                        ))
+                    }
+                }
+                Some(column_type) => columns.push((column.to_string(), column_type)),
+                None => {}
+            }
+        }
+        columns
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn json_schema_type(column_type: ColumnType) -> (&'static str, &'static str) {
+    match column_type {
+        ColumnType::Boolean => ("boolean", "bool"),
+        ColumnType::UInt32 => ("integer", "uint32"),
+        ColumnType::UInt64 => ("integer", "uint64"),
+        ColumnType::UInt256 => ("string", "uint256"),
+        ColumnType::Int32 => ("integer", "int32"),
+        ColumnType::Int64 => ("integer", "int64"),
+        ColumnType::Float32 => ("number", "float"),
+        ColumnType::Float64 => ("number", "double"),
+        ColumnType::Decimal128 => ("number", "decimal128"),
+        ColumnType::String => ("string", "string"),
+        ColumnType::Binary => ("string", "binary"),
+        ColumnType::Hex => ("string", "hex"),
+        ColumnType::UInt32List => ("array", "uint32"),
+    }
+}
+
+fn avro_type(column_type: ColumnType) -> Value {
+    match column_type {
+        ColumnType::Boolean => json!("boolean"),
+        ColumnType::Int32 => json!("int"),
+        ColumnType::UInt32 | ColumnType::UInt64 | ColumnType::Int64 => json!("long"),
+        ColumnType::Float32 => json!("float"),
+        ColumnType::Float64 => json!("double"),
+        ColumnType::Decimal128 => {
+            json!({ "type": "bytes", "logicalType": "decimal", "precision": 38, "scale": 0 })
+        }
+        ColumnType::String | ColumnType::Hex | ColumnType::UInt256 => json!("string"),
+        ColumnType::Binary => json!("bytes"),
+        ColumnType::UInt32List => json!({ "type": "array", "items": "long" }),
+    }
+}
+
+fn parquet_type(column_type: ColumnType) -> (&'static str, Option<&'static str>) {
+    match column_type {
+        ColumnType::Boolean => ("boolean", None),
+        ColumnType::UInt32 => ("int32", Some("INTEGER(32,false)")),
+        ColumnType::UInt64 => ("int64", Some("INTEGER(64,false)")),
+        ColumnType::Int32 => ("int32", None),
+        ColumnType::Int64 => ("int64", None),
+        ColumnType::Float32 => ("float", None),
+        ColumnType::Float64 => ("double", None),
+        ColumnType::Decimal128 => ("fixed_len_byte_array(16)", Some("DECIMAL(38,0)")),
+        ColumnType::String | ColumnType::Hex | ColumnType::UInt256 => ("binary", Some("STRING")),
+        ColumnType::Binary => ("binary", None),
+        ColumnType::UInt32List => ("int32", Some("INTEGER(32,false)")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnEncoding, Datatype, U256Type};
+
+    fn blocks_schema() -> Table {
+        let columns = Some(vec![
+            "block_number".to_string(),
+            "block_hash".to_string(),
+            "total_difficulty".to_string(),
+        ]);
+        let u256_types = [U256Type::Binary, U256Type::F64];
+        Datatype::Blocks
+            .table_schema(
+                &u256_types,
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &columns,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_blocks_json_schema() {
+        let schema = blocks_schema().to_json_schema();
+        assert_eq!(schema["title"], "blocks");
+        let properties = schema["properties"].as_object().unwrap();
+        let mut names: Vec<&str> = properties.keys().map(|name| name.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["block_hash", "block_number", "total_difficulty_binary", "total_difficulty_f64"]
+        );
+        assert_eq!(
+            properties["block_number"],
+            json!({ "type": ["integer", "null"], "format": "uint32" })
+        );
+        assert_eq!(
+            properties["block_hash"],
+            json!({ "type": ["string", "null"], "format": "hex" })
+        );
+        assert_eq!(properties["total_difficulty_binary"]["format"], "binary");
+        assert_eq!(properties["total_difficulty_f64"]["type"], json!(["number", "null"]));
+    }
+
+    #[test]
+    fn test_blocks_avro_schema() {
+        let schema = blocks_schema().to_avro_schema();
+        assert_eq!(schema["type"], "record");
+        assert_eq!(schema["name"], "blocks");
+        let types: Vec<(&str, &Value)> = schema["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|field| (field["name"].as_str().unwrap(), &field["type"][1]))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("block_number", &json!("long")),
+                ("block_hash", &json!("string")),
+                ("total_difficulty_binary", &json!("bytes")),
+                ("total_difficulty_f64", &json!("double")),
+            ]
+        );
+        assert!(schema["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|field| field["default"].is_null()));
+    }
+}
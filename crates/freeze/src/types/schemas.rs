@@ -1,8 +1,12 @@
 /// types and functions related to schemas
 use std::collections::HashMap;
 
-use crate::{err, CollectError, ColumnEncoding, Datatype, LogDecoder};
+use crate::{
+    err, CallOutputDecoder, CollectError, ColumnEncoding, ComputedColumn, Datatype, FileFormat,
+    LogDecoder, ParseError,
+};
 use indexmap::{IndexMap, IndexSet};
+use polars::export::arrow::datatypes::{ArrowDataType, ArrowSchema};
 use thiserror::Error;
 
 /// collection of schemas
@@ -21,7 +25,7 @@ impl SchemaFunctions for HashMap<Datatype, Table> {
 }
 
 /// Schema for a particular table
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Table {
     columns: IndexMap<String, ColumnType>,
 
@@ -31,6 +35,11 @@ pub struct Table {
     /// sort order for rows
     pub sort_columns: Option<Vec<String>>,
 
+    /// order of rows within output files when it differs from the sort columns, which are then
+    /// the key of rows: rows with the same key keep only their first row
+    #[serde(default)]
+    pub file_order: Option<Vec<String>>,
+
     /// representations to use for u256 columns
     pub u256_types: Vec<U256Type>,
 
@@ -39,6 +48,24 @@ pub struct Table {
 
     /// log decoder for table
     pub log_decoder: Option<LogDecoder>,
+
+    /// handling of u256 values that do not fit a narrower u256 type
+    pub overflow_policy: OverflowPolicy,
+
+    /// priority fee percentiles to collect, each adds a reward column to gas_fees
+    pub reward_percentiles: Vec<f64>,
+
+    /// order of the columns written to output files, set by matching an existing file
+    #[serde(default)]
+    pub column_order: Option<Vec<String>>,
+
+    /// columns computed from other columns after collection, in the order they are added
+    #[serde(default)]
+    pub computed_columns: Vec<ComputedColumn>,
+
+    /// decoder of eth_call outputs, adds a decoded output column to eth_calls
+    #[serde(default)]
+    pub call_output_decoder: Option<CallOutputDecoder>,
 }
 
 impl Table {
@@ -56,10 +83,229 @@ impl Table {
     pub fn columns(&self) -> Vec<&str> {
         self.columns.keys().map(|x| x.as_str()).collect()
     }
+
+    /// iterate over the columns of Table and their ColumnTypes, in column order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, ColumnType)> {
+        self.columns.iter().map(|(name, column_type)| (name.as_str(), *column_type))
+    }
+
+    /// get sort order for rows
+    pub fn sort_columns(&self) -> Option<&[String]> {
+        self.sort_columns.as_deref()
+    }
+
+    /// get order of rows within output files, the sort columns unless a file order is set
+    pub fn row_order(&self) -> Option<&[String]> {
+        self.file_order.as_deref().or(self.sort_columns())
+    }
+
+    /// get key that rows are deduplicated on, the sort columns when a file order is set
+    pub fn row_key(&self) -> Option<&[String]> {
+        self.file_order.as_ref().and(self.sort_columns())
+    }
+
+    /// stable hash of the names, types, and order of the columns written to output files
+    ///
+    /// sort order and other options that do not change the columns of files are left out, so
+    /// files sorted differently still have the same hash and can be appended to one another
+    pub fn schema_hash(&self) -> u64 {
+        // FNV-1a, which unlike std's hasher is stable across rust versions
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for (name, column_type) in self.output_columns() {
+            for byte in format!("{}:{};", name, column_type.as_str()).bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+
+    /// key-value pair of the schema hash, stored in file metadata
+    pub fn schema_hash_metadata(&self) -> (String, String) {
+        ("cryo_schema_hash".to_string(), format!("{:016x}", self.schema_hash()))
+    }
+
+    /// key-value pair of the schema version of the datatype, stored in file metadata
+    pub fn schema_version_metadata(&self) -> (String, String) {
+        ("cryo_schema_version".to_string(), self.datatype.schema_version().to_string())
+    }
+
+    /// key-value pairs describing the schema, stored in file metadata
+    pub fn schema_metadata(&self) -> Vec<(String, String)> {
+        vec![self.schema_hash_metadata(), self.schema_version_metadata()]
+    }
+
+    /// set priority fee percentiles, replacing the reward columns of a gas_fees schema
+    pub fn set_reward_percentiles(&mut self, percentiles: Vec<f64>) {
+        if self.datatype != Datatype::GasFees {
+            return
+        }
+        self.columns.retain(|name, _| !name.starts_with("reward_p"));
+        for percentile in percentiles.iter() {
+            self.columns.insert(crate::reward_column_name(*percentile), ColumnType::UInt64);
+        }
+        self.reward_percentiles = percentiles;
+    }
+
+    /// set decoder of call outputs, adding its decoded column to an eth_calls schema
+    pub fn set_call_output_decoder(&mut self, decoder: CallOutputDecoder) {
+        if self.datatype != Datatype::EthCalls {
+            return
+        }
+        if let Some(previous) = &self.call_output_decoder {
+            self.columns.shift_remove(&previous.column_name());
+        }
+        self.columns.insert(decoder.column_name(), decoder.column_type(&self.binary_type));
+        self.call_output_decoder = Some(decoder);
+    }
+
+    /// add a computed column, typed from the columns of its expression
+    pub fn add_computed_column(&mut self, column: ComputedColumn) -> Result<(), SchemaError> {
+        if self.has_column(&column.name) {
+            return Err(SchemaError::DuplicateColumn(column.name))
+        }
+        let column_type = column.expression.column_type(self)?;
+        self.columns.insert(column.name.clone(), column_type);
+        self.computed_columns.push(column);
+        Ok(())
+    }
+
+    /// order output columns like the columns of an existing file, so that new files can be
+    /// appended to it
+    ///
+    /// errors if the file does not have exactly the output columns of the Table, or if one of its
+    /// columns has a type that the Table does not write
+    pub fn match_schema(&mut self, reference: &ArrowSchema) -> Result<(), SchemaError> {
+        self.column_order = None;
+        let output_columns: IndexMap<String, ColumnType> =
+            self.output_columns().into_iter().collect();
+        let mut order = Vec::new();
+        for field in reference.fields.iter() {
+            let Some(column_type) = output_columns.get(&field.name) else {
+                return Err(SchemaError::IncompatibleSchema(format!(
+                    "{} is not a column of {}",
+                    field.name,
+                    self.datatype.name()
+                )))
+            };
+            if !column_type.is_compatible_with(&field.data_type) {
+                return Err(SchemaError::IncompatibleSchema(format!(
+                    "column {} is {:?} in the reference file but {} in {}",
+                    field.name,
+                    field.data_type,
+                    column_type.as_str(),
+                    self.datatype.name()
+                )))
+            }
+            order.push(field.name.clone());
+        }
+        let missing: Vec<&str> = output_columns
+            .keys()
+            .filter(|name| !order.contains(name))
+            .map(|name| name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(SchemaError::IncompatibleSchema(format!(
+                "reference file has no column {}",
+                missing.join(", ")
+            )))
+        }
+        self.column_order = Some(order);
+        Ok(())
+    }
+
+    /// store trace_address as a list of positions, or as a dot separated string like "0.1.2"
+    ///
+    /// only parquet files store lists, other formats always use strings
+    pub fn set_trace_address_type(&mut self, format: &FileFormat, as_string: bool) {
+        let column_type = match as_string || (*format != FileFormat::Parquet) {
+            true => ColumnType::String,
+            false => ColumnType::UInt32List,
+        };
+        if let Some(current) = self.columns.get_mut("trace_address") {
+            *current = column_type;
+        }
+    }
+}
+
+/// build Table using builder pattern, for schemas that do not come from a Datatype's columns
+pub struct TableBuilder {
+    datatype: Datatype,
+    columns: Vec<(String, ColumnType)>,
+    sort_columns: Option<Vec<String>>,
+    u256_types: Vec<U256Type>,
+    binary_type: ColumnEncoding,
+}
+
+impl TableBuilder {
+    /// initialize TableBuilder for a datatype
+    pub fn new(datatype: Datatype) -> Self {
+        TableBuilder {
+            datatype,
+            columns: Vec::new(),
+            sort_columns: None,
+            u256_types: vec![U256Type::Binary, U256Type::String, U256Type::F64],
+            binary_type: ColumnEncoding::Binary,
+        }
+    }
+
+    /// add column, columns are kept in the order they are added
+    pub fn add_column(mut self, name: &str, column_type: ColumnType) -> Self {
+        self.columns.push((name.to_string(), column_type));
+        self
+    }
+
+    /// sort rows by columns
+    pub fn sort_by(mut self, columns: Vec<String>) -> Self {
+        self.sort_columns = Some(columns);
+        self
+    }
+
+    /// representations to use for u256 columns
+    pub fn u256_types(mut self, u256_types: Vec<U256Type>) -> Self {
+        self.u256_types = u256_types;
+        self
+    }
+
+    /// representation to use for binary columns
+    pub fn binary_type(mut self, binary_type: ColumnEncoding) -> Self {
+        self.binary_type = binary_type;
+        self
+    }
+
+    /// build final output
+    pub fn build(self) -> Result<Table, SchemaError> {
+        let mut columns = IndexMap::new();
+        for (name, column_type) in self.columns.into_iter() {
+            if columns.contains_key(&name) {
+                return Err(SchemaError::DuplicateColumn(name))
+            }
+            columns.insert(name, column_type);
+        }
+        if let Some(sort_columns) = &self.sort_columns {
+            if sort_columns.iter().any(|column| !columns.contains_key(column)) {
+                return Err(SchemaError::InvalidColumn)
+            }
+        }
+        Ok(Table {
+            columns,
+            datatype: self.datatype,
+            sort_columns: self.sort_columns,
+            file_order: None,
+            u256_types: self.u256_types,
+            binary_type: self.binary_type,
+            log_decoder: None,
+            overflow_policy: OverflowPolicy::default(),
+            reward_percentiles: Vec::new(),
+            column_order: None,
+            computed_columns: Vec::new(),
+            call_output_decoder: None,
+        })
+    }
 }
 
 /// representation of a U256 datum
-#[derive(Hash, Clone, Debug, Eq, PartialEq)]
+#[derive(Hash, Clone, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum U256Type {
     /// Binary representation
     Binary,
@@ -105,36 +351,103 @@ impl U256Type {
     }
 }
 
-/// datatype of column
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// handling of values that do not fit in the u256 type of their column
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum OverflowPolicy {
+    /// fail collection of the chunk
+    #[default]
+    Error,
+    /// clamp values to the range of the column type
+    Saturate,
+    /// write the whole column as decimal strings
+    String,
+}
+
+impl std::str::FromStr for OverflowPolicy {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<OverflowPolicy, ParseError> {
+        match s {
+            "error" => Ok(OverflowPolicy::Error),
+            "saturate" => Ok(OverflowPolicy::Saturate),
+            "string" => Ok(OverflowPolicy::String),
+            _ => Err(ParseError::ParseError(format!("invalid overflow policy: {}", s))),
+        }
+    }
+}
+
+/// datatype of column, serialized using the names of ColumnType::as_str()
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum ColumnType {
     /// Boolean column type
+    #[serde(rename = "bool")]
     Boolean,
     /// UInt32 column type
+    #[serde(rename = "uint32")]
     UInt32,
     /// UInt64 column type
+    #[serde(rename = "uint64")]
     UInt64,
     /// U256 column type
+    #[serde(rename = "uint256")]
     UInt256,
     /// Int32 column type
+    #[serde(rename = "int32")]
     Int32,
     /// Int64 column type
+    #[serde(rename = "int64")]
     Int64,
     /// Float32 column type
+    #[serde(rename = "float32")]
     Float32,
     /// Float64 column type
+    #[serde(rename = "float64")]
     Float64,
     /// Decimal128 column type
+    #[serde(rename = "decimal128")]
     Decimal128,
     /// String column type
+    #[serde(rename = "string")]
     String,
     /// Binary column type
+    #[serde(rename = "binary")]
     Binary,
     /// Hex column type
+    #[serde(rename = "hex")]
     Hex,
+    /// list of UInt32 column type
+    #[serde(rename = "uint32_list")]
+    UInt32List,
 }
 
 impl ColumnType {
+    /// whether cryo writes the ColumnType as an arrow column of data_type
+    ///
+    /// binary and string columns can be large or not, and binary columns can have a fixed size
+    pub fn is_compatible_with(&self, data_type: &ArrowDataType) -> bool {
+        use ArrowDataType as A;
+        let is_binary = matches!(data_type, A::Binary | A::LargeBinary | A::FixedSizeBinary(_));
+        let is_string = matches!(data_type, A::Utf8 | A::LargeUtf8);
+        match self {
+            ColumnType::Boolean => data_type == &A::Boolean,
+            ColumnType::UInt32 => data_type == &A::UInt32,
+            ColumnType::UInt64 => data_type == &A::UInt64,
+            ColumnType::Int32 => data_type == &A::Int32,
+            ColumnType::Int64 => data_type == &A::Int64,
+            ColumnType::Float32 => data_type == &A::Float32,
+            ColumnType::Float64 => data_type == &A::Float64,
+            ColumnType::Decimal128 => matches!(data_type, A::Decimal(_, _)),
+            ColumnType::String | ColumnType::UInt256 => is_string,
+            ColumnType::Binary => is_binary,
+            // hex columns are strings, unless written as fixed size binary
+            ColumnType::Hex => is_string || matches!(data_type, A::FixedSizeBinary(_)),
+            ColumnType::UInt32List => match data_type {
+                A::List(field) | A::LargeList(field) => field.data_type == A::UInt32,
+                _ => false,
+            },
+        }
+    }
+
     /// convert ColumnType to str
     pub fn as_str(&self) -> &'static str {
         match *self {
@@ -150,16 +463,79 @@ impl ColumnType {
             ColumnType::String => "string",
             ColumnType::Binary => "binary",
             ColumnType::Hex => "hex",
+            ColumnType::UInt32List => "uint32_list",
         }
     }
+
+    /// return Vec of all column types
+    pub fn all() -> Vec<ColumnType> {
+        vec![
+            ColumnType::Boolean,
+            ColumnType::UInt32,
+            ColumnType::UInt64,
+            ColumnType::UInt256,
+            ColumnType::Int32,
+            ColumnType::Int64,
+            ColumnType::Float32,
+            ColumnType::Float64,
+            ColumnType::Decimal128,
+            ColumnType::String,
+            ColumnType::Binary,
+            ColumnType::Hex,
+            ColumnType::UInt32List,
+        ]
+    }
 }
 
+impl std::str::FromStr for ColumnType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<ColumnType, ParseError> {
+        ColumnType::all().into_iter().find(|column_type| column_type.as_str() == s).ok_or_else(
+            || {
+                let names: Vec<&str> = ColumnType::all().iter().map(|x| x.as_str()).collect();
+                ParseError::ParseError(format!(
+                    "invalid column type: {}, valid column types are: {}",
+                    s,
+                    names.join(", ")
+                ))
+            },
+        )
+    }
+}
+
+impl TryFrom<&str> for ColumnType {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<ColumnType, ParseError> {
+        s.parse()
+    }
+}
+
+/// column of block timestamps joined into datatypes that have a block_number column
+pub(crate) const BLOCK_TIMESTAMP: &str = "block_timestamp";
+
+/// column of block hashes joined into datatypes that have a block_number column
+pub(crate) const BLOCK_HASH: &str = "block_hash";
+
 /// Error related to Schemas
 #[derive(Error, Debug)]
 pub enum SchemaError {
     /// Invalid column being operated on
     #[error("Invalid column")]
     InvalidColumn,
+    /// Column added to a table more than once
+    #[error("Duplicate column: {0}")]
+    DuplicateColumn(String),
+    /// Columns of a table do not match those of an existing file
+    #[error("Incompatible schema: {0}")]
+    IncompatibleSchema(String),
+    /// Explicit columns given along with columns to include or exclude
+    #[error("Conflicting columns: {0}")]
+    ConflictingColumns(String),
+    /// No columns left in a table
+    #[error("Empty schema: {0}")]
+    EmptySchema(String),
 }
 
 impl Datatype {
@@ -172,19 +548,63 @@ impl Datatype {
         include_columns: &Option<Vec<String>>,
         exclude_columns: &Option<Vec<String>>,
         columns: &Option<Vec<String>>,
+        preset: &Option<String>,
         sort: Option<Vec<String>>,
         log_decoder: Option<LogDecoder>,
+        include_deprecated: bool,
     ) -> Result<Table, SchemaError> {
-        let column_types = self.column_types();
+        let mut column_types = self.column_types();
         let all_columns = column_types.keys().map(|k| k.to_string()).collect();
-        let default_columns = self.default_columns();
-        let used_columns = compute_used_columns(
+        let deprecated_columns = match include_deprecated {
+            true => Vec::new(),
+            false => self.deprecated_columns(),
+        };
+        let default_columns = match preset {
+            Some(preset) => self
+                .preset_columns(preset)
+                .into_iter()
+                .filter(|column| !deprecated_columns.contains(column))
+                .collect(),
+            None => self.default_columns(),
+        };
+        let mut used_columns = compute_used_columns(
             all_columns,
+            deprecated_columns,
             default_columns,
             include_columns,
             exclude_columns,
             columns,
-        );
+        )?;
+
+        // joined block columns cost an extra request per block, so they are never part of `all`
+        for joined in self.joinable_block_columns() {
+            let column_type = match joined {
+                BLOCK_HASH => ColumnType::Binary,
+                _ => ColumnType::UInt32,
+            };
+            column_types.insert(joined, column_type);
+            let included = include_columns
+                .as_ref()
+                .is_some_and(|include| include.iter().any(|column| column == joined));
+            let excluded = exclude_columns
+                .as_ref()
+                .is_some_and(|exclude| exclude.iter().any(|column| column == joined));
+            if included && !excluded && (columns.is_none() || is_none_marker(columns)) {
+                used_columns.insert(joined.to_string());
+            }
+        }
+        if used_columns.is_empty() {
+            let reason = match columns {
+                Some(columns) if columns.is_empty() => "with an empty column list",
+                _ if is_none_marker(columns) => "after `none`",
+                _ => "after excluding columns",
+            };
+            return Err(SchemaError::EmptySchema(format!(
+                "no columns of {} remain {}, include at least one column",
+                self.name(),
+                reason
+            )))
+        }
         let mut columns = IndexMap::new();
         for column in used_columns {
             let mut ctype = column_types.get(column.as_str()).ok_or(SchemaError::InvalidColumn)?;
@@ -194,35 +614,97 @@ impl Datatype {
             columns.insert((*column.clone()).to_string(), *ctype);
         }
 
-        let schema = Table {
+        let mut schema = Table {
             datatype: *self,
             sort_columns: sort,
+            file_order: None,
             columns,
             u256_types: u256_types.to_owned(),
             binary_type: binary_column_format.clone(),
             log_decoder,
+            overflow_policy: OverflowPolicy::default(),
+            reward_percentiles: Vec::new(),
+            column_order: None,
+            computed_columns: Vec::new(),
+            call_output_decoder: None,
         };
+        schema.set_reward_percentiles(crate::DEFAULT_REWARD_PERCENTILES.to_vec());
         Ok(schema)
     }
+
+    /// columns of block data that can be joined into rows by their block_number
+    ///
+    /// block_timestamp is skipped for datatypes that have a timestamp, and block_hash for those
+    /// that collect their own block_hash
+    pub fn joinable_block_columns(&self) -> Vec<&'static str> {
+        let column_types = self.column_types();
+        if !column_types.contains_key("block_number") {
+            return vec![]
+        }
+        let mut columns = Vec::new();
+        if !column_types.contains_key("timestamp") && !column_types.contains_key(BLOCK_TIMESTAMP) {
+            columns.push(BLOCK_TIMESTAMP);
+        }
+        if !column_types.contains_key(BLOCK_HASH) {
+            columns.push(BLOCK_HASH);
+        }
+        columns
+    }
+
+    /// resolve a named preset to its column list
+    ///
+    /// `full` selects every column, presets that a dataset does not define fall back to its
+    /// default columns so that one preset can be applied across several datatypes
+    pub fn preset_columns(&self, preset: &str) -> Vec<&'static str> {
+        if preset == "full" {
+            return self.column_types().keys().cloned().collect()
+        }
+        match self.presets().remove(preset) {
+            Some(columns) => columns,
+            None => self.default_columns(),
+        }
+    }
 }
 
 fn compute_used_columns(
     all_columns: IndexSet<String>,
+    deprecated_columns: Vec<&str>,
     default_columns: Vec<&str>,
     include_columns: &Option<Vec<String>>,
     exclude_columns: &Option<Vec<String>>,
     columns: &Option<Vec<String>>,
-) -> IndexSet<String> {
+) -> Result<IndexSet<String>, SchemaError> {
+    // `none` is an empty base set for include columns to add to
+    let (columns, default_columns) = match is_none_marker(columns) {
+        true => (&None, vec![]),
+        false => (columns, default_columns),
+    };
+    // deprecated columns are only left out of `all`, they can still be named explicitly
+    let all_current_columns =
+        all_columns.iter().filter(|c| !deprecated_columns.contains(&c.as_str())).cloned().collect();
     if let Some(columns) = columns {
+        // explicit columns replace the defaults that include and exclude columns modify
+        let modifiers: Vec<&str> = [("include", include_columns), ("exclude", exclude_columns)]
+            .into_iter()
+            .filter(|(_, modifier)| modifier.is_some())
+            .map(|(name, _)| name)
+            .collect();
+        if !modifiers.is_empty() {
+            return Err(SchemaError::ConflictingColumns(format!(
+                "explicit columns cannot be combined with {} columns, explicit columns are \
+                used as given",
+                modifiers.join(" or ")
+            )))
+        }
         if (columns.len() == 1) & columns.contains(&"all".to_string()) {
-            return all_columns
+            return Ok(all_current_columns)
         }
-        return columns.iter().map(|x| x.to_string()).collect()
+        return Ok(columns.iter().map(|x| x.to_string()).collect())
     }
     let mut result_set = IndexSet::from_iter(default_columns.iter().map(|s| s.to_string()));
     if let Some(include) = include_columns {
         if (include.len() == 1) & include.contains(&"all".to_string()) {
-            return all_columns
+            return Ok(all_current_columns)
         }
         // Permissively skip `include` columns that are not in this dataset (they might apply to
         // other dataset)
@@ -233,7 +715,11 @@ fn compute_used_columns(
         let exclude_set = IndexSet::<String>::from_iter(exclude.iter().cloned());
         result_set = result_set.difference(&exclude_set).cloned().collect()
     }
-    result_set
+    Ok(result_set)
+}
+
+fn is_none_marker(columns: &Option<Vec<String>>) -> bool {
+    columns.as_ref().is_some_and(|columns| columns.as_slice() == ["none"])
 }
 
 #[cfg(test)]
@@ -244,24 +730,212 @@ mod tests {
         vec![U256Type::Binary, U256Type::String, U256Type::F64]
     }
 
+    #[test]
+    fn test_joined_block_hash_schema() {
+        let include = Some(vec!["block_hash".to_string()]);
+        let schema = |datatype: Datatype, encoding: ColumnEncoding| {
+            datatype
+                .table_schema(
+                    &get_u256_types(),
+                    &encoding,
+                    &include,
+                    &None,
+                    &None,
+                    &None,
+                    None,
+                    None,
+                    false,
+                )
+                .unwrap()
+        };
+        let hex = schema(Datatype::GethCalls, ColumnEncoding::Hex);
+        assert_eq!(hex.column_type("block_hash"), Some(ColumnType::Hex));
+        let binary = schema(Datatype::GethCalls, ColumnEncoding::Binary);
+        assert_eq!(binary.column_type("block_hash"), Some(ColumnType::Binary));
+
+        // datatypes that collect their own block hash are not joined, and `all` never joins
+        assert!(!Datatype::Logs.joinable_block_columns().contains(&"block_hash"));
+        assert!(schema(Datatype::Logs, ColumnEncoding::Binary).has_column("block_hash"));
+        let all = Some(vec!["all".to_string()]);
+        let table = Datatype::GethCalls
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Binary,
+                &all,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert!(!table.has_column("block_hash"));
+    }
+
     #[test]
     fn test_table_schema_explicit_cols() {
         let cols = Some(vec!["block_number".to_string(), "block_hash".to_string()]);
         let table = Datatype::Blocks
-            .table_schema(&get_u256_types(), &ColumnEncoding::Hex, &None, &None, &cols, None, None)
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &cols,
+                &None,
+                None,
+                None,
+                false,
+            )
             .unwrap();
         assert_eq!(vec!["block_number", "block_hash"], table.columns());
 
         // "all" marker support
         let cols = Some(vec!["all".to_string()]);
         let table = Datatype::Blocks
-            .table_schema(&get_u256_types(), &ColumnEncoding::Hex, &None, &None, &cols, None, None)
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &cols,
+                &None,
+                None,
+                None,
+                false,
+            )
             .unwrap();
-        assert_eq!(15, table.columns().len());
+        assert_eq!(18, table.columns().len());
         assert!(table.columns().contains(&"block_hash"));
         assert!(table.columns().contains(&"transactions_root"));
     }
 
+    #[test]
+    fn test_table_schema_deprecated_cols() {
+        let all = Some(vec!["all".to_string()]);
+        let schema = |include: &Option<Vec<String>>,
+                      columns: &Option<Vec<String>>,
+                      include_deprecated: bool| {
+            Datatype::Blocks
+                .table_schema(
+                    &get_u256_types(),
+                    &ColumnEncoding::Hex,
+                    include,
+                    &None,
+                    columns,
+                    &None,
+                    None,
+                    None,
+                    include_deprecated,
+                )
+                .unwrap()
+        };
+        assert!(!schema(&None, &all, false).has_column("total_difficulty"));
+        assert!(!schema(&all, &None, false).has_column("total_difficulty"));
+        assert!(schema(&None, &all, true).has_column("total_difficulty"));
+        assert!(schema(&all, &None, true).has_column("total_difficulty"));
+        assert_eq!(19, schema(&None, &all, true).columns().len());
+
+        // deprecated columns can still be named explicitly
+        let named = Some(vec!["total_difficulty".to_string()]);
+        assert!(schema(&named, &None, false).has_column("total_difficulty"));
+        assert!(schema(&None, &named, false).has_column("total_difficulty"));
+    }
+
+    #[test]
+    fn test_table_schema_none_cols() {
+        let none = Some(vec!["none".to_string()]);
+        let schema = |include: &Option<Vec<String>>| {
+            Datatype::GethCalls.table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                include,
+                &None,
+                &none,
+                &None,
+                None,
+                None,
+                false,
+            )
+        };
+        let include = Some(vec!["block_timestamp".to_string(), "block_hash".to_string()]);
+        let table = schema(&include).unwrap();
+        assert_eq!(vec!["block_timestamp", "block_hash"], table.columns());
+        let include = Some(vec!["chain_id".to_string()]);
+        assert_eq!(vec!["chain_id"], schema(&include).unwrap().columns());
+
+        // a schema without any columns is an error
+        let error = schema(&None).unwrap_err();
+        assert!(matches!(error, SchemaError::EmptySchema(_)));
+    }
+
+    #[test]
+    fn test_table_schema_excluding_all_default_cols() {
+        let exclude =
+            Some(Datatype::Blocks.default_columns().iter().map(|c| c.to_string()).collect());
+        let error = Datatype::Blocks
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &exclude,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap_err();
+        assert!(matches!(error, SchemaError::EmptySchema(_)));
+        assert!(error.to_string().contains("no columns of blocks remain after excluding columns"));
+    }
+
+    #[test]
+    fn test_table_schema_empty_column_list() {
+        // e.g. `--columns` without values, or `--columns blocks:`
+        let error = Datatype::Blocks
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &Some(vec![]),
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap_err();
+        assert!(matches!(error, SchemaError::EmptySchema(_)));
+        assert!(error.to_string().contains("with an empty column list"), "{}", error);
+    }
+
+    #[test]
+    fn test_table_schema_explicit_cols_conflict() {
+        let cols = Some(vec!["block_number".to_string()]);
+        let other_cols = Some(vec!["block_hash".to_string()]);
+        let schema = |include: &Option<Vec<String>>, exclude: &Option<Vec<String>>| {
+            Datatype::Blocks.table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                include,
+                exclude,
+                &cols,
+                &None,
+                None,
+                None,
+                false,
+            )
+        };
+        let error = schema(&other_cols, &None).unwrap_err();
+        assert!(matches!(error, SchemaError::ConflictingColumns(_)));
+        assert!(error.to_string().contains("combined with include columns"));
+        let error = schema(&other_cols, &other_cols).unwrap_err();
+        assert!(error.to_string().contains("combined with include or exclude columns"));
+        assert!(schema(&None, &None).is_ok());
+    }
+
     #[test]
     fn test_table_schema_include_cols() {
         let inc_cols = Some(vec!["chain_id".to_string(), "receipts_root".to_string()]);
@@ -272,8 +946,10 @@ mod tests {
                 &inc_cols,
                 &None,
                 &None,
+                &None,
                 None,
                 None,
+                false,
             )
             .unwrap();
         assert_eq!(9, table.columns().len());
@@ -288,8 +964,10 @@ mod tests {
                 &inc_cols,
                 &None,
                 &None,
+                &None,
                 None,
                 None,
+                false,
             )
             .unwrap();
         assert_eq!(Some(&"chain_id"), table.columns().last());
@@ -304,11 +982,13 @@ mod tests {
                 &inc_cols,
                 &None,
                 &None,
+                &None,
                 None,
                 None,
+                false,
             )
             .unwrap();
-        assert_eq!(15, table.columns().len());
+        assert_eq!(18, table.columns().len());
         assert!(table.columns().contains(&"block_hash"));
         assert!(table.columns().contains(&"transactions_root"));
     }
@@ -317,7 +997,17 @@ mod tests {
     fn test_table_schema_exclude_cols() {
         // defaults
         let table = Datatype::Blocks
-            .table_schema(&get_u256_types(), &ColumnEncoding::Hex, &None, &None, &None, None, None)
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
             .unwrap();
         assert_eq!(8, table.columns().len());
         assert!(table.columns().contains(&"author"));
@@ -331,8 +1021,10 @@ mod tests {
                 &None,
                 &ex_cols,
                 &None,
+                &None,
                 None,
                 None,
+                false,
             )
             .unwrap();
         assert_eq!(6, table.columns().len());
@@ -348,8 +1040,10 @@ mod tests {
                 &None,
                 &ex_cols,
                 &None,
+                &None,
                 None,
                 None,
+                false,
             )
             .unwrap();
         assert_eq!(7, table.columns().len());
@@ -368,8 +1062,10 @@ mod tests {
                 &inc_cols,
                 &ex_cols,
                 &None,
+                &None,
                 None,
                 None,
+                false,
             )
             .unwrap();
         assert!(!table.columns().contains(&"author"));
@@ -377,4 +1073,465 @@ mod tests {
         assert_eq!(7, table.columns().len());
         assert_eq!(["chain_id", "receipts_root"], table.columns()[5..7]);
     }
+
+    #[test]
+    fn test_table_schema_preset() {
+        let preset = Some("minimal".to_string());
+        let table = Datatype::Blocks
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &preset,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(vec!["block_number", "block_hash", "timestamp"], table.columns());
+
+        // include and exclude adjust the preset
+        let inc_cols = Some(vec!["gas_used".to_string()]);
+        let ex_cols = Some(vec!["block_hash".to_string()]);
+        let table = Datatype::Blocks
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &inc_cols,
+                &ex_cols,
+                &None,
+                &preset,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(vec!["block_number", "timestamp", "gas_used"], table.columns());
+
+        // full is equivalent to all
+        let preset = Some("full".to_string());
+        let table = Datatype::Blocks
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &preset,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(18, table.columns().len());
+    }
+
+    #[test]
+    fn test_table_schema_ordinal_cols() {
+        let table = Datatype::Logs
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert!(table.columns().contains(&"transaction_index"));
+        assert!(table.columns().contains(&"log_index"));
+        assert!(!table.columns().contains(&"transaction_log_index"));
+
+        let inc_cols = Some(vec!["transaction_log_index".to_string()]);
+        let table = Datatype::Logs
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &inc_cols,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(Some(&"transaction_log_index"), table.columns().last());
+
+        let table = Datatype::Transactions
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert!(table.columns().contains(&"transaction_index"));
+    }
+
+    #[test]
+    fn test_table_schema_blobs() {
+        let table = Datatype::Blobs
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            table.columns(),
+            vec![
+                "block_number",
+                "transaction_hash",
+                "blob_versioned_hash",
+                "max_fee_per_blob_gas",
+                "blob_gas_used",
+                "chain_id"
+            ]
+        );
+        assert_eq!(table.column_type("blob_versioned_hash"), Some(ColumnType::Hex));
+        assert_eq!(table.column_type("max_fee_per_blob_gas"), Some(ColumnType::UInt64));
+        assert_eq!(Datatype::Blobs, "blobs".parse().unwrap());
+    }
+
+    #[test]
+    fn test_table_builder() {
+        let table = TableBuilder::new(Datatype::Blocks)
+            .add_column("block_number", ColumnType::UInt32)
+            .add_column("label", ColumnType::String)
+            .sort_by(vec!["block_number".to_string()])
+            .build()
+            .unwrap();
+        assert_eq!(table.columns(), vec!["block_number", "label"]);
+        assert_eq!(table.column_type("block_number"), Some(ColumnType::UInt32));
+        assert_eq!(table.column_type("label"), Some(ColumnType::String));
+        assert_eq!(table.column_type("block_hash"), None);
+        assert_eq!(table.sort_columns, Some(vec!["block_number".to_string()]));
+
+        let result = TableBuilder::new(Datatype::Blocks)
+            .add_column("label", ColumnType::String)
+            .add_column("label", ColumnType::Binary)
+            .build();
+        assert!(matches!(result, Err(SchemaError::DuplicateColumn(name)) if name == "label"));
+    }
+
+    #[test]
+    fn test_table_serde_round_trip() {
+        let table = Datatype::Blocks
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        let json = serde_json::to_string(&table).unwrap();
+        assert!(json.contains(r#""block_number":"uint32""#));
+        let deserialized: Table = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, table);
+        assert_eq!(deserialized.columns(), table.columns());
+    }
+
+    #[test]
+    fn test_iter_deserialized_table() {
+        let table = TableBuilder::new(Datatype::Blocks)
+            .add_column("block_number", ColumnType::UInt32)
+            .add_column("timestamp", ColumnType::UInt32)
+            .add_column("block_hash", ColumnType::Binary)
+            .sort_by(vec!["block_number".to_string()])
+            .build()
+            .unwrap();
+        let json = serde_json::to_string(&table).unwrap();
+        let deserialized: Table = serde_json::from_str(&json).unwrap();
+        let columns: Vec<(&str, ColumnType)> = deserialized.iter().collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("block_number", ColumnType::UInt32),
+                ("timestamp", ColumnType::UInt32),
+                ("block_hash", ColumnType::Binary),
+            ]
+        );
+        assert_eq!(deserialized.iter().collect::<Vec<_>>(), table.iter().collect::<Vec<_>>());
+        assert_eq!(deserialized.sort_columns(), Some(&["block_number".to_string()][..]));
+    }
+
+    #[test]
+    fn test_column_type_from_str() {
+        for column_type in ColumnType::all() {
+            assert_eq!(column_type.as_str().parse::<ColumnType>().unwrap(), column_type);
+            assert_eq!(ColumnType::try_from(column_type.as_str()).unwrap(), column_type);
+        }
+
+        let message = match "uint128".parse::<ColumnType>() {
+            Err(ParseError::ParseError(message)) => message,
+            _ => panic!("expected ParseError"),
+        };
+        assert!(message.contains("uint128"));
+        assert!(message.contains("uint256"));
+        assert!(message.contains("hex"));
+    }
+
+    #[test]
+    fn test_gas_fees_reward_columns() {
+        let mut table = Datatype::GasFees
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Hex,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        table.set_reward_percentiles(vec![25.0, 75.5]);
+        assert_eq!(
+            table.columns(),
+            vec![
+                "block_number",
+                "base_fee_per_gas",
+                "gas_used_ratio",
+                "chain_id",
+                "reward_p25",
+                "reward_p75_5"
+            ]
+        );
+        assert_eq!(table.column_type("reward_p75_5"), Some(ColumnType::UInt64));
+        assert_eq!(table.reward_percentiles, vec![25.0, 75.5]);
+    }
+
+    #[test]
+    fn test_prestate_schema() {
+        let table = Datatype::Prestate
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            table.columns(),
+            vec![
+                "block_number",
+                "transaction_index",
+                "address",
+                "balance",
+                "nonce",
+                "code",
+                "storage_slot",
+                "storage_value",
+                "chain_id"
+            ]
+        );
+        assert_eq!(table.column_type("balance"), Some(ColumnType::UInt256));
+        assert_eq!(table.column_type("nonce"), Some(ColumnType::UInt64));
+        assert_eq!(table.column_type("storage_slot"), Some(ColumnType::Binary));
+    }
+
+    #[test]
+    fn test_access_list_schemas() {
+        let u256_types = get_u256_types();
+        let schema = |datatype: Datatype, include_columns: &Option<Vec<String>>| {
+            datatype
+                .table_schema(
+                    &u256_types,
+                    &ColumnEncoding::Binary,
+                    include_columns,
+                    &None,
+                    &None,
+                    &None,
+                    None,
+                    None,
+                    false,
+                )
+                .unwrap()
+        };
+
+        // access_list is opt-in on transactions
+        assert!(!schema(Datatype::Transactions, &None).has_column("access_list"));
+        let table = schema(Datatype::Transactions, &Some(vec!["access_list".to_string()]));
+        assert_eq!(table.column_type("access_list"), Some(ColumnType::String));
+        assert!(!schema(Datatype::Transactions, &None).has_column("authorization_list"));
+        let table = schema(Datatype::Transactions, &Some(vec!["authorization_list".to_string()]));
+        assert_eq!(table.column_type("authorization_list"), Some(ColumnType::String));
+
+        // receipt columns are opt-in on transactions and follow the binary encoding
+        let receipt_columns = Some(vec!["contract_address".to_string(), "logs_bloom".to_string()]);
+        let table = schema(Datatype::Transactions, &receipt_columns);
+        assert_eq!(table.column_type("contract_address"), Some(ColumnType::Binary));
+        assert_eq!(table.column_type("logs_bloom"), Some(ColumnType::Binary));
+        assert!(!schema(Datatype::Transactions, &None).has_column("contract_address"));
+        let hex = Datatype::Transactions
+            .table_schema(
+                &u256_types,
+                &ColumnEncoding::Hex,
+                &receipt_columns,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(hex.column_type("logs_bloom"), Some(ColumnType::Hex));
+
+        let table = schema(Datatype::AccessLists, &None);
+        assert_eq!(
+            table.columns(),
+            vec!["block_number", "transaction_hash", "address", "storage_key", "chain_id"]
+        );
+        assert_eq!(table.column_type("storage_key"), Some(ColumnType::Binary));
+    }
+
+    #[test]
+    fn test_pending_transactions_schema() {
+        let table = Datatype::PendingTransactions
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+        assert_eq!(table.columns()[0], "snapshot_time");
+        assert_eq!(table.column_type("snapshot_time"), Some(ColumnType::UInt64));
+        assert!(table.has_column("transaction_hash"));
+        // pending transactions have no block yet
+        assert!(!table.has_column("block_number"));
+        assert_eq!(Datatype::PendingTransactions.default_sort(), vec!["from_address", "nonce"]);
+    }
+
+    #[test]
+    fn test_logs_default_join_columns() {
+        let table = Datatype::Logs
+            .table_schema(
+                &get_u256_types(),
+                &ColumnEncoding::Binary,
+                &None,
+                &None,
+                &None,
+                &None,
+                None,
+                None,
+                false,
+            )
+            .unwrap();
+
+        // logs are joined to their transactions by these columns
+        assert_eq!(table.column_type("transaction_index"), Some(ColumnType::UInt32));
+        assert_eq!(table.column_type("transaction_hash"), Some(ColumnType::Binary));
+    }
+
+    #[test]
+    fn test_schema_hash() {
+        let table = |columns: &[(&str, ColumnType)], sort: &str| {
+            let mut builder = TableBuilder::new(Datatype::Blocks);
+            for (name, column_type) in columns.iter() {
+                builder = builder.add_column(name, *column_type);
+            }
+            builder.sort_by(vec![sort.to_string()]).build().unwrap()
+        };
+        let columns = [("block_number", ColumnType::UInt32), ("block_hash", ColumnType::Binary)];
+        let reordered = [("block_hash", ColumnType::Binary), ("block_number", ColumnType::UInt32)];
+        let retyped = [("block_number", ColumnType::UInt64), ("block_hash", ColumnType::Binary)];
+
+        let hash = table(&columns, "block_number").schema_hash();
+        assert_eq!(table(&columns, "block_hash").schema_hash(), hash);
+        assert_ne!(table(&reordered, "block_number").schema_hash(), hash);
+        assert_ne!(table(&retyped, "block_number").schema_hash(), hash);
+        assert_eq!(table(&columns, "block_number").schema_hash_metadata().1.len(), 16);
+    }
+
+    #[test]
+    fn test_match_schema() {
+        use polars::prelude::*;
+
+        let mut table = TableBuilder::new(Datatype::Blocks)
+            .add_column("block_number", ColumnType::UInt32)
+            .add_column("block_hash", ColumnType::Binary)
+            .add_column("total_difficulty", ColumnType::UInt256)
+            .u256_types(vec![U256Type::Binary, U256Type::F64])
+            .build()
+            .unwrap();
+        let hash = table.schema_hash();
+
+        // reference file written with a different column order
+        let dir = crate::test_helpers::TempDir::new("match_schema");
+        std::fs::create_dir_all(&dir).unwrap();
+        let write_reference = |mut df: DataFrame| {
+            let path = dir.join("reference.parquet");
+            let file = std::fs::File::create(&path).unwrap();
+            ParquetWriter::new(file).finish(&mut df).unwrap();
+            crate::read_parquet_schema(path.to_str().unwrap()).unwrap()
+        };
+        let reference = write_reference(
+            df!(
+                "total_difficulty_f64" => [1.0f64],
+                "block_hash" => [vec![1u8; 32]],
+                "block_number" => [1u32],
+                "total_difficulty_binary" => [vec![0u8; 32]],
+            )
+            .unwrap(),
+        );
+        table.match_schema(&reference).unwrap();
+        let names: Vec<String> = table.output_columns().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(
+            names,
+            vec!["total_difficulty_f64", "block_hash", "block_number", "total_difficulty_binary"]
+        );
+        assert_ne!(table.schema_hash(), hash);
+
+        // retyped and missing columns are rejected
+        let retyped = write_reference(
+            df!(
+                "total_difficulty_f64" => [1.0f64],
+                "block_hash" => [vec![1u8; 32]],
+                "block_number" => [1u64],
+                "total_difficulty_binary" => [vec![0u8; 32]],
+            )
+            .unwrap(),
+        );
+        let error = table.match_schema(&retyped).unwrap_err();
+        assert!(error.to_string().contains("column block_number"));
+        let missing = write_reference(df!("block_number" => [1u32]).unwrap());
+        let error = table.match_schema(&missing).unwrap_err();
+        assert!(error.to_string().contains("no column block_hash"));
+        assert_eq!(table.column_order, None);
+    }
 }
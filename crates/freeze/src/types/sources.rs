@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use ethers::prelude::*;
 use governor::{
@@ -10,6 +13,7 @@ use tokio::{
     sync::{AcquireError, Semaphore, SemaphorePermit},
     task,
 };
+use tracing::Instrument;
 
 use crate::CollectError;
 
@@ -33,10 +37,33 @@ pub struct Source {
     pub semaphore: Arc<Option<Semaphore>>,
     /// rate limiter for controlling request rate
     pub rate_limiter: Arc<Option<RateLimiter>>,
+    /// retry failed block range requests one block at a time, keeping the blocks that succeed
+    pub retry_failed_blocks_individually: bool,
+    /// ids of the eth_newFilter filters installed for each log filter, by its json
+    pub log_filters: Arc<std::sync::Mutex<HashMap<String, U256>>>,
+    /// set once the node rejects eth_getBlockReceipts, receipts are then fetched per transaction
+    pub block_receipts_unsupported: Arc<AtomicBool>,
+    /// maximum number of blocks per eth_getLogs request, for providers that cap the block span
+    pub max_blocks_per_log_request: Option<u64>,
+    /// handling of requested blocks above the head of the node
+    pub head_gap: HeadGap,
     /// Labels (these are non-functional)
     pub labels: SourceLabels,
 }
 
+/// handling of requested blocks that are above the head of the node, i.e. not produced yet, as
+/// opposed to blocks at or below the head that the node does not have
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HeadGap {
+    /// fail the chunk of the block
+    #[default]
+    Error,
+    /// skip the chunk of the block with a warning, leaving it to a later run
+    Skip,
+    /// wait for the block up to n times, polling at an interval, then fail its chunk
+    Wait(u32, std::time::Duration),
+}
+
 /// A non-generic wrapper over different provider types for use as a trait object
 #[derive(Clone, Debug)]
 pub enum ProviderWrapper {
@@ -77,27 +104,40 @@ impl From<Provider<Ipc>> for ProviderWrapper {
 /// extract the provider from a source and run specified method
 #[macro_export]
 macro_rules! source_provider {
-    ($source:expr, $method:ident($($arg:expr),*)) => {
+    ($source:expr, $method:ident($($arg:expr),*)) => {{
+        tracing::debug!(method = stringify!($method), "rpc request");
         match &$source.provider {
             ProviderWrapper::MockProvider(provider) => provider.$method($($arg),*),
             ProviderWrapper::RetryClientHttp(provider) => provider.$method($($arg),*),
             ProviderWrapper::WsClient(provider) => provider.$method($($arg),*),
             ProviderWrapper::IpcClient(provider) => provider.$method($($arg),*),
         }
-    };
+    }};
 }
 
 impl Source {
     /// Returns all receipts for a block.
     /// Tries to use `eth_getBlockReceipts` first, and falls back to `eth_getTransactionReceipt`
+    /// if the node does not support it. Once a node has rejected `eth_getBlockReceipts`, later
+    /// blocks go straight to `eth_getTransactionReceipt`
     pub async fn get_tx_receipts_in_block(
         &self,
         block: &Block<Transaction>,
     ) -> Result<Vec<TransactionReceipt>> {
         let block_number =
             block.number.ok_or(CollectError::CollectError("no block number".to_string()))?.as_u64();
-        if let Ok(receipts) = self.get_block_receipts(block_number).await {
-            return Ok(receipts);
+        if !self.block_receipts_unsupported.load(Ordering::Relaxed) {
+            match self.get_block_receipts(block_number).await {
+                Ok(receipts) => return Ok(receipts),
+                Err(e) if crate::preflight::is_method_missing(&e) => {
+                    tracing::debug!(
+                        block_number,
+                        "eth_getBlockReceipts unsupported, fetching receipts per transaction"
+                    );
+                    self.block_receipts_unsupported.store(true, Ordering::Relaxed);
+                }
+                Err(e) => return Err(e),
+            }
         }
 
         self.get_tx_receipts(&block.transactions).await
@@ -112,14 +152,17 @@ impl Source {
         for tx in transactions {
             let tx_hash = tx.hash;
             let source = self.clone();
-            let task = task::spawn(async move {
-                match source.get_transaction_receipt(tx_hash).await? {
-                    Some(receipt) => Ok(receipt),
-                    None => {
-                        Err(CollectError::CollectError("could not find tx receipt".to_string()))
+            let task = task::spawn(
+                async move {
+                    match source.get_transaction_receipt(tx_hash).await? {
+                        Some(receipt) => Ok(receipt),
+                        None => {
+                            Err(CollectError::CollectError("could not find tx receipt".to_string()))
+                        }
                     }
                 }
-            });
+                .in_current_span(),
+            );
             tasks.push(task);
         }
         let mut receipts = Vec::new();
@@ -150,12 +193,12 @@ impl Source {
             DEFAULT_MAX_RETRIES,
             DEFAULT_INTIAL_BACKOFF,
         )
-        .map_err(|_| CollectError::RPCError("could not connect to provider".to_string()))?;
-        let chain_id = provider
-            .get_chainid()
-            .await
-            .map_err(|_| CollectError::RPCError("could not get chain_id".to_string()))?
-            .as_u64();
+        .map_err(|_| {
+            CollectError::Rpc(ProviderError::CustomError(
+                "could not connect to provider".to_string(),
+            ))
+        })?;
+        let chain_id = provider.get_chainid().await.map_err(rpc_error)?.as_u64();
 
         let rate_limiter = None;
         let semaphore = None;
@@ -165,7 +208,11 @@ impl Source {
             DEFAULT_MAX_RETRIES,
             DEFAULT_INTIAL_BACKOFF,
         )
-        .map_err(|_| CollectError::RPCError("could not connect to provider".to_string()))?;
+        .map_err(|_| {
+            CollectError::Rpc(ProviderError::CustomError(
+                "could not connect to provider".to_string(),
+            ))
+        })?;
 
         let source = Source {
             provider: ProviderWrapper::RetryClientHttp(Arc::new(provider)),
@@ -181,11 +228,53 @@ impl Source {
             },
             rate_limiter: rate_limiter.into(),
             semaphore: semaphore.into(),
+            retry_failed_blocks_individually: false,
+            log_filters: Default::default(),
+            block_receipts_unsupported: Default::default(),
+            max_blocks_per_log_request: None,
+            head_gap: HeadGap::default(),
         };
 
         Ok(source)
     }
 
+    /// initialize source from a provider, with default request settings
+    pub async fn from_provider(provider: impl Into<ProviderWrapper>) -> Result<Source> {
+        let mut source = Source {
+            provider: provider.into(),
+            chain_id: 0,
+            inner_request_size: DEFAULT_INNER_REQUEST_SIZE,
+            max_concurrent_chunks: Some(DEFAULT_MAX_CONCURRENT_CHUNKS),
+            rpc_url: String::new(),
+            labels: SourceLabels {
+                max_concurrent_requests: None,
+                max_requests_per_second: None,
+                max_retries: None,
+                initial_backoff: None,
+            },
+            rate_limiter: None.into(),
+            semaphore: None.into(),
+            retry_failed_blocks_individually: false,
+            log_filters: Default::default(),
+            block_receipts_unsupported: Default::default(),
+            max_blocks_per_log_request: None,
+            head_gap: HeadGap::default(),
+        };
+        source.chain_id = Self::map_err(source_provider!(source, get_chainid()).await)?.as_u64();
+        Ok(source)
+    }
+
+    /// number of blocks per request of a datatype that collects block ranges, requests of
+    /// eth_getLogs are capped at max_blocks_per_log_request
+    pub fn block_range_size(&self, datatype: &crate::Datatype) -> u64 {
+        match self.max_blocks_per_log_request {
+            Some(max) if datatype.required_methods().contains(&"eth_getLogs") => {
+                self.inner_request_size.min(max)
+            }
+            _ => self.inner_request_size,
+        }
+    }
+
     // /// set rate limit
     // pub fn rate_limit(mut self, _requests_per_second: u64) -> Source {
     //     todo!();
@@ -260,6 +349,20 @@ pub struct Fetcher<P> {
 
 type Result<T> = ::core::result::Result<T, CollectError>;
 
+/// response to a paginated eth_getLogs request
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum LogsPage {
+    /// all logs, from providers that do not paginate
+    Logs(Vec<Log>),
+    /// logs of one page and the key of the next page, if there is one
+    Page {
+        logs: Vec<Log>,
+        #[serde(rename = "pageKey", default)]
+        page_key: Option<String>,
+    },
+}
+
 // impl<P: JsonRpcClient> Fetcher<P> {
 impl Source {
     /// Returns an array (possibly empty) of logs that match the filter
@@ -268,6 +371,93 @@ impl Source {
         Self::map_err(source_provider!(self, get_logs(filter)).await)
     }
 
+    /// Returns the logs that match the filter, requested in pages of at most page_size logs
+    ///
+    /// the page size is sent as a `pageSize` field of the filter, and each page is an object of
+    /// `logs` and the `pageKey` of the next page, which is looped over until the provider returns
+    /// no page key. providers that do not paginate return a plain array, which is the only page
+    pub async fn get_logs_paginated(&self, filter: &Filter, page_size: u64) -> Result<Vec<Log>> {
+        let mut params = serde_json::to_value(filter)
+            .map_err(|e| err(&format!("could not serialize log filter: {}", e)))?;
+        params["pageSize"] = page_size.into();
+        let mut logs = Vec::new();
+        loop {
+            let (page_logs, page_key) = match self.request_logs_page(&params).await? {
+                LogsPage::Logs(page_logs) => (page_logs, None),
+                LogsPage::Page { logs, page_key } => (logs, page_key),
+            };
+            logs.extend(page_logs);
+            match page_key {
+                Some(page_key) if params["pageKey"] == page_key.as_str() => {
+                    return Err(err(&format!("provider repeated eth_getLogs page key {}", page_key)))
+                }
+                Some(page_key) => params["pageKey"] = page_key.into(),
+                None => return Ok(logs),
+            }
+        }
+    }
+
+    async fn request_logs_page(&self, params: &serde_json::Value) -> Result<LogsPage> {
+        let _permit = self.permit_request().await;
+        tracing::debug!(method = "eth_getLogs", page_key = %params["pageKey"], "rpc request");
+        // request is not a Middleware method, so each provider type returns its own future
+        let method = "eth_getLogs";
+        let result = match &self.provider {
+            ProviderWrapper::MockProvider(provider) => provider.request(method, [params]).await,
+            ProviderWrapper::RetryClientHttp(provider) => provider.request(method, [params]).await,
+            ProviderWrapper::WsClient(provider) => provider.request(method, [params]).await,
+            ProviderWrapper::IpcClient(provider) => provider.request(method, [params]).await,
+        };
+        Self::map_err(result)
+    }
+
+    /// Returns the logs that match the filter using a filter installed with `eth_newFilter`
+    ///
+    /// filters are installed once and reused by later requests for the same filter, a filter that
+    /// the node has expired is reinstalled
+    pub async fn get_filter_logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        let key = serde_json::to_string(filter)
+            .map_err(|e| err(&format!("could not serialize log filter: {}", e)))?;
+        let installed = self.log_filters.lock().ok().and_then(|ids| ids.get(&key).copied());
+        let id = match installed {
+            Some(id) => id,
+            None => self.install_log_filter(&key, filter).await?,
+        };
+        match self.request_filter_logs(id).await {
+            Err(e) if is_expired_filter(&e) => {
+                tracing::debug!(filter_id = %id, "log filter expired, reinstalling");
+                let id = self.install_log_filter(&key, filter).await?;
+                self.request_filter_logs(id).await
+            }
+            result => result,
+        }
+    }
+
+    async fn install_log_filter(&self, key: &str, filter: &Filter) -> Result<U256> {
+        let id = {
+            let _permit = self.permit_request().await;
+            Self::map_err(source_provider!(self, new_filter(FilterKind::Logs(filter))).await)?
+        };
+        if let Ok(mut ids) = self.log_filters.lock() {
+            ids.insert(key.to_string(), id);
+        }
+        Ok(id)
+    }
+
+    async fn request_filter_logs(&self, id: U256) -> Result<Vec<Log>> {
+        let _permit = self.permit_request().await;
+        tracing::debug!(method = "eth_getFilterLogs", "rpc request");
+        // request is not a Middleware method, so each provider type returns its own future
+        let method = "eth_getFilterLogs";
+        let result = match &self.provider {
+            ProviderWrapper::MockProvider(provider) => provider.request(method, [id]).await,
+            ProviderWrapper::RetryClientHttp(provider) => provider.request(method, [id]).await,
+            ProviderWrapper::WsClient(provider) => provider.request(method, [id]).await,
+            ProviderWrapper::IpcClient(provider) => provider.request(method, [id]).await,
+        };
+        Self::map_err(result)
+    }
+
     /// Replays all transactions in a block returning the requested traces for each transaction
     pub async fn trace_replay_block_transactions(
         &self,
@@ -376,8 +566,17 @@ impl Source {
 
     /// Gets the block at `block_num` (transaction hashes only)
     pub async fn get_block(&self, block_num: u64) -> Result<Option<Block<TxHash>>> {
+        self.request_block(block_num, || async {
+            let _permit = self.permit_request().await;
+            Self::map_err(source_provider!(self, get_block(block_num)).await)
+        })
+        .await
+    }
+
+    /// Gets the uncle at `index` of the block at `block_num`
+    pub async fn get_uncle(&self, block_num: u64, index: u64) -> Result<Option<Block<TxHash>>> {
         let _permit = self.permit_request().await;
-        Self::map_err(source_provider!(self, get_block(block_num)).await)
+        Self::map_err(source_provider!(self, get_uncle(block_num, index.into())).await)
     }
 
     /// Gets the block at `block_num` (transaction hashes only)
@@ -388,19 +587,69 @@ impl Source {
 
     /// Gets the block at `block_num` (full transactions included)
     pub async fn get_block_with_txs(&self, block_num: u64) -> Result<Option<Block<Transaction>>> {
+        self.request_block(block_num, || async {
+            let _permit = self.permit_request().await;
+            Self::map_err(source_provider!(self, get_block_with_txs(block_num)).await)
+        })
+        .await
+    }
+
+    /// request a block, telling blocks that are not produced yet from blocks that are missing
+    ///
+    /// a block that is not found is missing if it is at or below the head of the node, blocks
+    /// above the head are waited for as set by head_gap and are then a BlockNotProduced error
+    async fn request_block<T, F, Fut>(&self, block_num: u64, request: F) -> Result<Option<T>>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<Option<T>>>,
+    {
+        let mut n_waits = 0;
+        loop {
+            if let Some(block) = request().await? {
+                return Ok(Some(block))
+            }
+            if block_num <= self.get_block_number().await?.as_u64() {
+                return Ok(None)
+            }
+            match self.head_gap {
+                HeadGap::Wait(max_waits, interval) if n_waits < max_waits => {
+                    n_waits += 1;
+                    tokio::time::sleep(interval).await;
+                }
+                _ => return Err(CollectError::BlockNotProduced(block_num)),
+            }
+        }
+    }
+
+    /// Gets the pending block of the node, with the full transactions of the mempool
+    pub async fn get_pending_block_with_txs(&self) -> Result<Option<Block<Transaction>>> {
         let _permit = self.permit_request().await;
-        Self::map_err(source_provider!(self, get_block_with_txs(block_num)).await)
+        Self::map_err(source_provider!(self, get_block_with_txs(BlockNumber::Pending)).await)
     }
 
     /// Returns all receipts for a block.
     /// Note that this uses the `eth_getBlockReceipts` method which is not supported by all nodes.
-    /// Consider using `FetcherExt::get_tx_receipts_in_block` which takes a block, and falls back to
+    /// Consider using `Source::get_tx_receipts_in_block` which takes a block, and falls back to
     /// `eth_getTransactionReceipt` if `eth_getBlockReceipts` is not supported.
     pub async fn get_block_receipts(&self, block_num: u64) -> Result<Vec<TransactionReceipt>> {
         let _permit = self.permit_request().await;
         Self::map_err(source_provider!(self, get_block_receipts(block_num)).await)
     }
 
+    /// Returns base fees, gas used ratios, and priority fee percentiles of the block_count
+    /// blocks ending at last_block
+    pub async fn fee_history(
+        &self,
+        block_count: u64,
+        last_block: BlockNumber,
+        reward_percentiles: &[f64],
+    ) -> Result<FeeHistory> {
+        let _permit = self.permit_request().await;
+        Self::map_err(
+            source_provider!(self, fee_history(block_count, last_block, reward_percentiles)).await,
+        )
+    }
+
     /// Returns traces created at given block
     pub async fn trace_block(&self, block_num: BlockNumber) -> Result<Vec<Trace>> {
         let _permit = self.permit_request().await;
@@ -410,9 +659,28 @@ impl Source {
     /// Returns all traces of a given transaction
     pub async fn trace_transaction(&self, tx_hash: TxHash) -> Result<Vec<Trace>> {
         let _permit = self.permit_request().await;
-        source_provider!(self, trace_transaction(tx_hash))
-            .await
-            .map_err(CollectError::ProviderError)
+        source_provider!(self, trace_transaction(tx_hash)).await.map_err(rpc_error)
+    }
+
+    /// Returns traces created at given block, tracing each transaction individually
+    /// `batch_size` transactions are traced concurrently at a time
+    pub async fn trace_block_by_transactions(
+        &self,
+        block_num: u64,
+        batch_size: u64,
+    ) -> Result<Vec<Trace>> {
+        let block = self
+            .get_block(block_num)
+            .await?
+            .ok_or(CollectError::CollectError("could not find block".to_string()))?;
+        let mut traces = Vec::new();
+        for batch in block.transactions.chunks(std::cmp::max(batch_size, 1) as usize) {
+            let requests = batch.iter().map(|tx_hash| self.trace_transaction(*tx_hash));
+            for result in futures::future::join_all(requests).await.into_iter() {
+                traces.extend(result?);
+            }
+        }
+        Ok(traces)
     }
 
     /// Deprecated
@@ -423,9 +691,7 @@ impl Source {
     ) -> Result<Bytes> {
         let _permit = self.permit_request().await;
         let tx: ethers::core::types::transaction::eip2718::TypedTransaction = transaction.into();
-        source_provider!(self, call(&tx, Some(block_number.into())))
-            .await
-            .map_err(CollectError::ProviderError)
+        source_provider!(self, call(&tx, Some(block_number.into()))).await.map_err(rpc_error)
     }
 
     /// Returns traces for given call data
@@ -438,7 +704,7 @@ impl Source {
         let _permit = self.permit_request().await;
         source_provider!(self, trace_call(transaction, trace_type, block_number))
             .await
-            .map_err(CollectError::ProviderError)
+            .map_err(rpc_error)
     }
 
     /// Get nonce of address
@@ -450,7 +716,7 @@ impl Source {
         let _permit = self.permit_request().await;
         source_provider!(self, get_transaction_count(address, Some(block_number.into())))
             .await
-            .map_err(CollectError::ProviderError)
+            .map_err(rpc_error)
     }
 
     /// Get code at address
@@ -458,7 +724,7 @@ impl Source {
         let _permit = self.permit_request().await;
         source_provider!(self, get_balance(address, Some(block_number.into())))
             .await
-            .map_err(CollectError::ProviderError)
+            .map_err(rpc_error)
     }
 
     /// Get code at address
@@ -466,7 +732,7 @@ impl Source {
         let _permit = self.permit_request().await;
         source_provider!(self, get_code(address, Some(block_number.into())))
             .await
-            .map_err(CollectError::ProviderError)
+            .map_err(rpc_error)
     }
 
     /// Get stored data at given location
@@ -479,7 +745,7 @@ impl Source {
         let _permit = self.permit_request().await;
         source_provider!(self, get_storage_at(address, slot, Some(block_number.into())))
             .await
-            .map_err(CollectError::ProviderError)
+            .map_err(rpc_error)
     }
 
     /// Get the block number
@@ -522,9 +788,7 @@ impl Source {
         };
         let _permit = self.permit_request().await;
         let tx: ethers::core::types::transaction::eip2718::TypedTransaction = transaction.into();
-        source_provider!(self, call(&tx, Some(block_number.into())))
-            .await
-            .map_err(CollectError::ProviderError)
+        source_provider!(self, call(&tx, Some(block_number.into()))).await.map_err(rpc_error)
     }
 
     /// Return output data of a contract call
@@ -543,7 +807,7 @@ impl Source {
         let _permit = self.permit_request().await;
         source_provider!(self, trace_call(transaction, trace_type, block_number))
             .await
-            .map_err(CollectError::ProviderError)
+            .map_err(rpc_error)
     }
 
     /// get geth debug block traces
@@ -557,7 +821,7 @@ impl Source {
             let _permit = self.permit_request().await;
             source_provider!(self, debug_trace_block_by_number(Some(block_number.into()), options))
                 .await
-                .map_err(CollectError::ProviderError)?
+                .map_err(rpc_error)?
         };
 
         let txs = if include_transaction_hashes {
@@ -689,6 +953,7 @@ impl Source {
         for trace in traces.into_iter() {
             match trace {
                 GethTrace::Known(GethTraceFrame::CallTracer(call_frame)) => calls.push(call_frame),
+                GethTrace::Unknown(value) => calls.push(serde_json::from_value(value)?),
                 _ => return Err(CollectError::CollectError("invalid trace result".to_string())),
             }
         }
@@ -745,7 +1010,7 @@ impl Source {
             let _permit = self.permit_request().await;
             source_provider!(self, debug_trace_transaction(ethers_tx, options))
                 .await
-                .map_err(CollectError::ProviderError)?
+                .map_err(rpc_error)?
         };
         let traces = vec![trace];
 
@@ -880,6 +1145,7 @@ impl Source {
         for trace in traces.into_iter() {
             match trace {
                 GethTrace::Known(GethTraceFrame::CallTracer(call_frame)) => calls.push(call_frame),
+                GethTrace::Unknown(value) => calls.push(serde_json::from_value(value)?),
                 _ => return Err(CollectError::CollectError("invalid trace result".to_string())),
             }
         }
@@ -931,12 +1197,50 @@ impl Source {
     }
 
     fn map_err<T>(res: ::core::result::Result<T, ProviderError>) -> Result<T> {
-        res.map_err(CollectError::ProviderError)
+        res.map_err(rpc_error)
     }
 }
 
 use crate::err;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+
+/// error of an rpc request, naming errors of missing historical state as needing an archive node
+pub(crate) fn rpc_error(error: ProviderError) -> CollectError {
+    let message = match &error {
+        ProviderError::JsonRpcClientError(e) => e.as_error_response().map(|e| e.message.clone()),
+        _ => None,
+    };
+    match message {
+        Some(message) if is_missing_state(&message) => CollectError::ArchiveNodeRequired(format!(
+            "node does not have the historical state of the requested block ({}), \
+             use an archive node",
+            message
+        )),
+        _ => CollectError::Rpc(error),
+    }
+}
+
+/// whether an rpc error message is that of a node that pruned the state of a block
+fn is_missing_state(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["missing trie node", "historical state", "state is not available", "is pruned"]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// whether the node no longer knows a filter, e.g. because it was not polled for a while
+fn is_expired_filter(error: &CollectError) -> bool {
+    match error {
+        CollectError::Rpc(ProviderError::JsonRpcClientError(e)) => {
+            e.as_error_response().is_some_and(|e| {
+                let message = e.message.to_lowercase();
+                message.contains("filter") &&
+                    (message.contains("not found") || message.contains("does not exist"))
+            })
+        }
+        _ => false,
+    }
+}
 
 fn parse_geth_diff_object(
     map: ethers::utils::__serde_json::Map<String, ethers::utils::__serde_json::Value>,
@@ -6,8 +6,8 @@ use thousands::Separable;
 
 use crate::{
     chunks::chunk_ops::ValueToString, err, ChunkData, ChunkStats, CollectError, ColumnType,
-    Datatype, Dim, ExecutionEnv, FileOutput, MetaDatatype, MultiDatatype, Partition, Query, Source,
-    Table,
+    Datatype, Dim, ExecutionEnv, FileOutput, MultiDatatype, Partition, Query, Source, Table,
+    TimestampRange,
 };
 use std::path::PathBuf;
 
@@ -27,8 +27,24 @@ pub struct FreezeSummary {
     pub skipped: Vec<Partition>,
     /// partitions errored
     pub errored: Vec<(Option<Partition>, CollectError)>,
+    /// partitions not started because of shutdown
+    pub cancelled: Vec<Partition>,
     /// rows written
     pub n_rows: u64,
+    /// block timestamp range of each written file
+    pub timestamp_ranges: HashMap<PathBuf, TimestampRange>,
+}
+
+impl FreezeSummary {
+    /// add the partitions and rows of another summary, e.g. of a later run into the same sink
+    pub fn extend(&mut self, other: FreezeSummary) {
+        self.completed.extend(other.completed);
+        self.skipped.extend(other.skipped);
+        self.errored.extend(other.errored);
+        self.cancelled.extend(other.cancelled);
+        self.n_rows += other.n_rows;
+        self.timestamp_ranges.extend(other.timestamp_ranges);
+    }
 }
 
 /// print all datasets
@@ -92,6 +108,11 @@ pub fn print_dataset_info(datatype: Datatype, schema: &Table) {
         if optional_parameters.is_empty() { "[none]".to_string() } else { optional_parameters };
     print_bullet("optional parameters", optional_parameters);
 
+    let required_methods = datatype.required_methods().join(", ");
+    let required_methods =
+        if required_methods.is_empty() { "[none]".to_string() } else { required_methods };
+    print_bullet("required rpc methods", required_methods);
+
     let dataset_aliases = datatype.aliases().join(", ");
     let dataset_aliases =
         if dataset_aliases.is_empty() { "[none]".to_string() } else { dataset_aliases };
@@ -164,13 +185,18 @@ pub(crate) fn print_cryo_intro(
     print_header("cryo parameters");
     print_bullet("version", super::reports::CRYO_VERSION);
     let datatype_strs: Vec<_> =
-        query.datatypes.iter().flat_map(|d| d.datatypes()).map(|d| d.name()).collect();
+        query.datatypes.iter().flat_map(|d| query.datatypes_of(d)).map(|d| d.name()).collect();
     print_bullet("data", "");
     print_bullet_indent("datatypes", datatype_strs.join(", "), 4);
 
     let align = Some(query.labels.align);
     let reorg_buffer = Some(query.labels.reorg_buffer);
     print_chunks(&query.partitions, align, reorg_buffer);
+    if !query.labels.skipped_blocks.is_empty() {
+        let skipped: Vec<String> =
+            query.labels.skipped_blocks.iter().map(|x| x.to_string()).collect();
+        print_bullet_indent("skipped blocks", skipped.join(", "), 4);
+    }
 
     if env.verbose > 1 {
         print_bullet_indent("exclude failed items", query.exclude_failed.to_string(), 4);
@@ -268,7 +294,7 @@ pub(crate) fn print_cryo_intro(
     };
 
     // print schemas
-    print_schemas(&query.datatypes, &query.schemas)?;
+    print_schemas(query)?;
 
     if env.dry {
         println!("\n\n[dry run, exiting]");
@@ -353,13 +379,10 @@ fn print_chunk<T: Ord + ValueToString>(
     }
 }
 
-fn print_schemas(
-    datatypes: &[MetaDatatype],
-    schemas: &HashMap<Datatype, Table>,
-) -> Result<(), CollectError> {
-    for meta_datatype in datatypes.iter() {
-        for datatype in meta_datatype.datatypes().iter() {
-            if let Some(schema) = schemas.get(datatype) {
+fn print_schemas(query: &Query) -> Result<(), CollectError> {
+    for meta_datatype in query.datatypes.iter() {
+        for datatype in query.datatypes_of(meta_datatype).iter() {
+            if let Some(schema) = query.schemas.get(datatype) {
                 println!();
                 println!();
                 print_schema(datatype, &schema.clone())
@@ -388,8 +411,11 @@ fn print_schema(name: &Datatype, schema: &Table) {
         }
     }
     println!();
-    if let Some(sort_cols) = schema.sort_columns.clone() {
+    if let Some(sort_cols) = schema.row_order() {
         println!("sorting {} by: {}", name.name(), sort_cols.join(", "));
+        if let Some(key) = schema.row_key() {
+            println!("deduplicating {} by: {}", name.name(), key.join(", "));
+        }
     } else {
         println!("sorting disabled for {}", name.name());
     }
@@ -482,6 +508,26 @@ pub(crate) fn print_cryo_conclusion(
         ),
         4,
     );
+    if !query.labels.skipped_blocks.is_empty() {
+        print_bullet_indent(
+            "blocks skipped",
+            format!("  {:>width$}", query.labels.skipped_blocks.len(), width = width),
+            4,
+        );
+    }
+    if !freeze_summary.cancelled.is_empty() {
+        print_bullet_indent(
+            "chunks cancelled",
+            format!(
+                "{:>width$} / {} ({}%)",
+                freeze_summary.cancelled.len().separate_with_commas(),
+                n_chunks_str,
+                format_float((100 * freeze_summary.cancelled.len() / n_chunks) as f64),
+                width = width
+            ),
+            4,
+        );
+    }
     print_bullet_indent(
         "chunks collected",
         format!(
@@ -0,0 +1,20 @@
+use crate::{CollectError, Datatype};
+use polars::prelude::*;
+
+/// transformation applied to each collected dataframe before it is written
+///
+/// library consumers can register a transform on [`crate::ExecutionEnv`] to normalize or add
+/// columns without modifying the datasets themselves
+pub trait RowTransform: Send + Sync {
+    /// transform the dataframe of a single chunk of a datatype
+    fn transform(&self, datatype: Datatype, df: DataFrame) -> Result<DataFrame, CollectError>;
+}
+
+impl<F> RowTransform for F
+where
+    F: Fn(Datatype, DataFrame) -> Result<DataFrame, CollectError> + Send + Sync,
+{
+    fn transform(&self, datatype: Datatype, df: DataFrame) -> Result<DataFrame, CollectError> {
+        self(datatype, df)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{CollectError, Datatype};
+use polars::prelude::*;
+use std::path::Path;
+
+/// destination of the dataframes of each chunk, in place of writing them to files
+///
+/// library consumers can register a writer on [`crate::ExecutionEnv`] to receive the output of
+/// [`crate::freeze`] without touching the filesystem, outputs that build on files already written
+/// (single files, streaming, delta tables and target file sizes) cannot be combined with a writer
+pub trait DataWriter: Send + Sync {
+    /// write the dataframe of a chunk of a datatype, path is where its file would have been
+    fn write(
+        &self,
+        datatype: Datatype,
+        path: &Path,
+        df: &DataFrame,
+        metadata: &[(String, String)],
+    ) -> Result<(), CollectError>;
+}
+
+#[cfg(any(test, feature = "test-util"))]
+pub use memory::{MemoryWriter, WrittenFrame};
+
+#[cfg(any(test, feature = "test-util"))]
+mod memory {
+    use super::*;
+    use std::{
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    /// dataframe captured by a [`MemoryWriter`]
+    #[derive(Clone, Debug)]
+    pub struct WrittenFrame {
+        /// datatype of the dataframe
+        pub datatype: Datatype,
+        /// path of the file that the dataframe would have been written to
+        pub path: PathBuf,
+        /// rows of the chunk
+        pub df: DataFrame,
+        /// metadata that would have been stored in the file
+        pub metadata: Vec<(String, String)>,
+    }
+
+    /// writer that keeps written dataframes in memory, for tests of the whole pipeline
+    #[derive(Clone, Default)]
+    pub struct MemoryWriter {
+        frames: Arc<Mutex<Vec<WrittenFrame>>>,
+    }
+
+    impl MemoryWriter {
+        /// create an empty MemoryWriter
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// dataframes written so far, in the order that they were written
+        pub fn frames(&self) -> Vec<WrittenFrame> {
+            self.frames.lock().map(|frames| frames.clone()).unwrap_or_default()
+        }
+
+        /// rows written for a datatype, with chunks ordered by path
+        pub fn rows(&self, datatype: Datatype) -> Result<Option<DataFrame>, CollectError> {
+            let mut frames: Vec<WrittenFrame> =
+                self.frames().into_iter().filter(|frame| frame.datatype == datatype).collect();
+            frames.sort_by(|a, b| a.path.cmp(&b.path));
+            let mut frames = frames.into_iter();
+            let Some(first) = frames.next() else { return Ok(None) };
+            let mut df = first.df;
+            for frame in frames {
+                df.vstack_mut(&frame.df).map_err(CollectError::PolarsError)?;
+            }
+            Ok(Some(df))
+        }
+    }
+
+    impl DataWriter for MemoryWriter {
+        fn write(
+            &self,
+            datatype: Datatype,
+            path: &Path,
+            df: &DataFrame,
+            metadata: &[(String, String)],
+        ) -> Result<(), CollectError> {
+            let frame = WrittenFrame {
+                datatype,
+                path: path.to_path_buf(),
+                df: df.clone(),
+                metadata: metadata.to_vec(),
+            };
+            let mut frames = self
+                .frames
+                .lock()
+                .map_err(|_| CollectError::CollectError("memory writer poisoned".to_string()))?;
+            frames.push(frame);
+            Ok(())
+        }
+    }
+}
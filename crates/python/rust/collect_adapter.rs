@@ -14,6 +14,7 @@ use cryo_freeze::collect;
         command = None,
         timestamps = None,
         txs = None,
+        at_block = None,
         align = false,
         reorg_buffer = 0,
         include_columns = None,
@@ -64,6 +65,69 @@ use cryo_freeze::collect;
         verbose = false,
         no_verbose = false,
         event_signature = None,
+        trace_granularity = None,
+        trace_backend = None,
+        log_level = None,
+        log_format = None,
+        follow = false,
+        poll_interval = 12,
+        preset = None,
+        single_file = false,
+        ordered = None,
+        skip_preflight = false,
+        assume_archive = false,
+        aggregate = None,
+        no_timestamp_range = false,
+        log_filter_json = None,
+        on_overflow = None,
+        checkpoint_every = None,
+        optimize_types = false,
+        partition_by_date = false,
+        streaming = false,
+        step = None,
+        reward_percentiles = None,
+        dir_mode = None,
+        print_schema = false,
+        column_compression = None,
+        dictionary_columns = None,
+        no_dictionary_columns = None,
+        min_chunk_size = None,
+        deduplicate_logs_on_reorg = false,
+        csv_types = false,
+        limit = None,
+        schema_format = "json-schema".to_string(),
+        exclude_zero_value = false,
+        partition_by_column = None,
+        max_open_partitions = 1000,
+        trace_address_as_string = false,
+        retry_failed_blocks_individually = false,
+        decimal_scale = None,
+        blocks_with_logs = None,
+        target_file_size = None,
+        clean_empty_dirs = false,
+        logs_backend = None,
+        validate_decimals_fit = false,
+        fixed_size_hex = false,
+        checksum_addresses = false,
+        match_schema = None,
+        genesis = None,
+        max_blocks_per_log_request = None,
+        delta = false,
+        write_index = false,
+        quiet = false,
+        skip_blocks = None,
+        skip_blocks_file = None,
+        split_logs_by_topic0 = false,
+        paginate = false,
+        page_size = None,
+        no_json_bigints_as_strings = false,
+        chunk_timeout_ms = None,
+        order_within_file = None,
+        add_column = None,
+        head_wait_retries = 5,
+        resume_from_output = false,
+        include_deprecated = false,
+        decode_output = None,
     )
 )]
 #[allow(clippy::too_many_arguments)]
@@ -75,6 +139,7 @@ pub fn _collect(
     command: Option<String>,
     timestamps: Option<Vec<String>>,
     txs: Option<Vec<String>>,
+    at_block: Option<u64>,
     align: bool,
     reorg_buffer: u64,
     include_columns: Option<Vec<String>>,
@@ -125,6 +190,69 @@ pub fn _collect(
     verbose: bool,
     no_verbose: bool,
     event_signature: Option<String>,
+    trace_granularity: Option<String>,
+    trace_backend: Option<String>,
+    log_level: Option<String>,
+    log_format: Option<String>,
+    follow: bool,
+    poll_interval: u64,
+    preset: Option<String>,
+    single_file: bool,
+    ordered: Option<bool>,
+    skip_preflight: bool,
+    assume_archive: bool,
+    aggregate: Option<Vec<String>>,
+    no_timestamp_range: bool,
+    log_filter_json: Option<String>,
+    on_overflow: Option<String>,
+    checkpoint_every: Option<String>,
+    optimize_types: bool,
+    partition_by_date: bool,
+    streaming: bool,
+    step: Option<u64>,
+    reward_percentiles: Option<Vec<f64>>,
+    dir_mode: Option<String>,
+    print_schema: bool,
+    column_compression: Option<Vec<String>>,
+    dictionary_columns: Option<Vec<String>>,
+    no_dictionary_columns: Option<Vec<String>>,
+    min_chunk_size: Option<u64>,
+    deduplicate_logs_on_reorg: bool,
+    csv_types: bool,
+    limit: Option<u64>,
+    schema_format: String,
+    exclude_zero_value: bool,
+    partition_by_column: Option<String>,
+    max_open_partitions: usize,
+    trace_address_as_string: bool,
+    retry_failed_blocks_individually: bool,
+    decimal_scale: Option<Vec<String>>,
+    blocks_with_logs: Option<String>,
+    target_file_size: Option<String>,
+    clean_empty_dirs: bool,
+    logs_backend: Option<String>,
+    validate_decimals_fit: bool,
+    fixed_size_hex: bool,
+    checksum_addresses: bool,
+    match_schema: Option<String>,
+    genesis: Option<String>,
+    max_blocks_per_log_request: Option<u64>,
+    delta: bool,
+    write_index: bool,
+    quiet: bool,
+    skip_blocks: Option<Vec<u64>>,
+    skip_blocks_file: Option<String>,
+    split_logs_by_topic0: bool,
+    paginate: bool,
+    page_size: Option<u64>,
+    no_json_bigints_as_strings: bool,
+    chunk_timeout_ms: Option<u64>,
+    order_within_file: Option<Vec<String>>,
+    add_column: Option<Vec<String>>,
+    head_wait_retries: u32,
+    resume_from_output: bool,
+    include_deprecated: bool,
+    decode_output: Option<String>,
 ) -> PyResult<&PyAny> {
     if let Some(command) = command {
         pyo3_asyncio::tokio::future_into_py(py, async move {
@@ -140,6 +268,7 @@ pub fn _collect(
             remember,
             timestamps,
             txs,
+            at_block,
             align,
             reorg_buffer,
             include_columns,
@@ -190,6 +319,69 @@ pub fn _collect(
             verbose,
             no_verbose,
             event_signature,
+            trace_granularity,
+            trace_backend,
+            log_level,
+            log_format,
+            follow,
+            poll_interval,
+            preset,
+            single_file,
+            ordered,
+            skip_preflight,
+            assume_archive,
+            aggregate,
+            no_timestamp_range,
+            log_filter_json,
+            on_overflow,
+            checkpoint_every,
+            optimize_types,
+            partition_by_date,
+            streaming,
+            step,
+            reward_percentiles,
+            dir_mode,
+            print_schema,
+            column_compression,
+            dictionary_columns,
+            no_dictionary_columns,
+            min_chunk_size,
+            deduplicate_logs_on_reorg,
+            csv_types,
+            limit,
+            schema_format,
+            exclude_zero_value,
+            partition_by_column,
+            max_open_partitions,
+            trace_address_as_string,
+            retry_failed_blocks_individually,
+            decimal_scale,
+            blocks_with_logs,
+            target_file_size,
+            clean_empty_dirs,
+            logs_backend,
+            validate_decimals_fit,
+            fixed_size_hex,
+            checksum_addresses,
+            match_schema,
+            genesis,
+            max_blocks_per_log_request,
+            delta,
+            write_index,
+            quiet,
+            skip_blocks,
+            skip_blocks_file,
+            split_logs_by_topic0,
+            paginate,
+            page_size,
+            no_json_bigints_as_strings,
+            chunk_timeout_ms,
+            order_within_file,
+            add_column,
+            head_wait_retries,
+            resume_from_output,
+            include_deprecated,
+            decode_output,
         };
         pyo3_asyncio::tokio::future_into_py(py, async move {
             match run_collect(args).await {
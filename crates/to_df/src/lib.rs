@@ -42,6 +42,7 @@ pub fn to_df(attrs: TokenStream, input: TokenStream) -> TokenStream {
                 "Vec < Option < Vec < u8 > > >" => {
                     syn::Ident::new("with_series_binary", Span::call_site())
                 }
+                "Vec < String >" => syn::Ident::new("with_series_string", Span::call_site()),
                 "Vec < U256 >" => syn::Ident::new("with_series_u256", Span::call_site()),
                 "Vec < Option < U256 > >" => {
                     syn::Ident::new("with_series_option_u256", Span::call_site())
@@ -60,6 +61,20 @@ pub fn to_df(attrs: TokenStream, input: TokenStream) -> TokenStream {
         .filter(|(name, _)| name == "event_cols")
         .collect::<Vec<_>>()
         .is_empty();
+    let has_dynamic_cols = field_names_and_types.iter().any(|(name, _)| name == "dynamic_cols");
+    let dynamic_code = if has_dynamic_cols {
+        // columns whose names depend on the query, stored in schema order
+        quote! {
+            for (name, values) in self.dynamic_cols {
+                if schema.has_column(&name) {
+                    cols.push(Series::new(name.as_str(), values));
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let event_code = if has_event_cols {
         // Generate the tokens for the event processing code
         quote! {
@@ -150,6 +165,7 @@ pub fn to_df(attrs: TokenStream, input: TokenStream) -> TokenStream {
                             chunk_len as usize,
                             &u256_types,
                             &schema.binary_type,
+                            &schema.overflow_policy,
                         );
                         match series_vec {
                             Ok(s) => {
@@ -208,7 +224,7 @@ pub fn to_df(attrs: TokenStream, input: TokenStream) -> TokenStream {
         if let Some(column_type) = map_type_to_column_type(ty) {
             let field_name_str = format!("{}", quote!(#name));
             column_types.push(quote! { (#field_name_str, #column_type) });
-        } else if name != "n_rows" && name != "event_cols" {
+        } else if name != "n_rows" && name != "event_cols" && name != "dynamic_cols" {
             println!("invalid column type for {name} in table {}", datatype_str);
         }
     }
@@ -240,6 +256,8 @@ pub fn to_df(attrs: TokenStream, input: TokenStream) -> TokenStream {
                     with_series!(cols, "chain_id", self.chain_id, schema);
                 }
 
+                #dynamic_code
+
                 #event_code
 
                 let df = DataFrame::new(cols).map_err(CollectError::PolarsError).sort_by_schema(schema)?;